@@ -248,6 +248,12 @@ fn main() {
         if env::var_os("RUSTC_FORCE_UNSTABLE").is_some() {
             cmd.arg("-Z").arg("force-unstable-if-unmarked");
         }
+
+        // Make the compiler's own data structures thread-safe so that it can
+        // run queries in parallel.
+        if env::var_os("RUSTC_PARALLEL_QUERIES").is_some() {
+            cmd.arg("--cfg").arg("parallel_queries");
+        }
     }
 
     let color = match env::var("RUSTC_COLOR") {
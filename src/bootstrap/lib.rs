@@ -151,6 +151,7 @@ use util::{exe, libdir, OutputFolder, CiEnv};
 mod cc;
 mod channel;
 mod check;
+mod test;
 mod clean;
 mod compile;
 mod metadata;
@@ -0,0 +1,511 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A general purpose allocator for targets which have nothing but a linear
+//! memory that can be grown, modelled after Doug Lea's malloc.
+//!
+//! Memory is split into chunks, each starting with a header that records its
+//! size and whether it and the chunk before it are in use. Free chunks also
+//! store their size at the start of the chunk after them, so that a chunk
+//! being freed can be merged with free neighbours on either side in constant
+//! time. Free chunks are kept in bins: one for each small size, and one for
+//! each power of two above that. The free memory at the end of the heap is
+//! the "top" chunk, which is carved up when no free chunk is large enough and
+//! grown through `grow_memory` when it runs out.
+
+use core::cmp;
+use core::mem;
+use core::ptr;
+
+#[cfg(test)]
+use self::tests::grow_memory;
+
+/// Grows linear memory by `pages` pages, returning the address the new
+/// memory starts at.
+#[cfg(not(test))]
+unsafe fn grow_memory(pages: usize) -> Option<usize> {
+    extern {
+        #[link_name = "llvm.wasm.grow.memory.i32"]
+        fn llvm_grow_memory(pages: u32) -> i32;
+    }
+
+    let prev_pages = llvm_grow_memory(pages as u32);
+    if prev_pages < 0 {
+        None
+    } else {
+        Some(prev_pages as usize * PAGE_SIZE)
+    }
+}
+
+const PAGE_SIZE: usize = 64 * 1024;
+
+const WORD: usize = mem::size_of::<usize>();
+
+/// The alignment of every chunk, and of the memory handed out.
+pub const ALIGN: usize = 2 * WORD;
+
+/// Free chunks hold their header and two list links.
+const MIN_CHUNK_SIZE: usize = 4 * WORD;
+
+/// The memory handed out for a chunk starts after its header...
+const CHUNK_MEM_OFFSET: usize = 2 * WORD;
+
+/// ...but may extend over the `prev_size` field of the next chunk, which is
+/// only used when this chunk is free.
+const CHUNK_OVERHEAD: usize = WORD;
+
+const PINUSE: usize = 1;
+const CINUSE: usize = 2;
+const FLAGS: usize = PINUSE | CINUSE;
+
+const NSMALLBINS: usize = 32;
+const NBINS: usize = 64;
+
+#[repr(C)]
+struct Chunk {
+    /// The size of the previous chunk, if it is free.
+    prev_size: usize,
+    /// The size of this chunk, along with `PINUSE` and `CINUSE`.
+    head: usize,
+    /// The links of the bin this chunk is in, if it is free.
+    next: *mut Chunk,
+    prev: *mut Chunk,
+}
+
+pub struct Dlmalloc {
+    bins: [*mut Chunk; NBINS],
+    /// The bins which are not empty.
+    binmap: u64,
+    top: *mut Chunk,
+    top_size: usize,
+}
+
+pub const DLMALLOC_INIT: Dlmalloc = Dlmalloc {
+    bins: [0 as *mut Chunk; NBINS],
+    binmap: 0,
+    top: 0 as *mut Chunk,
+    top_size: 0,
+};
+
+unsafe fn chunksize(chunk: *mut Chunk) -> usize {
+    (*chunk).head & !FLAGS
+}
+
+unsafe fn pinuse(chunk: *mut Chunk) -> usize {
+    (*chunk).head & PINUSE
+}
+
+unsafe fn plus_offset(chunk: *mut Chunk, offset: usize) -> *mut Chunk {
+    (chunk as *mut u8).offset(offset as isize) as *mut Chunk
+}
+
+unsafe fn next_chunk(chunk: *mut Chunk) -> *mut Chunk {
+    plus_offset(chunk, chunksize(chunk))
+}
+
+unsafe fn prev_chunk(chunk: *mut Chunk) -> *mut Chunk {
+    (chunk as *mut u8).offset(-((*chunk).prev_size as isize)) as *mut Chunk
+}
+
+unsafe fn chunk_to_mem(chunk: *mut Chunk) -> *mut u8 {
+    (chunk as *mut u8).offset(CHUNK_MEM_OFFSET as isize)
+}
+
+unsafe fn mem_to_chunk(mem: *mut u8) -> *mut Chunk {
+    mem.offset(-(CHUNK_MEM_OFFSET as isize)) as *mut Chunk
+}
+
+/// The size of the chunk needed to hand out `size` bytes, if there is one.
+fn request_to_size(size: usize) -> Option<usize> {
+    size.checked_add(CHUNK_OVERHEAD + ALIGN - 1).map(|size| {
+        cmp::max(size & !(ALIGN - 1), MIN_CHUNK_SIZE)
+    })
+}
+
+fn bin_index(size: usize) -> usize {
+    if size < NSMALLBINS * ALIGN {
+        size / ALIGN
+    } else {
+        let log2 = |n: usize| WORD * 8 - 1 - n.leading_zeros() as usize;
+        cmp::min(NSMALLBINS + log2(size) - log2(NSMALLBINS * ALIGN), NBINS - 1)
+    }
+}
+
+impl Dlmalloc {
+    /// Allocates `size` bytes aligned to `ALIGN`, returning null on failure.
+    pub unsafe fn malloc(&mut self, size: usize) -> *mut u8 {
+        let nb = match request_to_size(size) {
+            Some(nb) => nb,
+            None => return ptr::null_mut(),
+        };
+        let chunk = self.take_free_chunk(nb);
+        if !chunk.is_null() {
+            self.set_inuse(chunk);
+            self.trim(chunk, nb);
+            return chunk_to_mem(chunk);
+        }
+
+        // Carve the chunk out of the top, leaving enough for the top to stay
+        // a chunk of its own.
+        if !self.grow_top(nb) {
+            return ptr::null_mut();
+        }
+        let chunk = self.top;
+        self.top = plus_offset(chunk, nb);
+        self.top_size -= nb;
+        (*self.top).head = self.top_size | PINUSE;
+        (*chunk).head = nb | CINUSE | pinuse(chunk);
+        chunk_to_mem(chunk)
+    }
+
+    /// Allocates `size` bytes aligned to `align`, which must be a power of
+    /// two, returning null on failure.
+    pub unsafe fn memalign(&mut self, align: usize, size: usize) -> *mut u8 {
+        if align <= ALIGN {
+            return self.malloc(size);
+        }
+        let nb = match request_to_size(size) {
+            Some(nb) => nb,
+            None => return ptr::null_mut(),
+        };
+        // Allocate enough to find an aligned chunk inside, with room for a
+        // chunk before it to give back.
+        let mem = match nb.checked_add(align + MIN_CHUNK_SIZE) {
+            Some(padded) => self.malloc(padded),
+            None => return ptr::null_mut(),
+        };
+        if mem.is_null() {
+            return mem;
+        }
+        let mut chunk = mem_to_chunk(mem);
+        if mem as usize & (align - 1) != 0 {
+            let mut aligned = (mem as usize + align - 1) & !(align - 1);
+            if aligned - (mem as usize) < MIN_CHUNK_SIZE {
+                aligned += align;
+            }
+            let aligned_chunk = mem_to_chunk(aligned as *mut u8);
+            let lead = aligned_chunk as usize - chunk as usize;
+            (*aligned_chunk).head = (chunksize(chunk) - lead) | CINUSE | PINUSE;
+            (*chunk).head = lead | CINUSE | pinuse(chunk);
+            self.free(mem);
+            chunk = aligned_chunk;
+        }
+        self.trim(chunk, nb);
+        chunk_to_mem(chunk)
+    }
+
+    /// Frees memory returned by `malloc` or `memalign`.
+    pub unsafe fn free(&mut self, mem: *mut u8) {
+        let mut chunk = mem_to_chunk(mem);
+        let mut size = chunksize(chunk);
+        let next = next_chunk(chunk);
+
+        if pinuse(chunk) == 0 {
+            let prev = prev_chunk(chunk);
+            self.unlink(prev);
+            size += (*chunk).prev_size;
+            chunk = prev;
+        }
+
+        if next == self.top {
+            // Two free chunks are never next to each other, so the chunk
+            // before this one is in use.
+            self.top = chunk;
+            self.top_size += size;
+            (*chunk).head = self.top_size | PINUSE;
+            return;
+        }
+        if (*next).head & CINUSE == 0 {
+            self.unlink(next);
+            size += chunksize(next);
+        }
+
+        (*chunk).head = size | PINUSE;
+        let next = next_chunk(chunk);
+        (*next).prev_size = size;
+        (*next).head &= !PINUSE;
+        self.insert(chunk);
+    }
+
+    /// Removes a free chunk of at least `nb` bytes from the bins, if there is
+    /// one.
+    unsafe fn take_free_chunk(&mut self, nb: usize) -> *mut Chunk {
+        // Small bins only hold chunks of a single size, but the chunks in a
+        // large bin may be smaller than `nb`.
+        let index = bin_index(nb);
+        let mut chunk = self.bins[index];
+        while !chunk.is_null() {
+            if chunksize(chunk) >= nb {
+                self.unlink(chunk);
+                return chunk;
+            }
+            chunk = (*chunk).next;
+        }
+
+        // Any chunk in a later bin is large enough.
+        let later = if index + 1 < NBINS {
+            self.binmap >> (index + 1) << (index + 1)
+        } else {
+            0
+        };
+        if later == 0 {
+            return ptr::null_mut();
+        }
+        let chunk = self.bins[later.trailing_zeros() as usize];
+        self.unlink(chunk);
+        chunk
+    }
+
+    unsafe fn set_inuse(&mut self, chunk: *mut Chunk) {
+        (*chunk).head |= CINUSE;
+        (*next_chunk(chunk)).head |= PINUSE;
+    }
+
+    /// Gives back the part of an in use chunk past its first `nb` bytes, if
+    /// that is large enough to be a chunk of its own.
+    unsafe fn trim(&mut self, chunk: *mut Chunk, nb: usize) {
+        let rest = chunksize(chunk) - nb;
+        if rest >= MIN_CHUNK_SIZE {
+            (*chunk).head = nb | CINUSE | pinuse(chunk);
+            let remainder = plus_offset(chunk, nb);
+            (*remainder).head = rest | CINUSE | PINUSE;
+            self.free(chunk_to_mem(remainder));
+        }
+    }
+
+    /// Grows linear memory until the top chunk can hand out `nb` bytes and
+    /// still be a chunk of its own.
+    unsafe fn grow_top(&mut self, nb: usize) -> bool {
+        let needed = match nb.checked_add(MIN_CHUNK_SIZE) {
+            Some(needed) => needed,
+            None => return false,
+        };
+        while self.top_size < needed {
+            let pages = (needed - self.top_size + PAGE_SIZE - 1) / PAGE_SIZE;
+            let start = match grow_memory(pages) {
+                Some(start) => start,
+                None => return false,
+            };
+            if !self.top.is_null() && self.top as usize + self.top_size == start {
+                self.top_size += pages * PAGE_SIZE;
+                (*self.top).head = self.top_size | pinuse(self.top);
+            } else {
+                // Everything up to the memory we just got belongs to someone
+                // else, such as the stack and static data, so it starts a new
+                // heap. What is left of the old top can't be merged with
+                // anything and is never handed out again.
+                if !self.top.is_null() {
+                    (*self.top).head |= CINUSE;
+                }
+                self.top = start as *mut Chunk;
+                self.top_size = pages * PAGE_SIZE;
+                (*self.top).head = self.top_size | PINUSE;
+            }
+        }
+        true
+    }
+
+    unsafe fn insert(&mut self, chunk: *mut Chunk) {
+        let index = bin_index(chunksize(chunk));
+        let head = self.bins[index];
+        (*chunk).next = head;
+        (*chunk).prev = ptr::null_mut();
+        if !head.is_null() {
+            (*head).prev = chunk;
+        }
+        self.bins[index] = chunk;
+        self.binmap |= 1 << index;
+    }
+
+    unsafe fn unlink(&mut self, chunk: *mut Chunk) {
+        let index = bin_index(chunksize(chunk));
+        let (next, prev) = ((*chunk).next, (*chunk).prev);
+        if prev.is_null() {
+            self.bins[index] = next;
+        } else {
+            (*prev).next = next;
+        }
+        if !next.is_null() {
+            (*next).prev = prev;
+        }
+        if self.bins[index].is_null() {
+            self.binmap &= !(1 << index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// How far linear memory can grow in the tests.
+    const TEST_PAGES: usize = 256;
+
+    // Every test runs on a thread of its own, and so gets its own linear
+    // memory: where it starts, and how many of its pages are in use.
+    thread_local!(static MEMORY: Cell<(usize, usize)> = Cell::new((0, 0)));
+
+    pub unsafe fn grow_memory(pages: usize) -> Option<usize> {
+        MEMORY.with(|memory| {
+            let (mut start, used) = memory.get();
+            if start == 0 {
+                let buf = Vec::<u8>::with_capacity(TEST_PAGES * PAGE_SIZE + ALIGN);
+                start = (buf.as_ptr() as usize + ALIGN - 1) & !(ALIGN - 1);
+                mem::forget(buf);
+            }
+            if pages > TEST_PAGES - used {
+                memory.set((start, used));
+                return None;
+            }
+            memory.set((start, used + pages));
+            Some(start + used * PAGE_SIZE)
+        })
+    }
+
+    fn pages_used() -> usize {
+        MEMORY.with(|memory| memory.get().1)
+    }
+
+    unsafe fn fill(mem: *mut u8, size: usize, seed: u8) {
+        for i in 0..size {
+            *mem.offset(i as isize) = seed.wrapping_add(i as u8);
+        }
+    }
+
+    unsafe fn check(mem: *mut u8, size: usize, seed: u8) {
+        for i in 0..size {
+            assert_eq!(*mem.offset(i as isize), seed.wrapping_add(i as u8));
+        }
+    }
+
+    #[test]
+    fn allocations_do_not_overlap() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            let sizes = [1, 7, 16, 24, 100, 512, 1000, 4096, 70000];
+            let mems: Vec<_> = sizes.iter().enumerate().map(|(i, &size)| {
+                let mem = a.malloc(size);
+                assert!(!mem.is_null());
+                assert_eq!(mem as usize % ALIGN, 0);
+                fill(mem, size, i as u8);
+                mem
+            }).collect();
+            for (i, (&mem, &size)) in mems.iter().zip(&sizes).enumerate() {
+                check(mem, size, i as u8);
+            }
+        }
+    }
+
+    #[test]
+    fn freed_memory_is_reused() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            let mut pages = 0;
+            for round in 0..3 {
+                let mems: Vec<_> = (1..200).map(|size| a.malloc(size * 8)).collect();
+                for mem in mems {
+                    assert!(!mem.is_null());
+                    a.free(mem);
+                }
+                if round == 0 {
+                    pages = pages_used();
+                } else {
+                    assert_eq!(pages_used(), pages);
+                }
+            }
+            // Everything was merged back into the top chunk.
+            assert_eq!(a.binmap, 0);
+        }
+    }
+
+    #[test]
+    fn free_merges_neighbours() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            let x = a.malloc(100);
+            let y = a.malloc(100);
+            let z = a.malloc(100);
+            a.free(x);
+            a.free(y);
+            // Neither chunk is large enough on its own.
+            let w = a.malloc(200);
+            assert_eq!(w, x);
+            a.free(w);
+            a.free(z);
+            assert_eq!(a.binmap, 0);
+        }
+    }
+
+    #[test]
+    fn memalign_aligns() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            let aligns = [32, 64, 256, 4096, PAGE_SIZE];
+            let mems: Vec<_> = aligns.iter().map(|&align| {
+                let mem = a.memalign(align, 100);
+                assert!(!mem.is_null());
+                assert_eq!(mem as usize % align, 0);
+                fill(mem, 100, align as u8);
+                mem
+            }).collect();
+            for (&mem, &align) in mems.iter().zip(&aligns) {
+                check(mem, 100, align as u8);
+                a.free(mem);
+            }
+            assert_eq!(a.binmap, 0);
+        }
+    }
+
+    #[test]
+    fn out_of_memory() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            assert!(a.malloc(usize::max_value()).is_null());
+            assert!(a.memalign(64, usize::max_value() - 64).is_null());
+            assert!(a.malloc(TEST_PAGES * PAGE_SIZE).is_null());
+            // What could be allocated before still can be.
+            let mem = a.malloc(100);
+            assert!(!mem.is_null());
+            a.free(mem);
+        }
+    }
+
+    #[test]
+    fn random_allocations() {
+        unsafe {
+            let mut a = DLMALLOC_INIT;
+            let mut live = Vec::new();
+            let mut rng = 0x2545_f491u32;
+            for i in 0..10000 {
+                rng = rng.wrapping_mul(1664525).wrapping_add(1013904223);
+                let r = (rng >> 8) as usize;
+                if !live.is_empty() && r % 2 == 0 {
+                    let (mem, size, seed) = live.swap_remove(r / 2 % live.len());
+                    check(mem, size, seed);
+                    a.free(mem);
+                } else {
+                    let size = r % 1000 + 1;
+                    let mem = if r % 7 == 0 { a.memalign(64, size) } else { a.malloc(size) };
+                    assert!(!mem.is_null());
+                    fill(mem, size, i as u8);
+                    live.push((mem, size, i as u8));
+                }
+            }
+            for (mem, size, seed) in live {
+                check(mem, size, seed);
+                a.free(mem);
+            }
+            assert_eq!(a.binmap, 0);
+        }
+    }
+}
@@ -22,6 +22,8 @@
 #![feature(core_intrinsics)]
 #![feature(staged_api)]
 #![cfg_attr(any(unix, target_os = "redox"), feature(libc))]
+#![cfg_attr(all(target_arch = "wasm32", not(target_os = "emscripten")),
+            feature(link_llvm_intrinsics, const_fn))]
 
 // The minimum alignment guaranteed by the architecture. This value is used to
 // add fast paths for low alignment values. In practice, the alignment is a
@@ -43,6 +45,12 @@ const MIN_ALIGN: usize = 16;
 
 extern crate alloc;
 
+// Allow testing this library
+
+#[cfg(test)]
+#[macro_use]
+extern crate std;
+
 use self::alloc::heap::{Alloc, AllocErr, Layout, Excess, CannotReallocInPlace};
 
 #[unstable(feature = "allocator_api", issue = "32838")]
@@ -457,3 +465,45 @@ mod platform {
         }
     }
 }
+
+// The allocator only runs on wasm32, but is tested on the host.
+#[cfg(any(test, all(target_arch = "wasm32", not(target_os = "emscripten"))))]
+mod dlmalloc;
+
+#[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+mod platform {
+    use core::intrinsics;
+
+    use System;
+    use alloc::heap::{Alloc, AllocErr, Layout};
+    use dlmalloc::{Dlmalloc, DLMALLOC_INIT};
+
+    // WebAssembly has no allocator to defer to, only a linear memory that can
+    // be grown, so we bring our own. Wasm has no threads either, so there is
+    // no need to synchronize access to it.
+    static mut DLMALLOC: Dlmalloc = DLMALLOC_INIT;
+
+    #[unstable(feature = "allocator_api", issue = "32838")]
+    unsafe impl<'a> Alloc for &'a System {
+        #[inline]
+        unsafe fn alloc(&mut self, layout: Layout) -> Result<*mut u8, AllocErr> {
+            let ptr = DLMALLOC.memalign(layout.align(), layout.size());
+            if ptr.is_null() {
+                Err(AllocErr::Exhausted { request: layout })
+            } else {
+                Ok(ptr)
+            }
+        }
+
+        #[inline]
+        unsafe fn dealloc(&mut self, ptr: *mut u8, _layout: Layout) {
+            DLMALLOC.free(ptr)
+        }
+
+        fn oom(&mut self, _err: AllocErr) -> ! {
+            unsafe {
+                intrinsics::abort();
+            }
+        }
+    }
+}
@@ -362,6 +362,24 @@ impl DefId {
     }
 }
 
+impl DepKind {
+    /// True for the nodes that are not computed from other nodes but stand
+    /// for data the compiler starts out with, i.e. the HIR and the metadata
+    /// of other crates. Their fingerprint is computed directly from that
+    /// data at the start of every session.
+    #[inline]
+    pub fn is_input(&self) -> bool {
+        match *self {
+            DepKind::Krate |
+            DepKind::Hir |
+            DepKind::HirBody |
+            DepKind::MetaData |
+            DepKind::AllLocalTraitImpls => true,
+            _ => false,
+        }
+    }
+}
+
 define_dep_nodes!( <'tcx>
     // Represents the `Krate` as a whole (the `hir::Krate` value) (as
     // distinct from the krate module). This is basically a hash of
@@ -408,6 +426,7 @@ define_dep_nodes!( <'tcx>
 
     [] BorrowCheckKrate,
     [] BorrowCheck(DefId),
+    [] MirBorrowCheck(DefId),
     [] RvalueCheck(DefId),
     [] Reachability,
     [] MirKeys,
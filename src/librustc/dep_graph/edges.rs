@@ -19,6 +19,8 @@ use super::debug::EdgeFilter;
 
 pub struct DepGraphEdges {
     nodes: Vec<DepNode>,
+    /// The nodes each node read, in the order they were first read.
+    deps: Vec<Vec<DepNodeIndex>>,
     indices: FxHashMap<DepNode, DepNodeIndex>,
     edges: FxHashSet<(DepNodeIndex, DepNodeIndex)>,
     task_stack: Vec<OpenTask>,
@@ -34,12 +36,12 @@ impl DepNodeIndex {
 
     pub const INVALID: DepNodeIndex = DepNodeIndex { index: ::std::u32::MAX };
 
-    fn new(v: usize) -> DepNodeIndex {
+    pub fn new(v: usize) -> DepNodeIndex {
         assert!((v & 0xFFFF_FFFF) == v);
         DepNodeIndex { index: v as u32 }
     }
 
-    fn index(self) -> usize {
+    pub fn index(self) -> usize {
         self.index as usize
     }
 }
@@ -76,6 +78,7 @@ impl DepGraphEdges {
 
         DepGraphEdges {
             nodes: vec![],
+            deps: vec![],
             indices: FxHashMap(),
             edges: FxHashSet(),
             task_stack: Vec::new(),
@@ -115,12 +118,7 @@ impl DepGraphEdges {
             debug_assert_eq!(node, key);
 
             let target_id = self.get_or_create_node(node);
-
-            for read in reads.into_iter() {
-                let source_id = self.get_or_create_node(read);
-                self.edges.insert((source_id, target_id));
-            }
-
+            self.add_reads(target_id, reads);
             target_id
         } else {
             bug!("pop_task() - Expected regular task to be popped")
@@ -165,12 +163,7 @@ impl DepGraphEdges {
             }
 
             let target_id = self.get_or_create_node(target_dep_node);
-
-            for read in reads.into_iter() {
-                let source_id = self.get_or_create_node(read);
-                self.edges.insert((source_id, target_id));
-            }
-
+            self.add_reads(target_id, reads);
             target_id
         } else {
             bug!("pop_anon_task() - Expected anonymous task to be popped")
@@ -231,24 +224,68 @@ impl DepGraphEdges {
     pub fn add_edge(&mut self, source: DepNode, target: DepNode) {
         let source = self.get_or_create_node(source);
         let target = self.get_or_create_node(target);
-        self.edges.insert((source, target));
+        if self.edges.insert((source, target)) {
+            self.deps[target.index()].push(source);
+        }
     }
 
     pub fn add_node(&mut self, node: DepNode) {
         self.get_or_create_node(node);
     }
 
+    /// Adds `node`, which read `deps`, without running a task for it. Used
+    /// for nodes whose result is known to be unchanged from the previous
+    /// session.
+    pub fn add_node_with_deps(&mut self,
+                              node: DepNode,
+                              deps: Vec<DepNodeIndex>)
+                              -> DepNodeIndex {
+        let target_id = self.get_or_create_node(node);
+        for source_id in deps {
+            if self.edges.insert((source_id, target_id)) {
+                self.deps[target_id.index()].push(source_id);
+            }
+        }
+        target_id
+    }
+
+    pub fn node_index(&self, node: &DepNode) -> Option<DepNodeIndex> {
+        self.indices.get(node).cloned()
+    }
+
+    /// All nodes of the graph, in the order they were created, so that the
+    /// position of a node is its `DepNodeIndex`.
+    pub fn nodes(&self) -> &[DepNode] {
+        &self.nodes
+    }
+
+    /// The nodes `index` read, in the order it first read them.
+    pub fn deps(&self, index: DepNodeIndex) -> &[DepNodeIndex] {
+        &self.deps[index.index()]
+    }
+
+    fn add_reads(&mut self, target_id: DepNodeIndex, reads: Vec<DepNode>) {
+        for read in reads.into_iter() {
+            let source_id = self.get_or_create_node(read);
+            if self.edges.insert((source_id, target_id)) {
+                self.deps[target_id.index()].push(source_id);
+            }
+        }
+    }
+
     #[inline]
     fn get_or_create_node(&mut self, dep_node: DepNode) -> DepNodeIndex {
         let DepGraphEdges {
             ref mut indices,
             ref mut nodes,
+            ref mut deps,
             ..
         } = *self;
 
         *indices.entry(dep_node).or_insert_with(|| {
             let next_id = nodes.len();
             nodes.push(dep_node);
+            deps.push(vec![]);
             DepNodeIndex::new(next_id)
         })
      }
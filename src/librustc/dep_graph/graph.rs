@@ -8,16 +8,21 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use rustc_data_structures::fx::FxHashMap;
+use ich::Fingerprint;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 use session::config::OutputType;
 use std::cell::{Ref, RefCell};
 use std::rc::Rc;
+use ty::TyCtxt;
 
 use super::dep_node::{DepNode, DepKind, WorkProductId};
 use super::query::DepGraphQuery;
 use super::raii;
 use super::safe::DepGraphSafe;
 use super::edges::{DepGraphEdges, DepNodeIndex};
+use super::prev::PreviousDepGraph;
+use super::serialized::{SerializedDepGraph, SerializedDepNodeIndex};
 
 #[derive(Clone)]
 pub struct DepGraph {
@@ -38,6 +43,39 @@ struct DepGraphData {
     work_products: RefCell<FxHashMap<WorkProductId, WorkProduct>>,
 
     dep_node_debug: RefCell<FxHashMap<DepNode, String>>,
+
+    /// The dep-graph of the previous compilation session. Empty if there
+    /// was none, or if it could not be reused.
+    previous: RefCell<Rc<PreviousDepGraph>>,
+
+    /// The colors of the nodes of the previous graph whose result is known
+    /// to have changed (red) or not (green) in this session.
+    colors: RefCell<FxHashMap<DepNode, DepNodeColor>>,
+
+    /// The fingerprints of the results of the nodes computed or marked green
+    /// in this session.
+    fingerprints: RefCell<FxHashMap<DepNode, Fingerprint>>,
+
+    /// The nodes whose computation emitted diagnostics in this session.
+    emitted_diagnostics: RefCell<FxHashSet<DepNodeIndex>>,
+}
+
+/// Whether the result of a node of the previous dep-graph is the same in
+/// this session. A green node has been added to the current graph, at the
+/// given index.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepNodeColor {
+    Red,
+    Green(DepNodeIndex),
+}
+
+impl DepNodeColor {
+    pub fn is_green(self) -> bool {
+        match self {
+            DepNodeColor::Red => false,
+            DepNodeColor::Green(_) => true,
+        }
+    }
 }
 
 impl DepGraph {
@@ -49,6 +87,11 @@ impl DepGraph {
                     work_products: RefCell::new(FxHashMap()),
                     edges: RefCell::new(DepGraphEdges::new()),
                     dep_node_debug: RefCell::new(FxHashMap()),
+                    previous: RefCell::new(Rc::new(PreviousDepGraph::new(
+                        SerializedDepGraph::new()))),
+                    colors: RefCell::new(FxHashMap()),
+                    fingerprints: RefCell::new(FxHashMap()),
+                    emitted_diagnostics: RefCell::new(FxHashSet()),
                 }))
             } else {
                 None
@@ -229,6 +272,229 @@ impl DepGraph {
     pub(super) fn dep_node_debug_str(&self, dep_node: DepNode) -> Option<String> {
         self.data.as_ref().and_then(|t| t.dep_node_debug.borrow().get(&dep_node).cloned())
     }
+
+    /// Installs the dep-graph of the previous session, against which the
+    /// nodes of this session are colored. Only to be used during graph
+    /// loading.
+    pub fn set_previous(&self, previous: PreviousDepGraph) {
+        *self.data.as_ref().unwrap().previous.borrow_mut() = Rc::new(previous);
+    }
+
+    pub fn previous(&self) -> Rc<PreviousDepGraph> {
+        self.data.as_ref().unwrap().previous.borrow().clone()
+    }
+
+    pub fn prev_dep_node_index_of(&self, dep_node: &DepNode) -> SerializedDepNodeIndex {
+        self.data.as_ref().unwrap().previous.borrow().node_to_index_opt(dep_node).unwrap()
+    }
+
+    pub fn node_color(&self, dep_node: &DepNode) -> Option<DepNodeColor> {
+        self.data.as_ref().and_then(|data| data.colors.borrow().get(dep_node).cloned())
+    }
+
+    fn set_color(&self, dep_node: DepNode, color: DepNodeColor) {
+        let data = self.data.as_ref().unwrap();
+        let old = data.colors.borrow_mut().insert(dep_node, color);
+        debug_assert!(old.is_none(), "node {:?} colored twice", dep_node);
+    }
+
+    /// Records the result of the task for `dep_node`, which was just run.
+    /// The node becomes green if the result has the same `fingerprint` as in
+    /// the previous session, and red otherwise, or if the result could not
+    /// be fingerprinted.
+    pub fn complete_task(&self,
+                         dep_node: DepNode,
+                         index: DepNodeIndex,
+                         fingerprint: Option<Fingerprint>,
+                         emitted_diagnostics: bool) {
+        let data = match self.data {
+            Some(ref data) => data,
+            None => return,
+        };
+
+        if emitted_diagnostics {
+            data.emitted_diagnostics.borrow_mut().insert(index);
+        }
+
+        let prev_fingerprint = data.previous.borrow().fingerprint_of(&dep_node);
+        let color = match fingerprint {
+            Some(fingerprint) => {
+                data.fingerprints.borrow_mut().insert(dep_node, fingerprint);
+                if prev_fingerprint == Some(fingerprint) {
+                    DepNodeColor::Green(index)
+                } else {
+                    DepNodeColor::Red
+                }
+            }
+            None => DepNodeColor::Red,
+        };
+        debug!("complete_task({:?}) - {:?}", dep_node, color);
+        self.set_color(dep_node, color);
+    }
+
+    /// Colors the input node `dep_node` by comparing its `fingerprint` in
+    /// this session to that of the previous one. Only to be used during
+    /// graph loading.
+    pub fn mark_input(&self, dep_node: DepNode, fingerprint: Fingerprint) {
+        debug_assert!(dep_node.kind.is_input());
+        let data = self.data.as_ref().unwrap();
+        let color = if data.previous.borrow().fingerprint_of(&dep_node) == Some(fingerprint) {
+            let index = data.edges.borrow_mut().add_node_with_deps(dep_node, vec![]);
+            DepNodeColor::Green(index)
+        } else {
+            DepNodeColor::Red
+        };
+        data.fingerprints.borrow_mut().insert(dep_node, fingerprint);
+        self.set_color(dep_node, color);
+    }
+
+    /// Tries to show that the result of `dep_node` is the same as in the
+    /// previous session without computing it, by checking that everything
+    /// it read back then is unchanged: inputs by their fingerprint, other
+    /// nodes recursively, or, as a last resort, by computing them. If this
+    /// succeeds, the node is added to the current graph with the same
+    /// dependencies as before and its index is returned.
+    pub fn try_mark_green<'a, 'tcx>(&self,
+                                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    dep_node: &DepNode)
+                                    -> Option<DepNodeIndex> {
+        let data = match self.data {
+            Some(ref data) => data,
+            None => return None,
+        };
+
+        match self.node_color(dep_node) {
+            Some(DepNodeColor::Green(index)) => return Some(index),
+            Some(DepNodeColor::Red) => return None,
+            None => {}
+        }
+
+        let previous = data.previous.borrow().clone();
+        let prev_index = match previous.node_to_index_opt(dep_node) {
+            Some(prev_index) => prev_index,
+            // A new node, nothing to compare it to.
+            None => return None,
+        };
+
+        // Inputs are all colored when the previous graph is loaded, one
+        // without a color does not exist anymore. Nodes which emitted
+        // diagnostics have to be recomputed to emit them again.
+        if dep_node.kind.is_input() || previous.emitted_diagnostics(prev_index) {
+            return None;
+        }
+
+        let mut deps = Vec::with_capacity(previous.edge_targets_from(prev_index).len());
+        for &dep_prev_index in previous.edge_targets_from(prev_index) {
+            let dep = previous.index_to_node(dep_prev_index);
+            let dep_index = match self.node_color(&dep) {
+                Some(DepNodeColor::Green(index)) => index,
+                Some(DepNodeColor::Red) => {
+                    debug!("try_mark_green({:?}) - dependency {:?} is red", dep_node, dep);
+                    return None;
+                }
+                None => {
+                    if let Some(index) = self.try_mark_green(tcx, &dep) {
+                        index
+                    } else {
+                        // We could not show that the dependency is unchanged
+                        // from what it read, but its result may still be the
+                        // same. The only way to find out is to compute it.
+                        if dep.kind.is_input() || dep.kind.is_anon() ||
+                           !::ty::maps::force_from_dep_node(tcx, &dep) {
+                            debug!("try_mark_green({:?}) - could not force {:?}",
+                                   dep_node, dep);
+                            return None;
+                        }
+                        match self.node_color(&dep) {
+                            Some(DepNodeColor::Green(index)) => index,
+                            _ => {
+                                debug!("try_mark_green({:?}) - dependency {:?} changed",
+                                       dep_node, dep);
+                                return None;
+                            }
+                        }
+                    }
+                }
+            };
+            deps.push(dep_index);
+        }
+
+        // Forcing the dependencies might have computed the node itself.
+        if let Some(color) = self.node_color(dep_node) {
+            return match color {
+                DepNodeColor::Green(index) => Some(index),
+                DepNodeColor::Red => None,
+            };
+        }
+
+        let index = data.edges.borrow_mut().add_node_with_deps(*dep_node, deps);
+        data.fingerprints.borrow_mut().insert(*dep_node, previous.fingerprint_by_index(prev_index));
+        debug!("try_mark_green({:?}) - green", dep_node);
+        self.set_color(*dep_node, DepNodeColor::Green(index));
+        Some(index)
+    }
+
+    /// The number of nodes of the previous graph found to be green and red
+    /// so far.
+    pub fn color_counts(&self) -> (usize, usize) {
+        let data = self.data.as_ref().unwrap();
+        let colors = data.colors.borrow();
+        let green = colors.values().filter(|color| color.is_green()).count();
+        (green, colors.len() - green)
+    }
+
+    /// Converts the current graph into the form it is saved in for the next
+    /// session. The fingerprints of input nodes are not tracked by the
+    /// graph, they are provided by `input_fingerprint`.
+    pub fn serialize<F>(&self, mut input_fingerprint: F) -> SerializedDepGraph
+        where F: FnMut(&DepNode) -> Option<Fingerprint>
+    {
+        let data = self.data.as_ref().unwrap();
+        let edges = data.edges.borrow();
+        let fingerprints = data.fingerprints.borrow();
+
+        // Nodes keep their index, so that the `DepNodeIndex` of a result in
+        // this session is its `SerializedDepNodeIndex` in the next one.
+        let nodes: IndexVec<SerializedDepNodeIndex, DepNode> =
+            edges.nodes().iter().cloned().collect();
+
+        let fingerprints = nodes.iter().map(|dep_node| {
+            let fingerprint = if dep_node.kind.is_input() {
+                input_fingerprint(dep_node)
+            } else {
+                fingerprints.get(dep_node).cloned()
+            };
+            fingerprint.unwrap_or(Fingerprint::zero())
+        }).collect();
+
+        let mut edge_list_indices = IndexVec::with_capacity(nodes.len());
+        let mut edge_list_data = vec![];
+        for index in 0..nodes.len() {
+            let start = edge_list_data.len() as u32;
+            edge_list_data.extend(edges.deps(DepNodeIndex::new(index))
+                                       .iter()
+                                       .map(|dep| SerializedDepNodeIndex::new(dep.index())));
+            let end = edge_list_data.len() as u32;
+            edge_list_indices.push((start, end));
+        }
+
+        let mut emitted_diagnostics: Vec<_> = data.emitted_diagnostics
+                                                  .borrow()
+                                                  .iter()
+                                                  .map(|index| {
+                                                      SerializedDepNodeIndex::new(index.index())
+                                                  })
+                                                  .collect();
+        emitted_diagnostics.sort();
+
+        SerializedDepGraph {
+            nodes,
+            fingerprints,
+            edge_list_indices,
+            edge_list_data,
+            emitted_diagnostics,
+        }
+    }
 }
 
 /// A "work product" is an intermediate result that we save into the
@@ -0,0 +1,79 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use ich::Fingerprint;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use super::dep_node::DepNode;
+use super::serialized::{SerializedDepGraph, SerializedDepNodeIndex};
+
+/// The dep-graph of the previous compilation session, as loaded from the
+/// incremental compilation directory.
+pub struct PreviousDepGraph {
+    data: SerializedDepGraph,
+    index: FxHashMap<DepNode, SerializedDepNodeIndex>,
+    emitted_diagnostics: FxHashSet<SerializedDepNodeIndex>,
+}
+
+impl PreviousDepGraph {
+    pub fn new(data: SerializedDepGraph) -> PreviousDepGraph {
+        let index: FxHashMap<_, _> = data.nodes
+                                         .iter_enumerated()
+                                         .map(|(idx, &dep_node)| (dep_node, idx))
+                                         .collect();
+        let emitted_diagnostics = data.emitted_diagnostics.iter().cloned().collect();
+        PreviousDepGraph { data, index, emitted_diagnostics }
+    }
+
+    #[inline]
+    pub fn edges_from(&self, dep_node: &DepNode) -> Option<&[SerializedDepNodeIndex]> {
+        self.index
+            .get(dep_node)
+            .map(|&node_index| self.data.edge_targets_from(node_index))
+    }
+
+    #[inline]
+    pub fn edge_targets_from(&self, dep_node_index: SerializedDepNodeIndex)
+                             -> &[SerializedDepNodeIndex] {
+        self.data.edge_targets_from(dep_node_index)
+    }
+
+    #[inline]
+    pub fn index_to_node(&self, dep_node_index: SerializedDepNodeIndex) -> DepNode {
+        self.data.nodes[dep_node_index]
+    }
+
+    #[inline]
+    pub fn node_to_index_opt(&self, dep_node: &DepNode) -> Option<SerializedDepNodeIndex> {
+        self.index.get(dep_node).cloned()
+    }
+
+    #[inline]
+    pub fn fingerprint_of(&self, dep_node: &DepNode) -> Option<Fingerprint> {
+        self.index
+            .get(dep_node)
+            .map(|&node_index| self.data.fingerprints[node_index])
+    }
+
+    #[inline]
+    pub fn fingerprint_by_index(&self, dep_node_index: SerializedDepNodeIndex) -> Fingerprint {
+        self.data.fingerprints[dep_node_index]
+    }
+
+    /// True if computing `dep_node_index` emitted diagnostics in the
+    /// previous session.
+    #[inline]
+    pub fn emitted_diagnostics(&self, dep_node_index: SerializedDepNodeIndex) -> bool {
+        self.emitted_diagnostics.contains(&dep_node_index)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.index.len()
+    }
+}
@@ -465,6 +465,16 @@ impl Definitions {
         self.node_to_hir_id[node_id]
     }
 
+    /// Builds the inverse of `node_to_hir_id`, for the nodes that have a
+    /// `HirId`.
+    pub fn hir_to_node_id_map(&self) -> FxHashMap<hir::HirId, ast::NodeId> {
+        self.node_to_hir_id
+            .iter_enumerated()
+            .filter(|&(_, &hir_id)| hir_id != hir::DUMMY_HIR_ID)
+            .map(|(node_id, &hir_id)| (hir_id, node_id))
+            .collect()
+    }
+
     /// Add a definition with a parent definition.
     pub fn create_root_def(&mut self,
                            crate_name: &str,
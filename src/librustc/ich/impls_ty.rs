@@ -161,6 +161,8 @@ impl<'a, 'gcx, 'tcx, T> HashStable<StableHashingContext<'a, 'gcx, 'tcx>> for ty:
 
 impl_stable_hash_for!(enum ty::ClosureKind { Fn, FnMut, FnOnce });
 
+impl_stable_hash_for!(struct ty::SymbolName { name });
+
 impl_stable_hash_for!(enum ty::Visibility {
     Public,
     Restricted(def_id),
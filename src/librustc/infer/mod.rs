@@ -983,6 +983,18 @@ impl<'a, 'gcx, 'tcx> InferCtxt<'a, 'gcx, 'tcx> {
         self.tcx.mk_region(ty::ReVar(self.region_vars.new_region_var(origin)))
     }
 
+    /// The number of region variables created so far.
+    pub fn num_region_vars(&self) -> usize {
+        self.region_vars.num_vars() as usize
+    }
+
+    /// The region constraints recorded so far. These are normally solved by
+    /// `resolve_regions_and_report_errors`; this is for callers that solve
+    /// them on their own.
+    pub fn region_constraints(&self) -> Vec<region_inference::Constraint<'tcx>> {
+        self.region_vars.constraints()
+    }
+
     /// Create a region inference variable for the given
     /// region parameter definition.
     pub fn region_var_for_def(&self,
@@ -456,6 +456,12 @@ impl<'a, 'gcx, 'tcx> RegionVarBindings<'a, 'gcx, 'tcx> {
         len as u32
     }
 
+    /// The constraints added so far, for callers that solve them on their
+    /// own rather than with `resolve_regions`.
+    pub fn constraints(&self) -> Vec<Constraint<'tcx>> {
+        self.constraints.borrow().keys().cloned().collect()
+    }
+
     pub fn new_region_var(&self, origin: RegionVariableOrigin) -> RegionVid {
         let vid = RegionVid { index: self.num_vars() };
         self.var_origins.borrow_mut().push(origin.clone());
@@ -45,7 +45,7 @@ use syntax::attr;
 use syntax::ast;
 use syntax::symbol::Symbol;
 use syntax_pos::{MultiSpan, Span};
-use errors::{self, Diagnostic, DiagnosticBuilder};
+use errors::{self, Applicability, Diagnostic, DiagnosticBuilder};
 use hir;
 use hir::def_id::LOCAL_CRATE;
 use hir::intravisit as hir_visit;
@@ -1245,8 +1245,8 @@ enum CheckLintNameResult {
     // Lint doesn't exist
     NoLint,
     // The lint is either renamed or removed. This is the warning
-    // message.
-    Warning(String),
+    // message, and the new name if the lint was renamed.
+    Warning(String, Option<String>),
 }
 
 /// Checks the name of a lint for its existence, and whether it was
@@ -1261,12 +1261,14 @@ fn check_lint_name(lint_cx: &LintStore,
     match lint_cx.by_name.get(lint_name) {
         Some(&Renamed(ref new_name, _)) => {
             CheckLintNameResult::Warning(
-                format!("lint {} has been renamed to {}", lint_name, new_name)
+                format!("lint {} has been renamed to {}", lint_name, new_name),
+                Some(new_name.clone())
             )
         },
         Some(&Removed(ref reason)) => {
             CheckLintNameResult::Warning(
-                format!("lint {} has been removed: {}", lint_name, reason)
+                format!("lint {} has been removed: {}", lint_name, reason),
+                None
             )
         },
         None => {
@@ -1298,9 +1300,17 @@ fn check_lint_name_attribute(cx: &LateContext, attr: &ast::Attribute) {
             Ok((lint_name, _, span)) => {
                 match check_lint_name(&cx.lint_sess.lints, &lint_name.as_str()) {
                     CheckLintNameResult::Ok => (),
-                    CheckLintNameResult::Warning(ref msg) => {
-                        cx.span_lint(builtin::RENAMED_AND_REMOVED_LINTS,
-                                     span, msg);
+                    CheckLintNameResult::Warning(ref msg, ref new_name) => {
+                        let mut err = cx.struct_span_lint(builtin::RENAMED_AND_REMOVED_LINTS,
+                                                          span, msg);
+                        if let Some(ref new_name) = *new_name {
+                            err.span_suggestion_with_applicability(
+                                span,
+                                "use the new name",
+                                new_name.clone(),
+                                Applicability::MachineApplicable);
+                        }
+                        err.emit();
                     }
                     CheckLintNameResult::NoLint => {
                         cx.span_lint(builtin::UNKNOWN_LINTS, span,
@@ -1318,7 +1328,7 @@ fn check_lint_name_cmdline(sess: &Session, lint_cx: &LintStore,
                            lint_name: &str, level: Level) {
     let db = match check_lint_name(lint_cx, lint_name) {
         CheckLintNameResult::Ok => None,
-        CheckLintNameResult::Warning(ref msg) => {
+        CheckLintNameResult::Warning(ref msg, _) => {
             Some(sess.struct_warn(msg))
         },
         CheckLintNameResult::NoLint => {
@@ -106,7 +106,19 @@ pub enum ErrKind<'tcx> {
 
     ErroneousReferencedConstant(Box<ConstEvalErr<'tcx>>),
 
-    TypeckError
+    TypeckError,
+
+    /// An error raised by the MIR interpreter, with the calls that led to it.
+    Interpreter { message: String, backtrace: Vec<FrameInfo> },
+}
+
+/// A call made by the MIR interpreter while evaluating a constant.
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    /// The span of the call.
+    pub span: Span,
+    /// The path of the called function.
+    pub location: String,
 }
 
 impl<'tcx> From<ConstMathErr> for ErrKind<'tcx> {
@@ -171,6 +183,7 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
             ErroneousReferencedConstant(_) => simple!("could not evaluate referenced constant"),
 
             TypeckError => simple!("type-checking failed"),
+            Interpreter { ref message, .. } => simple!("{}", message),
         }
     }
 
@@ -204,6 +217,12 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
             }
         }
 
+        if let ErrKind::Interpreter { ref backtrace, .. } = self.kind {
+            for frame in backtrace {
+                diag.span_note(frame.span, &format!("inside call to `{}`", frame.location));
+            }
+        }
+
         if !primary_span.contains(self.span) {
             diag.span_note(primary_span,
                         &format!("for {} here", primary_kind));
@@ -33,7 +33,7 @@ use std::fmt::{self, Debug, Formatter, Write};
 use std::{iter, u32};
 use std::ops::{Index, IndexMut};
 use std::vec::IntoIter;
-use syntax::ast::Name;
+use syntax::ast::{Name, NodeId};
 use syntax_pos::Span;
 
 mod cache;
@@ -395,6 +395,10 @@ pub struct LocalDecl<'tcx> {
     /// True if this corresponds to a user-declared local variable.
     pub is_user_variable: bool,
 
+    /// The binding of a user-declared local variable. This is only
+    /// meaningful in the crate the MIR was built in.
+    pub var_id: Option<NodeId>,
+
     /// Type of this local.
     pub ty: Ty<'tcx>,
 
@@ -420,7 +424,8 @@ impl<'tcx> LocalDecl<'tcx> {
                 span,
                 scope: ARGUMENT_VISIBILITY_SCOPE
             },
-            is_user_variable: false
+            is_user_variable: false,
+            var_id: None,
         }
     }
 
@@ -437,7 +442,8 @@ impl<'tcx> LocalDecl<'tcx> {
                 scope: ARGUMENT_VISIBILITY_SCOPE
             },
             name: None,     // FIXME maybe we do want some name here?
-            is_user_variable: false
+            is_user_variable: false,
+            var_id: None,
         }
     }
 }
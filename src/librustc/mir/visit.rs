@@ -640,6 +640,7 @@ macro_rules! make_mir_visitor {
                     name: _,
                     ref $($mutability)* source_info,
                     is_user_variable: _,
+                    var_id: _,
                 } = *local_decl;
 
                 self.visit_ty(ty, Lookup::Src(*source_info));
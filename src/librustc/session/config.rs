@@ -32,6 +32,7 @@ use syntax::symbol::Symbol;
 use syntax::feature_gate::UnstableFeatures;
 
 use errors::{ColorConfig, FatalError, Handler};
+use rustc_data_structures::sync;
 
 use getopts;
 use std::collections::{BTreeMap, BTreeSet};
@@ -44,7 +45,7 @@ use std::hash::Hasher;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::iter::FromIterator;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Config {
     pub target: Target,
@@ -60,6 +61,33 @@ pub enum Sanitizer {
     Thread,
 }
 
+/// Which borrow checker to run, chosen with `-Z borrowck`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BorrowckMode {
+    /// Only the borrow checker working on the HIR.
+    Ast,
+    /// Only the borrow checker working on the MIR.
+    Mir,
+    /// Both, with each error saying which one reported it.
+    Compare,
+}
+
+impl BorrowckMode {
+    pub fn use_ast(self) -> bool {
+        match self {
+            BorrowckMode::Ast | BorrowckMode::Compare => true,
+            BorrowckMode::Mir => false,
+        }
+    }
+
+    pub fn use_mir(self) -> bool {
+        match self {
+            BorrowckMode::Mir | BorrowckMode::Compare => true,
+            BorrowckMode::Ast => false,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Hash)]
 pub enum OptLevel {
     No, // -O0
@@ -135,6 +163,8 @@ impl OutputType {
 pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
     Json,
+    Sarif,
+    Checkstyle,
 }
 
 impl Default for ErrorOutputType {
@@ -658,6 +688,8 @@ macro_rules! options {
             Some("one of: `full`, `partial`, or `off`");
         pub const parse_sanitizer: Option<&'static str> =
             Some("one of: `address`, `leak`, `memory` or `thread`");
+        pub const parse_borrowck_mode: Option<&'static str> =
+            Some("one of: `ast`, `mir`, or `compare`");
         pub const parse_linker_flavor: Option<&'static str> =
             Some(::rustc_back::LinkerFlavor::one_of());
         pub const parse_optimization_fuel: Option<&'static str> =
@@ -666,7 +698,7 @@ macro_rules! options {
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer, BorrowckMode};
         use rustc_back::{LinkerFlavor, PanicStrategy, RelroLevel};
 
         $(
@@ -801,6 +833,16 @@ macro_rules! options {
             true
         }
 
+        fn parse_borrowck_mode(slot: &mut BorrowckMode, v: Option<&str>) -> bool {
+            match v {
+                Some("ast") => *slot = BorrowckMode::Ast,
+                Some("mir") => *slot = BorrowckMode::Mir,
+                Some("compare") => *slot = BorrowckMode::Compare,
+                _ => return false,
+            }
+            true
+        }
+
         fn parse_sanitizer(slote: &mut Option<Sanitizer>, v: Option<&str>) -> bool {
             match v {
                 Some("address") => *slote = Some(Sanitizer::Address),
@@ -981,6 +1023,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "treat all errors that occur as bugs"),
     continue_parse_after_error: bool = (false, parse_bool, [TRACKED],
           "attempt to recover from parse errors (experimental)"),
+    diagnostic_summary: bool = (false, parse_bool, [UNTRACKED],
+          "only show the first of a group of repeated diagnostics and summarize the rest"),
+    apply_suggestions: bool = (false, parse_bool, [UNTRACKED],
+          "rewrite source files with machine-applicable suggestions and recompile until \
+           none are left"),
     incremental: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "enable incremental compilation (experimental)"),
     incremental_cc: bool = (true, parse_bool, [UNTRACKED],
@@ -1027,6 +1074,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "if set, exclude the pass number when dumping MIR (used in tests)"),
     perf_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some performance-related statistics"),
+    self_profile: bool = (false, parse_bool, [UNTRACKED],
+          "record the time spent in queries and compiler phases, write it out as a Chrome \
+           trace and print a summary of the slowest queries"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some statistics about AST and HIR"),
     mir_stats: bool = (false, parse_bool, [UNTRACKED],
@@ -1055,10 +1105,21 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "extra arguments to prepend to the linker invocation (space separated)"),
     profile: bool = (false, parse_bool, [TRACKED],
                      "insert profiling code"),
+    pgo_gen: Option<String> = (None, parse_opt_string, [TRACKED],
+        "generate PGO profile data, to a given file, or to the default \
+         location if it's empty"),
+    pgo_use: Option<String> = (None, parse_opt_string, [TRACKED],
+        "use PGO profile data from the given file to optimize the crate"),
     relro_level: Option<RelroLevel> = (None, parse_relro_level, [TRACKED],
         "choose which RELRO level to use"),
+    borrowck: BorrowckMode = (BorrowckMode::Ast, parse_borrowck_mode, [UNTRACKED],
+        "select which borrow checker to run (`ast`, `mir`, or `compare` to run both)"),
     nll: bool = (false, parse_bool, [UNTRACKED],
-                 "run the non-lexical lifetimes MIR pass"),
+                 "make the MIR borrow checker use non-lexical lifetimes"),
+    threads: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "number of threads to evaluate item-level queries such as type-checking on"),
+    thinlto: bool = (false, parse_bool, [TRACKED],
+        "perform ThinLTO across codegen units, and across upstream crates with `-C lto`"),
 }
 
 pub fn default_lib_output() -> CrateType {
@@ -1320,7 +1381,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
         opt::multi("Z", "", "Set internal debugging options", "FLAG"),
         opt::opt_s("", "error-format",
                       "How errors and other messages are produced",
-                      "human|json"),
+                      "human|json|sarif|checkstyle"),
         opt::opt_s("", "color", "Configure coloring of output:
                                  auto   = colorize, if output goes to a tty (default);
                                  always = always colorize output;
@@ -1389,13 +1450,25 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         match matches.opt_str("error-format").as_ref().map(|s| &s[..]) {
             Some("human")   => ErrorOutputType::HumanReadable(color),
             Some("json") => ErrorOutputType::Json,
+            Some("sarif") if nightly_options::is_unstable_enabled(matches) => {
+                ErrorOutputType::Sarif
+            }
+            Some("checkstyle") if nightly_options::is_unstable_enabled(matches) => {
+                ErrorOutputType::Checkstyle
+            }
+            Some(arg @ "sarif") | Some(arg @ "checkstyle") => {
+                early_error(ErrorOutputType::HumanReadable(color),
+                            &format!("the `-Z unstable-options` flag must also be passed to \
+                                      enable the `{}` error format",
+                                     arg))
+            }
 
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => {
                 early_error(ErrorOutputType::HumanReadable(color),
-                            &format!("argument for --error-format must be human or json (instead \
-                                      was `{}`)",
+                            &format!("argument for --error-format must be human, json, sarif \
+                                      or checkstyle (instead was `{}`)",
                                      arg))
             }
         }
@@ -1428,6 +1501,28 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
 
     let debugging_opts = build_debugging_options(matches, error_format);
 
+    if debugging_opts.pgo_gen.is_some() && debugging_opts.pgo_use.is_some() {
+        early_error(error_format, "options `-Z pgo-gen` and `-Z pgo-use` are exclusive");
+    }
+
+    if let Some(ref path) = debugging_opts.pgo_use {
+        if !Path::new(path).exists() {
+            early_error(error_format, &format!("profile `{}` passed to `-Z pgo-use` \
+                                                does not exist", path));
+        }
+    }
+
+    match debugging_opts.threads {
+        Some(0) => {
+            early_error(error_format, "Value for threads must be a positive nonzero integer");
+        }
+        Some(n) if n > 1 && !sync::is_parallel() => {
+            early_warn(error_format, "`-Z threads` greater than 1 has no effect unless the \
+                                      compiler is built with parallel queries");
+        }
+        _ => {}
+    }
+
     let mut output_types = BTreeMap::new();
     if !debugging_opts.parse_only {
         for list in matches.opt_strs("emit") {
@@ -2508,8 +2603,18 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.borrowck = super::BorrowckMode::Compare;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.threads = Some(4);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.self_profile = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.debug_llvm = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.apply_suggestions = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.diagnostic_summary = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.print_link_args = true;
@@ -2609,5 +2714,9 @@ mod tests {
         opts = reference.clone();
         opts.debugging_opts.relro_level = Some(RelroLevel::Full);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.thinlto = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 }
@@ -23,10 +23,13 @@ use session::config::DebugInfoLevel;
 use ty::tls;
 use util::nodemap::{FxHashMap, FxHashSet};
 use util::common::{duration_to_secs_str, ErrorReported};
+use util::profiling::SelfProfiler;
 
 use syntax::ast::NodeId;
 use errors::{self, DiagnosticBuilder};
 use errors::emitter::{Emitter, EmitterWriter};
+use errors::checkstyle::CheckstyleEmitter;
+use errors::sarif::SarifEmitter;
 use syntax::json::JsonEmitter;
 use syntax::feature_gate;
 use syntax::parse;
@@ -39,6 +42,7 @@ use syntax_pos::{Span, MultiSpan};
 use rustc_back::{LinkerFlavor, PanicStrategy};
 use rustc_back::target::Target;
 use rustc_data_structures::flock;
+use rustc_data_structures::sync::Lock;
 use jobserver::Client;
 
 use std::cell::{self, Cell, RefCell};
@@ -80,6 +84,8 @@ pub struct Session {
     pub working_dir: (String, bool),
     pub lint_store: RefCell<lint::LintStore>,
     pub lints: RefCell<lint::LintTable>,
+    /// The number of lints added to `lints` so far.
+    lint_count: Cell<usize>,
     /// Set of (LintId, Option<Span>, message) tuples tracking lint
     /// (sub)diagnostics that have been set once, but should not be set again,
     /// in order to avoid redundantly verbose output (Issue #24690).
@@ -123,6 +129,9 @@ pub struct Session {
     /// Data about code being compiled, gathered during compilation.
     pub code_stats: RefCell<CodeStats>,
 
+    /// The events recorded by `-Z self-profile`.
+    pub self_profiling: Lock<SelfProfiler>,
+
     next_node_id: Cell<ast::NodeId>,
 
     /// If -zfuel=crate=n is specified, Some(crate).
@@ -314,6 +323,7 @@ impl Session {
                                         msg: String)
     {
         self.lints.borrow_mut().add_lint(lint, id, sp, msg);
+        self.lint_count.set(self.lint_count.get() + 1);
     }
 
     pub fn add_lint_diagnostic<M>(&self,
@@ -323,6 +333,14 @@ impl Session {
         where M: lint::IntoEarlyLint,
     {
         self.lints.borrow_mut().add_lint_diagnostic(lint, id, msg);
+        self.lint_count.set(self.lint_count.get() + 1);
+    }
+
+    /// The number of diagnostics emitted or lints buffered so far. A query
+    /// that changes this can't be reused in the next session without
+    /// losing its diagnostics.
+    pub fn diagnostic_count(&self) -> usize {
+        self.diagnostic().emitted_diagnostic_count() + self.lint_count.get()
     }
 
     pub fn reserve_node_ids(&self, count: usize) -> ast::NodeId {
@@ -363,9 +381,11 @@ impl Session {
         };
 
         match self.opts.error_format {
-            // when outputting JSON for tool consumption, the tool might want
-            // the duplicates
-            config::ErrorOutputType::Json => {
+            // when outputting JSON, SARIF or Checkstyle for tool consumption,
+            // the tool might want the duplicates
+            config::ErrorOutputType::Json |
+            config::ErrorOutputType::Sarif |
+            config::ErrorOutputType::Checkstyle => {
                 do_method()
             },
             _ => {
@@ -410,9 +430,21 @@ impl Session {
     pub fn print_llvm_passes(&self) -> bool {
         self.opts.debugging_opts.print_llvm_passes
     }
+    /// The number of threads item-level queries are evaluated on.
+    pub fn threads(&self) -> usize {
+        self.opts.debugging_opts.threads.unwrap_or(1)
+    }
     pub fn lto(&self) -> bool {
         self.opts.cg.lto
     }
+    /// Returns whether ThinLTO is to be performed. This needs either several
+    /// codegen units or `-C lto` to have anything to work on, and isn't
+    /// supported together with incremental compilation.
+    pub fn thinlto(&self) -> bool {
+        self.opts.debugging_opts.thinlto &&
+            (self.lto() || self.opts.cg.codegen_units > 1) &&
+            self.opts.incremental.is_none()
+    }
     /// Returns the panic strategy for this compile session. If the user explicitly selected one
     /// using '-C panic', use that, otherwise use the panic strategy defined by the target.
     pub fn panic_strategy(&self) -> PanicStrategy {
@@ -562,6 +594,16 @@ impl Session {
                  duration_to_secs_str(self.perf_stats.decode_def_path_tables_time.get()));
     }
 
+    /// Records an event with the self-profiler if `-Z self-profile` is
+    /// enabled.
+    #[inline]
+    pub fn profiler<F: FnOnce(&mut SelfProfiler)>(&self, f: F) {
+        if self.opts.debugging_opts.self_profile {
+            let mut profiler = self.self_profiling.lock();
+            f(&mut profiler)
+        }
+    }
+
     /// We want to know if we're allowed to do an optimization for crate foo from -z fuel=foo=n.
     /// This expends fuel if applicable, and records fuel if applicable.
     pub fn consider_optimizing<T: Fn() -> String>(&self, crate_name: &str, msg: T) -> bool {
@@ -640,12 +682,30 @@ pub fn build_session_with_codemap(sopts: config::Options,
         (config::ErrorOutputType::Json, Some(dst)) => {
             Box::new(JsonEmitter::new(dst, Some(registry), codemap.clone()))
         }
+        (config::ErrorOutputType::Sarif, None) => {
+            Box::new(SarifEmitter::stderr(Some(registry), Some(codemap.clone())))
+        }
+        (config::ErrorOutputType::Sarif, Some(dst)) => {
+            Box::new(SarifEmitter::new(dst, Some(registry), Some(codemap.clone())))
+        }
+        (config::ErrorOutputType::Checkstyle, None) => {
+            Box::new(CheckstyleEmitter::stderr(Some(codemap.clone())))
+        }
+        (config::ErrorOutputType::Checkstyle, Some(dst)) => {
+            Box::new(CheckstyleEmitter::new(dst, Some(codemap.clone())))
+        }
     };
 
     let diagnostic_handler =
         errors::Handler::with_emitter(can_print_warnings,
                                       treat_err_as_bug,
                                       emitter);
+    if sopts.debugging_opts.apply_suggestions {
+        diagnostic_handler.collect_suggestions();
+    }
+    if sopts.debugging_opts.diagnostic_summary {
+        diagnostic_handler.enable_diagnostic_summary(codemap.clone());
+    }
 
     build_session_(sopts,
                    dep_graph,
@@ -709,6 +769,7 @@ pub fn build_session_(sopts: config::Options,
         working_dir,
         lint_store: RefCell::new(lint::LintStore::new()),
         lints: RefCell::new(lint::LintTable::new()),
+        lint_count: Cell::new(0),
         one_time_diagnostics: RefCell::new(FxHashSet()),
         plugin_llvm_passes: RefCell::new(Vec::new()),
         plugin_attributes: RefCell::new(Vec::new()),
@@ -733,6 +794,7 @@ pub fn build_session_(sopts: config::Options,
             decode_def_path_tables_time: Cell::new(Duration::from_secs(0)),
         },
         code_stats: RefCell::new(CodeStats::new()),
+        self_profiling: Lock::new(SelfProfiler::new()),
         optimization_fuel_crate,
         optimization_fuel_limit,
         print_fuel_crate,
@@ -793,6 +855,8 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
                                            None))
         }
         config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::stderr(None, None)),
+        config::ErrorOutputType::Checkstyle => Box::new(CheckstyleEmitter::stderr(None)),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -806,6 +870,10 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
                                            None))
         }
         config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        // Early warnings are written out with the rest of the session's
+        // diagnostics, so that there is a single log.
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::early()),
+        config::ErrorOutputType::Checkstyle => Box::new(CheckstyleEmitter::early()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
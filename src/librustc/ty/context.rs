@@ -39,6 +39,7 @@ use ty::TypeVariants::*;
 use ty::layout::{Layout, TargetDataLayout};
 use ty::inhabitedness::DefIdForest;
 use ty::maps;
+use ty::on_disk_cache;
 use ty::steal::Steal;
 use ty::BindingMode;
 use util::nodemap::{NodeMap, NodeSet, DefIdSet};
@@ -472,6 +473,10 @@ pub struct GlobalCtxt<'tcx> {
 
     pub maps: maps::Maps<'tcx>,
 
+    /// Query results saved by the previous incremental compilation session,
+    /// see `ty::on_disk_cache`.
+    pub on_disk_query_result_cache: on_disk_cache::OnDiskCache,
+
     pub mir_passes: Rc<Passes>,
 
     // Records the free variables refrenced by every closure
@@ -719,6 +724,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             hir,
             def_path_hash_to_def_id,
             maps: maps::Maps::new(providers),
+            on_disk_query_result_cache: on_disk_cache::OnDiskCache::new_empty(),
             mir_passes,
             freevars: RefCell::new(resolutions.freevars),
             maybe_unused_trait_imports: resolutions.maybe_unused_trait_imports,
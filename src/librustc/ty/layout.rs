@@ -592,6 +592,54 @@ enum StructKind {
     EnumVariant,
 }
 
+/// A leaf field which cannot hold every value of its integer type, found
+/// while looking for a place to store the discriminant of an enum.
+struct Niche {
+    /// The path to the field, in memory order, innermost field first.
+    path: FieldPath,
+    /// Like `path`, but in source order.
+    source_path: FieldPath,
+    value: Integer,
+    /// Inclusive range of the valid values of the field, which wraps
+    /// around if `valid_start > valid_end`.
+    valid_start: u64,
+    valid_end: u64,
+}
+
+impl Niche {
+    fn new(value: Integer, valid_start: u64, valid_end: u64) -> Option<Niche> {
+        let bits = value.size().bits();
+        if bits > 64 {
+            return None;
+        }
+        let mask = !0u64 >> (64 - bits);
+        Some(Niche {
+            path: vec![],
+            source_path: vec![],
+            value,
+            valid_start: valid_start & mask,
+            valid_end: valid_end & mask,
+        })
+    }
+
+    fn mask(&self) -> u64 {
+        !0u64 >> (64 - self.value.size().bits())
+    }
+
+    /// The number of invalid values of the field.
+    fn available(&self) -> u64 {
+        self.valid_start.wrapping_sub(self.valid_end).wrapping_sub(1) & self.mask()
+    }
+
+    /// Uses `count` invalid values, returning the first of them.
+    fn reserve(&mut self, count: u64) -> u64 {
+        assert!(count <= self.available());
+        let start = self.valid_end.wrapping_add(1) & self.mask();
+        self.valid_end = self.valid_end.wrapping_add(count) & self.mask();
+        start
+    }
+}
+
 impl<'a, 'tcx> Struct {
     fn new(dl: &TargetDataLayout,
            fields: &Vec<&'a Layout>,
@@ -884,6 +932,106 @@ impl<'a, 'tcx> Struct {
         Ok(None)
     }
 
+    /// Find the path leading to a leaf field with at least `count` invalid
+    /// values, starting from the given type and recursing through aggregates
+    /// and enums which already store their discriminant in such a field.
+    /// Fields of packed structs are skipped, as they may be misaligned.
+    fn niche_field_in_type(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                           param_env: ty::ParamEnv<'tcx>,
+                           ty: Ty<'tcx>,
+                           count: u64)
+                           -> Result<Option<Niche>, LayoutError<'tcx>> {
+        let niche = match (ty.layout(tcx, param_env)?, &ty.sty) {
+            (_, &ty::TyBool) => Niche::new(I8, 0, 1),
+            (_, &ty::TyChar) => Niche::new(I32, 0, 0x10FFFF),
+            (&CEnum { discr, min, max, .. }, _) => Niche::new(discr, min, max),
+
+            // Is this an enum already using a niche, with room to spare?
+            (&NicheFilling { niche, niche_valid: (start, end),
+                             ref niche_field, ref niche_field_source, .. }, _) => {
+                Niche::new(niche, start, end).map(|mut niche| {
+                    // Skip the 0 for GEP and go back to innermost first.
+                    niche.path = niche_field[1..].iter().rev().cloned().collect();
+                    niche.source_path = niche_field_source[1..].iter().rev().cloned().collect();
+                    niche
+                })
+            }
+
+            (&Univariant { ref variant, .. }, &ty::TyAdt(def, substs))
+                    if def.is_struct() && !variant.packed => {
+                return Struct::niche_field_paths(
+                    tcx,
+                    param_env,
+                    def.struct_variant().fields.iter().map(|field| {
+                        field.ty(tcx, substs)
+                    }),
+                    Some(&variant.memory_index[..]),
+                    count);
+            }
+            (&Univariant { ref variant, .. }, &ty::TyClosure(def, substs)) => {
+                return Struct::niche_field_paths(
+                    tcx,
+                    param_env,
+                    substs.upvar_tys(def, tcx),
+                    Some(&variant.memory_index[..]),
+                    count);
+            }
+            (&Univariant { ref variant, .. }, &ty::TyTuple(tys, _)) => {
+                return Struct::niche_field_paths(
+                    tcx,
+                    param_env,
+                    tys.iter().cloned(),
+                    Some(&variant.memory_index[..]),
+                    count);
+            }
+            (_, &ty::TyArray(ety, d)) if d > 0 => {
+                return Struct::niche_field_paths(
+                    tcx,
+                    param_env,
+                    Some(ety).into_iter(),
+                    None,
+                    count);
+            }
+
+            (_, &ty::TyProjection(_)) | (_, &ty::TyAnon(..)) => {
+                let normalized = tcx.normalize_associated_type_in_env(&ty, param_env);
+                if ty == normalized {
+                    return Ok(None);
+                }
+                return Struct::niche_field_in_type(tcx, param_env, normalized, count);
+            }
+
+            _ => None
+        };
+        Ok(niche.and_then(|niche| if niche.available() >= count { Some(niche) } else { None }))
+    }
+
+    /// Find the path leading to a leaf field with at least `count` invalid
+    /// values, starting from the given set of fields and recursing through
+    /// aggregates. The paths are translated to memory order like in
+    /// `non_zero_field_paths`.
+    fn niche_field_paths<I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            param_env: ty::ParamEnv<'tcx>,
+                            fields: I,
+                            permutation: Option<&[u32]>,
+                            count: u64)
+                            -> Result<Option<Niche>, LayoutError<'tcx>>
+    where I: Iterator<Item=Ty<'tcx>> {
+        for (i, ty) in fields.enumerate() {
+            if let Some(mut niche) = Struct::niche_field_in_type(tcx, param_env, ty, count)? {
+                niche.source_path.push(i as u32);
+                let index = if let Some(p) = permutation {
+                    p[i] as usize
+                } else {
+                    i
+                };
+                niche.path.push(index as u32);
+                return Ok(Some(niche));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn over_align(&self) -> Option<u32> {
         let align = self.align.abi();
         let primitive_align = self.primitive_align.abi();
@@ -1088,6 +1236,34 @@ pub enum Layout {
         discrfield: FieldPath,
         // Like discrfield, but in source order. For debuginfo.
         discrfield_source: FieldPath
+    },
+
+    /// Two or more cases, where all cases but `dataful` are zero sized.
+    /// The case with discriminant `dataful` is represented by the struct
+    /// `variant`, where the `niche_field`th field cannot hold every value
+    /// of its integer type `niche` (e.g. a `bool`, `char` or C-like enum).
+    /// The other cases, with discriminants in the inclusive range
+    /// `niche_variants`, are encoded using those invalid values: the case
+    /// `niche_variants.0 + i` is represented by the field holding the value
+    /// `niche_start + i` (wrapping around), any other value means `dataful`.
+    ///
+    /// For example, `Option<bool>` is a single byte, which is 2 for `None`,
+    /// and `Option<Option<bool>>` another enum of that kind, where 3 is the
+    /// outer `None`.
+    NicheFilling {
+        dataful: u64,
+        niche_variants: (u64, u64),
+        niche: Integer,
+        niche_start: u64,
+        // Inclusive range of the values the niche field can hold, including
+        // the ones used for the other cases. If start > end, it represents
+        // start...max followed by 0...end, like in CEnum.
+        niche_valid: (u64, u64),
+        variant: Struct,
+        // N.B. There is a 0 at the start, for LLVM GEP through a pointer.
+        niche_field: FieldPath,
+        // Like niche_field, but in source order. For debuginfo.
+        niche_field_source: FieldPath
     }
 }
 
@@ -1409,6 +1585,65 @@ impl<'a, 'tcx> Layout {
                     }
                 }
 
+                // Niche-filling enum optimization: if all variants but one
+                // are zero-sized, try to encode them in the invalid values
+                // of one of the fields of the remaining one.
+                if !def.repr.inhibit_enum_layout_opt() {
+                    let mut dataful = None;
+                    for (i, fields) in variants.iter().enumerate() {
+                        let field_layouts = fields.iter().map(|ty| ty.layout(tcx, param_env));
+                        if !Struct::would_be_zero_sized(dl, field_layouts)? {
+                            if dataful.is_some() {
+                                dataful = None;
+                                break;
+                            }
+                            dataful = Some(i);
+                        }
+                    }
+
+                    if let Some(dataful) = dataful {
+                        let last = variants.len() - 1;
+                        let niche_variants = (if dataful == 0 { 1 } else { 0 },
+                                              if dataful == last { last - 1 } else { last });
+                        let count = (niche_variants.1 - niche_variants.0 + 1) as u64;
+                        let niche = Struct::niche_field_paths(tcx,
+                                                              param_env,
+                                                              variants[dataful].iter().cloned(),
+                                                              None,
+                                                              count)?;
+                        if let Some(mut niche) = niche {
+                            let st = Struct::new(dl,
+                                &variants[dataful].iter().map(|ty| ty.layout(tcx, param_env))
+                                  .collect::<Result<Vec<_>, _>>()?,
+                                &def.repr, StructKind::AlwaysSizedUnivariant, ty)?;
+
+                            let niche_start = niche.reserve(count);
+
+                            // As above, fix the last element of the path.
+                            let Niche { mut path, mut source_path, .. } = niche;
+                            let mut i = *path.last().unwrap();
+                            i = st.memory_index[i as usize];
+                            *path.last_mut().unwrap() = i;
+                            path.push(0); // For GEP through a pointer.
+                            path.reverse();
+                            source_path.push(0);
+                            source_path.reverse();
+
+                            return success(NicheFilling {
+                                dataful: dataful as u64,
+                                niche_variants: (niche_variants.0 as u64,
+                                                 niche_variants.1 as u64),
+                                niche: niche.value,
+                                niche_start,
+                                niche_valid: (niche.valid_start, niche.valid_end),
+                                variant: st,
+                                niche_field: path,
+                                niche_field_source: source_path
+                            });
+                        }
+                    }
+                }
+
                 // The general case.
                 let discr_max = (variants.len() - 1) as i64;
                 assert!(discr_max >= 0);
@@ -1541,7 +1776,8 @@ impl<'a, 'tcx> Layout {
             Scalar {..} | Vector {..} | FatPointer {..} |
             CEnum {..} | UntaggedUnion {..} | General {..} |
             RawNullablePointer {..} |
-            StructWrappedNullablePointer {..} => false,
+            StructWrappedNullablePointer {..} |
+            NicheFilling {..} => false,
 
             Array { sized, .. } |
             Univariant { variant: Struct { sized, .. }, .. } => !sized
@@ -1586,7 +1822,8 @@ impl<'a, 'tcx> Layout {
             UntaggedUnion { ref variants } => variants.stride(),
 
             Univariant { ref variant, .. } |
-            StructWrappedNullablePointer { nonnull: ref variant, .. } => {
+            StructWrappedNullablePointer { nonnull: ref variant, .. } |
+            NicheFilling { ref variant, .. } => {
                 variant.stride()
             }
         }
@@ -1628,7 +1865,8 @@ impl<'a, 'tcx> Layout {
             UntaggedUnion { ref variants } => variants.align,
 
             Univariant { ref variant, .. } |
-            StructWrappedNullablePointer { nonnull: ref variant, .. } => {
+            StructWrappedNullablePointer { nonnull: ref variant, .. } |
+            NicheFilling { ref variant, .. } => {
                 variant.align
             }
         }
@@ -1639,7 +1877,8 @@ impl<'a, 'tcx> Layout {
         match *self {
             Array { primitive_align, .. } | General { primitive_align, .. } => primitive_align,
             Univariant { ref variant, .. } |
-            StructWrappedNullablePointer { nonnull: ref variant, .. } => {
+            StructWrappedNullablePointer { nonnull: ref variant, .. } |
+            NicheFilling { ref variant, .. } => {
                 variant.primitive_align
             },
 
@@ -1710,6 +1949,14 @@ impl<'a, 'tcx> Layout {
                     Size::from_bytes(0)
                 }
             }
+
+            NicheFilling { dataful, ref variant, .. } => {
+                if Some(dataful as usize) == variant_index {
+                    variant.offsets[i]
+                } else {
+                    Size::from_bytes(0)
+                }
+            }
         }
     }
 
@@ -1854,6 +2101,39 @@ impl<'a, 'tcx> Layout {
                 record(adt_kind.into(), None,
                        vec![build_primitive_info(variant_def.name, &value)]);
             }
+            Layout::NicheFilling { dataful, variant: ref variant_layout, .. } => {
+                debug!("print-type-size t: `{:?}` adt niche-filling dataful {} is {:?}",
+                       ty, dataful, variant_layout);
+                let variant_infos: Vec<_> =
+                    adt_def.variants.iter()
+                                    .enumerate()
+                                    .map(|(i, variant_def)| {
+                                        let fields: Vec<_> =
+                                            variant_def.fields
+                                                       .iter()
+                                                       .map(|f| (f.name, f.ty(tcx, substs)))
+                                                       .collect();
+                                        if i == dataful as usize {
+                                            build_variant_info(Some(variant_def.name),
+                                                               &fields,
+                                                               Fields::NoDiscrim(variant_layout))
+                                        } else {
+                                            // The other variants are zero-sized.
+                                            let zero = Size::from_bytes(0);
+                                            session::VariantInfo {
+                                                name: Some(variant_def.name.to_string()),
+                                                kind: session::SizeKind::Exact,
+                                                align: 1,
+                                                size: 0,
+                                                fields: fields.into_iter().map(|field| {
+                                                    build_field_info(field, &zero)
+                                                }).collect(),
+                                            }
+                                        }
+                                    })
+                                    .collect();
+                record(adt_kind.into(), None, variant_infos);
+            }
             Layout::Univariant { variant: ref variant_layout, non_zero: _ } => {
                 let variant_names = || {
                     adt_def.variants.iter().map(|v|format!("{}", v.name)).collect::<Vec<_>>()
@@ -2178,7 +2458,8 @@ impl<'a, 'tcx> TyLayout<'tcx> {
             General { .. } |
             UntaggedUnion { .. } |
             RawNullablePointer { .. } |
-            StructWrappedNullablePointer { .. } => bug!(),
+            StructWrappedNullablePointer { .. } |
+            NicheFilling { .. } => bug!(),
 
             FatPointer { .. } => 2,
 
@@ -8,10 +8,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use dep_graph::{DepConstructor, DepNode, DepNodeIndex};
+use dep_graph::{DepConstructor, DepKind, DepNode, DepNodeIndex, SerializedDepNodeIndex};
 use hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefId, LOCAL_CRATE};
 use hir::def::Def;
 use hir;
+use ich::{Fingerprint, StableHashingContext};
 use middle::const_val;
 use middle::cstore::{ExternCrate, LinkagePreference};
 use middle::privacy::AccessLevels;
@@ -26,18 +27,24 @@ use ty::item_path;
 use ty::steal::Steal;
 use ty::subst::Substs;
 use ty::fast_reject::SimplifiedType;
+use ty::on_disk_cache::CacheEncoder;
 use util::nodemap::{DefIdSet, NodeSet};
+use util::profiling::ProviderKind;
 
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc_data_structures::fx::FxHashMap;
-use std::cell::{RefCell, RefMut};
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_data_structures::sync::{Lock, Lrc, RwLock, Sharded};
+use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
-use std::mem;
 use std::collections::BTreeMap;
 use std::ops::Deref;
+use std::ptr;
 use std::rc::Rc;
+#[cfg(parallel_queries)]
+use std::thread::{self, ThreadId};
 use syntax_pos::{Span, DUMMY_SP};
 use syntax::attr;
 use syntax::ast;
@@ -184,32 +191,184 @@ impl<'tcx> Value<'tcx> for ty::SymbolName {
     }
 }
 
-struct QueryMap<D: QueryDescription> {
+/// Computes the fingerprint of a query result, used to find out whether
+/// it changed since the previous session. Results whose type cannot be
+/// hashed have no fingerprint and are always considered changed.
+trait ResultFingerprint<'a, 'gcx: 'a+'tcx, 'tcx: 'a> {
+    fn result_fingerprint(&self,
+                          hcx: &mut StableHashingContext<'a, 'gcx, 'tcx>)
+                          -> Option<Fingerprint>;
+}
+
+impl<'a, 'gcx, 'tcx, T> ResultFingerprint<'a, 'gcx, 'tcx> for T {
+    default fn result_fingerprint(&self,
+                                  _: &mut StableHashingContext<'a, 'gcx, 'tcx>)
+                                  -> Option<Fingerprint> {
+        None
+    }
+}
+
+impl<'a, 'gcx, 'tcx, T> ResultFingerprint<'a, 'gcx, 'tcx> for T
+    where T: HashStable<StableHashingContext<'a, 'gcx, 'tcx>>
+{
+    fn result_fingerprint(&self,
+                          hcx: &mut StableHashingContext<'a, 'gcx, 'tcx>)
+                          -> Option<Fingerprint> {
+        let mut hasher = StableHasher::new();
+        self.hash_stable(hcx, &mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+struct QueryMap<'tcx, D: QueryDescription<'tcx>> {
     phantom: PhantomData<D>,
     map: FxHashMap<D::Key, (D::Value, DepNodeIndex)>,
+
+    /// The queries that are being computed right now, on this thread or
+    /// another one.
+    active: FxHashMap<D::Key, Lrc<QueryJob<'tcx>>>,
 }
 
-impl<M: QueryDescription> QueryMap<M> {
-    fn new() -> QueryMap<M> {
+impl<'tcx, M: QueryDescription<'tcx>> QueryMap<'tcx, M> {
+    fn new() -> QueryMap<'tcx, M> {
         QueryMap {
             phantom: PhantomData,
             map: FxHashMap(),
+            active: FxHashMap(),
+        }
+    }
+}
+
+/// A query whose computation has started but not finished yet.
+///
+/// The jobs form a tree: the parent of a job is the query that requested it,
+/// so following the parents of the job a thread is currently working on
+/// gives its query stack. The parent can live on another thread when a
+/// query hands out work to several threads, see `TyCtxt::par_body_owners`.
+pub struct QueryJob<'tcx> {
+    info: (Span, Query<'tcx>),
+    parent: Option<Lrc<QueryJob<'tcx>>>,
+
+    /// The thread computing this query.
+    #[cfg(parallel_queries)]
+    thread: ThreadId,
+
+    /// Released once the query is complete, for other threads waiting on it.
+    #[cfg(parallel_queries)]
+    latch: QueryLatch,
+}
+
+impl<'tcx> QueryJob<'tcx> {
+    #[cfg(not(parallel_queries))]
+    fn new(info: (Span, Query<'tcx>), parent: Option<Lrc<QueryJob<'tcx>>>) -> QueryJob<'tcx> {
+        QueryJob { info, parent }
+    }
+
+    #[cfg(parallel_queries)]
+    fn new(info: (Span, Query<'tcx>), parent: Option<Lrc<QueryJob<'tcx>>>) -> QueryJob<'tcx> {
+        QueryJob {
+            info,
+            parent,
+            thread: thread::current().id(),
+            latch: QueryLatch::new(),
         }
     }
+
+    /// The queries from this one down to `leaf`, if computing this query is
+    /// what led to `leaf`.
+    fn path_to(&self, leaf: &Lrc<QueryJob<'tcx>>) -> Option<Vec<(Span, Query<'tcx>)>> {
+        let mut path = vec![];
+        let mut current = Some(leaf);
+        while let Some(job) = current {
+            path.push(job.info);
+            if ptr::eq(&**job, self) {
+                path.reverse();
+                return Some(path);
+            }
+            current = job.parent.as_ref();
+        }
+        None
+    }
+
+    #[cfg(parallel_queries)]
+    fn wait(&self) {
+        self.latch.wait();
+    }
+
+    #[cfg(parallel_queries)]
+    fn complete(&self) {
+        self.latch.set();
+    }
+
+    #[cfg(not(parallel_queries))]
+    fn complete(&self) {
+    }
+}
+
+#[cfg(parallel_queries)]
+struct QueryLatch {
+    complete: ::std::sync::Mutex<bool>,
+    cond: ::std::sync::Condvar,
 }
 
-pub struct CycleError<'a, 'tcx: 'a> {
+#[cfg(parallel_queries)]
+impl QueryLatch {
+    fn new() -> QueryLatch {
+        QueryLatch {
+            complete: ::std::sync::Mutex::new(false),
+            cond: ::std::sync::Condvar::new(),
+        }
+    }
+
+    fn wait(&self) {
+        let mut complete = self.complete.lock().unwrap();
+        while !*complete {
+            complete = self.cond.wait(complete).unwrap();
+        }
+    }
+
+    fn set(&self) {
+        *self.complete.lock().unwrap() = true;
+        self.cond.notify_all();
+    }
+}
+
+/// Owns the `QueryJob` of a query computed by the current thread. Dropping
+/// it marks the query as no longer active, whether it completed or is being
+/// unwound from, and wakes up the threads waiting on it.
+struct JobOwner<'a, 'tcx: 'a, D: QueryDescription<'tcx> + 'a> {
+    maps: &'a Maps<'tcx>,
+    shard: &'a RwLock<QueryMap<'tcx, D>>,
+    key: D::Key,
+    job: Lrc<QueryJob<'tcx>>,
+}
+
+impl<'a, 'tcx, D: QueryDescription<'tcx>> JobOwner<'a, 'tcx, D> {
+    fn new(maps: &'a Maps<'tcx>,
+           shard: &'a RwLock<QueryMap<'tcx, D>>,
+           key: D::Key,
+           job: Lrc<QueryJob<'tcx>>)
+           -> JobOwner<'a, 'tcx, D> {
+        maps.current_jobs.set(Some(job.clone()));
+        JobOwner { maps, shard, key, job }
+    }
+}
+
+impl<'a, 'tcx, D: QueryDescription<'tcx>> Drop for JobOwner<'a, 'tcx, D> {
+    fn drop(&mut self) {
+        self.maps.current_jobs.set(self.job.parent.clone());
+        self.shard.write().active.remove(&self.key);
+        self.job.complete();
+    }
+}
+
+pub struct CycleError<'tcx> {
     span: Span,
-    cycle: RefMut<'a, [(Span, Query<'tcx>)]>,
+    cycle: Vec<(Span, Query<'tcx>)>,
 }
 
 impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
-    pub fn report_cycle(self, CycleError { span, cycle }: CycleError) {
-        // Subtle: release the refcell lock before invoking `describe()`
-        // below by dropping `cycle`.
-        let stack = cycle.to_vec();
-        mem::drop(cycle);
-
+    pub fn report_cycle(self, CycleError { span, cycle: stack }: CycleError) {
         assert!(!stack.is_empty());
 
         // Disable naming impls with types in this path, since that
@@ -236,28 +395,144 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             err.emit();
         });
     }
+}
 
-    fn cycle_check<F, R>(self, span: Span, query: Query<'gcx>, compute: F)
-                         -> Result<R, CycleError<'a, 'gcx>>
+/// The query each thread is computing, if any. Without `parallel_queries`,
+/// queries are only ever computed on one thread.
+#[cfg(not(parallel_queries))]
+struct CurrentJobs<'tcx> {
+    job: Lock<Option<Lrc<QueryJob<'tcx>>>>,
+}
+
+#[cfg(parallel_queries)]
+struct CurrentJobs<'tcx> {
+    jobs: Lock<FxHashMap<ThreadId, Lrc<QueryJob<'tcx>>>>,
+
+    /// The query each blocked thread is waiting on another thread to
+    /// complete.
+    waiting_on: Lock<FxHashMap<ThreadId, Lrc<QueryJob<'tcx>>>>,
+}
+
+impl<'tcx> CurrentJobs<'tcx> {
+    #[cfg(not(parallel_queries))]
+    fn new() -> CurrentJobs<'tcx> {
+        CurrentJobs { job: Lock::new(None) }
+    }
+
+    #[cfg(parallel_queries)]
+    fn new() -> CurrentJobs<'tcx> {
+        CurrentJobs {
+            jobs: Lock::new(FxHashMap()),
+            waiting_on: Lock::new(FxHashMap()),
+        }
+    }
+
+    /// The query the current thread is computing, if any.
+    #[cfg(not(parallel_queries))]
+    fn get(&self) -> Option<Lrc<QueryJob<'tcx>>> {
+        self.job.lock().clone()
+    }
+
+    #[cfg(parallel_queries)]
+    fn get(&self) -> Option<Lrc<QueryJob<'tcx>>> {
+        self.jobs.lock().get(&thread::current().id()).cloned()
+    }
+
+    #[cfg(not(parallel_queries))]
+    fn set(&self, job: Option<Lrc<QueryJob<'tcx>>>) {
+        *self.job.lock() = job;
+    }
+
+    #[cfg(parallel_queries)]
+    fn set(&self, job: Option<Lrc<QueryJob<'tcx>>>) {
+        let thread = thread::current().id();
+        let mut current_jobs = self.jobs.lock();
+        match job {
+            Some(job) => current_jobs.insert(thread, job),
+            None => current_jobs.remove(&thread),
+        };
+    }
+}
+
+impl<'tcx> Maps<'tcx> {
+    /// The query the current thread is computing, if any.
+    pub fn current_job(&self) -> Option<Lrc<QueryJob<'tcx>>> {
+        self.current_jobs.get()
+    }
+
+    /// Runs `f` on the current thread as part of computing `job`, so that
+    /// the queries it makes get `job` as their parent.
+    pub fn with_current_job<F, R>(&self, job: Option<Lrc<QueryJob<'tcx>>>, f: F) -> R
         where F: FnOnce() -> R
     {
+        let old_job = self.current_jobs.get();
+        self.current_jobs.set(job);
+        let result = f();
+        self.current_jobs.set(old_job);
+        result
+    }
+
+    /// Returns the cycle that needing the result of `job`, which is active,
+    /// would close. Queries are only computed on the current thread, so that
+    /// is the only way for a query to be active.
+    #[cfg(not(parallel_queries))]
+    fn await_job(&self, span: Span, job: &Lrc<QueryJob<'tcx>>) -> Result<(), CycleError<'tcx>> {
+        match self.current_jobs.get().and_then(|leaf| job.path_to(&leaf)) {
+            Some(cycle) => Err(CycleError { span, cycle }),
+            None => bug!("{:?} is active but not being computed", job.info.1),
+        }
+    }
+
+    /// Waits until `job`, which is active, has completed. If it never would,
+    /// because it needs the result of a query the current thread is
+    /// computing, the queries making up that cycle are returned instead.
+    #[cfg(parallel_queries)]
+    fn await_job(&self, span: Span, job: &Lrc<QueryJob<'tcx>>) -> Result<(), CycleError<'tcx>> {
+        let thread = thread::current().id();
         {
-            let mut stack = self.maps.query_stack.borrow_mut();
-            if let Some((i, _)) = stack.iter().enumerate().rev()
-                                       .find(|&(_, &(_, ref q))| *q == query) {
-                return Err(CycleError {
-                    span,
-                    cycle: RefMut::map(stack, |stack| &mut stack[i..])
-                });
+            let mut waiting_on = self.current_jobs.waiting_on.lock();
+            if let Some(cycle) = self.find_cycle(&waiting_on, job) {
+                return Err(CycleError { span, cycle });
             }
-            stack.push((span, query));
+            waiting_on.insert(thread, job.clone());
         }
 
-        let result = compute();
-
-        self.maps.query_stack.borrow_mut().pop();
+        job.wait();
+
+        self.current_jobs.waiting_on.lock().remove(&thread);
+        Ok(())
+    }
+
+    /// Follows the chain of threads waiting on each other, starting with the
+    /// current thread waiting on `job`, to find out whether it leads back to
+    /// a query the current thread is computing.
+    #[cfg(parallel_queries)]
+    fn find_cycle(&self,
+                  waiting_on: &FxHashMap<ThreadId, Lrc<QueryJob<'tcx>>>,
+                  job: &Lrc<QueryJob<'tcx>>)
+                  -> Option<Vec<(Span, Query<'tcx>)>> {
+        let current_jobs = self.current_jobs.jobs.lock();
+        let leaf = current_jobs.get(&thread::current().id());
+
+        let mut cycle = vec![];
+        let mut target = job.clone();
+        // Every thread waits on at most one job, so a chain longer than the
+        // number of waiting threads would be a cycle not involving us, which
+        // its own threads have already reported.
+        for _ in 0..waiting_on.len() + 1 {
+            if let Some(path) = leaf.and_then(|leaf| target.path_to(leaf)) {
+                cycle.extend(path);
+                return Some(cycle);
+            }
 
-        Ok(result)
+            // `target` completes when its thread is done with it, unless
+            // that thread is itself waiting on something.
+            let next = waiting_on.get(&target.thread)?;
+            let path = current_jobs.get(&target.thread).and_then(|leaf| target.path_to(leaf))?;
+            cycle.extend(path);
+            target = next.clone();
+        }
+        None
     }
 }
 
@@ -266,54 +541,106 @@ pub trait QueryConfig {
     type Value;
 }
 
-trait QueryDescription: QueryConfig {
+trait QueryDescription<'tcx>: QueryConfig {
     fn describe(tcx: TyCtxt, key: Self::Key) -> String;
+
+    /// Whether the result for `key` is saved to the on-disk query result
+    /// cache, to be loaded instead of recomputed in the next session if it
+    /// has not changed.
+    #[inline]
+    fn cache_on_disk(_: Self::Key) -> bool {
+        false
+    }
+
+    fn try_load_from_disk<'a>(_: TyCtxt<'a, 'tcx, 'tcx>,
+                              _: SerializedDepNodeIndex)
+                              -> Option<Self::Value> {
+        bug!("QueryDescription::try_load_from_disk() called for unsupported query.")
+    }
 }
 
-impl<M: QueryConfig<Key=DefId>> QueryDescription for M {
+impl<'tcx, M: QueryConfig<Key=DefId>> QueryDescription<'tcx> for M {
     default fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("processing `{}`", tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_copy_raw<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::typeck_tables_of<'tcx> {
+    fn describe(tcx: TyCtxt, def_id: DefId) -> String {
+        format!("processing `{}`", tcx.item_path_str(def_id))
+    }
+
+    #[inline]
+    fn cache_on_disk(def_id: DefId) -> bool {
+        def_id.is_local()
+    }
+
+    fn try_load_from_disk<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              id: SerializedDepNodeIndex)
+                              -> Option<Self::Value> {
+        let tables: Option<ty::TypeckTables<'tcx>> = tcx.on_disk_query_result_cache
+                                                        .try_load_query_result(tcx, id);
+        tables.map(|tables| tcx.alloc_tables(tables))
+    }
+}
+
+impl<'tcx> QueryDescription<'tcx> for queries::optimized_mir<'tcx> {
+    fn describe(tcx: TyCtxt, def_id: DefId) -> String {
+        format!("processing `{}`", tcx.item_path_str(def_id))
+    }
+
+    #[inline]
+    fn cache_on_disk(def_id: DefId) -> bool {
+        def_id.is_local()
+    }
+
+    fn try_load_from_disk<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              id: SerializedDepNodeIndex)
+                              -> Option<Self::Value> {
+        let mir: Option<mir::Mir<'tcx>> = tcx.on_disk_query_result_cache
+                                             .try_load_query_result(tcx, id);
+        mir.map(|mir| tcx.alloc_mir(mir))
+    }
+}
+
+impl<'tcx> QueryDescription<'tcx> for queries::is_copy_raw<'tcx> {
     fn describe(_tcx: TyCtxt, env: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> String {
         format!("computing whether `{}` is `Copy`", env.value)
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_sized_raw<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_sized_raw<'tcx> {
     fn describe(_tcx: TyCtxt, env: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> String {
         format!("computing whether `{}` is `Sized`", env.value)
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_freeze_raw<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_freeze_raw<'tcx> {
     fn describe(_tcx: TyCtxt, env: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> String {
         format!("computing whether `{}` is freeze", env.value)
     }
 }
 
-impl<'tcx> QueryDescription for queries::needs_drop_raw<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::needs_drop_raw<'tcx> {
     fn describe(_tcx: TyCtxt, env: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> String {
         format!("computing whether `{}` needs drop", env.value)
     }
 }
 
-impl<'tcx> QueryDescription for queries::layout_raw<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::layout_raw<'tcx> {
     fn describe(_tcx: TyCtxt, env: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> String {
         format!("computing layout of `{}`", env.value)
     }
 }
 
-impl<'tcx> QueryDescription for queries::super_predicates_of<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::super_predicates_of<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("computing the supertraits of `{}`",
                 tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::type_param_predicates<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::type_param_predicates<'tcx> {
     fn describe(tcx: TyCtxt, (_, def_id): (DefId, DefId)) -> String {
         let id = tcx.hir.as_local_node_id(def_id).unwrap();
         format!("computing the bounds for type parameter `{}`",
@@ -321,192 +648,203 @@ impl<'tcx> QueryDescription for queries::type_param_predicates<'tcx> {
     }
 }
 
-impl<'tcx> QueryDescription for queries::coherent_trait<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::coherent_trait<'tcx> {
     fn describe(tcx: TyCtxt, (_, def_id): (CrateNum, DefId)) -> String {
         format!("coherence checking all impls of trait `{}`",
                 tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::crate_inherent_impls<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::crate_inherent_impls<'tcx> {
     fn describe(_: TyCtxt, k: CrateNum) -> String {
         format!("all inherent impls defined in crate `{:?}`", k)
     }
 }
 
-impl<'tcx> QueryDescription for queries::crate_inherent_impls_overlap_check<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::crate_inherent_impls_overlap_check<'tcx> {
     fn describe(_: TyCtxt, _: CrateNum) -> String {
         format!("check for overlap between inherent impls defined in this crate")
     }
 }
 
-impl<'tcx> QueryDescription for queries::crate_variances<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::crate_variances<'tcx> {
     fn describe(_tcx: TyCtxt, _: CrateNum) -> String {
         format!("computing the variances for items in this crate")
     }
 }
 
-impl<'tcx> QueryDescription for queries::mir_shims<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::mir_shims<'tcx> {
     fn describe(tcx: TyCtxt, def: ty::InstanceDef<'tcx>) -> String {
         format!("generating MIR shim for `{}`",
                 tcx.item_path_str(def.def_id()))
     }
 }
 
-impl<'tcx> QueryDescription for queries::privacy_access_levels<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::privacy_access_levels<'tcx> {
     fn describe(_: TyCtxt, _: CrateNum) -> String {
         format!("privacy access levels")
     }
 }
 
-impl<'tcx> QueryDescription for queries::typeck_item_bodies<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::typeck_item_bodies<'tcx> {
     fn describe(_: TyCtxt, _: CrateNum) -> String {
         format!("type-checking all item bodies")
     }
 }
 
-impl<'tcx> QueryDescription for queries::reachable_set<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::reachable_set<'tcx> {
     fn describe(_: TyCtxt, _: CrateNum) -> String {
         format!("reachability")
     }
 }
 
-impl<'tcx> QueryDescription for queries::const_eval<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::const_eval<'tcx> {
     fn describe(tcx: TyCtxt, key: ty::ParamEnvAnd<'tcx, (DefId, &'tcx Substs<'tcx>)>) -> String {
         format!("const-evaluating `{}`", tcx.item_path_str(key.value.0))
     }
 }
 
-impl<'tcx> QueryDescription for queries::mir_keys<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::mir_keys<'tcx> {
     fn describe(_: TyCtxt, _: CrateNum) -> String {
         format!("getting a list of all mir_keys")
     }
 }
 
-impl<'tcx> QueryDescription for queries::symbol_name<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::symbol_name<'tcx> {
     fn describe(_tcx: TyCtxt, instance: ty::Instance<'tcx>) -> String {
         format!("computing the symbol for `{}`", instance)
     }
+
+    #[inline]
+    fn cache_on_disk(_: ty::Instance<'tcx>) -> bool {
+        true
+    }
+
+    fn try_load_from_disk<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              id: SerializedDepNodeIndex)
+                              -> Option<Self::Value> {
+        tcx.on_disk_query_result_cache.try_load_query_result(tcx, id)
+    }
 }
 
-impl<'tcx> QueryDescription for queries::describe_def<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::describe_def<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("describe_def")
     }
 }
 
-impl<'tcx> QueryDescription for queries::def_span<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::def_span<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("def_span")
     }
 }
 
 
-impl<'tcx> QueryDescription for queries::stability<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::stability<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("stability")
     }
 }
 
-impl<'tcx> QueryDescription for queries::deprecation<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::deprecation<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("deprecation")
     }
 }
 
-impl<'tcx> QueryDescription for queries::item_attrs<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::item_attrs<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("item_attrs")
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_exported_symbol<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_exported_symbol<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("is_exported_symbol")
     }
 }
 
-impl<'tcx> QueryDescription for queries::fn_arg_names<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::fn_arg_names<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("fn_arg_names")
     }
 }
 
-impl<'tcx> QueryDescription for queries::impl_parent<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::impl_parent<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("impl_parent")
     }
 }
 
-impl<'tcx> QueryDescription for queries::trait_of_item<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::trait_of_item<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         bug!("trait_of_item")
     }
 }
 
-impl<'tcx> QueryDescription for queries::item_body_nested_bodies<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::item_body_nested_bodies<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("nested item bodies of `{}`", tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::const_is_rvalue_promotable_to_static<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::const_is_rvalue_promotable_to_static<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("const checking if rvalue is promotable to static `{}`",
             tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_mir_available<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_mir_available<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("checking if item is mir available: `{}`",
             tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::trait_impls_of<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::trait_impls_of<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("trait impls of `{}`", tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::relevant_trait_impls_for<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::relevant_trait_impls_for<'tcx> {
     fn describe(tcx: TyCtxt, (def_id, ty): (DefId, SimplifiedType)) -> String {
         format!("relevant impls for: `({}, {:?})`", tcx.item_path_str(def_id), ty)
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_object_safe<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_object_safe<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("determine object safety of trait `{}`", tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_const_fn<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_const_fn<'tcx> {
     fn describe(tcx: TyCtxt, def_id: DefId) -> String {
         format!("checking if item is const fn: `{}`", tcx.item_path_str(def_id))
     }
 }
 
-impl<'tcx> QueryDescription for queries::dylib_dependency_formats<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::dylib_dependency_formats<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         "dylib dependency formats of crate".to_string()
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_allocator<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_allocator<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         "checking if the crate is_allocator".to_string()
     }
 }
 
-impl<'tcx> QueryDescription for queries::is_panic_runtime<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::is_panic_runtime<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         "checking if the crate is_panic_runtime".to_string()
     }
 }
 
-impl<'tcx> QueryDescription for queries::extern_crate<'tcx> {
+impl<'tcx> QueryDescription<'tcx> for queries::extern_crate<'tcx> {
     fn describe(_: TyCtxt, _: DefId) -> String {
         "getting crate's ExternCrateData".to_string()
     }
@@ -526,8 +864,8 @@ macro_rules! define_maps {
                        -> Self {
                 Maps {
                     providers,
-                    query_stack: RefCell::new(vec![]),
-                    $($name: RefCell::new(QueryMap::new())),*
+                    current_jobs: CurrentJobs::new(),
+                    $($name: Sharded::new(QueryMap::new)),*
                 }
             }
         }
@@ -572,7 +910,7 @@ macro_rules! define_maps {
                                   mut span: Span,
                                   key: $K,
                                   f: F)
-                                  -> Result<R, CycleError<'a, $tcx>>
+                                  -> Result<R, CycleError<$tcx>>
                 where F: FnOnce(&$V) -> R
             {
                 debug!("ty::queries::{}::try_get_with(key={:?}, span={:?})",
@@ -580,51 +918,131 @@ macro_rules! define_maps {
                        key,
                        span);
 
-                if let Some(&(ref result, dep_node_index)) = tcx.maps.$name.borrow().map.get(&key) {
-                    tcx.dep_graph.read_index(dep_node_index);
-                    return Ok(f(result));
-                }
+                let shard = tcx.maps.$name.get_shard_by_value(&key);
 
-                // FIXME(eddyb) Get more valid Span's on queries.
-                // def_span guard is necesary to prevent a recursive loop,
-                // default_span calls def_span query internally.
-                if span == DUMMY_SP && stringify!($name) != "def_span" {
-                    span = key.default_span(tcx)
-                }
+                // Either find the result, start computing the query ourselves,
+                // or wait for whoever is computing it and look again.
+                let job = loop {
+                    if let Some(&(ref result, dep_node_index)) = shard.read().map.get(&key) {
+                        tcx.sess.profiler(|p| p.record_cache_hit(stringify!($name)));
+                        tcx.dep_graph.read_index(dep_node_index);
+                        return Ok(f(result));
+                    }
 
-                let (result, dep_node_index) = tcx.cycle_check(span, Query::$name(key), || {
-                    let dep_node = Self::to_dep_node(tcx, &key);
+                    // FIXME(eddyb) Get more valid Span's on queries.
+                    // def_span guard is necesary to prevent a recursive loop,
+                    // default_span calls def_span query internally.
+                    if span == DUMMY_SP && stringify!($name) != "def_span" {
+                        span = key.default_span(tcx)
+                    }
 
-                    if dep_node.kind.is_anon() {
-                        tcx.dep_graph.with_anon_task(dep_node.kind, || {
-                            let provider = tcx.maps.providers[key.map_crate()].$name;
-                            provider(tcx.global_tcx(), key)
-                        })
-                    } else {
-                        fn run_provider<'a, 'tcx, 'lcx>(tcx: TyCtxt<'a, 'tcx, 'lcx>,
-                                                        key: $K)
-                                                        -> $V {
-                            let provider = tcx.maps.providers[key.map_crate()].$name;
-                            provider(tcx.global_tcx(), key)
+                    let active = {
+                        let mut lock = shard.write();
+                        if lock.map.contains_key(&key) {
+                            // Another thread completed it since we looked.
+                            continue;
+                        }
+                        match lock.active.entry(key) {
+                            Entry::Occupied(entry) => entry.get().clone(),
+                            Entry::Vacant(entry) => {
+                                let job = QueryJob::new((span, Query::$name(key)),
+                                                        tcx.maps.current_jobs.get());
+                                let job = Lrc::new(job);
+                                entry.insert(job.clone());
+                                break job;
+                            }
                         }
+                    };
+                    tcx.maps.await_job(span, &active)?;
+                };
+
+                let owner = JobOwner::new(&tcx.maps, shard, key, job);
+
+                let provider_kind = if key.map_crate() == LOCAL_CRATE {
+                    ProviderKind::Local
+                } else {
+                    ProviderKind::Extern
+                };
+                tcx.sess.profiler(|p| p.start_query(stringify!($name), provider_kind));
+
+                let dep_node = Self::to_dep_node(tcx, &key);
+
+                // If the dep-node is green, the result is the same as in the
+                // previous session and need not be computed again.
+                let green_index = if dep_node.kind.is_anon() {
+                    None
+                } else {
+                    tcx.dep_graph.try_mark_green(tcx.global_tcx(), &dep_node)
+                };
+
+                let (result, dep_node_index) = if let Some(dep_node_index) = green_index {
+                    (Self::load_green_result(tcx, key, &dep_node), dep_node_index)
+                } else if dep_node.kind.is_anon() {
+                    tcx.dep_graph.with_anon_task(dep_node.kind, || {
+                        let provider = tcx.maps.providers[key.map_crate()].$name;
+                        provider(tcx.global_tcx(), key)
+                    })
+                } else {
+                    fn run_provider<'a, 'tcx, 'lcx>(tcx: TyCtxt<'a, 'tcx, 'lcx>,
+                                                    key: $K)
+                                                    -> $V {
+                        let provider = tcx.maps.providers[key.map_crate()].$name;
+                        provider(tcx.global_tcx(), key)
+                    }
 
-                        tcx.dep_graph.with_task(dep_node, tcx, key, run_provider)
+                    let diagnostic_count = tcx.sess.diagnostic_count();
+                    let (result, dep_node_index) =
+                        tcx.dep_graph.with_task(dep_node, tcx, key, run_provider);
+
+                    if tcx.dep_graph.is_fully_enabled() {
+                        let mut hcx = StableHashingContext::new(tcx.global_tcx());
+                        let fingerprint = result.result_fingerprint(&mut hcx);
+                        let emitted_diagnostics = tcx.sess.diagnostic_count() != diagnostic_count;
+                        tcx.dep_graph.complete_task(dep_node,
+                                                    dep_node_index,
+                                                    fingerprint,
+                                                    emitted_diagnostics);
                     }
-                })?;
 
+                    (result, dep_node_index)
+                };
+
+                tcx.sess.profiler(|p| p.end_query(stringify!($name)));
                 tcx.dep_graph.read_index(dep_node_index);
 
-                Ok(f(&tcx.maps
-                         .$name
-                         .borrow_mut()
-                         .map
-                         .entry(key)
-                         .or_insert((result, dep_node_index))
-                         .0))
+                // Store the result before `owner` marks the query as no
+                // longer active, so that threads waiting on it find it.
+                shard.write().map.entry(key).or_insert((result, dep_node_index));
+                let value = f(&shard.read().map[&key].0);
+                drop(owner);
+                Ok(value)
+            }
+
+            /// Produces the result for `key` after its dep-node was marked
+            /// green: from the on-disk cache if it was saved there, and by
+            /// running the provider otherwise. Reads are not tracked either
+            /// way, the dep-node already has the dependencies it had in the
+            /// previous session.
+            fn load_green_result(tcx: TyCtxt<'a, $tcx, 'lcx>, key: $K, dep_node: &DepNode) -> $V {
+                // The node is already green with its dependencies from the
+                // previous session, so nothing read here may add to them.
+                tcx.dep_graph.with_ignore(|| {
+                    let result = if Self::cache_on_disk(key) {
+                        let prev_index = tcx.dep_graph.prev_dep_node_index_of(dep_node);
+                        Self::try_load_from_disk(tcx.global_tcx(), prev_index)
+                    } else {
+                        None
+                    };
+
+                    result.unwrap_or_else(|| {
+                        let provider = tcx.maps.providers[key.map_crate()].$name;
+                        provider(tcx.global_tcx(), key)
+                    })
+                })
             }
 
             pub fn try_get(tcx: TyCtxt<'a, $tcx, 'lcx>, span: Span, key: $K)
-                           -> Result<$V, CycleError<'a, $tcx>> {
+                           -> Result<$V, CycleError<$tcx>> {
                 Self::try_get_with(tcx, span, key, Clone::clone)
             }
 
@@ -708,7 +1126,10 @@ macro_rules! define_map_struct {
      output: ($($output:tt)*)) => {
         pub struct Maps<$tcx> {
             providers: IndexVec<CrateNum, Providers<$tcx>>,
-            query_stack: RefCell<Vec<(Span, Query<$tcx>)>>,
+
+            /// The innermost query each thread is computing.
+            current_jobs: CurrentJobs<$tcx>,
+
             $($output)*
         }
     };
@@ -722,7 +1143,7 @@ macro_rules! define_map_struct {
             tcx: $tcx,
             input: $input,
             output: ($($output)*
-                     $(#[$attr])* $($pub)* $name: RefCell<QueryMap<queries::$name<$tcx>>>,)
+                     $(#[$attr])* $($pub)* $name: Sharded<QueryMap<$tcx, queries::$name<$tcx>>>,)
         }
     };
 
@@ -922,6 +1343,8 @@ define_maps! { <'tcx>
     [] coherent_trait: coherent_trait_dep_node((CrateNum, DefId)) -> (),
 
     [] borrowck: BorrowCheck(DefId) -> (),
+    /// Borrow-checks the MIR of a body, if `-Z borrowck` asks for it.
+    [] mir_borrowck: MirBorrowCheck(DefId) -> (),
 
     /// Gets a complete map from all types to their inherent impls.
     /// Not meant to be used directly outside of coherence.
@@ -999,6 +1422,128 @@ define_maps! { <'tcx>
     [] extern_crate: ExternCrate(DefId) -> Rc<Option<ExternCrate>>,
 }
 
+/// Runs the query that `dep_node` was created for, so that its color is
+/// known afterwards. This only works for the queries whose key can be
+/// recovered from the dep-node, which are those keyed by a `DefId`. Returns
+/// false if the query could not be run.
+pub fn force_from_dep_node<'a, 'gcx, 'lcx>(tcx: TyCtxt<'a, 'gcx, 'lcx>,
+                                           dep_node: &DepNode)
+                                           -> bool {
+    let def_id = match dep_node.extract_def_id(tcx) {
+        Some(def_id) => def_id,
+        // The item is gone, so there is nothing to run the query on.
+        None => return false,
+    };
+
+    macro_rules! force {
+        ($query:ident) => {
+            queries::$query::force(tcx, DUMMY_SP, def_id)
+        }
+    };
+
+    match dep_node.kind {
+        DepKind::TypeOfItem => { force!(type_of); }
+        DepKind::GenericsOfItem => { force!(generics_of); }
+        DepKind::PredicatesOfItem => { force!(predicates_of); }
+        DepKind::SuperPredicatesOfItem => { force!(super_predicates_of); }
+        DepKind::TraitDefOfItem => { force!(trait_def); }
+        DepKind::AdtDefOfItem => { force!(adt_def); }
+        DepKind::AdtDestructor => { force!(adt_destructor); }
+        DepKind::SizedConstraint => { force!(adt_sized_constraint); }
+        DepKind::DtorckConstraint => { force!(adt_dtorck_constraint); }
+        DepKind::IsConstFn => { force!(is_const_fn); }
+        DepKind::IsForeignItem => { force!(is_foreign_item); }
+        DepKind::IsDefaultImpl => { force!(is_default_impl); }
+        DepKind::ItemVariances => { force!(variances_of); }
+        DepKind::AssociatedItemDefIds => { force!(associated_item_def_ids); }
+        DepKind::AssociatedItems => { force!(associated_item); }
+        DepKind::ImplTraitRef => { force!(impl_trait_ref); }
+        DepKind::ImplPolarity => { force!(impl_polarity); }
+        DepKind::InherentImpls => { force!(inherent_impls); }
+        DepKind::MirConstQualif => { force!(mir_const_qualif); }
+        DepKind::MirOptimized => { force!(optimized_mir); }
+        DepKind::ClosureKind => { force!(closure_kind); }
+        DepKind::FnSignature => { force!(fn_sig); }
+        DepKind::CoerceUnsizedInfo => { force!(coerce_unsized_info); }
+        DepKind::TypeckTables => { force!(typeck_tables_of); }
+        DepKind::HasTypeckTables => { force!(has_typeck_tables); }
+        DepKind::BorrowCheck => { force!(borrowck); }
+        DepKind::MirBorrowCheck => { force!(mir_borrowck); }
+        DepKind::RegionMaps => { force!(region_maps); }
+        DepKind::SymbolName => { force!(def_symbol_name); }
+        DepKind::DescribeDef => { force!(describe_def); }
+        DepKind::DefSpan => { force!(def_span); }
+        DepKind::Stability => { force!(stability); }
+        DepKind::Deprecation => { force!(deprecation); }
+        DepKind::ItemAttrs => { force!(item_attrs); }
+        DepKind::FnArgNames => { force!(fn_arg_names); }
+        DepKind::ImplParent => { force!(impl_parent); }
+        DepKind::TraitOfItem => { force!(trait_of_item); }
+        DepKind::IsExportedSymbol => { force!(is_exported_symbol); }
+        DepKind::ItemBodyNestedBodies => { force!(item_body_nested_bodies); }
+        DepKind::ConstIsRvaluePromotableToStatic => {
+            force!(const_is_rvalue_promotable_to_static);
+        }
+        DepKind::IsMirAvailable => { force!(is_mir_available); }
+        DepKind::TraitImpls => { force!(trait_impls_of); }
+        DepKind::SpecializationGraph => { force!(specialization_graph_of); }
+        DepKind::ObjectSafety => { force!(is_object_safe); }
+        DepKind::ParamEnv => { force!(param_env); }
+        DepKind::DylibDepFormats => { force!(dylib_dependency_formats); }
+        DepKind::IsAllocator => { force!(is_allocator); }
+        DepKind::IsPanicRuntime => { force!(is_panic_runtime); }
+        DepKind::ExternCrate => { force!(extern_crate); }
+
+        // `mir_const` and `mir_validated` return values that are stolen by
+        // the queries after them, so running them again is not possible.
+        DepKind::MirConst |
+        DepKind::MirValidated => return false,
+
+        // The rest either are not queries or have keys that can't be
+        // recovered from the dep-node.
+        _ => return false,
+    }
+
+    true
+}
+
+/// Encodes the results of the queries that are cached on disk into the query
+/// result cache, recording where each one starts in `query_result_index`.
+pub fn encode_query_results<'enc, 'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    encoder: &mut CacheEncoder<'enc, 'a, 'tcx>,
+    query_result_index: &mut Vec<(SerializedDepNodeIndex, usize)>)
+    -> Result<(), ::std::io::Error>
+{
+    fn encode<'enc, 'a, 'tcx, Q>(map: &Sharded<QueryMap<'tcx, Q>>,
+                                 encoder: &mut CacheEncoder<'enc, 'a, 'tcx>,
+                                 query_result_index: &mut Vec<(SerializedDepNodeIndex, usize)>)
+                                 -> Result<(), ::std::io::Error>
+        where Q: QueryDescription<'tcx>,
+              Q::Value: ::serialize::Encodable
+    {
+        for shard in map.read_shards().iter() {
+            for (key, &(ref value, dep_node_index)) in shard.map.iter() {
+                if !Q::cache_on_disk(key.clone()) || dep_node_index == DepNodeIndex::INVALID {
+                    continue;
+                }
+
+                // The current dep-nodes become the previous ones of the
+                // next session, keeping their indices.
+                let dep_node_index = SerializedDepNodeIndex::new(dep_node_index.index());
+                let pos = encoder.encode_query_result(dep_node_index, value)?;
+                query_result_index.push((dep_node_index, pos));
+            }
+        }
+        Ok(())
+    }
+
+    encode(&tcx.maps.typeck_tables_of, encoder, query_result_index)?;
+    encode(&tcx.maps.optimized_mir, encoder, query_result_index)?;
+    encode(&tcx.maps.symbol_name, encoder, query_result_index)?;
+    Ok(())
+}
+
 fn type_param_predicates<'tcx>((item_id, param_id): (DefId, DefId)) -> DepConstructor<'tcx> {
     DepConstructor::TypeParamPredicates {
         item_id,
@@ -54,6 +54,7 @@ use rustc_const_math::ConstInt;
 use rustc_data_structures::accumulate_vec::IntoIter as AccIntoIter;
 use rustc_data_structures::stable_hasher::{StableHasher, StableHasherResult,
                                            HashStable};
+use rustc_data_structures::sync;
 use rustc_data_structures::transitive_relation::TransitiveRelation;
 
 use hir;
@@ -96,6 +97,7 @@ pub mod item_path;
 pub mod layout;
 pub mod _match;
 pub mod maps;
+pub mod on_disk_cache;
 pub mod outlives;
 pub mod relate;
 pub mod steal;
@@ -1954,6 +1956,18 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                 .map(move |&body_id| self.hir.body_owner_def_id(body_id))
     }
 
+    /// Calls `f` on the def-id of every body-owner in this crate, like
+    /// iterating over `body_owners`. With `-Z threads=N` and a compiler
+    /// built with parallel queries, the calls are spread over `N` threads.
+    pub fn par_body_owners<F: Fn(DefId) + sync::Send + sync::Sync>(self, f: F) {
+        let job = self.maps.current_job();
+        sync::par_for_each(self.sess.threads(), self.body_owners(), |def_id| {
+            // Queries made from the other threads are still part of
+            // computing the current query, if any.
+            self.maps.with_current_job(job.clone(), || f(def_id))
+        });
+    }
+
     pub fn expr_span(self, id: NodeId) -> Span {
         match self.hir.find(id) {
             Some(hir_map::NodeExpr(e)) => {
@@ -2577,7 +2591,7 @@ impl<'tcx> DtorckConstraint<'tcx> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, RustcEncodable, RustcDecodable)]
 pub struct SymbolName {
     // FIXME: we don't rely on interning or equality here - better have
     // this be a `&'tcx str`.
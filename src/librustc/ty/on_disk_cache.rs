@@ -0,0 +1,649 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A cache of query results, saved at the end of an incremental compilation
+//! session and consulted in the next one.
+//!
+//! When a query's dep-node is marked green, its result is known to be the
+//! same as in the previous session. For the queries that opt into it (see
+//! `QueryDescription::cache_on_disk`), that result is then decoded from this
+//! cache instead of being recomputed.
+//!
+//! Results are written with a `CacheEncoder`, which replaces everything that
+//! is only meaningful within one session by something that is not: `DefId`s
+//! and `CrateNum`s become `DefPathHash`es, `NodeId`s become the `HirId` of
+//! the node, with the owner as a `DefPathHash`, and spans become a file name,
+//! line, column and length, followed by the expansion info of their outermost
+//! mark. The `CacheDecoder` maps these back to the values of the current
+//! session, creating a fresh mark for each expansion info.
+
+use dep_graph::SerializedDepNodeIndex;
+use hir;
+use hir::def_id::{CrateNum, DefId, CRATE_DEF_INDEX, LOCAL_CRATE};
+use hir::map::DefPathHash;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::{Lock, Lrc};
+use serialize::{Decodable, Decoder, Encodable, Encoder, SpecializedDecoder,
+                SpecializedEncoder, opaque};
+use std::borrow::Cow;
+use std::intrinsics;
+use std::io::{self, Write};
+use std::mem;
+use std::rc::Rc;
+use syntax::ast;
+use syntax::codemap::CodeMap;
+use syntax_pos::{BytePos, FileMap, Span, DUMMY_SP};
+use syntax_pos::hygiene::{ExpnInfo, Mark, SyntaxContext};
+use ty::{self, Ty, TyCtxt};
+use ty::maps;
+use ty::subst::Substs;
+
+/// The shorthand encoding of types uses the same usize as the discriminant
+/// of `TypeVariants`, with an offset so they can't conflict.
+const SHORTHAND_OFFSET: usize = 0x80;
+
+const TAG_INVALID_SPAN: u8 = 0;
+const TAG_VALID_SPAN: u8 = 1;
+
+const TAG_NO_EXPANSION_INFO: u8 = 0;
+const TAG_EXPANSION_INFO_SHORTHAND: u8 = 1;
+const TAG_EXPANSION_INFO_INLINE: u8 = 2;
+
+/// The query results saved by the previous session.
+pub struct OnDiskCache {
+    /// Empty until the cache of the previous session is loaded.
+    prev: Lock<Option<Lrc<PrevCacheData>>>,
+
+    /// Maps the `HirId`s of this session back to `NodeId`s, built when the
+    /// first result is decoded.
+    hir_to_node_id: Lock<Option<Lrc<FxHashMap<hir::HirId, ast::NodeId>>>>,
+
+    /// The syntax contexts created for the expansion infos decoded so far,
+    /// by their position, so that spans from the same expansion share one.
+    synthetic_expansion_infos: Lock<FxHashMap<usize, SyntaxContext>>,
+}
+
+struct PrevCacheData {
+    serialized_data: Vec<u8>,
+
+    /// Where the result of each cached dep-node starts in `serialized_data`.
+    query_result_index: FxHashMap<SerializedDepNodeIndex, usize>,
+
+    /// The names of the files the spans in `serialized_data` refer to.
+    file_names: Vec<String>,
+}
+
+/// Written after the query results, followed by its own position as eight
+/// little-endian bytes.
+#[derive(RustcEncodable, RustcDecodable)]
+struct Footer {
+    query_result_index: Vec<(SerializedDepNodeIndex, usize)>,
+    file_names: Vec<String>,
+}
+
+impl OnDiskCache {
+    pub fn new_empty() -> OnDiskCache {
+        OnDiskCache {
+            prev: Lock::new(None),
+            hir_to_node_id: Lock::new(None),
+            synthetic_expansion_infos: Lock::new(FxHashMap()),
+        }
+    }
+
+    /// Makes the query results in `data`, as written by `serialize` in the
+    /// previous session, available to `try_load_query_result`.
+    pub fn load(&self, data: Vec<u8>) -> Result<(), String> {
+        if data.len() < 8 {
+            return Err("query result cache is truncated".to_string());
+        }
+
+        let footer_pos = data[data.len() - 8..]
+            .iter()
+            .rev()
+            .fold(0, |pos, &byte| (pos << 8) | byte as usize);
+        if footer_pos > data.len() - 8 {
+            return Err("query result cache is corrupt".to_string());
+        }
+
+        let footer = {
+            let mut decoder = opaque::Decoder::new(&data, footer_pos);
+            Footer::decode(&mut decoder)?
+        };
+
+        *self.prev.lock() = Some(Lrc::new(PrevCacheData {
+            serialized_data: data,
+            query_result_index: footer.query_result_index.into_iter().collect(),
+            file_names: footer.file_names,
+        }));
+        self.synthetic_expansion_infos.lock().clear();
+        Ok(())
+    }
+
+    /// Decodes the result of the query that had the dep-node
+    /// `dep_node_index` in the previous session, if it was saved.
+    pub fn try_load_query_result<'a, 'tcx, T>(&self,
+                                              tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                              dep_node_index: SerializedDepNodeIndex)
+                                              -> Option<T>
+        where T: Decodable
+    {
+        // Decoding can run queries, which can load other results, so none of
+        // the locks are held while doing so.
+        let prev = match *self.prev.lock() {
+            Some(ref prev) => prev.clone(),
+            None => return None,
+        };
+
+        let pos = match prev.query_result_index.get(&dep_node_index) {
+            Some(&pos) => pos,
+            None => return None,
+        };
+
+        let hir_to_node_id = {
+            let mut hir_to_node_id = self.hir_to_node_id.lock();
+            if hir_to_node_id.is_none() {
+                *hir_to_node_id = Some(Lrc::new(tcx.hir.definitions().hir_to_node_id_map()));
+            }
+            hir_to_node_id.as_ref().unwrap().clone()
+        };
+
+        let mut decoder = CacheDecoder {
+            tcx,
+            opaque: opaque::Decoder::new(&prev.serialized_data, pos),
+            codemap: tcx.sess.codemap(),
+            file_names: &prev.file_names,
+            filemaps: FxHashMap(),
+            hir_to_node_id: &hir_to_node_id,
+            type_shorthands: FxHashMap(),
+            synthetic_expansion_infos: &self.synthetic_expansion_infos,
+        };
+
+        match decode_tagged(&mut decoder, dep_node_index) {
+            Ok(value) => Some(value),
+            Err(err) => bug!("could not decode cached query result: {}", err),
+        }
+    }
+}
+
+/// Writes the results of the queries that are cached on disk to `encoder`,
+/// followed by the footer needed to find them again.
+pub fn serialize<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                           encoder: &mut opaque::Encoder)
+                           -> io::Result<()> {
+    let (footer, footer_pos) = {
+        let mut encoder = CacheEncoder {
+            tcx,
+            start_pos: encoder.position(),
+            encoder: opaque::Encoder::new(&mut *encoder.cursor),
+            type_shorthands: FxHashMap(),
+            expn_info_shorthands: FxHashMap(),
+            file_indices: FxHashMap(),
+            file_names: vec![],
+        };
+
+        let mut query_result_index = vec![];
+        maps::encode_query_results(tcx, &mut encoder, &mut query_result_index)?;
+
+        let footer = Footer {
+            query_result_index,
+            file_names: mem::replace(&mut encoder.file_names, vec![]),
+        };
+        (footer, encoder.position())
+    };
+
+    footer.encode(encoder)?;
+
+    let mut footer_pos_bytes = [0u8; 8];
+    for (i, byte) in footer_pos_bytes.iter_mut().enumerate() {
+        *byte = (footer_pos >> (i * 8)) as u8;
+    }
+    encoder.cursor.write_all(&footer_pos_bytes)
+}
+
+/// Encodes `value`, preceded by `tag` to check it is decoded as the right
+/// result.
+fn encode_tagged<'enc, 'a, 'tcx, T>(encoder: &mut CacheEncoder<'enc, 'a, 'tcx>,
+                                    tag: SerializedDepNodeIndex,
+                                    value: &T)
+                                    -> io::Result<()>
+    where T: Encodable
+{
+    tag.encode(encoder)?;
+    value.encode(encoder)
+}
+
+fn decode_tagged<'x, 'a, 'tcx, T>(decoder: &mut CacheDecoder<'x, 'a, 'tcx>,
+                                  expected_tag: SerializedDepNodeIndex)
+                                  -> Result<T, String>
+    where T: Decodable
+{
+    let tag = SerializedDepNodeIndex::decode(decoder)?;
+    if tag != expected_tag {
+        return Err(format!("expected the result of {:?}, found {:?}", expected_tag, tag));
+    }
+    T::decode(decoder)
+}
+
+//- ENCODING -------------------------------------------------------------------
+
+pub struct CacheEncoder<'enc, 'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    encoder: opaque::Encoder<'enc>,
+
+    /// Positions are stored relative to where the cache starts, as the
+    /// file header before it is not part of the data read back.
+    start_pos: usize,
+
+    type_shorthands: FxHashMap<Ty<'tcx>, usize>,
+    expn_info_shorthands: FxHashMap<SyntaxContext, usize>,
+    file_indices: FxHashMap<String, usize>,
+    file_names: Vec<String>,
+}
+
+impl<'enc, 'a, 'tcx> CacheEncoder<'enc, 'a, 'tcx> {
+    pub fn position(&self) -> usize {
+        self.encoder.position() - self.start_pos
+    }
+
+    /// Encodes the result of a query, returning where it starts.
+    pub fn encode_query_result<T: Encodable>(&mut self,
+                                             dep_node_index: SerializedDepNodeIndex,
+                                             value: &T)
+                                             -> io::Result<usize> {
+        let pos = self.position();
+        encode_tagged(self, dep_node_index, value)?;
+        Ok(pos)
+    }
+
+    fn file_index(&mut self, filemap: &FileMap) -> usize {
+        let file_names = &mut self.file_names;
+        *self.file_indices.entry(filemap.name.clone()).or_insert_with(|| {
+            file_names.push(filemap.name.clone());
+            file_names.len() - 1
+        })
+    }
+}
+
+macro_rules! encoder_methods {
+    ($($name:ident($ty:ty);)*) => {
+        $(fn $name(&mut self, value: $ty) -> Result<(), Self::Error> {
+            self.encoder.$name(value)
+        })*
+    }
+}
+
+impl<'enc, 'a, 'tcx> Encoder for CacheEncoder<'enc, 'a, 'tcx> {
+    type Error = <opaque::Encoder<'enc> as Encoder>::Error;
+
+    fn emit_nil(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    encoder_methods! {
+        emit_usize(usize);
+        emit_u128(u128);
+        emit_u64(u64);
+        emit_u32(u32);
+        emit_u16(u16);
+        emit_u8(u8);
+
+        emit_isize(isize);
+        emit_i128(i128);
+        emit_i64(i64);
+        emit_i32(i32);
+        emit_i16(i16);
+        emit_i8(i8);
+
+        emit_bool(bool);
+        emit_f64(f64);
+        emit_f32(f32);
+        emit_char(char);
+        emit_str(&str);
+    }
+}
+
+impl<'enc, 'a, 'tcx> SpecializedEncoder<Ty<'tcx>> for CacheEncoder<'enc, 'a, 'tcx> {
+    fn specialized_encode(&mut self, ty: &Ty<'tcx>) -> Result<(), Self::Error> {
+        if let Some(&shorthand) = self.type_shorthands.get(ty) {
+            return self.emit_usize(shorthand);
+        }
+
+        let start = self.position();
+        ty.sty.encode(self)?;
+        let len = self.position() - start;
+
+        let discriminant = unsafe { intrinsics::discriminant_value(&ty.sty) };
+        assert!(discriminant < SHORTHAND_OFFSET as u64);
+        let shorthand = start + SHORTHAND_OFFSET;
+
+        // Only use the shorthand if it is not longer than the type itself,
+        // going by the number of bits leb128 fits in the same space.
+        let leb128_bits = len * 7;
+        if leb128_bits >= 64 || (shorthand as u64) < (1 << leb128_bits) {
+            self.type_shorthands.insert(*ty, shorthand);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'enc, 'a, 'tcx> SpecializedEncoder<DefId> for CacheEncoder<'enc, 'a, 'tcx> {
+    fn specialized_encode(&mut self, def_id: &DefId) -> Result<(), Self::Error> {
+        self.tcx.def_path_hash(*def_id).encode(self)
+    }
+}
+
+impl<'enc, 'a, 'tcx> SpecializedEncoder<CrateNum> for CacheEncoder<'enc, 'a, 'tcx> {
+    fn specialized_encode(&mut self, cnum: &CrateNum) -> Result<(), Self::Error> {
+        cnum.as_def_id().encode(self)
+    }
+}
+
+impl<'enc, 'a, 'tcx> SpecializedEncoder<ast::NodeId> for CacheEncoder<'enc, 'a, 'tcx> {
+    fn specialized_encode(&mut self, node_id: &ast::NodeId) -> Result<(), Self::Error> {
+        let hir::HirId { owner, local_id } = self.tcx.hir.definitions().node_to_hir_id(*node_id);
+        let owner = self.tcx.hir.definitions().def_path_hash(owner);
+        owner.encode(self)?;
+        local_id.encode(self)
+    }
+}
+
+impl<'enc, 'a, 'tcx> SpecializedEncoder<Span> for CacheEncoder<'enc, 'a, 'tcx> {
+    fn specialized_encode(&mut self, span: &Span) -> Result<(), Self::Error> {
+        let tcx = self.tcx;
+        let codemap = tcx.sess.codemap();
+        if *span == DUMMY_SP || span.lo > span.hi || codemap.files().is_empty() {
+            return TAG_INVALID_SPAN.encode(self);
+        }
+
+        // Spans that cross files can't be described relative to one.
+        let filemap = codemap.lookup_byte_offset(span.lo).fm;
+        if span.hi > filemap.end_pos {
+            return TAG_INVALID_SPAN.encode(self);
+        }
+        let line = match filemap.lookup_line(span.lo) {
+            Some(line) => line,
+            None => return TAG_INVALID_SPAN.encode(self),
+        };
+        let column = span.lo - filemap.lines.borrow()[line];
+        let file_index = self.file_index(&filemap);
+
+        TAG_VALID_SPAN.encode(self)?;
+        file_index.encode(self)?;
+        line.encode(self)?;
+        column.encode(self)?;
+        (span.hi - span.lo).encode(self)?;
+        self.encode_expansion_info(span.ctxt)
+    }
+}
+
+impl<'enc, 'a, 'tcx> CacheEncoder<'enc, 'a, 'tcx> {
+    /// Encodes the expansion info of the outermost mark of `ctxt`, or where
+    /// it was already encoded.
+    fn encode_expansion_info(&mut self, ctxt: SyntaxContext) -> io::Result<()> {
+        if ctxt == SyntaxContext::empty() {
+            return TAG_NO_EXPANSION_INFO.encode(self);
+        }
+        if let Some(&pos) = self.expn_info_shorthands.get(&ctxt) {
+            TAG_EXPANSION_INFO_SHORTHAND.encode(self)?;
+            return pos.encode(self);
+        }
+        let expn_info = match ctxt.outer().expn_info() {
+            Some(expn_info) => expn_info,
+            None => return TAG_NO_EXPANSION_INFO.encode(self),
+        };
+
+        TAG_EXPANSION_INFO_INLINE.encode(self)?;
+        let pos = self.position();
+        self.expn_info_shorthands.insert(ctxt, pos);
+        expn_info.encode(self)
+    }
+}
+
+//- DECODING -------------------------------------------------------------------
+
+pub struct CacheDecoder<'x, 'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    opaque: opaque::Decoder<'x>,
+    codemap: &'x CodeMap,
+    file_names: &'x [String],
+    filemaps: FxHashMap<usize, Option<Rc<FileMap>>>,
+    hir_to_node_id: &'x FxHashMap<hir::HirId, ast::NodeId>,
+    type_shorthands: FxHashMap<usize, Ty<'tcx>>,
+    synthetic_expansion_infos: &'x Lock<FxHashMap<usize, SyntaxContext>>,
+}
+
+impl<'x, 'a, 'tcx> CacheDecoder<'x, 'a, 'tcx> {
+    fn with_position<F: FnOnce(&mut Self) -> R, R>(&mut self, pos: usize, f: F) -> R {
+        let new_opaque = opaque::Decoder::new(self.opaque.data, pos);
+        let old_opaque = mem::replace(&mut self.opaque, new_opaque);
+        let r = f(self);
+        self.opaque = old_opaque;
+        r
+    }
+
+    fn def_id_for_hash(&self, def_path_hash: DefPathHash) -> Result<DefId, String> {
+        let def_path_hash_to_def_id = self.tcx.def_path_hash_to_def_id.as_ref().unwrap();
+        match def_path_hash_to_def_id.get(&def_path_hash) {
+            Some(&def_id) => Ok(def_id),
+            None => Err(format!("no definition with the hash {:?}", def_path_hash)),
+        }
+    }
+
+    /// The file of this session with the name of file `index` of the
+    /// previous one, if there still is one.
+    fn filemap(&mut self, index: usize) -> Option<Rc<FileMap>> {
+        let codemap = self.codemap;
+        let file_names = self.file_names;
+        self.filemaps.entry(index).or_insert_with(|| {
+            file_names.get(index).and_then(|name| codemap.get_filemap(name))
+        }).clone()
+    }
+}
+
+macro_rules! decoder_methods {
+    ($($name:ident -> $ty:ty;)*) => {
+        $(fn $name(&mut self) -> Result<$ty, Self::Error> {
+            self.opaque.$name()
+        })*
+    }
+}
+
+impl<'x, 'a, 'tcx> Decoder for CacheDecoder<'x, 'a, 'tcx> {
+    type Error = <opaque::Decoder<'x> as Decoder>::Error;
+
+    decoder_methods! {
+        read_nil -> ();
+
+        read_u128 -> u128;
+        read_u64 -> u64;
+        read_u32 -> u32;
+        read_u16 -> u16;
+        read_u8 -> u8;
+        read_usize -> usize;
+
+        read_i128 -> i128;
+        read_i64 -> i64;
+        read_i32 -> i32;
+        read_i16 -> i16;
+        read_i8 -> i8;
+        read_isize -> isize;
+
+        read_bool -> bool;
+        read_f64 -> f64;
+        read_f32 -> f32;
+        read_char -> char;
+        read_str -> Cow<str>;
+    }
+
+    fn error(&mut self, err: &str) -> Self::Error {
+        self.opaque.error(err)
+    }
+}
+
+// FIXME(#36588) These impls are horribly unsound as they allow
+// the caller to pick any lifetime for 'tcx, including 'static,
+// by using the unspecialized proxies to them.
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<Ty<'tcx>> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<Ty<'tcx>, Self::Error> {
+        // Handle shorthands first, if we have an usize > 0x80.
+        if self.opaque.data[self.opaque.position()] & 0x80 != 0 {
+            let pos = self.read_usize()?;
+            assert!(pos >= SHORTHAND_OFFSET);
+            let pos = pos - SHORTHAND_OFFSET;
+            if let Some(&ty) = self.type_shorthands.get(&pos) {
+                return Ok(ty);
+            }
+
+            let ty = self.with_position(pos, Ty::decode)?;
+            self.type_shorthands.insert(pos, ty);
+            Ok(ty)
+        } else {
+            Ok(self.tcx.mk_ty(ty::TypeVariants::decode(self)?))
+        }
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<&'tcx Substs<'tcx>> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<&'tcx Substs<'tcx>, Self::Error> {
+        let tcx = self.tcx;
+        Ok(tcx.mk_substs((0..self.read_usize()?).map(|_| Decodable::decode(self)))?)
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<ty::Region<'tcx>> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<ty::Region<'tcx>, Self::Error> {
+        Ok(self.tcx.mk_region(Decodable::decode(self)?))
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<&'tcx ty::Slice<Ty<'tcx>>>
+    for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<&'tcx ty::Slice<Ty<'tcx>>, Self::Error> {
+        let tcx = self.tcx;
+        Ok(tcx.mk_type_list((0..self.read_usize()?).map(|_| Decodable::decode(self)))?)
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<&'tcx ty::AdtDef> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<&'tcx ty::AdtDef, Self::Error> {
+        let def_id = DefId::decode(self)?;
+        Ok(self.tcx.adt_def(def_id))
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<&'tcx ty::Slice<ty::ExistentialPredicate<'tcx>>>
+    for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self)
+        -> Result<&'tcx ty::Slice<ty::ExistentialPredicate<'tcx>>, Self::Error> {
+        let tcx = self.tcx;
+        Ok(tcx.mk_existential_predicates((0..self.read_usize()?)
+                                         .map(|_| Decodable::decode(self)))?)
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<DefId> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<DefId, Self::Error> {
+        let def_path_hash = DefPathHash::decode(self)?;
+        self.def_id_for_hash(def_path_hash)
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<CrateNum> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<CrateNum, Self::Error> {
+        let crate_root = DefId::decode(self)?;
+        debug_assert_eq!(crate_root.index, CRATE_DEF_INDEX);
+        Ok(crate_root.krate)
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<ast::NodeId> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<ast::NodeId, Self::Error> {
+        let owner = self.def_id_for_hash(DefPathHash::decode(self)?)?;
+        debug_assert_eq!(owner.krate, LOCAL_CRATE);
+        let hir_id = hir::HirId {
+            owner: owner.index,
+            local_id: hir::ItemLocalId::decode(self)?,
+        };
+        match self.hir_to_node_id.get(&hir_id) {
+            Some(&node_id) => Ok(node_id),
+            None => Err(format!("no node with the id {:?}", hir_id)),
+        }
+    }
+}
+
+impl<'x, 'a, 'tcx> SpecializedDecoder<Span> for CacheDecoder<'x, 'a, 'tcx> {
+    fn specialized_decode(&mut self) -> Result<Span, Self::Error> {
+        let tag = u8::decode(self)?;
+        if tag == TAG_INVALID_SPAN {
+            return Ok(DUMMY_SP);
+        }
+        debug_assert_eq!(tag, TAG_VALID_SPAN);
+
+        let file_index = usize::decode(self)?;
+        let line = usize::decode(self)?;
+        let column = BytePos::decode(self)?;
+        let len = BytePos::decode(self)?;
+        let ctxt = self.decode_expansion_info()?;
+
+        // The result was found to be unchanged, so the file should still be
+        // there, but be careful not to make up a span past its end.
+        let filemap = match self.filemap(file_index) {
+            Some(filemap) => filemap,
+            None => return Ok(DUMMY_SP),
+        };
+        let line_start = match filemap.lines.borrow().get(line) {
+            Some(&line_start) => line_start,
+            None => return Ok(DUMMY_SP),
+        };
+        let lo = line_start + column;
+        let hi = lo + len;
+        if hi > filemap.end_pos {
+            return Ok(DUMMY_SP);
+        }
+        Ok(Span { lo, hi, ctxt })
+    }
+}
+
+impl<'x, 'a, 'tcx> CacheDecoder<'x, 'a, 'tcx> {
+    /// Decodes what `encode_expansion_info` wrote, into a syntax context
+    /// with a single mark carrying the expansion info.
+    fn decode_expansion_info(&mut self) -> Result<SyntaxContext, String> {
+        let (pos, expn_info) = match u8::decode(self)? {
+            TAG_NO_EXPANSION_INFO => return Ok(SyntaxContext::empty()),
+            TAG_EXPANSION_INFO_INLINE => {
+                // Decoded even if it was seen before, to skip over it.
+                let pos = self.opaque.position();
+                (pos, ExpnInfo::decode(self)?)
+            }
+            TAG_EXPANSION_INFO_SHORTHAND => {
+                let pos = usize::decode(self)?;
+                if let Some(&ctxt) = self.synthetic_expansion_infos.lock().get(&pos) {
+                    return Ok(ctxt);
+                }
+                // The call site is a span itself, so the lock can't be held
+                // while decoding it.
+                (pos, self.with_position(pos, ExpnInfo::decode)?)
+            }
+            tag => return Err(format!("invalid expansion info tag {}", tag)),
+        };
+
+        if let Some(&ctxt) = self.synthetic_expansion_infos.lock().get(&pos) {
+            return Ok(ctxt);
+        }
+        let mark = Mark::fresh(Mark::root());
+        mark.set_expn_info(expn_info);
+        let ctxt = SyntaxContext::empty().apply_mark(mark);
+        self.synthetic_expansion_infos.lock().insert(pos, ctxt);
+        Ok(ctxt)
+    }
+}
+
@@ -0,0 +1,227 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Self-profiling of the compiler, enabled with `-Z self-profile`.
+//!
+//! While active, the profiler records when every query in `ty::maps` starts
+//! and finishes computing, which provider computed it, and every time a
+//! query is answered from its cache instead. The major phases of the driver
+//! are recorded as well. At the end of the compilation the events are
+//! written out in the Chrome trace-event format, which can be loaded into
+//! `chrome://tracing`, and a summary of the queries that took the most time
+//! is printed.
+
+use util::common::duration_to_secs_str;
+use util::nodemap::FxHashMap;
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// How many queries the summary table shows.
+const SUMMARY_ROWS: usize = 20;
+
+/// Which set of providers computed a query: the ones for the local crate,
+/// or the ones loading the result from the metadata of an extern crate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProviderKind {
+    Local,
+    Extern,
+}
+
+impl ProviderKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProviderKind::Local => "local",
+            ProviderKind::Extern => "extern",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ProfilerEvent {
+    ActivityStart { name: &'static str, time: Duration, thread: usize },
+    ActivityEnd { name: &'static str, time: Duration, thread: usize },
+    QueryStart { query: &'static str, provider: ProviderKind, time: Duration, thread: usize },
+    QueryEnd { query: &'static str, time: Duration, thread: usize },
+    QueryCacheHit { query: &'static str, time: Duration, thread: usize },
+}
+
+/// The statistics gathered for a single query in the summary table.
+#[derive(Clone, Default)]
+struct QueryStats {
+    self_time: Duration,
+    executions: usize,
+    cache_hits: usize,
+}
+
+pub struct SelfProfiler {
+    start: Instant,
+    events: Vec<ProfilerEvent>,
+    // Small, stable numbers for the threads events are recorded on, which
+    // is what trace viewers expect as thread ids.
+    threads: FxHashMap<ThreadId, usize>,
+}
+
+impl SelfProfiler {
+    pub fn new() -> SelfProfiler {
+        SelfProfiler {
+            start: Instant::now(),
+            events: Vec::new(),
+            threads: FxHashMap(),
+        }
+    }
+
+    fn now(&mut self) -> (Duration, usize) {
+        let time = self.start.elapsed();
+        let next = self.threads.len();
+        let thread = *self.threads.entry(thread::current().id()).or_insert(next);
+        (time, thread)
+    }
+
+    pub fn start_activity(&mut self, name: &'static str) {
+        let (time, thread) = self.now();
+        self.events.push(ProfilerEvent::ActivityStart { name, time, thread });
+    }
+
+    pub fn end_activity(&mut self, name: &'static str) {
+        let (time, thread) = self.now();
+        self.events.push(ProfilerEvent::ActivityEnd { name, time, thread });
+    }
+
+    pub fn start_query(&mut self, query: &'static str, provider: ProviderKind) {
+        let (time, thread) = self.now();
+        self.events.push(ProfilerEvent::QueryStart { query, provider, time, thread });
+    }
+
+    pub fn end_query(&mut self, query: &'static str) {
+        let (time, thread) = self.now();
+        self.events.push(ProfilerEvent::QueryEnd { query, time, thread });
+    }
+
+    pub fn record_cache_hit(&mut self, query: &'static str) {
+        let (time, thread) = self.now();
+        self.events.push(ProfilerEvent::QueryCacheHit { query, time, thread });
+    }
+
+    /// Writes all recorded events to `path` as a Chrome trace.
+    pub fn save_trace(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        write!(file, "[")?;
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                write!(file, ",")?;
+            }
+            write!(file, "\n")?;
+            match *event {
+                ProfilerEvent::ActivityStart { name, time, thread } => {
+                    write_trace_event(&mut file, name, "phase", "B", time, thread, "")?
+                }
+                ProfilerEvent::ActivityEnd { name, time, thread } => {
+                    write_trace_event(&mut file, name, "phase", "E", time, thread, "")?
+                }
+                ProfilerEvent::QueryStart { query, provider, time, thread } => {
+                    let args = format!(",\"args\":{{\"provider\":\"{}\"}}", provider.as_str());
+                    write_trace_event(&mut file, query, "query", "B", time, thread, &args)?
+                }
+                ProfilerEvent::QueryEnd { query, time, thread } => {
+                    write_trace_event(&mut file, query, "query", "E", time, thread, "")?
+                }
+                ProfilerEvent::QueryCacheHit { query, time, thread } => {
+                    write_trace_event(&mut file, query, "cache hit", "i", time, thread,
+                                      ",\"s\":\"t\"")?
+                }
+            }
+        }
+        write!(file, "\n]\n")?;
+        file.flush()
+    }
+
+    /// Prints the queries that took the most time to compute themselves,
+    /// i.e. not counting the time spent in the queries they called.
+    pub fn print_summary(&self) {
+        let stats = self.query_stats();
+        let mut queries: Vec<_> = stats.iter().collect();
+        queries.sort_by(|&(a_name, a), &(b_name, b)| {
+            b.self_time.cmp(&a.self_time).then(a_name.cmp(b_name))
+        });
+
+        println!("{:<40} {:>12} {:>12} {:>12} {:>10}",
+                 "Query", "Self time", "Executions", "Cache hits", "Hit rate");
+        for &(query, stats) in queries.iter().take(SUMMARY_ROWS) {
+            let total = stats.executions + stats.cache_hits;
+            let hit_rate = if total == 0 {
+                0.0
+            } else {
+                stats.cache_hits as f64 * 100.0 / total as f64
+            };
+            println!("{:<40} {:>12} {:>12} {:>12} {:>9.2}%",
+                     query,
+                     duration_to_secs_str(stats.self_time),
+                     stats.executions,
+                     stats.cache_hits,
+                     hit_rate);
+        }
+    }
+
+    fn query_stats(&self) -> FxHashMap<&'static str, QueryStats> {
+        let mut stats: FxHashMap<&'static str, QueryStats> = FxHashMap();
+        // For every thread, the queries currently executing on it, along with
+        // when they started and how long the queries they called took.
+        let mut stacks: FxHashMap<usize, Vec<(&'static str, Duration, Duration)>> =
+            FxHashMap();
+
+        for event in &self.events {
+            match *event {
+                ProfilerEvent::QueryStart { query, time, thread, .. } => {
+                    stats.entry(query).or_insert_with(QueryStats::default).executions += 1;
+                    stacks.entry(thread).or_insert_with(Vec::new)
+                          .push((query, time, Duration::new(0, 0)));
+                }
+                ProfilerEvent::QueryEnd { time, thread, .. } => {
+                    let stack = stacks.entry(thread).or_insert_with(Vec::new);
+                    let (query, start, children) = match stack.pop() {
+                        Some(frame) => frame,
+                        None => continue,
+                    };
+                    let elapsed = time - start;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.2 += elapsed;
+                    }
+                    stats.entry(query).or_insert_with(QueryStats::default).self_time +=
+                        elapsed - children;
+                }
+                ProfilerEvent::QueryCacheHit { query, .. } => {
+                    stats.entry(query).or_insert_with(QueryStats::default).cache_hits += 1;
+                }
+                ProfilerEvent::ActivityStart { .. } |
+                ProfilerEvent::ActivityEnd { .. } => {}
+            }
+        }
+
+        stats
+    }
+}
+
+fn write_trace_event<W: Write>(out: &mut W,
+                               name: &str,
+                               category: &str,
+                               phase: &str,
+                               time: Duration,
+                               thread: usize,
+                               extra: &str)
+                               -> io::Result<()> {
+    let micros = time.as_secs() * 1_000_000 + (time.subsec_nanos() / 1_000) as u64;
+    write!(out,
+           "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"{}\",\"ts\":{},\"pid\":0,\"tid\":{}{}}}",
+           name, category, phase, micros, thread, extra)
+}
@@ -0,0 +1,67 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// The wasm32-unknown-unknown target is WebAssembly with no assumptions about
+// the environment it runs in: no Emscripten runtime, no libc, and nothing
+// besides what the embedder chooses to import into the module. Code is
+// generated by LLVM's wasm backend directly and linked with LLD.
+
+use {LinkerFlavor, PanicStrategy};
+use super::{LinkArgs, Target, TargetOptions};
+
+pub fn target() -> Result<Target, String> {
+    let mut pre_link_args = LinkArgs::new();
+    pre_link_args.insert(LinkerFlavor::WasmLd,
+                         vec!["-flavor".to_string(), "wasm".to_string()]);
+
+    let opts = TargetOptions {
+        linker: "lld".to_string(),
+        pre_link_args: pre_link_args,
+
+        // A cdylib is the way to get a wasm module exporting some symbols,
+        // there are no intermediate Rust `dylib`s.
+        dynamic_linking: true,
+        only_cdylib: true,
+
+        // Executables are modules exporting their `main` function.
+        executables: true,
+
+        exe_suffix: ".wasm".to_string(),
+        dll_prefix: "".to_string(),
+        dll_suffix: ".wasm".to_string(),
+        linker_is_gnu: false,
+
+        // Wasm has no threads, and without them no atomic instructions
+        // either, so have LLVM lower atomics to plain memory operations.
+        max_atomic_width: Some(32),
+        singlethread: true,
+
+        // There is no unwinding support in wasm yet.
+        panic_strategy: PanicStrategy::Abort,
+
+        // Modules are not relocated, so there is no need for PIC.
+        relocation_model: "static".to_string(),
+
+        allow_asm: false,
+        .. Default::default()
+    };
+    Ok(Target {
+        llvm_target: "wasm32-unknown-unknown-wasm".to_string(),
+        target_endian: "little".to_string(),
+        target_pointer_width: "32".to_string(),
+        target_os: "unknown".to_string(),
+        target_env: "".to_string(),
+        target_vendor: "unknown".to_string(),
+        data_layout: "e-m:e-p:32:32-i64:64-n32:64-S128".to_string(),
+        arch: "wasm32".to_string(),
+        linker_flavor: LinkerFlavor::WasmLd,
+        options: opts,
+    })
+}
@@ -777,5 +777,14 @@ fn const_eval<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     } else {
         tcx.sess.cstore.item_body(tcx, def_id)
     };
+
+    // Running the MIR of the constant supports everything `const fn`s can
+    // do, and points errors at the calls that led to them; the HIR evaluator
+    // is only used for what the interpreter doesn't support.
+    if !tables.tainted_by_errors {
+        if let Some(result) = ::interpret::eval_body(tcx, key.param_env, def_id, substs) {
+            return result;
+        }
+    }
     ConstContext::new(tcx, key.param_env.and(substs), tables).eval(&body.value)
 }
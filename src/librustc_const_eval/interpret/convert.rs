@@ -0,0 +1,218 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between `ConstVal`s and the interpreter's memory.
+
+use rustc::middle::const_val::ConstVal;
+use rustc::ty::{self, Ty};
+use rustc_const_math::{ConstFloat, ConstInt};
+use syntax::ast::FloatTy;
+use syntax::symbol::Symbol;
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str;
+
+use super::{EvalContext, EvalError, EvalResult, Pointer, PrimVal, Value};
+use super::value::{sign_extend, truncate};
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    /// The value of the constant `val` of type `ty`.
+    pub fn const_to_value(&mut self, val: &ConstVal<'tcx>, ty: Ty<'tcx>)
+                          -> EvalResult<'tcx, Value> {
+        let primval = match *val {
+            ConstVal::Integral(i) => {
+                PrimVal::Bytes(truncate(i.to_u128_unchecked(), self.size_of(ty)?))
+            }
+            ConstVal::Float(ConstFloat::F32(f)) => PrimVal::from_f32(f),
+            ConstVal::Float(ConstFloat::F64(f)) => PrimVal::from_f64(f),
+            ConstVal::Bool(b) => PrimVal::from_bool(b),
+            ConstVal::Char(c) => PrimVal::from_char(c),
+            ConstVal::Str(ref s) => {
+                let ptr = self.memory.allocate_bytes(s.as_bytes());
+                let len = PrimVal::from_u64(s.len() as u64);
+                return Ok(Value::ByValPair(PrimVal::Ptr(ptr), len));
+            }
+            ConstVal::ByteStr(ref bytes) => {
+                let ptr = PrimVal::Ptr(self.memory.allocate_bytes(bytes));
+                return Ok(match self.scalar_pair(ty) {
+                    Some(true) => Value::ByValPair(ptr, PrimVal::from_u64(bytes.len() as u64)),
+                    _ => Value::ByVal(ptr),
+                });
+            }
+            ConstVal::Function(def_id, substs) => {
+                match ty.sty {
+                    ty::TyFnPtr(_) => {
+                        let instance = self.resolve(def_id, substs)?;
+                        PrimVal::Ptr(self.memory.create_fn_alloc(instance))
+                    }
+                    // Function items are zero-sized.
+                    _ => PrimVal::Undef,
+                }
+            }
+            ConstVal::Variant(_) |
+            ConstVal::Struct(_) |
+            ConstVal::Tuple(_) |
+            ConstVal::Array(_) |
+            ConstVal::Repeat(..) => {
+                let ptr = self.allocate(ty)?;
+                self.write_const_val(val, ptr, ty)?;
+                return Ok(Value::ByRef(ptr));
+            }
+        };
+        Ok(Value::ByVal(primval))
+    }
+
+    /// Writes the constant `val` of type `ty` to `dest`.
+    pub fn write_const_val(&mut self, val: &ConstVal<'tcx>, dest: Pointer, ty: Ty<'tcx>)
+                           -> EvalResult<'tcx> {
+        let dl = self.data_layout();
+        match *val {
+            ConstVal::Variant(variant_did) => {
+                let variant = match ty.sty {
+                    ty::TyAdt(adt, _) => adt.variant_index_with_id(variant_did),
+                    _ => bug!("write_const_val: variant of non-enum `{}`", ty),
+                };
+                self.write_discriminant(dest, ty, variant)
+            }
+            ConstVal::Struct(ref fields) => {
+                let adt = match ty.sty {
+                    ty::TyAdt(adt, _) => adt,
+                    _ => bug!("write_const_val: struct of non-ADT `{}`", ty),
+                };
+                for (i, field) in adt.struct_variant().fields.iter().enumerate() {
+                    if let Some(field_val) = fields.get(&field.name) {
+                        let offset = self.field_offset(ty, None, i)?;
+                        let field_ty = self.field_ty(ty, None, i)?;
+                        self.write_const_val(field_val, dest.offset(offset, dl)?, field_ty)?;
+                    }
+                }
+                Ok(())
+            }
+            ConstVal::Tuple(ref fields) => {
+                for (i, field_val) in fields.iter().enumerate() {
+                    let offset = self.field_offset(ty, None, i)?;
+                    let field_ty = self.field_ty(ty, None, i)?;
+                    self.write_const_val(field_val, dest.offset(offset, dl)?, field_ty)?;
+                }
+                Ok(())
+            }
+            ConstVal::Array(ref elems) => {
+                let elem_ty = ty.sequence_element_type(self.tcx);
+                let elem_size = self.size_of(elem_ty)?;
+                for (i, elem) in elems.iter().enumerate() {
+                    self.write_const_val(elem, dest.offset(i as u64 * elem_size, dl)?, elem_ty)?;
+                }
+                Ok(())
+            }
+            ConstVal::Repeat(ref elem, count) => {
+                let elem_ty = ty.sequence_element_type(self.tcx);
+                let elem_size = self.size_of(elem_ty)?;
+                for i in 0..count {
+                    self.write_const_val(elem, dest.offset(i * elem_size, dl)?, elem_ty)?;
+                }
+                Ok(())
+            }
+            _ => {
+                let value = self.const_to_value(val, ty)?;
+                self.write_value(value, dest, ty)
+            }
+        }
+    }
+
+    fn read_primval_of(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, PrimVal> {
+        let value = self.read_value(ptr, ty)?;
+        self.value_to_primval(value, ty)
+    }
+
+    /// Reads the value of type `ty` at `ptr` back into a `ConstVal`.
+    pub fn read_const_val(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, ConstVal<'tcx>> {
+        let tcx = self.tcx;
+        let dl = self.data_layout();
+        Ok(match ty.sty {
+            ty::TyBool => ConstVal::Bool(self.read_primval_of(ptr, ty)?.to_bool()?),
+            ty::TyChar => ConstVal::Char(self.read_primval_of(ptr, ty)?.to_char()?),
+            ty::TyInt(ity) => {
+                let bits = self.read_primval_of(ptr, ty)?.to_bytes()?;
+                let value = sign_extend(bits, self.size_of(ty)?);
+                let int_type = tcx.sess.target.int_type;
+                ConstVal::Integral(ConstInt::new_signed_truncating(value, ity, int_type))
+            }
+            ty::TyUint(uty) => {
+                let bits = self.read_primval_of(ptr, ty)?.to_bytes()?;
+                let uint_type = tcx.sess.target.uint_type;
+                ConstVal::Integral(ConstInt::new_unsigned_truncating(bits, uty, uint_type))
+            }
+            ty::TyFloat(FloatTy::F32) => {
+                ConstVal::Float(ConstFloat::F32(self.read_primval_of(ptr, ty)?.to_f32()?))
+            }
+            ty::TyFloat(FloatTy::F64) => {
+                ConstVal::Float(ConstFloat::F64(self.read_primval_of(ptr, ty)?.to_f64()?))
+            }
+            ty::TyFnDef(def_id, substs) => ConstVal::Function(def_id, substs),
+            ty::TyFnPtr(_) => {
+                let instance = self.memory.get_fn(self.read_primval_of(ptr, ty)?.to_ptr()?)?;
+                ConstVal::Function(instance.def_id(), instance.substs)
+            }
+            ty::TyRef(_, ty::TypeAndMut { ty: pointee, .. }) => {
+                match (self.read_value(ptr, ty)?, &pointee.sty) {
+                    (Value::ByValPair(data, len), &ty::TyStr) => {
+                        let bytes = self.memory.read_bytes(data.to_ptr()?, len.to_u64()?, dl)?;
+                        let s = str::from_utf8(bytes).map_err(|_| EvalError::InvalidUtf8)?;
+                        ConstVal::Str(Symbol::intern(s).as_str())
+                    }
+                    (Value::ByVal(data), &ty::TyArray(elem_ty, len))
+                            if elem_ty == tcx.types.u8 => {
+                        let bytes = self.memory.read_bytes(data.to_ptr()?, len as u64, dl)?;
+                        ConstVal::ByteStr(Rc::new(bytes.to_vec()))
+                    }
+                    _ => {
+                        return Err(EvalError::Unimplemented(format!("constants of type `{}`",
+                                                                    ty)));
+                    }
+                }
+            }
+            ty::TyTuple(tys, _) => {
+                let mut fields = Vec::with_capacity(tys.len());
+                for (i, &field_ty) in tys.iter().enumerate() {
+                    let offset = self.field_offset(ty, None, i)?;
+                    fields.push(self.read_const_val(ptr.offset(offset, dl)?, field_ty)?);
+                }
+                ConstVal::Tuple(fields)
+            }
+            ty::TyArray(elem_ty, len) => {
+                let elem_size = self.size_of(elem_ty)?;
+                let mut elems = Vec::with_capacity(len);
+                for i in 0..len as u64 {
+                    elems.push(self.read_const_val(ptr.offset(i * elem_size, dl)?, elem_ty)?);
+                }
+                ConstVal::Array(elems)
+            }
+            ty::TyAdt(adt, _) if adt.is_enum() => {
+                let variant = &adt.variants[self.read_variant_index(ptr, ty)?];
+                if !variant.fields.is_empty() {
+                    return Err(EvalError::Unimplemented("enum variants with fields".to_owned()));
+                }
+                ConstVal::Variant(variant.did)
+            }
+            ty::TyAdt(adt, _) if adt.is_struct() && !adt.is_box() => {
+                let mut fields = BTreeMap::new();
+                for (i, field) in adt.struct_variant().fields.iter().enumerate() {
+                    let offset = self.field_offset(ty, None, i)?;
+                    let field_ty = self.field_ty(ty, None, i)?;
+                    let field_val = self.read_const_val(ptr.offset(offset, dl)?, field_ty)?;
+                    fields.insert(field.name, field_val);
+                }
+                ConstVal::Struct(fields)
+            }
+            _ => return Err(EvalError::Unimplemented(format!("constants of type `{}`", ty))),
+        })
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use rustc::middle::const_val::ConstEvalErr;
+use rustc::ty::layout::LayoutError;
+use rustc_const_math::ConstMathErr;
+
+use std::fmt;
+
+use super::Pointer;
+
+pub type EvalResult<'tcx, T = ()> = Result<T, EvalError<'tcx>>;
+
+#[derive(Clone, Debug)]
+pub enum EvalError<'tcx> {
+    // Undefined behaviour.
+    DanglingPointerDeref,
+    DeadLocal,
+    InvalidFunctionPointer,
+    InvalidBool(u128),
+    InvalidChar(u128),
+    InvalidDiscriminant(u128),
+    InvalidUtf8,
+    PointerOutOfBounds { ptr: Pointer, size: u64, allocation_size: u64 },
+    AlignmentCheckFailed { required: u64, has: u64 },
+    ReadPointerAsBytes,
+    ReadBytesAsPointer,
+    ReadUndefBytes,
+    PartialPointerOverwrite,
+    InvalidPointerMath,
+    ModifiedConstantMemory,
+    FloatToIntOverflow,
+    Unreachable,
+
+    // Panics the MIR asks for.
+    Math(ConstMathErr),
+    IndexOutOfBounds { len: u64, index: u64 },
+    Panic,
+
+    // Evaluation limits.
+    StackFrameLimitReached,
+    ExecutionTimeLimitReached,
+
+    /// A constant referred to by the evaluated code failed to evaluate.
+    ReferencedConstant(ConstEvalErr<'tcx>),
+
+    // Things the interpreter cannot evaluate (yet), for which the HIR
+    // evaluator is used instead.
+    Unimplemented(String),
+    TooGeneric,
+    NoMirFor(String),
+    Layout(LayoutError<'tcx>),
+}
+
+impl<'tcx> EvalError<'tcx> {
+    /// Whether evaluation stopped because the interpreter does not support
+    /// something, rather than because the evaluated code is wrong.
+    pub fn is_unsupported(&self) -> bool {
+        match *self {
+            EvalError::Unimplemented(_) |
+            EvalError::TooGeneric |
+            EvalError::NoMirFor(_) |
+            EvalError::Layout(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<'tcx> From<LayoutError<'tcx>> for EvalError<'tcx> {
+    fn from(err: LayoutError<'tcx>) -> EvalError<'tcx> {
+        EvalError::Layout(err)
+    }
+}
+
+impl<'tcx> fmt::Display for EvalError<'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::EvalError::*;
+        match *self {
+            DanglingPointerDeref =>
+                write!(f, "dangling pointer was dereferenced"),
+            DeadLocal =>
+                write!(f, "tried to access a dead local variable"),
+            InvalidFunctionPointer =>
+                write!(f, "tried to use a pointer as a function pointer"),
+            InvalidBool(b) =>
+                write!(f, "invalid boolean value read: {}", b),
+            InvalidChar(c) =>
+                write!(f, "tried to interpret an invalid 32-bit value as a char: {}", c),
+            InvalidDiscriminant(d) =>
+                write!(f, "invalid enum discriminant value read: {}", d),
+            InvalidUtf8 =>
+                write!(f, "string constant is not valid UTF-8"),
+            PointerOutOfBounds { ptr, size, allocation_size } =>
+                write!(f, "memory access of {}..{} outside bounds of allocation {} \
+                           which has size {}",
+                       ptr.offset, ptr.offset + size, ptr.alloc_id, allocation_size),
+            AlignmentCheckFailed { required, has } =>
+                write!(f, "tried to access memory with alignment {}, but alignment {} \
+                           is required",
+                       has, required),
+            ReadPointerAsBytes =>
+                write!(f, "a raw memory access tried to access part of a pointer value \
+                           as raw bytes"),
+            ReadBytesAsPointer =>
+                write!(f, "a memory access tried to interpret some bytes as a pointer"),
+            ReadUndefBytes =>
+                write!(f, "attempted to read undefined bytes"),
+            PartialPointerOverwrite =>
+                write!(f, "attempted to overwrite part of a pointer"),
+            InvalidPointerMath =>
+                write!(f, "attempted to do invalid arithmetic on pointers that would leak \
+                           base addresses, e.g. comparing pointers into different \
+                           allocations"),
+            ModifiedConstantMemory =>
+                write!(f, "tried to modify constant memory"),
+            FloatToIntOverflow =>
+                write!(f, "float to integer cast overflowed"),
+            Unreachable =>
+                write!(f, "entered unreachable code"),
+            Math(ref err) =>
+                write!(f, "{}", err.description()),
+            IndexOutOfBounds { len, index } =>
+                write!(f, "index out of bounds: the len is {} but the index is {}", len, index),
+            Panic =>
+                write!(f, "the evaluated program panicked"),
+            StackFrameLimitReached =>
+                write!(f, "reached the configured maximum number of stack frames"),
+            ExecutionTimeLimitReached =>
+                write!(f, "constant evaluation took too long"),
+            ReferencedConstant(_) =>
+                write!(f, "could not evaluate referenced constant"),
+            Unimplemented(ref what) =>
+                write!(f, "unimplemented constant expression: {}", what),
+            TooGeneric =>
+                write!(f, "encountered overly generic constant"),
+            NoMirFor(ref path) =>
+                write!(f, "no MIR for `{}`", path),
+            Layout(ref err) =>
+                write!(f, "{}", err),
+        }
+    }
+}
@@ -0,0 +1,693 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use rustc::hir::def::Def;
+use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::{ConstEvalErr, ErrKind, FrameInfo};
+use rustc::mir;
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
+use rustc::ty::layout::{Layout, LayoutCx, TargetDataLayout, TyLayout};
+use rustc::ty::subst::{Subst, Substs};
+use rustc::ty::util::IntTypeExt;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use syntax_pos::Span;
+
+use std::collections::HashMap;
+
+use super::{EvalError, EvalResult, Memory, Pointer, PrimVal, Value};
+
+/// The number of statements and terminators a single evaluation may execute.
+const STEP_LIMIT: u64 = 1_000_000;
+
+pub struct EvalContext<'a, 'tcx: 'a> {
+    pub tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    pub param_env: ty::ParamEnv<'tcx>,
+    pub memory: Memory<'tcx>,
+    stack: Vec<Frame<'tcx>>,
+    /// The allocations holding the statics used so far.
+    statics: HashMap<DefId, Pointer>,
+    steps_remaining: u64,
+}
+
+/// An activation of a MIR body: a function call, or the evaluation of a
+/// constant, static or promoted value.
+pub struct Frame<'tcx> {
+    pub mir: &'tcx mir::Mir<'tcx>,
+    pub def_id: DefId,
+    pub substs: &'tcx Substs<'tcx>,
+    /// The allocation of every local that is currently live. The return
+    /// pointer is owned by the caller.
+    pub locals: IndexVec<mir::Local, Option<Pointer>>,
+    pub block: mir::BasicBlock,
+    pub stmt: usize,
+    pub cleanup: StackPopCleanup,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StackPopCleanup {
+    /// The frame evaluates a constant, static or promoted value. Its result
+    /// may borrow from its locals, which are therefore never freed.
+    Global,
+    /// The frame is a function call. Its locals are freed when it returns,
+    /// and the caller continues at the given block.
+    Goto(mir::BasicBlock),
+}
+
+/// The location of an lvalue.
+#[derive(Copy, Clone, Debug)]
+pub struct Lvalue {
+    pub ptr: Pointer,
+    pub extra: LvalueExtra,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LvalueExtra {
+    None,
+    /// The length of an unsized slice or `str`.
+    Length(u64),
+    /// The variant of an enum an lvalue was downcast to.
+    DowncastVariant(usize),
+}
+
+impl Lvalue {
+    pub fn from_ptr(ptr: Pointer) -> Lvalue {
+        Lvalue {
+            ptr: ptr,
+            extra: LvalueExtra::None,
+        }
+    }
+}
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    pub fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>, param_env: ty::ParamEnv<'tcx>) -> Self {
+        EvalContext {
+            tcx: tcx,
+            param_env: param_env,
+            memory: Memory::new(),
+            stack: Vec::new(),
+            statics: HashMap::new(),
+            steps_remaining: STEP_LIMIT,
+        }
+    }
+
+    pub fn data_layout(&self) -> &'a TargetDataLayout {
+        &self.tcx.data_layout
+    }
+
+    pub fn stack(&self) -> &[Frame<'tcx>] {
+        &self.stack
+    }
+
+    pub fn frame(&self) -> &Frame<'tcx> {
+        self.stack.last().expect("no call frames exist")
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame<'tcx> {
+        self.stack.last_mut().expect("no call frames exist")
+    }
+
+    /// The span of the statement or terminator `frame` is executing.
+    fn frame_span(frame: &Frame<'tcx>) -> Span {
+        let block = &frame.mir[frame.block];
+        if frame.stmt < block.statements.len() {
+            block.statements[frame.stmt].source_info.span
+        } else {
+            block.terminator().source_info.span
+        }
+    }
+
+    pub fn cur_span(&self) -> Span {
+        Self::frame_span(self.frame())
+    }
+
+    /// Turns `err` into a constant evaluation error pointing at the code
+    /// that caused it, with a backtrace of the calls leading there.
+    pub fn report(&self, err: EvalError<'tcx>) -> ConstEvalErr<'tcx> {
+        let span = self.stack.last().map_or(::syntax_pos::DUMMY_SP, Self::frame_span);
+        let backtrace = self.stack.windows(2).rev().filter(|frames| {
+            frames[1].cleanup != StackPopCleanup::Global
+        }).map(|frames| {
+            FrameInfo {
+                span: Self::frame_span(&frames[0]),
+                location: self.tcx.item_path_str(frames[1].def_id),
+            }
+        }).collect::<Vec<_>>();
+
+        let kind = match err {
+            EvalError::ReferencedConstant(ConstEvalErr { kind: ErrKind::TypeckError, .. }) => {
+                ErrKind::TypeckError
+            }
+            EvalError::ReferencedConstant(err) => {
+                ErrKind::ErroneousReferencedConstant(box err)
+            }
+            // Report panics in the evaluated constant itself just like
+            // the HIR evaluator did.
+            EvalError::Math(err) if backtrace.is_empty() => ErrKind::Math(err),
+            EvalError::IndexOutOfBounds { len, index } if backtrace.is_empty() => {
+                ErrKind::IndexOutOfBounds { len, index }
+            }
+            err => ErrKind::Interpreter {
+                message: err.to_string(),
+                backtrace: backtrace,
+            },
+        };
+        ConstEvalErr { span, kind }
+    }
+
+    pub fn monomorphize(&self, ty: Ty<'tcx>, substs: &'tcx Substs<'tcx>) -> Ty<'tcx> {
+        let substituted = ty.subst(self.tcx, substs);
+        self.tcx.normalize_associated_type_in_env(&substituted, self.param_env)
+    }
+
+    /// The type of `lvalue` in the current frame.
+    pub fn lvalue_ty(&self, lvalue: &mir::Lvalue<'tcx>) -> Ty<'tcx> {
+        let frame = self.frame();
+        self.monomorphize(lvalue.ty(frame.mir, self.tcx).to_ty(self.tcx), frame.substs)
+    }
+
+    /// The type of `operand` in the current frame.
+    pub fn operand_ty(&self, operand: &mir::Operand<'tcx>) -> Ty<'tcx> {
+        let frame = self.frame();
+        self.monomorphize(operand.ty(frame.mir, self.tcx), frame.substs)
+    }
+
+    pub fn layout_of(&self, ty: Ty<'tcx>) -> EvalResult<'tcx, &'tcx Layout> {
+        if ty.needs_subst() || ty.has_projection_types() {
+            return Err(EvalError::TooGeneric);
+        }
+        Ok(ty.layout(self.tcx, self.param_env)?)
+    }
+
+    pub fn size_of(&self, ty: Ty<'tcx>) -> EvalResult<'tcx, u64> {
+        let layout = self.layout_of(ty)?;
+        if layout.is_unsized() {
+            bug!("size_of: `{}` is unsized", ty);
+        }
+        Ok(layout.size(self.data_layout()).bytes())
+    }
+
+    pub fn align_of(&self, ty: Ty<'tcx>) -> EvalResult<'tcx, u64> {
+        Ok(self.layout_of(ty)?.align(self.data_layout()).abi())
+    }
+
+    pub fn pointer_size(&self) -> u64 {
+        self.data_layout().pointer_size.bytes()
+    }
+
+    /// The offset of field `i` of `ty`, or of its variant `variant`.
+    pub fn field_offset(&self, ty: Ty<'tcx>, variant: Option<usize>, i: usize)
+                        -> EvalResult<'tcx, u64> {
+        match *self.layout_of(ty)? {
+            Layout::Array { element_size, .. } => Ok(element_size.bytes() * i as u64),
+            ref layout => Ok(layout.field_offset(self.data_layout(), i, variant).bytes()),
+        }
+    }
+
+    /// The type of field `i` of `ty`, or of its variant `variant`.
+    pub fn field_ty(&self, ty: Ty<'tcx>, variant: Option<usize>, i: usize)
+                    -> EvalResult<'tcx, Ty<'tcx>> {
+        let layout = TyLayout {
+            ty: ty,
+            layout: self.layout_of(ty)?,
+            variant_index: variant,
+        };
+        let field_ty = layout.field_type(LayoutCx::new(self.tcx, self.param_env), i);
+        Ok(self.tcx.normalize_associated_type_in_env(&field_ty, self.param_env))
+    }
+
+    pub fn allocate(&mut self, ty: Ty<'tcx>) -> EvalResult<'tcx, Pointer> {
+        let size = self.size_of(ty)?;
+        let align = self.align_of(ty)?;
+        Ok(self.memory.allocate(size, align))
+    }
+
+    pub fn push_frame(&mut self,
+                      def_id: DefId,
+                      substs: &'tcx Substs<'tcx>,
+                      mir: &'tcx mir::Mir<'tcx>,
+                      return_ptr: Pointer,
+                      cleanup: StackPopCleanup)
+                      -> EvalResult<'tcx> {
+        if self.stack.len() >= self.tcx.sess.recursion_limit.get() {
+            return Err(EvalError::StackFrameLimitReached);
+        }
+        let mut locals = IndexVec::with_capacity(mir.local_decls.len());
+        for (local, decl) in mir.local_decls.iter_enumerated() {
+            if local == mir::RETURN_POINTER {
+                locals.push(Some(return_ptr));
+            } else {
+                let ty = self.monomorphize(decl.ty, substs);
+                locals.push(Some(self.allocate(ty)?));
+            }
+        }
+        self.stack.push(Frame {
+            mir: mir,
+            def_id: def_id,
+            substs: substs,
+            locals: locals,
+            block: mir::START_BLOCK,
+            stmt: 0,
+            cleanup: cleanup,
+        });
+        Ok(())
+    }
+
+    pub fn pop_frame(&mut self) -> EvalResult<'tcx> {
+        let frame = self.stack.pop().expect("tried to pop a nonexistent frame");
+        if let StackPopCleanup::Goto(target) = frame.cleanup {
+            for (local, ptr) in frame.locals.into_iter_enumerated() {
+                match ptr {
+                    Some(ptr) if local != mir::RETURN_POINTER => self.memory.deallocate(ptr)?,
+                    _ => {}
+                }
+            }
+            self.goto_block(target);
+        }
+        Ok(())
+    }
+
+    pub fn goto_block(&mut self, target: mir::BasicBlock) {
+        let frame = self.frame_mut();
+        frame.block = target;
+        frame.stmt = 0;
+    }
+
+    /// Runs the topmost frame, and everything it calls, until it returns.
+    pub fn run_frame(&mut self) -> EvalResult<'tcx> {
+        let depth = self.stack.len();
+        while self.stack.len() >= depth {
+            if self.steps_remaining == 0 {
+                return Err(EvalError::ExecutionTimeLimitReached);
+            }
+            self.steps_remaining -= 1;
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Evaluates the constant `def_id` by running its MIR, returning the
+    /// allocation holding its value.
+    pub fn eval_global(&mut self, def_id: DefId, substs: &'tcx Substs<'tcx>, ty: Ty<'tcx>)
+                       -> EvalResult<'tcx, Pointer> {
+        if !self.tcx.is_mir_available(def_id) {
+            return Err(EvalError::NoMirFor(self.tcx.item_path_str(def_id)));
+        }
+        let mir = self.tcx.optimized_mir(def_id);
+        self.eval_global_mir(def_id, substs, mir, ty)
+    }
+
+    pub fn eval_global_mir(&mut self,
+                           def_id: DefId,
+                           substs: &'tcx Substs<'tcx>,
+                           mir: &'tcx mir::Mir<'tcx>,
+                           ty: Ty<'tcx>)
+                           -> EvalResult<'tcx, Pointer> {
+        let ptr = self.allocate(ty)?;
+        self.push_frame(def_id, substs, mir, ptr, StackPopCleanup::Global)?;
+        self.run_frame()?;
+        self.memory.mark_immutable(ptr.alloc_id)?;
+        Ok(ptr)
+    }
+
+    /// The allocation of the static `def_id`, which is evaluated on first use.
+    pub fn static_ptr(&mut self, def_id: DefId, ty: Ty<'tcx>) -> EvalResult<'tcx, Pointer> {
+        if let Some(&ptr) = self.statics.get(&def_id) {
+            return Ok(ptr);
+        }
+        if !self.tcx.is_mir_available(def_id) {
+            return Err(EvalError::NoMirFor(self.tcx.item_path_str(def_id)));
+        }
+        // Statics may refer to each other's addresses, so the allocation
+        // has to be known before the initializer runs.
+        let ptr = self.allocate(ty)?;
+        self.statics.insert(def_id, ptr);
+        let mir = self.tcx.optimized_mir(def_id);
+        let substs = self.tcx.intern_substs(&[]);
+        self.push_frame(def_id, substs, mir, ptr, StackPopCleanup::Global)?;
+        self.run_frame()?;
+        if let Some(Def::Static(_, false)) = self.tcx.describe_def(def_id) {
+            self.memory.mark_immutable(ptr.alloc_id)?;
+        }
+        Ok(ptr)
+    }
+
+    pub fn eval_lvalue(&mut self, lvalue: &mir::Lvalue<'tcx>) -> EvalResult<'tcx, Lvalue> {
+        match *lvalue {
+            mir::Lvalue::Local(local) => {
+                match self.frame().locals[local] {
+                    Some(ptr) => Ok(Lvalue::from_ptr(ptr)),
+                    None => Err(EvalError::DeadLocal),
+                }
+            }
+            mir::Lvalue::Static(ref s) => {
+                let ty = self.monomorphize(s.ty, self.frame().substs);
+                self.static_ptr(s.def_id, ty).map(Lvalue::from_ptr)
+            }
+            mir::Lvalue::Projection(ref proj) => {
+                let base = self.eval_lvalue(&proj.base)?;
+                let base_ty = self.lvalue_ty(&proj.base);
+                self.eval_projection(base, base_ty, &proj.elem)
+            }
+        }
+    }
+
+    fn eval_projection(&mut self,
+                       base: Lvalue,
+                       base_ty: Ty<'tcx>,
+                       elem: &mir::LvalueElem<'tcx>)
+                       -> EvalResult<'tcx, Lvalue> {
+        let dl = self.data_layout();
+        match *elem {
+            mir::ProjectionElem::Field(field, field_ty) => {
+                let variant = match base.extra {
+                    LvalueExtra::DowncastVariant(variant) => Some(variant),
+                    _ => None,
+                };
+                let offset = self.field_offset(base_ty, variant, field.index())?;
+                let field_ty = self.monomorphize(field_ty, self.frame().substs);
+                // Only the last field of a struct can be unsized, and it
+                // shares the length of the whole struct.
+                let extra = if self.type_is_sized(field_ty) {
+                    LvalueExtra::None
+                } else {
+                    base.extra
+                };
+                Ok(Lvalue {
+                    ptr: base.ptr.offset(offset, dl)?,
+                    extra: extra,
+                })
+            }
+
+            mir::ProjectionElem::Downcast(_, variant) => {
+                Ok(Lvalue {
+                    ptr: base.ptr,
+                    extra: LvalueExtra::DowncastVariant(variant),
+                })
+            }
+
+            mir::ProjectionElem::Deref => {
+                let pointee = base_ty.builtin_deref(true, ty::LvaluePreference::NoPreference);
+                let pointee_ty = match pointee {
+                    Some(mt) => mt.ty,
+                    None => bug!("eval_projection: cannot deref `{}`", base_ty),
+                };
+                match self.read_value(base.ptr, base_ty)? {
+                    Value::ByVal(ptr) => Ok(Lvalue::from_ptr(ptr.to_ptr()?)),
+                    Value::ByValPair(ptr, len) => {
+                        match self.tcx.struct_tail(pointee_ty).sty {
+                            ty::TySlice(_) | ty::TyStr => {}
+                            _ => return Err(EvalError::Unimplemented("trait objects".to_owned())),
+                        }
+                        Ok(Lvalue {
+                            ptr: ptr.to_ptr()?,
+                            extra: LvalueExtra::Length(len.to_u64()?),
+                        })
+                    }
+                    Value::ByRef(_) => bug!("eval_projection: `{}` is not a pointer", base_ty),
+                }
+            }
+
+            mir::ProjectionElem::Index(ref operand) => {
+                let index = self.eval_operand(operand)?;
+                let index = match index {
+                    Value::ByVal(index) => index.to_u64()?,
+                    _ => bug!("eval_projection: index is not a scalar"),
+                };
+                self.index(base, base_ty, index)
+            }
+
+            mir::ProjectionElem::ConstantIndex { offset, min_length, from_end } => {
+                let len = self.lvalue_len(base, base_ty)?;
+                assert!(min_length as u64 <= len, "ConstantIndex beyond the length");
+                let index = if from_end {
+                    len - offset as u64
+                } else {
+                    offset as u64
+                };
+                self.index(base, base_ty, index)
+            }
+
+            mir::ProjectionElem::Subslice { from, to } => {
+                let len = self.lvalue_len(base, base_ty)?;
+                let elem_ty = base_ty.sequence_element_type(self.tcx);
+                let elem_size = self.size_of(elem_ty)?;
+                let extra = match base_ty.sty {
+                    ty::TyArray(..) => LvalueExtra::None,
+                    _ => LvalueExtra::Length(len - from as u64 - to as u64),
+                };
+                Ok(Lvalue {
+                    ptr: base.ptr.offset(from as u64 * elem_size, dl)?,
+                    extra: extra,
+                })
+            }
+        }
+    }
+
+    fn index(&self, base: Lvalue, base_ty: Ty<'tcx>, index: u64) -> EvalResult<'tcx, Lvalue> {
+        let len = self.lvalue_len(base, base_ty)?;
+        if index >= len {
+            return Err(EvalError::IndexOutOfBounds { len: len, index: index });
+        }
+        let elem_size = self.size_of(base_ty.sequence_element_type(self.tcx))?;
+        Ok(Lvalue::from_ptr(base.ptr.offset(index * elem_size, self.data_layout())?))
+    }
+
+    /// The number of elements of the array, slice or `str` lvalue `lvalue`.
+    pub fn lvalue_len(&self, lvalue: Lvalue, ty: Ty<'tcx>) -> EvalResult<'tcx, u64> {
+        match (&ty.sty, lvalue.extra) {
+            (&ty::TyArray(_, len), _) => Ok(len as u64),
+            (_, LvalueExtra::Length(len)) => Ok(len),
+            _ => bug!("lvalue_len: `{}` has no length", ty),
+        }
+    }
+
+    pub fn type_is_sized(&self, ty: Ty<'tcx>) -> bool {
+        ty.is_sized(self.tcx, self.param_env, ::syntax_pos::DUMMY_SP)
+    }
+
+    /// Whether a value of type `ty` is a fat pointer, and a thin pointer
+    /// or scalar otherwise, or `None` for aggregates.
+    pub fn scalar_pair(&self, ty: Ty<'tcx>) -> Option<bool> {
+        match ty.sty {
+            ty::TyBool | ty::TyChar | ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) |
+            ty::TyFnPtr(_) => Some(false),
+            ty::TyRef(_, ty::TypeAndMut { ty: pointee, .. }) |
+            ty::TyRawPtr(ty::TypeAndMut { ty: pointee, .. }) => {
+                Some(!self.type_is_sized(pointee))
+            }
+            ty::TyAdt(def, _) if def.is_box() => Some(!self.type_is_sized(ty.boxed_ty())),
+            _ => None,
+        }
+    }
+
+    /// Reads the value of type `ty` at `ptr`.
+    pub fn read_value(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, Value> {
+        let dl = self.data_layout();
+        match self.scalar_pair(ty) {
+            Some(false) => {
+                let size = self.size_of(ty)?;
+                Ok(Value::ByVal(self.memory.read_primval(ptr, size, dl)?))
+            }
+            Some(true) => {
+                let pointer_size = self.pointer_size();
+                let data = self.memory.read_primval(ptr, pointer_size, dl)?;
+                let len_ptr = ptr.offset(pointer_size, dl)?;
+                let len = self.memory.read_primval(len_ptr, pointer_size, dl)?;
+                Ok(Value::ByValPair(data, len))
+            }
+            None => Ok(Value::ByRef(ptr)),
+        }
+    }
+
+    /// Writes `value`, of type `ty`, to `dest`.
+    pub fn write_value(&mut self, value: Value, dest: Pointer, ty: Ty<'tcx>)
+                       -> EvalResult<'tcx> {
+        let dl = self.data_layout();
+        match value {
+            Value::ByRef(src) => {
+                let size = self.size_of(ty)?;
+                self.memory.copy(src, dest, size, dl)
+            }
+            Value::ByVal(val) => {
+                let size = self.size_of(ty)?;
+                self.memory.write_primval(dest, val, size, dl)
+            }
+            Value::ByValPair(data, len) => {
+                let pointer_size = self.pointer_size();
+                self.memory.write_primval(dest, data, pointer_size, dl)?;
+                let len_ptr = dest.offset(pointer_size, dl)?;
+                self.memory.write_primval(len_ptr, len, pointer_size, dl)
+            }
+        }
+    }
+
+    /// Moves `value` to memory, if it is not there already.
+    pub fn force_allocation(&mut self, value: Value, ty: Ty<'tcx>) -> EvalResult<'tcx, Pointer> {
+        match value {
+            Value::ByRef(ptr) => Ok(ptr),
+            value => {
+                let ptr = self.allocate(ty)?;
+                self.write_value(value, ptr, ty)?;
+                Ok(ptr)
+            }
+        }
+    }
+
+    /// Reads `value` as a scalar of type `ty`.
+    pub fn value_to_primval(&self, value: Value, ty: Ty<'tcx>) -> EvalResult<'tcx, PrimVal> {
+        match value {
+            Value::ByVal(val) => Ok(val),
+            Value::ByRef(ptr) => match self.read_value(ptr, ty)? {
+                Value::ByVal(val) => Ok(val),
+                _ => bug!("value_to_primval: `{}` is not a scalar", ty),
+            },
+            Value::ByValPair(..) => bug!("value_to_primval: `{}` is a fat pointer", ty),
+        }
+    }
+
+    /// The offset and type of the value telling the variants of an enum
+    /// with a `StructWrappedNullablePointer` or `NicheFilling` layout apart.
+    fn discr_field(&self, ty: Ty<'tcx>, variant: u64, path: &[u32])
+                   -> EvalResult<'tcx, (u64, Ty<'tcx>)> {
+        let mut offset = 0;
+        let mut variant = Some(variant as usize);
+        let mut field_ty = ty;
+        // The first element of the path is only there for LLVM's GEP.
+        for &i in &path[1..] {
+            offset += self.field_offset(field_ty, variant, i as usize)?;
+            field_ty = self.field_ty(field_ty, variant, i as usize)?;
+            // The path goes through the dataful variant of nested enums.
+            variant = match *self.layout_of(field_ty)? {
+                Layout::NicheFilling { dataful, .. } => Some(dataful as usize),
+                _ => None,
+            };
+        }
+        Ok((offset, field_ty))
+    }
+
+    /// The discriminant value of the enum of type `ty` at `ptr`, or zero if
+    /// `ty` is not an enum.
+    pub fn read_discriminant_value(&self, ptr: Pointer, ty: Ty<'tcx>)
+                                   -> EvalResult<'tcx, u128> {
+        let adt = match ty.sty {
+            ty::TyAdt(adt, _) if adt.is_enum() => adt,
+            _ => return Ok(0),
+        };
+        let dl = self.data_layout();
+        let variant = match *self.layout_of(ty)? {
+            Layout::CEnum { discr, .. } | Layout::General { discr, .. } => {
+                let bits = self.memory.read_primval(ptr, discr.size().bytes(), dl)?.to_bytes()?;
+                let size = discr.size().bytes();
+                return match adt.discriminants(self.tcx).find(|d| {
+                    super::value::truncate(d.to_u128_unchecked(), size) == bits
+                }) {
+                    Some(_) => Ok(bits),
+                    None => Err(EvalError::InvalidDiscriminant(bits)),
+                };
+            }
+            Layout::Univariant { .. } => 0,
+            Layout::RawNullablePointer { nndiscr, value } => {
+                let size = value.size(dl).bytes();
+                if self.memory.read_primval(ptr, size, dl)?.is_null()? {
+                    1 - nndiscr as usize
+                } else {
+                    nndiscr as usize
+                }
+            }
+            Layout::StructWrappedNullablePointer { nndiscr, ref discrfield_source, .. } => {
+                let (offset, field_ty) = self.discr_field(ty, nndiscr, discrfield_source)?;
+                let size = self.size_of(field_ty)?;
+                let field_ptr = ptr.offset(offset, dl)?;
+                if self.memory.read_primval(field_ptr, size, dl)?.is_null()? {
+                    1 - nndiscr as usize
+                } else {
+                    nndiscr as usize
+                }
+            }
+            Layout::NicheFilling { dataful, niche_variants: (min, max), niche, niche_start,
+                                   ref niche_field_source, .. } => {
+                let (offset, _) = self.discr_field(ty, dataful, niche_field_source)?;
+                let size = niche.size().bytes();
+                let field_ptr = ptr.offset(offset, dl)?;
+                let bits = self.memory.read_primval(field_ptr, size, dl)?.to_bytes()?;
+                let relative = super::value::truncate(bits.wrapping_sub(niche_start as u128),
+                                                      size);
+                if relative <= (max - min) as u128 {
+                    (relative as u64 + min) as usize
+                } else {
+                    dataful as usize
+                }
+            }
+            ref layout => bug!("read_discriminant_value: unexpected layout {:?}", layout),
+        };
+        Ok(adt.discriminant_for_variant(self.tcx, variant).to_u128_unchecked())
+    }
+
+    /// The index of the variant of the enum of type `ty` at `ptr`.
+    pub fn read_variant_index(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<'tcx, usize> {
+        let adt = match ty.sty {
+            ty::TyAdt(adt, _) => adt,
+            _ => bug!("read_variant_index: `{}` is not an ADT", ty),
+        };
+        let value = self.read_discriminant_value(ptr, ty)?;
+        let size = self.size_of(adt.repr.discr_type().to_ty(self.tcx))?;
+        let truncate = super::value::truncate;
+        adt.discriminants(self.tcx)
+           .position(|d| truncate(d.to_u128_unchecked(), size) == truncate(value, size))
+           .ok_or(EvalError::InvalidDiscriminant(value))
+    }
+
+    /// Marks the enum of type `ty` at `ptr` as being of variant `variant`.
+    pub fn write_discriminant(&mut self, ptr: Pointer, ty: Ty<'tcx>, variant: usize)
+                              -> EvalResult<'tcx> {
+        let adt = match ty.sty {
+            ty::TyAdt(adt, _) if adt.is_enum() => adt,
+            _ => return Ok(()),
+        };
+        let dl = self.data_layout();
+        match *self.layout_of(ty)? {
+            Layout::CEnum { discr, .. } | Layout::General { discr, .. } => {
+                let value = adt.discriminant_for_variant(self.tcx, variant).to_u128_unchecked();
+                self.memory.write_primval(ptr, PrimVal::Bytes(value), discr.size().bytes(), dl)
+            }
+            Layout::Univariant { .. } => Ok(()),
+            Layout::RawNullablePointer { nndiscr, value } => {
+                if variant as u64 != nndiscr {
+                    let size = value.size(dl).bytes();
+                    self.memory.write_primval(ptr, PrimVal::Bytes(0), size, dl)?;
+                }
+                Ok(())
+            }
+            Layout::StructWrappedNullablePointer { nndiscr, ref discrfield_source, .. } => {
+                if variant as u64 != nndiscr {
+                    let (offset, field_ty) =
+                        self.discr_field(ty, nndiscr, discrfield_source)?;
+                    let size = self.size_of(field_ty)?;
+                    let field_ptr = ptr.offset(offset, dl)?;
+                    self.memory.write_primval(field_ptr, PrimVal::Bytes(0), size, dl)?;
+                }
+                Ok(())
+            }
+            Layout::NicheFilling { dataful, niche_variants: (min, _), niche, niche_start,
+                                   ref niche_field_source, .. } => {
+                if variant as u64 != dataful {
+                    let (offset, _) = self.discr_field(ty, dataful, niche_field_source)?;
+                    let size = niche.size().bytes();
+                    let field_ptr = ptr.offset(offset, dl)?;
+                    let value = (variant as u64 - min).wrapping_add(niche_start);
+                    let value = super::value::truncate(value as u128, size);
+                    self.memory.write_primval(field_ptr, PrimVal::Bytes(value), size, dl)?;
+                }
+                Ok(())
+            }
+            ref layout => bug!("write_discriminant: unexpected layout {:?}", layout),
+        }
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The memory of the interpreter.
+//!
+//! Memory is a set of allocations, each a vector of bytes. Bytes that hold
+//! a pointer are covered by a *relocation*, which records the allocation the
+//! pointer points into, while the bytes themselves only hold the offset.
+//! This keeps the provenance of every pointer, so that the interpreter can
+//! tell a pointer from an integer that happens to have the same bits, and
+//! never has to make up an absolute address.
+
+use rustc::ty::Instance;
+use rustc::ty::layout::{Endian, TargetDataLayout};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use super::{EvalError, EvalResult, PrimVal};
+use super::value::truncate;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AllocId(u64);
+
+impl fmt::Display for AllocId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "alloc{}", self.0)
+    }
+}
+
+/// A pointer to `offset` bytes into the allocation `alloc_id`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pointer {
+    pub alloc_id: AllocId,
+    pub offset: u64,
+}
+
+impl Pointer {
+    pub fn new(alloc_id: AllocId, offset: u64) -> Pointer {
+        Pointer {
+            alloc_id: alloc_id,
+            offset: offset,
+        }
+    }
+
+    pub fn offset<'tcx>(self, i: u64, dl: &TargetDataLayout) -> EvalResult<'tcx, Pointer> {
+        self.signed_offset(i as i128, dl)
+    }
+
+    pub fn signed_offset<'tcx>(self, i: i128, dl: &TargetDataLayout)
+                               -> EvalResult<'tcx, Pointer> {
+        let offset = self.offset as i128 + i;
+        if offset < 0 || truncate(offset as u128, dl.pointer_size.bytes()) != offset as u128 {
+            return Err(EvalError::InvalidPointerMath);
+        }
+        Ok(Pointer::new(self.alloc_id, offset as u64))
+    }
+}
+
+pub struct Allocation {
+    pub bytes: Vec<u8>,
+    /// Pointers stored in `bytes`, keyed by the offset of their first byte.
+    pub relocations: BTreeMap<u64, AllocId>,
+    /// Which of `bytes` have been initialized.
+    pub defined: Vec<bool>,
+    pub align: u64,
+    /// Whether the allocation may still be written to. Promoted values and
+    /// the values of constants and statics become immutable once evaluated.
+    pub mutable: bool,
+}
+
+pub struct Memory<'tcx> {
+    allocs: HashMap<AllocId, Allocation>,
+    /// Function "allocations", which only exist to be pointed to.
+    functions: HashMap<AllocId, Instance<'tcx>>,
+    function_ids: HashMap<Instance<'tcx>, AllocId>,
+    next_id: u64,
+}
+
+impl<'tcx> Memory<'tcx> {
+    pub fn new() -> Memory<'tcx> {
+        Memory {
+            allocs: HashMap::new(),
+            functions: HashMap::new(),
+            function_ids: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    fn fresh_id(&mut self) -> AllocId {
+        let id = AllocId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Allocates `size` bytes, which are all undefined.
+    pub fn allocate(&mut self, size: u64, align: u64) -> Pointer {
+        let id = self.fresh_id();
+        self.allocs.insert(id, Allocation {
+            bytes: vec![0; size as usize],
+            relocations: BTreeMap::new(),
+            defined: vec![false; size as usize],
+            align: align,
+            mutable: true,
+        });
+        Pointer::new(id, 0)
+    }
+
+    /// Allocates an immutable copy of `bytes`, e.g. for a string literal.
+    pub fn allocate_bytes(&mut self, bytes: &[u8]) -> Pointer {
+        let id = self.fresh_id();
+        self.allocs.insert(id, Allocation {
+            bytes: bytes.to_vec(),
+            relocations: BTreeMap::new(),
+            defined: vec![true; bytes.len()],
+            align: 1,
+            mutable: false,
+        });
+        Pointer::new(id, 0)
+    }
+
+    pub fn deallocate(&mut self, ptr: Pointer) -> EvalResult<'tcx> {
+        if ptr.offset != 0 || self.allocs.remove(&ptr.alloc_id).is_none() {
+            return Err(EvalError::DanglingPointerDeref);
+        }
+        Ok(())
+    }
+
+    pub fn mark_immutable(&mut self, id: AllocId) -> EvalResult<'tcx> {
+        self.get_mut(id)?.mutable = false;
+        Ok(())
+    }
+
+    pub fn create_fn_alloc(&mut self, instance: Instance<'tcx>) -> Pointer {
+        if let Some(&id) = self.function_ids.get(&instance) {
+            return Pointer::new(id, 0);
+        }
+        let id = self.fresh_id();
+        self.functions.insert(id, instance);
+        self.function_ids.insert(instance, id);
+        Pointer::new(id, 0)
+    }
+
+    pub fn get_fn(&self, ptr: Pointer) -> EvalResult<'tcx, Instance<'tcx>> {
+        if ptr.offset != 0 {
+            return Err(EvalError::InvalidFunctionPointer);
+        }
+        match self.functions.get(&ptr.alloc_id) {
+            Some(&instance) => Ok(instance),
+            None if self.allocs.contains_key(&ptr.alloc_id) => {
+                Err(EvalError::InvalidFunctionPointer)
+            }
+            None => Err(EvalError::DanglingPointerDeref),
+        }
+    }
+
+    pub fn get(&self, id: AllocId) -> EvalResult<'tcx, &Allocation> {
+        match self.allocs.get(&id) {
+            Some(alloc) => Ok(alloc),
+            None if self.functions.contains_key(&id) => Err(EvalError::InvalidFunctionPointer),
+            None => Err(EvalError::DanglingPointerDeref),
+        }
+    }
+
+    fn get_mut(&mut self, id: AllocId) -> EvalResult<'tcx, &mut Allocation> {
+        if self.functions.contains_key(&id) {
+            return Err(EvalError::InvalidFunctionPointer);
+        }
+        match self.allocs.get_mut(&id) {
+            Some(ref alloc) if !alloc.mutable => Err(EvalError::ModifiedConstantMemory),
+            Some(alloc) => Ok(alloc),
+            None => Err(EvalError::DanglingPointerDeref),
+        }
+    }
+
+    pub fn check_align(&self, ptr: Pointer, align: u64) -> EvalResult<'tcx> {
+        let alloc = self.get(ptr.alloc_id)?;
+        // The largest power of two dividing the offset.
+        let offset_align = if ptr.offset == 0 {
+            alloc.align
+        } else {
+            ptr.offset & ptr.offset.wrapping_neg()
+        };
+        let has = ::std::cmp::min(alloc.align, offset_align);
+        if has < align {
+            return Err(EvalError::AlignmentCheckFailed {
+                required: align,
+                has: has,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_bounds(&self, ptr: Pointer, size: u64) -> EvalResult<'tcx> {
+        let allocation_size = self.get(ptr.alloc_id)?.bytes.len() as u64;
+        if ptr.offset + size > allocation_size {
+            return Err(EvalError::PointerOutOfBounds {
+                ptr: ptr,
+                size: size,
+                allocation_size: allocation_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Relocations starting in `ptr.offset..ptr.offset + size`, or in the
+    /// pointer-sized stretch before it, i.e. all which may overlap it.
+    fn relocations(&self, ptr: Pointer, size: u64, dl: &TargetDataLayout)
+                   -> EvalResult<'tcx, Vec<(u64, AllocId)>> {
+        let start = ptr.offset.saturating_sub(dl.pointer_size.bytes() - 1);
+        let end = ptr.offset + size;
+        let alloc = self.get(ptr.alloc_id)?;
+        Ok(alloc.relocations.range(start..end).map(|(&k, &v)| (k, v)).collect())
+    }
+
+    /// Removes the relocations overlapping `ptr.offset..ptr.offset + size`,
+    /// which must not stick out of that range.
+    fn clear_relocations(&mut self, ptr: Pointer, size: u64, dl: &TargetDataLayout)
+                         -> EvalResult<'tcx> {
+        let relocations = self.relocations(ptr, size, dl)?;
+        let pointer_size = dl.pointer_size.bytes();
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        for (offset, _) in relocations {
+            if offset < ptr.offset || offset + pointer_size > ptr.offset + size {
+                return Err(EvalError::PartialPointerOverwrite);
+            }
+            alloc.relocations.remove(&offset);
+        }
+        Ok(())
+    }
+
+    /// The raw bytes `ptr.offset..ptr.offset + size`, which must be
+    /// defined and must not contain any part of a pointer.
+    pub fn read_bytes(&self, ptr: Pointer, size: u64, dl: &TargetDataLayout)
+                      -> EvalResult<'tcx, &[u8]> {
+        self.check_bounds(ptr, size)?;
+        if !self.relocations(ptr, size, dl)?.is_empty() {
+            return Err(EvalError::ReadPointerAsBytes);
+        }
+        let alloc = self.get(ptr.alloc_id)?;
+        let range = ptr.offset as usize..(ptr.offset + size) as usize;
+        if alloc.defined[range.clone()].iter().any(|&defined| !defined) {
+            return Err(EvalError::ReadUndefBytes);
+        }
+        Ok(&alloc.bytes[range])
+    }
+
+    pub fn write_bytes(&mut self, ptr: Pointer, bytes: &[u8], dl: &TargetDataLayout)
+                       -> EvalResult<'tcx> {
+        let size = bytes.len() as u64;
+        self.check_bounds(ptr, size)?;
+        self.clear_relocations(ptr, size, dl)?;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        let range = ptr.offset as usize..(ptr.offset + size) as usize;
+        alloc.bytes[range.clone()].copy_from_slice(bytes);
+        for defined in &mut alloc.defined[range] {
+            *defined = true;
+        }
+        Ok(())
+    }
+
+    /// Marks `ptr.offset..ptr.offset + size` as undefined.
+    pub fn write_undef(&mut self, ptr: Pointer, size: u64, dl: &TargetDataLayout)
+                       -> EvalResult<'tcx> {
+        self.check_bounds(ptr, size)?;
+        self.clear_relocations(ptr, size, dl)?;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        for defined in &mut alloc.defined[ptr.offset as usize..(ptr.offset + size) as usize] {
+            *defined = false;
+        }
+        Ok(())
+    }
+
+    /// Copies `size` bytes from `src` to `dest`, along with their
+    /// definedness and any pointers among them.
+    pub fn copy(&mut self, src: Pointer, dest: Pointer, size: u64, dl: &TargetDataLayout)
+                -> EvalResult<'tcx> {
+        if size == 0 {
+            return Ok(());
+        }
+        self.check_bounds(src, size)?;
+        let relocations = self.relocations(src, size, dl)?;
+        let pointer_size = dl.pointer_size.bytes();
+        if relocations.iter().any(|&(offset, _)| {
+            offset < src.offset || offset + pointer_size > src.offset + size
+        }) {
+            return Err(EvalError::ReadPointerAsBytes);
+        }
+        let (bytes, defined) = {
+            let alloc = self.get(src.alloc_id)?;
+            let range = src.offset as usize..(src.offset + size) as usize;
+            (alloc.bytes[range.clone()].to_vec(), alloc.defined[range].to_vec())
+        };
+
+        self.check_bounds(dest, size)?;
+        self.clear_relocations(dest, size, dl)?;
+        let alloc = self.get_mut(dest.alloc_id)?;
+        let range = dest.offset as usize..(dest.offset + size) as usize;
+        alloc.bytes[range.clone()].copy_from_slice(&bytes);
+        alloc.defined[range].copy_from_slice(&defined);
+        for (offset, id) in relocations {
+            alloc.relocations.insert(offset - src.offset + dest.offset, id);
+        }
+        Ok(())
+    }
+
+    /// Reads a scalar of `size` bytes. Undefined bytes give `PrimVal::Undef`
+    /// rather than an error, which is only raised once the value is used.
+    pub fn read_primval(&self, ptr: Pointer, size: u64, dl: &TargetDataLayout)
+                        -> EvalResult<'tcx, PrimVal> {
+        self.check_bounds(ptr, size)?;
+        let alloc = self.get(ptr.alloc_id)?;
+        let range = ptr.offset as usize..(ptr.offset + size) as usize;
+        if alloc.defined[range.clone()].iter().any(|&defined| !defined) {
+            return Ok(PrimVal::Undef);
+        }
+        let bits = read_target_uint(&alloc.bytes[range], dl.endian);
+
+        let relocations = self.relocations(ptr, size, dl)?;
+        match relocations.first() {
+            None => {}
+            Some(&(offset, id)) if offset == ptr.offset && size == dl.pointer_size.bytes() => {
+                return Ok(PrimVal::Ptr(Pointer::new(id, bits as u64)));
+            }
+            Some(_) => return Err(EvalError::ReadPointerAsBytes),
+        }
+
+        Ok(PrimVal::Bytes(bits))
+    }
+
+    pub fn write_primval(&mut self,
+                         ptr: Pointer,
+                         val: PrimVal,
+                         size: u64,
+                         dl: &TargetDataLayout)
+                         -> EvalResult<'tcx> {
+        match val {
+            PrimVal::Undef => self.write_undef(ptr, size, dl),
+            PrimVal::Bytes(bits) => {
+                let bytes = write_target_uint(truncate(bits, size), size, dl.endian);
+                self.write_bytes(ptr, &bytes, dl)
+            }
+            PrimVal::Ptr(p) => {
+                if size != dl.pointer_size.bytes() {
+                    return Err(EvalError::ReadPointerAsBytes);
+                }
+                let bytes = write_target_uint(p.offset as u128, size, dl.endian);
+                self.write_bytes(ptr, &bytes, dl)?;
+                self.get_mut(ptr.alloc_id)?.relocations.insert(ptr.offset, p.alloc_id);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn read_target_uint(bytes: &[u8], endian: Endian) -> u128 {
+    let mut bits = 0;
+    match endian {
+        Endian::Little => {
+            for &b in bytes.iter().rev() {
+                bits = (bits << 8) | b as u128;
+            }
+        }
+        Endian::Big => {
+            for &b in bytes {
+                bits = (bits << 8) | b as u128;
+            }
+        }
+    }
+    bits
+}
+
+fn write_target_uint(mut bits: u128, size: u64, endian: Endian) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(size as usize);
+    for _ in 0..size {
+        bytes.push(bits as u8);
+        bits >>= 8;
+    }
+    if let Endian::Big = endian {
+        bytes.reverse();
+    }
+    bytes
+}
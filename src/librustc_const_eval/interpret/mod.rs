@@ -0,0 +1,74 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An interpreter for MIR, used to evaluate constants.
+//!
+//! Unlike the HIR evaluator, the interpreter runs the optimized MIR of a
+//! constant, so it can call `const fn`s, read and write through references
+//! and work with unions. Values live in a small model of memory made of
+//! separate allocations (see `memory`), which lets it detect undefined
+//! behaviour such as reading uninitialized bytes or comparing pointers into
+//! different allocations, and report it as an error.
+//!
+//! Anything it does not support yet (trait method calls, heap allocations,
+//! destructors, ...) makes `eval_body` return `None`, and the HIR evaluator
+//! is used instead.
+
+use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::{ConstEvalErr, ConstVal};
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::subst::Substs;
+
+mod convert;
+mod error;
+mod eval_context;
+mod memory;
+mod operator;
+mod step;
+mod value;
+
+pub use self::error::{EvalError, EvalResult};
+pub use self::eval_context::{EvalContext, Frame, Lvalue, LvalueExtra, StackPopCleanup};
+pub use self::memory::{AllocId, Allocation, Memory, Pointer};
+pub use self::value::{PrimVal, Value};
+
+/// Evaluates the constant or static `def_id` with the substitutions
+/// `substs` by running its MIR, or returns `None` if that is unsupported.
+pub fn eval_body<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                           param_env: ty::ParamEnv<'tcx>,
+                           def_id: DefId,
+                           substs: &'tcx Substs<'tcx>)
+                           -> Option<Result<ConstVal<'tcx>, ConstEvalErr<'tcx>>> {
+    if !tcx.is_mir_available(def_id) {
+        return None;
+    }
+    let mut ecx = EvalContext::new(tcx, param_env);
+    let ty = ecx.monomorphize(tcx.type_of(def_id), substs);
+    let result = match ecx.eval_global(def_id, substs, ty) {
+        Ok(ptr) => ecx.read_const_val(ptr, ty),
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(val) => Some(Ok(val)),
+        Err(ref err) if err.is_unsupported() => {
+            debug!("eval_body: falling back to the HIR for {:?}: {}", def_id, err);
+            None
+        }
+        Err(err) => {
+            let mut err = ecx.report(err);
+            if ecx.stack().is_empty() {
+                // The body ran to completion, but its result is not a valid
+                // value of its type.
+                err.span = tcx.def_span(def_id);
+            }
+            Some(Err(err))
+        }
+    }
+}
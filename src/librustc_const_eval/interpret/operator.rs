@@ -0,0 +1,399 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Arithmetic, comparisons and casts on primitive values.
+
+use rustc::mir;
+use rustc::ty::{self, Ty};
+use rustc::ty::util::IntTypeExt;
+use rustc_const_math::ConstMathErr;
+use syntax::ast::FloatTy;
+
+use std::cmp::Ordering;
+
+use super::{EvalContext, EvalError, EvalResult, Pointer, PrimVal, Value};
+use super::value::{sign_extend, truncate};
+
+macro_rules! float_binary_op {
+    ($op:expr, $left:expr, $right:expr, $from:path) => {{
+        use rustc::mir::BinOp::*;
+        match $op {
+            Eq => PrimVal::from_bool($left == $right),
+            Ne => PrimVal::from_bool($left != $right),
+            Lt => PrimVal::from_bool($left < $right),
+            Le => PrimVal::from_bool($left <= $right),
+            Gt => PrimVal::from_bool($left > $right),
+            Ge => PrimVal::from_bool($left >= $right),
+            Add => $from($left + $right),
+            Sub => $from($left - $right),
+            Mul => $from($left * $right),
+            Div => $from($left / $right),
+            Rem => $from($left % $right),
+            _ => bug!("float_binary_op: unexpected operator {:?}", $op),
+        }
+    }}
+}
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    pub fn eval_binop(&mut self,
+                      op: mir::BinOp,
+                      left: &mir::Operand<'tcx>,
+                      right: &mir::Operand<'tcx>)
+                      -> EvalResult<'tcx, (PrimVal, bool)> {
+        let left_ty = self.operand_ty(left);
+        let right_ty = self.operand_ty(right);
+        let left = self.eval_operand_to_primval(left)?;
+        let right = self.eval_operand_to_primval(right)?;
+        self.binary_op(op, left, left_ty, right, right_ty)
+    }
+
+    /// Applies `op` to `left` and `right`, also returning whether the
+    /// operation overflowed.
+    pub fn binary_op(&self,
+                     op: mir::BinOp,
+                     left: PrimVal,
+                     left_ty: Ty<'tcx>,
+                     right: PrimVal,
+                     right_ty: Ty<'tcx>)
+                     -> EvalResult<'tcx, (PrimVal, bool)> {
+        match (left, right) {
+            (PrimVal::Ptr(left), PrimVal::Ptr(right)) => {
+                return self.ptr_binary_op(op, left, right).map(|val| (val, false));
+            }
+            (PrimVal::Ptr(ptr), PrimVal::Bytes(bits)) => {
+                return self.ptr_int_binary_op(op, ptr, bits, left_ty).map(|val| (val, false));
+            }
+            (PrimVal::Bytes(bits), PrimVal::Ptr(ptr)) => {
+                // Only commutative operations may have the pointer on the right.
+                return match op {
+                    mir::BinOp::Eq | mir::BinOp::Ne | mir::BinOp::Add => {
+                        self.ptr_int_binary_op(op, ptr, bits, right_ty).map(|val| (val, false))
+                    }
+                    _ => Err(EvalError::InvalidPointerMath),
+                };
+            }
+            _ => {}
+        }
+
+        let left = left.to_bytes()?;
+        let right = right.to_bytes()?;
+        match left_ty.sty {
+            ty::TyFloat(FloatTy::F32) => {
+                let left = PrimVal::Bytes(left).to_f32()?;
+                let right = PrimVal::Bytes(right).to_f32()?;
+                let val = float_binary_op!(op, left, right, PrimVal::from_f32);
+                Ok((val, false))
+            }
+            ty::TyFloat(FloatTy::F64) => {
+                let left = PrimVal::Bytes(left).to_f64()?;
+                let right = PrimVal::Bytes(right).to_f64()?;
+                let val = float_binary_op!(op, left, right, PrimVal::from_f64);
+                Ok((val, false))
+            }
+            ty::TyBool => {
+                PrimVal::Bytes(left).to_bool()?;
+                PrimVal::Bytes(right).to_bool()?;
+                self.int_binary_op(op, left, left_ty, right, right_ty)
+            }
+            ty::TyChar => {
+                PrimVal::Bytes(left).to_char()?;
+                PrimVal::Bytes(right).to_char()?;
+                self.int_binary_op(op, left, left_ty, right, right_ty)
+            }
+            _ => self.int_binary_op(op, left, left_ty, right, right_ty),
+        }
+    }
+
+    fn ptr_binary_op(&self, op: mir::BinOp, left: Pointer, right: Pointer)
+                     -> EvalResult<'tcx, PrimVal> {
+        use rustc::mir::BinOp::*;
+        match op {
+            Eq => return Ok(PrimVal::from_bool(left == right)),
+            Ne => return Ok(PrimVal::from_bool(left != right)),
+            _ => {}
+        }
+        // Anything else would depend on where the allocations are placed.
+        if left.alloc_id != right.alloc_id {
+            return Err(EvalError::InvalidPointerMath);
+        }
+        let ordering = left.offset.cmp(&right.offset);
+        Ok(match op {
+            Lt => PrimVal::from_bool(ordering == Ordering::Less),
+            Le => PrimVal::from_bool(ordering != Ordering::Greater),
+            Gt => PrimVal::from_bool(ordering == Ordering::Greater),
+            Ge => PrimVal::from_bool(ordering != Ordering::Less),
+            Sub => {
+                let diff = left.offset.wrapping_sub(right.offset) as u128;
+                PrimVal::Bytes(truncate(diff, self.pointer_size()))
+            }
+            _ => return Err(EvalError::InvalidPointerMath),
+        })
+    }
+
+    fn ptr_int_binary_op(&self, op: mir::BinOp, ptr: Pointer, bits: u128, ptr_ty: Ty<'tcx>)
+                         -> EvalResult<'tcx, PrimVal> {
+        use rustc::mir::BinOp::*;
+        let dl = self.data_layout();
+        let pointer_size = self.pointer_size();
+        Ok(match op {
+            // Pointers into allocations are never null.
+            Eq if bits == 0 => PrimVal::from_bool(false),
+            Ne if bits == 0 => PrimVal::from_bool(true),
+            Offset => {
+                let pointee = ptr_ty.builtin_deref(true, ty::LvaluePreference::NoPreference);
+                let pointee_ty = match pointee {
+                    Some(mt) => mt.ty,
+                    None => bug!("ptr_int_binary_op: offset of non-pointer `{}`", ptr_ty),
+                };
+                let count = sign_extend(bits, pointer_size);
+                let pointee_size = self.size_of(pointee_ty)? as i128;
+                PrimVal::Ptr(ptr.signed_offset(count * pointee_size, dl)?)
+            }
+            Add => PrimVal::Ptr(ptr.signed_offset(sign_extend(bits, pointer_size), dl)?),
+            Sub => PrimVal::Ptr(ptr.signed_offset(-sign_extend(bits, pointer_size), dl)?),
+            _ => return Err(EvalError::InvalidPointerMath),
+        })
+    }
+
+    fn int_binary_op(&self,
+                     op: mir::BinOp,
+                     left: u128,
+                     left_ty: Ty<'tcx>,
+                     right: u128,
+                     right_ty: Ty<'tcx>)
+                     -> EvalResult<'tcx, (PrimVal, bool)> {
+        use rustc::mir::BinOp::*;
+        let size = self.size_of(left_ty)?;
+        let signed = match left_ty.sty {
+            ty::TyInt(_) => true,
+            _ => false,
+        };
+
+        match op {
+            Shl | Shr => {
+                let bits = size as u32 * 8;
+                let right_size = self.size_of(right_ty)?;
+                let amount = match right_ty.sty {
+                    ty::TyInt(_) => sign_extend(right, right_size) as u128,
+                    _ => right,
+                };
+                let overflowed = amount >= bits as u128;
+                let amount = (amount % bits as u128) as u32;
+                let result = match (op, signed) {
+                    (Shl, _) => left << amount,
+                    (_, true) => (sign_extend(left, size) >> amount) as u128,
+                    (_, false) => left >> amount,
+                };
+                return Ok((PrimVal::Bytes(truncate(result, size)), overflowed));
+            }
+            _ => {}
+        }
+
+        let ordering = if signed {
+            sign_extend(left, size).cmp(&sign_extend(right, size))
+        } else {
+            left.cmp(&right)
+        };
+        let result = match op {
+            Eq => PrimVal::from_bool(ordering == Ordering::Equal),
+            Ne => PrimVal::from_bool(ordering != Ordering::Equal),
+            Lt => PrimVal::from_bool(ordering == Ordering::Less),
+            Le => PrimVal::from_bool(ordering != Ordering::Greater),
+            Gt => PrimVal::from_bool(ordering == Ordering::Greater),
+            Ge => PrimVal::from_bool(ordering != Ordering::Less),
+            BitAnd => PrimVal::Bytes(left & right),
+            BitOr => PrimVal::Bytes(left | right),
+            BitXor => PrimVal::Bytes(left ^ right),
+            _ => {
+                let (result, overflowed) = if signed {
+                    let (result, overflowed) = signed_arith(op, sign_extend(left, size),
+                                                            sign_extend(right, size))?;
+                    let truncated = truncate(result as u128, size);
+                    (truncated, overflowed || sign_extend(truncated, size) != result)
+                } else {
+                    let (result, overflowed) = unsigned_arith(op, left, right)?;
+                    (truncate(result, size), overflowed || truncate(result, size) != result)
+                };
+                return Ok((PrimVal::Bytes(result), overflowed));
+            }
+        };
+        Ok((result, false))
+    }
+
+    pub fn unary_op(&self, op: mir::UnOp, val: PrimVal, ty: Ty<'tcx>)
+                    -> EvalResult<'tcx, PrimVal> {
+        match (op, &ty.sty) {
+            (mir::UnOp::Not, &ty::TyBool) => Ok(PrimVal::from_bool(!val.to_bool()?)),
+            (mir::UnOp::Neg, &ty::TyFloat(FloatTy::F32)) => Ok(PrimVal::from_f32(-val.to_f32()?)),
+            (mir::UnOp::Neg, &ty::TyFloat(FloatTy::F64)) => Ok(PrimVal::from_f64(-val.to_f64()?)),
+            (mir::UnOp::Not, _) => {
+                Ok(PrimVal::Bytes(truncate(!val.to_bytes()?, self.size_of(ty)?)))
+            }
+            (mir::UnOp::Neg, _) => {
+                let size = self.size_of(ty)?;
+                let negated = sign_extend(val.to_bytes()?, size).wrapping_neg();
+                Ok(PrimVal::Bytes(truncate(negated as u128, size)))
+            }
+        }
+    }
+
+    pub fn eval_cast(&mut self,
+                     kind: mir::CastKind,
+                     operand: &mir::Operand<'tcx>,
+                     dest_ty: Ty<'tcx>)
+                     -> EvalResult<'tcx, Value> {
+        let src_ty = self.operand_ty(operand);
+        let src = self.eval_operand(operand)?;
+        match kind {
+            mir::CastKind::Misc => {
+                if let Value::ByValPair(data, _) = src {
+                    // Casting a fat pointer either keeps it fat or drops its length.
+                    return Ok(match self.scalar_pair(dest_ty) {
+                        Some(true) => src,
+                        _ => Value::ByVal(data),
+                    });
+                }
+                if let ty::TyAdt(adt, _) = src_ty.sty {
+                    if adt.is_enum() {
+                        let ptr = self.force_allocation(src, src_ty)?;
+                        let discr = self.read_discriminant_value(ptr, src_ty)?;
+                        let discr_ty = adt.repr.discr_type().to_ty(self.tcx);
+                        let val = self.cast_primval(PrimVal::Bytes(discr), discr_ty, dest_ty)?;
+                        return Ok(Value::ByVal(val));
+                    }
+                }
+                let val = self.value_to_primval(src, src_ty)?;
+                Ok(Value::ByVal(self.cast_primval(val, src_ty, dest_ty)?))
+            }
+            mir::CastKind::ReifyFnPointer => {
+                match src_ty.sty {
+                    ty::TyFnDef(def_id, substs) => {
+                        let instance = self.resolve(def_id, substs)?;
+                        Ok(Value::ByVal(PrimVal::Ptr(self.memory.create_fn_alloc(instance))))
+                    }
+                    _ => bug!("eval_cast: cannot reify `{}`", src_ty),
+                }
+            }
+            mir::CastKind::UnsafeFnPointer => Ok(src),
+            mir::CastKind::ClosureFnPointer => {
+                Err(EvalError::Unimplemented("closures as function pointers".to_owned()))
+            }
+            mir::CastKind::Unsize => {
+                let pref = ty::LvaluePreference::NoPreference;
+                let src_pointee = src_ty.builtin_deref(true, pref).map(|mt| mt.ty);
+                let dest_pointee = dest_ty.builtin_deref(true, pref).map(|mt| mt.ty);
+                match (src_pointee.map(|ty| &ty.sty), dest_pointee.map(|ty| &ty.sty)) {
+                    (Some(&ty::TyArray(_, len)), Some(&ty::TySlice(_))) => {
+                        let data = self.value_to_primval(src, src_ty)?;
+                        Ok(Value::ByValPair(data, PrimVal::from_u64(len as u64)))
+                    }
+                    _ => Err(EvalError::Unimplemented("unsizing casts other than array to \
+                                                       slice".to_owned())),
+                }
+            }
+        }
+    }
+
+    fn cast_primval(&self, val: PrimVal, src_ty: Ty<'tcx>, dest_ty: Ty<'tcx>)
+                    -> EvalResult<'tcx, PrimVal> {
+        let bits = match val {
+            PrimVal::Ptr(_) => {
+                // Pointers only survive casts to types they fit into.
+                let dest_size = self.size_of(dest_ty)?;
+                return if dest_size == self.pointer_size() {
+                    Ok(val)
+                } else {
+                    Err(EvalError::ReadPointerAsBytes)
+                };
+            }
+            PrimVal::Bytes(bits) => bits,
+            PrimVal::Undef => return Err(EvalError::ReadUndefBytes),
+        };
+
+        if let ty::TyFloat(fty) = src_ty.sty {
+            let f = match fty {
+                FloatTy::F32 => val.to_f32()? as f64,
+                FloatTy::F64 => val.to_f64()?,
+            };
+            return self.cast_from_float(f, dest_ty);
+        }
+
+        let src_size = self.size_of(src_ty)?;
+        let signed = match src_ty.sty {
+            ty::TyInt(_) => true,
+            _ => false,
+        };
+        // The value, sign-extended to 128 bits if it is signed.
+        let value = if signed {
+            sign_extend(bits, src_size) as u128
+        } else {
+            bits
+        };
+        Ok(match dest_ty.sty {
+            ty::TyFloat(FloatTy::F32) if signed => PrimVal::from_f32(value as i128 as f32),
+            ty::TyFloat(FloatTy::F32) => PrimVal::from_f32(value as f32),
+            ty::TyFloat(FloatTy::F64) if signed => PrimVal::from_f64(value as i128 as f64),
+            ty::TyFloat(FloatTy::F64) => PrimVal::from_f64(value as f64),
+            _ => PrimVal::Bytes(truncate(value, self.size_of(dest_ty)?)),
+        })
+    }
+
+    fn cast_from_float(&self, f: f64, dest_ty: Ty<'tcx>) -> EvalResult<'tcx, PrimVal> {
+        match dest_ty.sty {
+            ty::TyFloat(FloatTy::F32) => return Ok(PrimVal::from_f32(f as f32)),
+            ty::TyFloat(FloatTy::F64) => return Ok(PrimVal::from_f64(f)),
+            _ => {}
+        }
+        let size = self.size_of(dest_ty)?;
+        let bits = size as i32 * 8;
+        let f = f.trunc();
+        let (min, max) = match dest_ty.sty {
+            ty::TyInt(_) => (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1)),
+            _ => (0.0, 2f64.powi(bits)),
+        };
+        if f.is_nan() || f < min || f >= max {
+            return Err(EvalError::FloatToIntOverflow);
+        }
+        let value = match dest_ty.sty {
+            ty::TyInt(_) => f as i128 as u128,
+            _ => f as u128,
+        };
+        Ok(PrimVal::Bytes(truncate(value, size)))
+    }
+}
+
+fn signed_arith<'tcx>(op: mir::BinOp, left: i128, right: i128) -> EvalResult<'tcx, (i128, bool)> {
+    use rustc::mir::BinOp::*;
+    Ok(match op {
+        Add => left.overflowing_add(right),
+        Sub => left.overflowing_sub(right),
+        Mul => left.overflowing_mul(right),
+        Div if right == 0 => return Err(EvalError::Math(ConstMathErr::DivisionByZero)),
+        Rem if right == 0 => return Err(EvalError::Math(ConstMathErr::RemainderByZero)),
+        Div => left.overflowing_div(right),
+        Rem => left.overflowing_rem(right),
+        _ => bug!("signed_arith: unexpected operator {:?}", op),
+    })
+}
+
+fn unsigned_arith<'tcx>(op: mir::BinOp, left: u128, right: u128)
+                        -> EvalResult<'tcx, (u128, bool)> {
+    use rustc::mir::BinOp::*;
+    Ok(match op {
+        Add => left.overflowing_add(right),
+        Sub => left.overflowing_sub(right),
+        Mul => left.overflowing_mul(right),
+        Div if right == 0 => return Err(EvalError::Math(ConstMathErr::DivisionByZero)),
+        Rem if right == 0 => return Err(EvalError::Math(ConstMathErr::RemainderByZero)),
+        Div => (left / right, false),
+        Rem => (left % right, false),
+        _ => bug!("unsigned_arith: unexpected operator {:?}", op),
+    })
+}
@@ -0,0 +1,436 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Execution of MIR statements and terminators.
+
+use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::{ConstEvalErr, ErrKind};
+use rustc::mir;
+use rustc::ty::{self, Ty};
+use rustc::ty::subst::{Subst, Substs};
+use syntax::abi::Abi;
+
+use eval::lookup_const_by_id;
+
+use super::{EvalContext, EvalError, EvalResult, Pointer, PrimVal, StackPopCleanup, Value};
+use super::LvalueExtra;
+use super::value::truncate;
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    /// Executes the next statement or terminator of the topmost frame.
+    pub fn step(&mut self) -> EvalResult<'tcx> {
+        let mir = self.frame().mir;
+        let block = &mir[self.frame().block];
+        let stmt = self.frame().stmt;
+        if stmt < block.statements.len() {
+            self.statement(&block.statements[stmt])?;
+            self.frame_mut().stmt += 1;
+        } else {
+            self.terminator(block.terminator())?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &mir::Statement<'tcx>) -> EvalResult<'tcx> {
+        debug!("statement: {:?}", stmt);
+        match stmt.kind {
+            mir::StatementKind::Assign(ref lvalue, ref rvalue) => {
+                let dest = self.eval_lvalue(lvalue)?;
+                let dest_ty = self.lvalue_ty(lvalue);
+                self.eval_rvalue_into(rvalue, dest.ptr, dest_ty)
+            }
+            mir::StatementKind::SetDiscriminant { ref lvalue, variant_index } => {
+                let dest = self.eval_lvalue(lvalue)?;
+                let dest_ty = self.lvalue_ty(lvalue);
+                self.write_discriminant(dest.ptr, dest_ty, variant_index)
+            }
+            mir::StatementKind::StorageLive(mir::Lvalue::Local(local)) => {
+                self.storage_live(local)
+            }
+            mir::StatementKind::StorageDead(mir::Lvalue::Local(local)) => {
+                self.storage_dead(local)
+            }
+            mir::StatementKind::StorageLive(_) |
+            mir::StatementKind::StorageDead(_) |
+            mir::StatementKind::EndRegion(_) |
+            mir::StatementKind::Nop => Ok(()),
+            mir::StatementKind::InlineAsm { .. } => {
+                Err(EvalError::Unimplemented("inline assembly".to_owned()))
+            }
+        }
+    }
+
+    /// Gives `local` a fresh allocation. The locals of constants and statics
+    /// are never freed, because the final value may borrow from them.
+    fn storage_live(&mut self, local: mir::Local) -> EvalResult<'tcx> {
+        let ty = {
+            let frame = self.frame();
+            self.monomorphize(frame.mir.local_decls[local].ty, frame.substs)
+        };
+        if self.frame().cleanup == StackPopCleanup::Global {
+            if self.frame().locals[local].is_some() {
+                return Ok(());
+            }
+        } else if let Some(ptr) = self.frame().locals[local] {
+            self.memory.deallocate(ptr)?;
+        }
+        let ptr = self.allocate(ty)?;
+        self.frame_mut().locals[local] = Some(ptr);
+        Ok(())
+    }
+
+    fn storage_dead(&mut self, local: mir::Local) -> EvalResult<'tcx> {
+        if self.frame().cleanup == StackPopCleanup::Global {
+            return Ok(());
+        }
+        if let Some(ptr) = self.frame_mut().locals[local].take() {
+            self.memory.deallocate(ptr)?;
+        }
+        Ok(())
+    }
+
+    fn eval_rvalue_into(&mut self,
+                        rvalue: &mir::Rvalue<'tcx>,
+                        dest: Pointer,
+                        dest_ty: Ty<'tcx>)
+                        -> EvalResult<'tcx> {
+        let dl = self.data_layout();
+        match *rvalue {
+            mir::Rvalue::Use(ref operand) => {
+                let value = self.eval_operand(operand)?;
+                self.write_value(value, dest, dest_ty)
+            }
+
+            mir::Rvalue::Repeat(ref operand, count) => {
+                let elem_ty = dest_ty.sequence_element_type(self.tcx);
+                let elem_size = self.size_of(elem_ty)?;
+                let value = self.eval_operand(operand)?;
+                for i in 0..count.as_u64(self.tcx.sess.target.uint_type) {
+                    self.write_value(value, dest.offset(i * elem_size, dl)?, elem_ty)?;
+                }
+                Ok(())
+            }
+
+            mir::Rvalue::Ref(_, _, ref lvalue) => {
+                let lvalue = self.eval_lvalue(lvalue)?;
+                let value = match lvalue.extra {
+                    LvalueExtra::Length(len) => {
+                        Value::ByValPair(PrimVal::Ptr(lvalue.ptr), PrimVal::from_u64(len))
+                    }
+                    LvalueExtra::None |
+                    LvalueExtra::DowncastVariant(_) => Value::ByVal(PrimVal::Ptr(lvalue.ptr)),
+                };
+                self.write_value(value, dest, dest_ty)
+            }
+
+            mir::Rvalue::Len(ref lvalue) => {
+                let ty = self.lvalue_ty(lvalue);
+                let lvalue = self.eval_lvalue(lvalue)?;
+                let len = self.lvalue_len(lvalue, ty)?;
+                self.write_value(Value::ByVal(PrimVal::from_u64(len)), dest, dest_ty)
+            }
+
+            mir::Rvalue::Cast(kind, ref operand, _) => {
+                let value = self.eval_cast(kind, operand, dest_ty)?;
+                self.write_value(value, dest, dest_ty)
+            }
+
+            mir::Rvalue::BinaryOp(op, ref left, ref right) => {
+                let (value, _) = self.eval_binop(op, left, right)?;
+                self.write_value(Value::ByVal(value), dest, dest_ty)
+            }
+
+            mir::Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                let (value, overflowed) = self.eval_binop(op, left, right)?;
+                let value_ty = self.field_ty(dest_ty, None, 0)?;
+                let flag_offset = self.field_offset(dest_ty, None, 1)?;
+                self.write_value(Value::ByVal(value), dest, value_ty)?;
+                let flag = Value::ByVal(PrimVal::from_bool(overflowed));
+                let bool_ty = self.tcx.types.bool;
+                self.write_value(flag, dest.offset(flag_offset, dl)?, bool_ty)
+            }
+
+            mir::Rvalue::NullaryOp(mir::NullOp::SizeOf, ty) => {
+                let ty = self.monomorphize(ty, self.frame().substs);
+                let size = self.size_of(ty)?;
+                self.write_value(Value::ByVal(PrimVal::from_u64(size)), dest, dest_ty)
+            }
+
+            mir::Rvalue::NullaryOp(mir::NullOp::Box, _) => {
+                Err(EvalError::Unimplemented("heap allocations".to_owned()))
+            }
+
+            mir::Rvalue::UnaryOp(op, ref operand) => {
+                let ty = self.operand_ty(operand);
+                let value = self.eval_operand_to_primval(operand)?;
+                let result = self.unary_op(op, value, ty)?;
+                self.write_value(Value::ByVal(result), dest, dest_ty)
+            }
+
+            mir::Rvalue::Discriminant(ref lvalue) => {
+                let ty = self.lvalue_ty(lvalue);
+                let lvalue = self.eval_lvalue(lvalue)?;
+                let discr = self.read_discriminant_value(lvalue.ptr, ty)?;
+                self.write_value(Value::ByVal(PrimVal::Bytes(discr)), dest, dest_ty)
+            }
+
+            mir::Rvalue::Aggregate(ref kind, ref operands) => {
+                let (variant, active_field) = match **kind {
+                    mir::AggregateKind::Adt(_, variant, _, active_field) => {
+                        (Some(variant), active_field)
+                    }
+                    _ => (None, None),
+                };
+                for (i, operand) in operands.iter().enumerate() {
+                    let value = self.eval_operand(operand)?;
+                    let ty = self.operand_ty(operand);
+                    let offset = self.field_offset(dest_ty, variant, active_field.unwrap_or(i))?;
+                    self.write_value(value, dest.offset(offset, dl)?, ty)?;
+                }
+                match variant {
+                    Some(variant) => self.write_discriminant(dest, dest_ty, variant),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    pub fn eval_operand(&mut self, operand: &mir::Operand<'tcx>) -> EvalResult<'tcx, Value> {
+        match *operand {
+            mir::Operand::Consume(ref lvalue) => {
+                let ty = self.lvalue_ty(lvalue);
+                let lvalue = self.eval_lvalue(lvalue)?;
+                self.read_value(lvalue.ptr, ty)
+            }
+            mir::Operand::Constant(ref constant) => {
+                let ty = self.monomorphize(constant.ty, self.frame().substs);
+                match constant.literal {
+                    mir::Literal::Value { ref value } => self.const_to_value(value, ty),
+                    mir::Literal::Item { def_id, substs } => {
+                        let substs = substs.subst(self.tcx, self.frame().substs);
+                        let substs = self.tcx.erase_regions(&substs);
+                        let ptr = self.referenced_const(def_id, substs, ty)?;
+                        self.read_value(ptr, ty)
+                    }
+                    mir::Literal::Promoted { index } => {
+                        let (def_id, substs, mir) = {
+                            let frame = self.frame();
+                            (frame.def_id, frame.substs, frame.mir)
+                        };
+                        let ptr = self.eval_global_mir(def_id, substs, &mir.promoted[index], ty)?;
+                        self.read_value(ptr, ty)
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn eval_operand_to_primval(&mut self, operand: &mir::Operand<'tcx>)
+                                   -> EvalResult<'tcx, PrimVal> {
+        let ty = self.operand_ty(operand);
+        let value = self.eval_operand(operand)?;
+        self.value_to_primval(value, ty)
+    }
+
+    /// The allocation holding the value of the constant `def_id`, which is
+    /// evaluated through the `const_eval` query so that its errors are only
+    /// reported once.
+    fn referenced_const(&mut self, def_id: DefId, substs: &'tcx Substs<'tcx>, ty: Ty<'tcx>)
+                        -> EvalResult<'tcx, Pointer> {
+        let key = self.param_env.and((def_id, substs));
+        match self.tcx.at(self.cur_span()).const_eval(key) {
+            Ok(val) => {
+                let ptr = self.allocate(ty)?;
+                self.write_const_val(&val, ptr, ty)?;
+                self.memory.mark_immutable(ptr.alloc_id)?;
+                Ok(ptr)
+            }
+            // The HIR evaluator cannot represent every value, e.g. unions,
+            // so evaluate the constant here instead.
+            Err(ConstEvalErr { kind: ErrKind::UnimplementedConstVal(_), .. }) |
+            Err(ConstEvalErr { kind: ErrKind::MiscCatchAll, .. }) => {
+                match lookup_const_by_id(self.tcx, key) {
+                    Some((def_id, substs)) => self.eval_global(def_id, substs, ty),
+                    None => Err(EvalError::TooGeneric),
+                }
+            }
+            Err(err) => Err(EvalError::ReferencedConstant(err)),
+        }
+    }
+
+    fn terminator(&mut self, terminator: &mir::Terminator<'tcx>) -> EvalResult<'tcx> {
+        debug!("terminator: {:?}", terminator.kind);
+        match terminator.kind {
+            mir::TerminatorKind::Goto { target } => self.goto_block(target),
+
+            mir::TerminatorKind::SwitchInt { ref discr, switch_ty, ref values, ref targets } => {
+                let discr = self.eval_operand_to_primval(discr)?;
+                let switch_ty = self.monomorphize(switch_ty, self.frame().substs);
+                let size = self.size_of(switch_ty)?;
+                let discr = match switch_ty.sty {
+                    ty::TyBool => discr.to_bool()? as u128,
+                    ty::TyChar => discr.to_char()? as u128,
+                    _ => discr.to_bytes()?,
+                };
+                let mut target = targets[targets.len() - 1];
+                for (value, &value_target) in values.iter().zip(targets) {
+                    if truncate(value.to_u128_unchecked(), size) == discr {
+                        target = value_target;
+                        break;
+                    }
+                }
+                self.goto_block(target);
+            }
+
+            mir::TerminatorKind::Return => self.pop_frame()?,
+
+            // Nothing unwinds, so there is nothing to resume.
+            mir::TerminatorKind::Resume |
+            mir::TerminatorKind::Unreachable => return Err(EvalError::Unreachable),
+
+            mir::TerminatorKind::Drop { ref location, target, .. } => {
+                let ty = self.lvalue_ty(location);
+                if ty.needs_drop(self.tcx, self.param_env) {
+                    return Err(EvalError::Unimplemented("destructors".to_owned()));
+                }
+                self.goto_block(target);
+            }
+
+            mir::TerminatorKind::DropAndReplace { ref location, ref value, target, .. } => {
+                let ty = self.lvalue_ty(location);
+                if ty.needs_drop(self.tcx, self.param_env) {
+                    return Err(EvalError::Unimplemented("destructors".to_owned()));
+                }
+                let dest = self.eval_lvalue(location)?;
+                let value = self.eval_operand(value)?;
+                self.write_value(value, dest.ptr, ty)?;
+                self.goto_block(target);
+            }
+
+            mir::TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.eval_call(func, args, destination)?;
+            }
+
+            mir::TerminatorKind::Assert { ref cond, expected, ref msg, target, .. } => {
+                if self.eval_operand_to_primval(cond)?.to_bool()? != expected {
+                    return Err(match *msg {
+                        mir::AssertMessage::BoundsCheck { ref len, ref index } => {
+                            EvalError::IndexOutOfBounds {
+                                len: self.eval_operand_to_primval(len)?.to_u64()?,
+                                index: self.eval_operand_to_primval(index)?.to_u64()?,
+                            }
+                        }
+                        mir::AssertMessage::Math(ref err) => EvalError::Math(err.clone()),
+                    });
+                }
+                self.goto_block(target);
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_call(&mut self,
+                 func: &mir::Operand<'tcx>,
+                 args: &[mir::Operand<'tcx>],
+                 destination: &Option<(mir::Lvalue<'tcx>, mir::BasicBlock)>)
+                 -> EvalResult<'tcx> {
+        let func_ty = self.operand_ty(func);
+        let instance = match func_ty.sty {
+            ty::TyFnDef(def_id, substs) => self.resolve(def_id, substs)?,
+            ty::TyFnPtr(_) => {
+                let ptr = self.eval_operand_to_primval(func)?.to_ptr()?;
+                self.memory.get_fn(ptr)?
+            }
+            _ => bug!("eval_call: `{}` is not callable", func_ty),
+        };
+        let def_id = instance.def_id();
+
+        let mut arg_values = Vec::with_capacity(args.len());
+        for arg in args {
+            let ty = self.operand_ty(arg);
+            arg_values.push((self.eval_operand(arg)?, ty));
+        }
+
+        if let ty::InstanceDef::Intrinsic(_) = instance.def {
+            let (dest, target) = match *destination {
+                Some((ref lvalue, target)) => (lvalue, target),
+                None => return Err(EvalError::Panic),
+            };
+            let dest_ty = self.lvalue_ty(dest);
+            let dest = self.eval_lvalue(dest)?.ptr;
+            self.call_intrinsic(instance, &arg_values, dest, dest_ty)?;
+            self.goto_block(target);
+            return Ok(());
+        }
+
+        if !self.tcx.is_const_fn(def_id) {
+            let path = self.tcx.item_path_str(def_id);
+            return Err(EvalError::Unimplemented(format!("calls to non-const fn `{}`", path)));
+        }
+        let (dest, target) = match *destination {
+            Some((ref lvalue, target)) => (self.eval_lvalue(lvalue)?.ptr, target),
+            None => return Err(EvalError::Panic),
+        };
+        if !self.tcx.is_mir_available(def_id) {
+            return Err(EvalError::NoMirFor(self.tcx.item_path_str(def_id)));
+        }
+        let mir = self.tcx.optimized_mir(def_id);
+        if mir.spread_arg.is_some() {
+            return Err(EvalError::Unimplemented("the \"rust-call\" ABI".to_owned()));
+        }
+
+        self.push_frame(def_id, instance.substs, mir, dest, StackPopCleanup::Goto(target))?;
+        let arg_locals = mir.args_iter().collect::<Vec<_>>();
+        for (local, (value, ty)) in arg_locals.into_iter().zip(arg_values) {
+            let ptr = self.frame().locals[local].expect("arguments are always live");
+            self.write_value(value, ptr, ty)?;
+        }
+        Ok(())
+    }
+
+    /// The function `def_id` with `substs` refers to. Trait methods are not
+    /// resolved to their implementation.
+    pub fn resolve(&self, def_id: DefId, substs: &'tcx Substs<'tcx>)
+                   -> EvalResult<'tcx, ty::Instance<'tcx>> {
+        if self.tcx.trait_of_item(def_id).is_some() {
+            return Err(EvalError::Unimplemented("calls to trait methods".to_owned()));
+        }
+        let def = match self.tcx.fn_sig(def_id).abi() {
+            Abi::RustIntrinsic | Abi::PlatformIntrinsic => ty::InstanceDef::Intrinsic(def_id),
+            _ => ty::InstanceDef::Item(def_id),
+        };
+        Ok(ty::Instance { def: def, substs: substs })
+    }
+
+    fn call_intrinsic(&mut self,
+                      instance: ty::Instance<'tcx>,
+                      args: &[(Value, Ty<'tcx>)],
+                      dest: Pointer,
+                      dest_ty: Ty<'tcx>)
+                      -> EvalResult<'tcx> {
+        let name = self.tcx.item_name(instance.def_id()).as_str();
+        let dl = self.data_layout();
+        let value = match &name[..] {
+            "size_of" => self.size_of(instance.substs.type_at(0))?,
+            "min_align_of" => self.align_of(instance.substs.type_at(0))?,
+            "pref_align_of" => {
+                self.layout_of(instance.substs.type_at(0))?.align(dl).pref()
+            }
+            "transmute" => {
+                let (value, ty) = args[0];
+                let src = self.force_allocation(value, ty)?;
+                let size = self.size_of(dest_ty)?;
+                return self.memory.copy(src, dest, size, dl);
+            }
+            _ => return Err(EvalError::Unimplemented(format!("intrinsic `{}`", name))),
+        };
+        self.write_value(Value::ByVal(PrimVal::from_u64(value)), dest, dest_ty)
+    }
+}
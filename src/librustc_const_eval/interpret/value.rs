@@ -0,0 +1,135 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::mem::transmute;
+
+use super::{EvalError, EvalResult, Pointer};
+
+/// A primitive value: an integer, float, bool or char, a pointer, or
+/// nothing at all if it was never initialized.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrimVal {
+    /// The bits of the value, zero-extended from its size to 128 bits.
+    Bytes(u128),
+
+    /// A pointer into an allocation. Pointers keep their provenance even
+    /// when cast to integers, so that they can be turned back into pointers.
+    Ptr(Pointer),
+
+    Undef,
+}
+
+/// The value of an operand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// A value that lives in memory, e.g. a struct or an array.
+    ByRef(Pointer),
+
+    /// A scalar, which includes thin pointers.
+    ByVal(PrimVal),
+
+    /// A fat pointer, i.e. a data pointer and a length.
+    ByValPair(PrimVal, PrimVal),
+}
+
+impl PrimVal {
+    pub fn from_bool(b: bool) -> PrimVal {
+        PrimVal::Bytes(b as u128)
+    }
+
+    pub fn from_char(c: char) -> PrimVal {
+        PrimVal::Bytes(c as u128)
+    }
+
+    pub fn from_u64(n: u64) -> PrimVal {
+        PrimVal::Bytes(n as u128)
+    }
+
+    pub fn from_f32(f: f32) -> PrimVal {
+        PrimVal::Bytes(unsafe { transmute::<f32, u32>(f) } as u128)
+    }
+
+    pub fn from_f64(f: f64) -> PrimVal {
+        PrimVal::Bytes(unsafe { transmute::<f64, u64>(f) } as u128)
+    }
+
+    pub fn to_bytes<'tcx>(self) -> EvalResult<'tcx, u128> {
+        match self {
+            PrimVal::Bytes(b) => Ok(b),
+            PrimVal::Ptr(_) => Err(EvalError::ReadPointerAsBytes),
+            PrimVal::Undef => Err(EvalError::ReadUndefBytes),
+        }
+    }
+
+    pub fn to_ptr<'tcx>(self) -> EvalResult<'tcx, Pointer> {
+        match self {
+            PrimVal::Ptr(p) => Ok(p),
+            PrimVal::Bytes(_) => Err(EvalError::ReadBytesAsPointer),
+            PrimVal::Undef => Err(EvalError::ReadUndefBytes),
+        }
+    }
+
+    pub fn to_bool<'tcx>(self) -> EvalResult<'tcx, bool> {
+        match self.to_bytes()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(EvalError::InvalidBool(b)),
+        }
+    }
+
+    pub fn to_char<'tcx>(self) -> EvalResult<'tcx, char> {
+        let bits = self.to_bytes()?;
+        if bits > ::std::u32::MAX as u128 {
+            return Err(EvalError::InvalidChar(bits));
+        }
+        ::std::char::from_u32(bits as u32).ok_or(EvalError::InvalidChar(bits))
+    }
+
+    pub fn to_u64<'tcx>(self) -> EvalResult<'tcx, u64> {
+        let bits = self.to_bytes()?;
+        assert!(bits <= ::std::u64::MAX as u128, "PrimVal::to_u64: {} does not fit", bits);
+        Ok(bits as u64)
+    }
+
+    pub fn to_f32<'tcx>(self) -> EvalResult<'tcx, f32> {
+        Ok(unsafe { transmute::<u32, f32>(self.to_bytes()? as u32) })
+    }
+
+    pub fn to_f64<'tcx>(self) -> EvalResult<'tcx, f64> {
+        Ok(unsafe { transmute::<u64, f64>(self.to_bytes()? as u64) })
+    }
+
+    pub fn is_null<'tcx>(self) -> EvalResult<'tcx, bool> {
+        match self {
+            PrimVal::Bytes(b) => Ok(b == 0),
+            PrimVal::Ptr(_) => Ok(false),
+            PrimVal::Undef => Err(EvalError::ReadUndefBytes),
+        }
+    }
+}
+
+/// Truncates `bits` to the low `size` bytes.
+pub fn truncate(bits: u128, size: u64) -> u128 {
+    if size >= 16 {
+        bits
+    } else {
+        bits & ((1 << (size * 8)) - 1)
+    }
+}
+
+/// Interprets the low `size` bytes of `bits` as a signed integer.
+pub fn sign_extend(bits: u128, size: u64) -> i128 {
+    if size >= 16 {
+        bits as i128
+    } else {
+        let shift = 128 - size * 8;
+        ((bits << shift) as i128) >> shift
+    }
+}
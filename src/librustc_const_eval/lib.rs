@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! constant evaluation on the HIR and code to validate patterns/matches
+//! constant evaluation on the HIR and MIR and code to validate patterns/matches
 //!
 //! # Note
 //!
@@ -44,6 +44,7 @@ extern crate syntax_pos;
 pub mod diagnostics;
 
 mod eval;
+mod interpret;
 mod _match;
 pub mod check_match;
 pub mod pattern;
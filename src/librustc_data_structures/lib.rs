@@ -63,6 +63,7 @@ pub mod obligation_forest;
 pub mod snapshot_map;
 pub mod snapshot_vec;
 pub mod stable_hasher;
+pub mod sync;
 pub mod transitive_relation;
 pub mod unify;
 pub mod fnv;
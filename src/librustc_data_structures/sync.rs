@@ -0,0 +1,326 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Types that are thread-safe when the compiler is built with
+//! `--cfg parallel_queries`, and cheap single-threaded equivalents otherwise.
+//!
+//! Code that has to work in both configurations should use these instead of
+//! picking between `Rc` and `Arc`, or `RefCell` and `Mutex`, itself:
+//!
+//! - `Lrc` is `Rc`, or `Arc` in parallel builds.
+//! - `Lock` is a `RefCell`, or a `Mutex` in parallel builds. Either way it
+//!   only hands out exclusive access, through `lock`.
+//! - `RwLock` is a `RefCell`, or a `std::sync::RwLock` in parallel builds. It
+//!   hands out shared access through `read` and exclusive access through
+//!   `write`.
+//! - `Sharded` splits a value into several `RwLock`s selected by the hash of
+//!   a key, so that threads working on different keys rarely contend.
+//! - `par_for_each` runs a closure over some items, on several threads in
+//!   parallel builds.
+//! - `Send` and `Sync` are the marker traits of the same name in parallel
+//!   builds, and are implemented for every type otherwise.
+
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use fx::FxHasher;
+
+#[cfg(not(parallel_queries))]
+pub use std::rc::Rc as Lrc;
+#[cfg(parallel_queries)]
+pub use std::sync::Arc as Lrc;
+
+#[cfg(not(parallel_queries))]
+pub trait Send {}
+#[cfg(not(parallel_queries))]
+impl<T: ?Sized> Send for T {}
+
+#[cfg(not(parallel_queries))]
+pub trait Sync {}
+#[cfg(not(parallel_queries))]
+impl<T: ?Sized> Sync for T {}
+
+#[cfg(parallel_queries)]
+pub use std::marker::{Send, Sync};
+
+#[cfg(not(parallel_queries))]
+pub type LockGuard<'a, T> = ::std::cell::RefMut<'a, T>;
+#[cfg(parallel_queries)]
+pub type LockGuard<'a, T> = ::std::sync::MutexGuard<'a, T>;
+
+#[cfg(not(parallel_queries))]
+pub type ReadGuard<'a, T> = ::std::cell::Ref<'a, T>;
+#[cfg(parallel_queries)]
+pub type ReadGuard<'a, T> = ::std::sync::RwLockReadGuard<'a, T>;
+
+#[cfg(not(parallel_queries))]
+pub type WriteGuard<'a, T> = ::std::cell::RefMut<'a, T>;
+#[cfg(parallel_queries)]
+pub type WriteGuard<'a, T> = ::std::sync::RwLockWriteGuard<'a, T>;
+
+/// Whether the compiler was built to run queries on several threads.
+pub fn is_parallel() -> bool {
+    cfg!(parallel_queries)
+}
+
+#[derive(Debug, Default)]
+pub struct Lock<T> {
+    #[cfg(not(parallel_queries))]
+    inner: ::std::cell::RefCell<T>,
+    #[cfg(parallel_queries)]
+    inner: ::std::sync::Mutex<T>,
+}
+
+impl<T> Lock<T> {
+    #[cfg(not(parallel_queries))]
+    pub fn new(value: T) -> Lock<T> {
+        Lock { inner: ::std::cell::RefCell::new(value) }
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn new(value: T) -> Lock<T> {
+        Lock { inner: ::std::sync::Mutex::new(value) }
+    }
+
+    #[cfg(not(parallel_queries))]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner().unwrap()
+    }
+
+    #[cfg(not(parallel_queries))]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut().unwrap()
+    }
+
+    /// Gives exclusive access to the value. Without `parallel_queries` this
+    /// panics if the value is already locked, otherwise it blocks until the
+    /// lock is released.
+    #[cfg(not(parallel_queries))]
+    pub fn lock(&self) -> LockGuard<T> {
+        self.inner.borrow_mut()
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn lock(&self) -> LockGuard<T> {
+        self.inner.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RwLock<T> {
+    #[cfg(not(parallel_queries))]
+    inner: ::std::cell::RefCell<T>,
+    #[cfg(parallel_queries)]
+    inner: ::std::sync::RwLock<T>,
+}
+
+impl<T> RwLock<T> {
+    #[cfg(not(parallel_queries))]
+    pub fn new(value: T) -> RwLock<T> {
+        RwLock { inner: ::std::cell::RefCell::new(value) }
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn new(value: T) -> RwLock<T> {
+        RwLock { inner: ::std::sync::RwLock::new(value) }
+    }
+
+    /// Gives shared access to the value. Without `parallel_queries` this
+    /// panics if the value is being written, otherwise it blocks until the
+    /// writer is done.
+    #[cfg(not(parallel_queries))]
+    pub fn read(&self) -> ReadGuard<T> {
+        self.inner.borrow()
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn read(&self) -> ReadGuard<T> {
+        self.inner.read().unwrap()
+    }
+
+    /// Gives exclusive access to the value, like `Lock::lock`.
+    #[cfg(not(parallel_queries))]
+    pub fn write(&self) -> WriteGuard<T> {
+        self.inner.borrow_mut()
+    }
+
+    #[cfg(parallel_queries)]
+    pub fn write(&self) -> WriteGuard<T> {
+        self.inner.write().unwrap()
+    }
+}
+
+#[cfg(not(parallel_queries))]
+const SHARD_BITS: usize = 0;
+#[cfg(parallel_queries)]
+const SHARD_BITS: usize = 5;
+
+const SHARDS: usize = 1 << SHARD_BITS;
+
+/// A value split into shards, each of them responsible for the keys whose
+/// hash selects it.
+pub struct Sharded<T> {
+    shards: Vec<RwLock<T>>,
+}
+
+impl<T: Default> Default for Sharded<T> {
+    fn default() -> Sharded<T> {
+        Sharded::new(T::default)
+    }
+}
+
+impl<T> Sharded<T> {
+    pub fn new<F: FnMut() -> T>(mut value: F) -> Sharded<T> {
+        Sharded {
+            shards: (0..SHARDS).map(|_| RwLock::new(value())).collect(),
+        }
+    }
+
+    /// The shard holding `key`, if it is in there at all.
+    pub fn get_shard_by_value<K: Hash + ?Sized>(&self, key: &K) -> &RwLock<T> {
+        if SHARDS == 1 {
+            &self.shards[0]
+        } else {
+            let mut hasher = BuildHasherDefault::<FxHasher>::default().build_hasher();
+            key.hash(&mut hasher);
+            // The low bits are the ones `HashMap` relies on within a shard,
+            // so pick the shard with the high ones.
+            let index = (hasher.finish() >> (64 - SHARD_BITS)) as usize;
+            &self.shards[index]
+        }
+    }
+
+    /// Gives shared access to every shard, e.g. to go over all values.
+    pub fn read_shards(&self) -> Vec<ReadGuard<T>> {
+        self.shards.iter().map(|shard| shard.read()).collect()
+    }
+}
+
+/// Calls `f` on each of `items`. In parallel builds and with more than one
+/// thread, the items are handed out to `threads` threads, including the
+/// current one, in no particular order.
+///
+/// If `f` panics for any item, the panic is propagated once all threads have
+/// stopped.
+#[cfg(not(parallel_queries))]
+pub fn par_for_each<T, I, F>(_threads: usize, items: I, f: F)
+    where I: IntoIterator<Item = T>,
+          F: Fn(T)
+{
+    for item in items {
+        f(item);
+    }
+}
+
+#[cfg(parallel_queries)]
+pub fn par_for_each<T, I, F>(threads: usize, items: I, f: F)
+    where I: IntoIterator<Item = T>,
+          T: Send,
+          F: Fn(T) + Sync
+{
+    use std::sync::Mutex;
+
+    let items: Vec<T> = items.into_iter().collect();
+    if threads <= 1 || items.len() <= 1 {
+        for item in items {
+            f(item);
+        }
+        return;
+    }
+
+    let helpers = ::std::cmp::min(threads, items.len()) - 1;
+    let queue = Mutex::new(items.into_iter());
+    let work = || {
+        loop {
+            let item = queue.lock().unwrap().next();
+            match item {
+                Some(item) => f(item),
+                None => break,
+            }
+        }
+    };
+    run_with_helpers(helpers, &work);
+}
+
+/// Runs `work` on the current thread and on `helpers` new threads, and
+/// waits for all of them to finish.
+#[cfg(parallel_queries)]
+fn run_with_helpers(helpers: usize, work: &(Fn() + Sync)) {
+    use std::mem;
+    use std::panic;
+    use std::thread;
+
+    // The compiler needs deep stacks, see `rustc_driver::in_rustc_thread`.
+    const STACK_SIZE: usize = 16 * 1024 * 1024;
+
+    // Every helper is joined before this function returns, even when
+    // unwinding, so they never outlive the borrow of `work`.
+    struct JoinOnDrop(Vec<thread::JoinHandle<()>>);
+
+    impl Drop for JoinOnDrop {
+        fn drop(&mut self) {
+            for handle in self.0.drain(..) {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    let work: &'static (Fn() + Sync) = unsafe { mem::transmute(work) };
+    let mut handles = JoinOnDrop(Vec::with_capacity(helpers));
+    for _ in 0..helpers {
+        let handle = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || work())
+            .unwrap();
+        handles.0.push(handle);
+    }
+
+    work();
+
+    let results: Vec<_> = handles.0.drain(..).map(|handle| handle.join()).collect();
+    for result in results {
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sharded_finds_same_shard() {
+        let sharded: Sharded<Vec<u32>> = Sharded::default();
+        sharded.get_shard_by_value(&"a").write().push(1);
+        sharded.get_shard_by_value(&"a").write().push(2);
+        assert_eq!(*sharded.get_shard_by_value(&"a").read(), vec![1, 2]);
+        let total: usize = sharded.read_shards().iter().map(|shard| shard.len()).sum();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn par_for_each_visits_everything() {
+        let seen = Lock::new(Vec::new());
+        par_for_each(4, 0..100, |i| seen.lock().push(i));
+        let mut seen = seen.into_inner();
+        seen.sort();
+        assert_eq!(seen, (0..100).collect::<Vec<_>>());
+    }
+}
@@ -30,7 +30,7 @@ use rustc::util::fs::rename_or_copy_remove;
 use rustc_allocator as allocator;
 use rustc_borrowck as borrowck;
 use rustc_incremental::{self, IncrementalHashesMap};
-use rustc_resolve::{MakeGlobMap, Resolver};
+use rustc_resolve::{MakeGlobMap, Resolver, ResolverArenas};
 use rustc_metadata::creader::CrateLoader;
 use rustc_metadata::cstore::{self, CStore};
 use rustc_trans::back::{link, write};
@@ -87,10 +87,20 @@ pub fn compile_input(sess: &Session,
         }}
     }
 
+    // Written however compilation ends. Until the output filenames are known,
+    // the trace goes next to where they would be.
+    let mut self_profile = SelfProfileWriter {
+        sess,
+        path: outdir.clone().unwrap_or_default()
+                    .join(input.filestem())
+                    .with_extension("profile.json"),
+    };
+
     // We need nested scopes here, because the intermediate results can keep
     // large chunks of memory alive and we want to free them as soon as
     // possible to keep the peak memory usage low
     let (outputs, trans) = {
+        sess.profiler(|p| p.start_activity("parsing"));
         let krate = match phase_1_parse_input(sess, input) {
             Ok(krate) => krate,
             Err(mut parse_error) => {
@@ -98,6 +108,7 @@ pub fn compile_input(sess: &Session,
                 return Err(CompileIncomplete::Errored(ErrorReported));
             }
         };
+        sess.profiler(|p| p.end_activity("parsing"));
 
         let (krate, registry) = {
             let mut compile_state = CompileState::state_after_parse(input,
@@ -115,8 +126,10 @@ pub fn compile_input(sess: &Session,
         };
 
         let outputs = build_output_filenames(input, outdir, output, &krate.attrs, sess);
+        self_profile.path = outputs.with_extension("profile.json");
         let crate_name = link::find_crate_name(Some(sess), &krate.attrs, input);
         let ExpansionResult { expanded_crate, defs, analysis, resolutions, mut hir_forest } = {
+            sess.profiler(|p| p.start_activity("expansion"));
             phase_2_configure_and_expand(
                 sess, &cstore, krate, registry, &crate_name, addl_plugins, control.make_glob_map,
                 |expanded_crate| {
@@ -128,6 +141,7 @@ pub fn compile_input(sess: &Session,
                 }
             )?
         };
+        sess.profiler(|p| p.end_activity("expansion"));
 
         write_out_deps(sess, &outputs, &crate_name);
         if sess.opts.output_types.contains_key(&OutputType::DepInfo) &&
@@ -174,6 +188,7 @@ pub fn compile_input(sess: &Session,
             None
         };
 
+        sess.profiler(|p| p.start_activity("analysis"));
         phase_3_run_analysis_passes(sess,
                                     hir_map,
                                     analysis,
@@ -182,6 +197,7 @@ pub fn compile_input(sess: &Session,
                                     &arenas,
                                     &crate_name,
                                     |tcx, analysis, incremental_hashes_map, result| {
+            sess.profiler(|p| p.end_activity("analysis"));
             {
                 // Eventually, we will want to track plugins.
                 let _ignore = tcx.dep_graph.in_ignore();
@@ -208,8 +224,10 @@ pub fn compile_input(sess: &Session,
                 println!("Pre-trans");
                 tcx.print_debug_stats();
             }
+            sess.profiler(|p| p.start_activity("translation"));
             let trans = phase_4_translate_to_llvm(tcx, analysis, &incremental_hashes_map,
                                                   &outputs);
+            sess.profiler(|p| p.end_activity("translation"));
 
             if log_enabled!(::log::LogLevel::Info) {
                 println!("Post-trans");
@@ -231,7 +249,9 @@ pub fn compile_input(sess: &Session,
         sess.code_stats.borrow().print_type_sizes();
     }
 
+    sess.profiler(|p| p.start_activity("LLVM passes"));
     let phase5_result = phase_5_run_llvm_passes(sess, &trans, &outputs);
+    sess.profiler(|p| p.end_activity("LLVM passes"));
 
     controller_entry_point!(after_llvm,
                             sess,
@@ -241,7 +261,9 @@ pub fn compile_input(sess: &Session,
 
     write::cleanup_llvm(&trans);
 
+    sess.profiler(|p| p.start_activity("linking"));
     phase_6_link_output(sess, &trans, &outputs);
+    sess.profiler(|p| p.end_activity("linking"));
 
     // Now that we won't touch anything in the incremental compilation directory
     // any more, we can finalize it (which involves renaming it)
@@ -259,6 +281,27 @@ pub fn compile_input(sess: &Session,
     Ok(())
 }
 
+/// Writes the `-Z self-profile` trace and summary when dropped, so that they
+/// are there even when compilation fails or is stopped early.
+struct SelfProfileWriter<'a> {
+    sess: &'a Session,
+    path: PathBuf,
+}
+
+impl<'a> Drop for SelfProfileWriter<'a> {
+    fn drop(&mut self) {
+        if !self.sess.opts.debugging_opts.self_profile {
+            return;
+        }
+        let profiler = self.sess.self_profiling.lock();
+        if let Err(e) = profiler.save_trace(&self.path) {
+            self.sess.err(&format!("could not write self-profile to {}: {}",
+                                   self.path.display(), e));
+        }
+        profiler.print_summary();
+    }
+}
+
 fn keep_hygiene_data(sess: &Session) -> bool {
     sess.opts.debugging_opts.keep_hygiene_data
 }
@@ -570,6 +613,65 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
                                        after_expand: F)
                                        -> Result<ExpansionResult, CompileIncomplete>
     where F: FnOnce(&ast::Crate) -> CompileResult,
+{
+    let mut crate_loader = CrateLoader::new(sess, &cstore, crate_name);
+    let resolver_arenas = Resolver::arenas();
+    let result = phase_2_configure_and_expand_inner(sess,
+                                                    cstore,
+                                                    krate,
+                                                    registry,
+                                                    crate_name,
+                                                    addl_plugins,
+                                                    make_glob_map,
+                                                    &resolver_arenas,
+                                                    &mut crate_loader,
+                                                    after_expand);
+    let InnerExpansionResult { expanded_crate, resolver, hir_forest } = result?;
+
+    Ok(ExpansionResult {
+        expanded_crate: expanded_crate,
+        defs: resolver.definitions,
+        analysis: ty::CrateAnalysis {
+            access_levels: Rc::new(AccessLevels::default()),
+            reachable: Rc::new(NodeSet()),
+            name: crate_name.to_string(),
+            glob_map: if resolver.make_glob_map { Some(resolver.glob_map) } else { None },
+        },
+        resolutions: Resolutions {
+            freevars: resolver.freevars,
+            export_map: resolver.export_map,
+            trait_map: resolver.trait_map,
+            maybe_unused_trait_imports: resolver.maybe_unused_trait_imports,
+        },
+        hir_forest: hir_forest,
+    })
+}
+
+/// The results of `phase_2_configure_and_expand_inner`, which keeps the
+/// resolver around for consumers (like rustdoc) that need to resolve paths
+/// after the crate has been lowered to HIR.
+pub struct InnerExpansionResult<'a> {
+    pub expanded_crate: ast::Crate,
+    pub resolver: Resolver<'a>,
+    pub hir_forest: hir_map::Forest,
+}
+
+/// Same as `phase_2_configure_and_expand`, but doesn't tear the resolver
+/// down into its resolutions, so that it has to be given the crate loader
+/// and arenas it lives on.
+pub fn phase_2_configure_and_expand_inner<'a, F>(sess: &'a Session,
+                                                 cstore: &'a CStore,
+                                                 krate: ast::Crate,
+                                                 registry: Option<Registry>,
+                                                 crate_name: &str,
+                                                 addl_plugins: Option<Vec<String>>,
+                                                 make_glob_map: MakeGlobMap,
+                                                 resolver_arenas: &'a ResolverArenas<'a>,
+                                                 crate_loader: &'a mut CrateLoader,
+                                                 after_expand: F)
+                                                 -> Result<InnerExpansionResult<'a>,
+                                                           CompileIncomplete>
+    where F: FnOnce(&ast::Crate) -> CompileResult,
 {
     let time_passes = sess.time_passes();
 
@@ -650,15 +752,13 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
     // its contents but the results of name resolution on those contents. Hopefully we'll push
     // this back at some point.
     let _ignore = sess.dep_graph.in_ignore();
-    let mut crate_loader = CrateLoader::new(sess, &cstore, crate_name);
     crate_loader.preprocess(&krate);
-    let resolver_arenas = Resolver::arenas();
     let mut resolver = Resolver::new(sess,
                                      &krate,
                                      crate_name,
                                      make_glob_map,
-                                     &mut crate_loader,
-                                     &resolver_arenas);
+                                     crate_loader,
+                                     resolver_arenas);
     resolver.whitelisted_legacy_custom_derives = whitelisted_legacy_custom_derives;
     syntax_ext::register_builtins(&mut resolver, syntax_exts, sess.features.borrow().quote);
 
@@ -820,21 +920,9 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
         syntax::ext::hygiene::clear_markings();
     }
 
-    Ok(ExpansionResult {
+    Ok(InnerExpansionResult {
         expanded_crate: krate,
-        defs: resolver.definitions,
-        analysis: ty::CrateAnalysis {
-            access_levels: Rc::new(AccessLevels::default()),
-            reachable: Rc::new(NodeSet()),
-            name: crate_name.to_string(),
-            glob_map: if resolver.make_glob_map { Some(resolver.glob_map) } else { None },
-        },
-        resolutions: Resolutions {
-            freevars: resolver.freevars,
-            export_map: resolver.export_map,
-            trait_map: resolver.trait_map,
-            maybe_unused_trait_imports: resolver.maybe_unused_trait_imports,
-        },
+        resolver: resolver,
         hir_forest: hir_forest,
     })
 }
@@ -938,7 +1026,6 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
     passes.push_pass(MIR_VALIDATED,
                      mir::transform::simplify_branches::SimplifyBranches::new("initial"));
     passes.push_pass(MIR_VALIDATED, mir::transform::simplify::SimplifyCfg::new("qualify-consts"));
-    passes.push_pass(MIR_VALIDATED, mir::transform::nll::NLL);
 
     // Optimizations begin.
     passes.push_pass(MIR_OPTIMIZED, mir::transform::no_landing_pads::NoLandingPads);
@@ -956,6 +1043,10 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
     passes.push_pass(MIR_OPTIMIZED, mir::transform::instcombine::InstCombine);
     passes.push_pass(MIR_OPTIMIZED, mir::transform::deaggregator::Deaggregator);
     passes.push_pass(MIR_OPTIMIZED, mir::transform::copy_prop::CopyPropagation);
+    passes.push_pass(MIR_OPTIMIZED, mir::transform::const_prop::ConstPropagation);
+    passes.push_pass(MIR_OPTIMIZED,
+                     mir::transform::simplify_branches::SimplifyBranches::new("after-const-prop"));
+    passes.push_pass(MIR_OPTIMIZED, mir::transform::dead_store::DeadStoreElimination);
     passes.push_pass(MIR_OPTIMIZED, mir::transform::simplify::SimplifyLocals);
     passes.push_pass(MIR_OPTIMIZED, mir::transform::add_call_guards::AddCallGuards);
     passes.push_pass(MIR_OPTIMIZED, mir::transform::dump_mir::Marker("PreTrans"));
@@ -1024,6 +1115,12 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
              "borrow checking",
              || borrowck::check_crate(tcx));
 
+        if sess.opts.debugging_opts.borrowck.use_mir() {
+            time(time_passes,
+                 "MIR borrow checking",
+                 || tcx.par_body_owners(|def_id| tcx.mir_borrowck(def_id)));
+        }
+
         // Avoid overwhelming user with errors if type checking failed.
         // I'm not sure how helpful this is, to be honest, but it avoids
         // a
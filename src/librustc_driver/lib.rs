@@ -86,6 +86,7 @@ use std::cmp::Ordering::Equal;
 use std::default::Default;
 use std::env;
 use std::ffi::OsString;
+use std::fs;
 use std::io::{self, Read, Write};
 use std::iter::repeat;
 use std::path::PathBuf;
@@ -159,6 +160,20 @@ pub fn run_compiler<'a>(args: &[String],
                         file_loader: Option<Box<FileLoader + 'static>>,
                         emitter_dest: Option<Box<Write + Send>>)
                         -> (CompileResult, Option<Session>)
+{
+    run_compiler_pass(args, callbacks, file_loader, emitter_dest, 1)
+}
+
+// The most times `-Z apply-suggestions` will recompile a crate before giving
+// up on reaching a fixpoint.
+const MAX_SUGGESTION_PASSES: usize = 16;
+
+fn run_compiler_pass<'a>(args: &[String],
+                         callbacks: &mut CompilerCalls<'a>,
+                         file_loader: Option<Box<FileLoader + 'static>>,
+                         emitter_dest: Option<Box<Write + Send>>,
+                         pass: usize)
+                         -> (CompileResult, Option<Session>)
 {
     macro_rules! do_or_return {($expr: expr, $sess: expr) => {
         match $expr {
@@ -196,6 +211,11 @@ pub fn run_compiler<'a>(args: &[String],
         },
     };
 
+    // Later passes of `-Z apply-suggestions` have to read the rewritten files
+    // from disk and can't reuse a custom emitter destination, so only run
+    // them when neither was given.
+    let can_recompile = file_loader.is_none() && emitter_dest.is_none();
+
     let dep_graph = DepGraph::new(sopts.build_dep_graph());
     let cstore = Rc::new(CStore::new(&dep_graph, box rustc_trans::LlvmMetadataLoader));
 
@@ -215,8 +235,50 @@ pub fn run_compiler<'a>(args: &[String],
 
     let plugins = sess.opts.debugging_opts.extra_plugins.clone();
     let control = callbacks.build_controller(&sess, &matches);
-    (driver::compile_input(&sess, &cstore, &input, &odir, &ofile, Some(plugins), &control),
-     Some(sess))
+    let result =
+        driver::compile_input(&sess, &cstore, &input, &odir, &ofile, Some(plugins), &control);
+    sess.diagnostic().emit_diagnostic_summary();
+
+    if sess.opts.debugging_opts.apply_suggestions {
+        let applied = apply_suggestions(&sess);
+        // Failing to write a file is an error even if compilation succeeded.
+        let result = result.and_then(|()| sess.compile_status());
+        if applied == 0 || !can_recompile {
+            return (result, Some(sess));
+        }
+        if pass == MAX_SUGGESTION_PASSES {
+            sess.warn(&format!("suggestions were still being applied after {} passes",
+                               MAX_SUGGESTION_PASSES));
+            return (result, Some(sess));
+        }
+        drop(sess);
+        return run_compiler_pass(args, callbacks, None, None, pass + 1);
+    }
+
+    (result, Some(sess))
+}
+
+// Write the machine-applicable suggestions collected during compilation back
+// to the source files, returning how many were applied.
+fn apply_suggestions(sess: &Session) -> usize {
+    let suggestions = sess.diagnostic().take_suggestions();
+    let mut applied = 0;
+    for file in errors::fix::apply_suggestions(sess.codemap(), &suggestions) {
+        let written = fs::File::create(&file.name).and_then(|mut f| {
+            f.write_all(file.src.as_bytes())
+        });
+        match written {
+            Ok(()) => {
+                sess.note_without_error(&format!("applied {} suggestion{} to `{}`",
+                                                 file.applied,
+                                                 if file.applied == 1 { "" } else { "s" },
+                                                 file.name));
+                applied += file.applied;
+            }
+            Err(e) => sess.err(&format!("failed to write `{}`: {}", file.name, e)),
+        }
+    }
+    applied
 }
 
 // Extract output directory and file from matches.
@@ -1202,6 +1264,7 @@ pub fn diagnostics_registry() -> errors::registry::Registry {
     all_errors.extend_from_slice(&rustc::DIAGNOSTICS);
     all_errors.extend_from_slice(&rustc_typeck::DIAGNOSTICS);
     all_errors.extend_from_slice(&rustc_borrowck::DIAGNOSTICS);
+    all_errors.extend_from_slice(&rustc_mir::DIAGNOSTICS);
     all_errors.extend_from_slice(&rustc_resolve::DIAGNOSTICS);
     all_errors.extend_from_slice(&rustc_privacy::DIAGNOSTICS);
     all_errors.extend_from_slice(&rustc_trans::DIAGNOSTICS);
@@ -0,0 +1,151 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An emitter producing a Checkstyle XML report of all diagnostics.
+//!
+//! Like SARIF, a Checkstyle report covers a whole run, with the diagnostics
+//! grouped by the file they're in. It is collected and written out in the same
+//! way as the SARIF log, see the `sarif` module.
+
+use {CodeMapper, DiagnosticBuilder, Level};
+use emitter::Emitter;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::mem;
+use std::rc::Rc;
+
+thread_local! {
+    /// Diagnostics emitted before the session's emitter was created.
+    static EARLY_REPORT: RefCell<Report> = RefCell::new(Report::new())
+}
+
+/// The `<error>` elements of the report, grouped by file name. Diagnostics
+/// without a location are reported against a file with an empty name.
+type Report = BTreeMap<String, Vec<String>>;
+
+pub struct CheckstyleEmitter {
+    /// Where the report is written, or `None` for an emitter of early
+    /// diagnostics, which leaves them for the session's emitter.
+    dst: Option<Box<Write + Send>>,
+    cm: Option<Rc<CodeMapper>>,
+    report: Report,
+}
+
+impl CheckstyleEmitter {
+    pub fn stderr(code_map: Option<Rc<CodeMapper>>) -> CheckstyleEmitter {
+        CheckstyleEmitter::new(Box::new(io::stderr()), code_map)
+    }
+
+    pub fn new(dst: Box<Write + Send>, code_map: Option<Rc<CodeMapper>>) -> CheckstyleEmitter {
+        let report = EARLY_REPORT.with(|r| mem::replace(&mut *r.borrow_mut(), Report::new()));
+        CheckstyleEmitter {
+            dst: Some(dst),
+            cm: code_map,
+            report: report,
+        }
+    }
+
+    /// An emitter for diagnostics reported before there is a session, which
+    /// end up in the report of the session's emitter.
+    pub fn early() -> CheckstyleEmitter {
+        CheckstyleEmitter {
+            dst: None,
+            cm: None,
+            report: Report::new(),
+        }
+    }
+}
+
+impl Emitter for CheckstyleEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        let severity = match db.level {
+            Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note | Level::Help => "info",
+            Level::Cancelled => return,
+        };
+
+        let mut text = db.message();
+        for child in &db.children {
+            text.push_str(&format!("\n{}: {}", child.level.to_str(), child.message()));
+        }
+        let source = match db.code {
+            Some(ref code) => format!("rustc.{}", code),
+            None => "rustc".to_owned(),
+        };
+
+        let location = match (db.span.primary_span(), &self.cm) {
+            (Some(span), &Some(ref cm)) => {
+                let loc = cm.lookup_char_pos(span.lo);
+                Some((loc.file.name.clone(), loc.line, loc.col.0 + 1))
+            }
+            _ => None,
+        };
+        let (file, position) = match location {
+            Some((file, line, col)) => (file, format!(" line=\"{}\" column=\"{}\"", line, col)),
+            None => (String::new(), String::new()),
+        };
+        let error = format!("<error{} severity=\"{}\" message=\"{}\" source=\"{}\"/>",
+                            position, severity, escape(&text), escape(&source));
+        self.report.entry(file).or_insert_with(Vec::new).push(error);
+    }
+}
+
+impl Drop for CheckstyleEmitter {
+    fn drop(&mut self) {
+        let report = mem::replace(&mut self.report, Report::new());
+        let mut dst = match self.dst.take() {
+            Some(dst) => dst,
+            None => {
+                EARLY_REPORT.with(|early| {
+                    let mut early = early.borrow_mut();
+                    for (file, errors) in report {
+                        early.entry(file).or_insert_with(Vec::new).extend(errors);
+                    }
+                });
+                return;
+            }
+        };
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                                    <checkstyle version=\"4.3\">\n");
+        for (file, errors) in report {
+            out.push_str(&format!("<file name=\"{}\">\n", escape(&file)));
+            for error in errors {
+                out.push_str(&error);
+                out.push('\n');
+            }
+            out.push_str("</file>\n");
+        }
+        out.push_str("</checkstyle>");
+        // Panicking here could abort the process while unwinding from a
+        // fatal error, so failing to write the report is silently ignored.
+        let _ = writeln!(&mut dst, "{}", out);
+        let _ = dst.flush();
+    }
+}
+
+/// Escapes `s` for use in an XML attribute value.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use Applicability;
 use Diagnostic;
 use DiagnosticStyledString;
 
@@ -98,7 +99,11 @@ impl<'a> DiagnosticBuilder<'a> {
             }
         };
 
-        self.handler.emitter.borrow_mut().emit(&self);
+        if !self.handler.is_repeated(&self.diagnostic) {
+            self.handler.emitter.borrow_mut().emit(&self);
+        }
+        self.handler.record_suggestions(&self.diagnostic);
+        self.handler.bump_emitted_diagnostic_count();
         self.cancel();
 
         if is_error {
@@ -157,6 +162,12 @@ impl<'a> DiagnosticBuilder<'a> {
                                     msg: &str,
                                     suggestion: String)
                                     -> &mut Self);
+    forward!(pub fn span_suggestion_with_applicability(&mut self,
+                                                       sp: Span,
+                                                       msg: &str,
+                                                       suggestion: String,
+                                                       applicability: Applicability)
+                                                       -> &mut Self);
     forward!(pub fn span_suggestions(&mut self,
                                      sp: Span,
                                      msg: &str,
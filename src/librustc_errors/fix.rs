@@ -0,0 +1,144 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Mechanically applying code suggestions to source files.
+//!
+//! Only suggestions marked `Applicability::MachineApplicable` that offer a
+//! single replacement for code written directly in a source file are applied. A
+//! suggestion is applied as a whole or not at all: if any of its parts
+//! overlaps an edit from an earlier suggestion, the later suggestion is
+//! dropped and will be reported again on the next compilation.
+
+use {Applicability, CodeSuggestion};
+use CodeMapper;
+
+use syntax_pos::{FileName, DUMMY_SP, NO_EXPANSION};
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// The new contents of a source file after applying suggestions to it.
+pub struct FixedFile {
+    pub name: FileName,
+    pub src: String,
+    /// The number of suggestions applied to this file.
+    pub applied: usize,
+}
+
+/// A single replacement of the bytes `lo..hi` of a file.
+#[derive(Clone, PartialEq, Eq)]
+struct Edit {
+    lo: usize,
+    hi: usize,
+    text: String,
+}
+
+impl Edit {
+    fn conflicts_with(&self, other: &Edit) -> bool {
+        if self.lo == self.hi && other.lo == other.hi {
+            // Two insertions at the same point would have to be ordered
+            // somehow, so keep only the first.
+            return self.lo == other.lo;
+        }
+        (self.lo < other.hi && other.lo < self.hi) ||
+        (self.lo == self.hi && other.lo < self.lo && self.lo < other.hi) ||
+        (other.lo == other.hi && self.lo < other.lo && other.lo < self.hi)
+    }
+}
+
+/// Whether `suggestion` can be applied without any human judgement.
+pub fn is_machine_applicable(suggestion: &CodeSuggestion) -> bool {
+    suggestion.applicability == Applicability::MachineApplicable &&
+    !suggestion.substitution_parts.is_empty() &&
+    suggestion.substitution_parts.iter().all(|part| {
+        part.substitutions.len() == 1 &&
+        part.span != DUMMY_SP &&
+        part.span.ctxt == NO_EXPANSION
+    })
+}
+
+/// Applies as many of `suggestions` as possible, in order, and returns the
+/// rewritten contents of every file that changed.
+pub fn apply_suggestions(cm: &CodeMapper, suggestions: &[CodeSuggestion]) -> Vec<FixedFile> {
+    // Accepted edits and the original source, keyed by file name.
+    let mut files: BTreeMap<FileName, (Rc<String>, Vec<Edit>, usize)> = BTreeMap::new();
+
+    'suggestions: for suggestion in suggestions {
+        if !is_machine_applicable(suggestion) {
+            continue;
+        }
+
+        let mut edits = Vec::new();
+        for part in &suggestion.substitution_parts {
+            let lo = cm.lookup_char_pos(part.span.lo);
+            let hi = cm.lookup_char_pos(part.span.hi);
+            let file = lo.file;
+            if file.name != hi.file.name || !file.is_real_file() || file.name_was_remapped {
+                continue 'suggestions;
+            }
+            let src = match file.src {
+                Some(ref src) => src.clone(),
+                None => continue 'suggestions,
+            };
+            let edit = Edit {
+                lo: (part.span.lo.0 - file.start_pos.0) as usize,
+                hi: (part.span.hi.0 - file.start_pos.0) as usize,
+                text: part.substitutions[0].clone(),
+            };
+            if edit.hi > src.len() || edit.lo > edit.hi ||
+               !src.is_char_boundary(edit.lo) || !src.is_char_boundary(edit.hi) {
+                continue 'suggestions;
+            }
+            edits.push((file.name.clone(), src, edit));
+        }
+
+        let duplicate = edits.iter().all(|&(ref name, _, ref edit)| {
+            files.get(name).map_or(false, |&(_, ref accepted, _)| accepted.contains(edit))
+        });
+        if duplicate {
+            continue;
+        }
+        for (i, &(ref name, _, ref edit)) in edits.iter().enumerate() {
+            let earlier_parts = edits[..i].iter().filter(|e| e.0 == *name).map(|e| &e.2);
+            let accepted = files.get(name).map(|f| &f.1[..]).unwrap_or(&[]);
+            if accepted.iter().chain(earlier_parts).any(|other| edit.conflicts_with(other)) {
+                continue 'suggestions;
+            }
+        }
+
+        let mut touched = Vec::new();
+        for (name, src, edit) in edits {
+            if !touched.contains(&name) {
+                touched.push(name.clone());
+            }
+            files.entry(name).or_insert_with(|| (src, Vec::new(), 0)).1.push(edit);
+        }
+        for name in touched {
+            files.get_mut(&name).unwrap().2 += 1;
+        }
+    }
+
+    files.into_iter().map(|(name, (src, mut edits, applied))| {
+        edits.sort_by_key(|edit| (edit.lo, edit.hi));
+        let mut fixed = String::with_capacity(src.len());
+        let mut pos = 0;
+        for edit in edits {
+            fixed.push_str(&src[pos..edit.lo]);
+            fixed.push_str(&edit.text);
+            pos = edit.hi;
+        }
+        fixed.push_str(&src[pos..]);
+        FixedFile {
+            name: name,
+            src: fixed,
+            applied: applied,
+        }
+    }).collect()
+}
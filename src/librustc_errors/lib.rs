@@ -35,14 +35,18 @@ use emitter::{Emitter, EmitterWriter};
 
 use std::borrow::Cow;
 use std::cell::{RefCell, Cell};
-use std::{error, fmt};
+use std::{error, fmt, mem};
 use std::rc::Rc;
 
 pub mod diagnostic;
 pub mod diagnostic_builder;
+pub mod checkstyle;
 pub mod emitter;
+pub mod fix;
 mod snippet;
 pub mod registry;
+pub mod sarif;
+mod summary;
 mod styled_buffer;
 mod lock;
 
@@ -85,6 +89,21 @@ pub struct CodeSuggestion {
     pub substitution_parts: Vec<Substitution>,
     pub msg: String,
     pub show_code_when_inline: bool,
+    /// Whether the suggestion can be applied without a human checking it.
+    pub applicability: Applicability,
+}
+
+/// How sure the compiler is that a suggestion does what the user intended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
+pub enum Applicability {
+    /// The suggestion is definitely what was meant, and can be applied to the
+    /// code mechanically, e.g. by `-Z apply-suggestions`.
+    MachineApplicable,
+    /// The suggestion may be what was meant, but it is only a guess, like a
+    /// similarly named item for an unresolved one.
+    MaybeIncorrect,
+    /// Nobody has decided how likely the suggestion is to be right.
+    Unspecified,
 }
 
 #[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
@@ -268,11 +287,19 @@ pub use diagnostic_builder::DiagnosticBuilder;
 /// others log errors for later reporting.
 pub struct Handler {
     err_count: Cell<usize>,
+    /// The number of diagnostics of any level emitted so far.
+    emitted_diagnostic_count: Cell<usize>,
     emitter: RefCell<Box<Emitter>>,
     pub can_emit_warnings: bool,
     treat_err_as_bug: bool,
     continue_after_error: Cell<bool>,
     delayed_span_bug: RefCell<Option<(MultiSpan, String)>>,
+    /// Machine-applicable suggestions from emitted diagnostics, if they
+    /// are being collected.
+    suggestions: RefCell<Option<Vec<CodeSuggestion>>>,
+    /// Groups of repeated diagnostics, if only the first of each group is
+    /// being emitted.
+    summary: RefCell<Option<summary::DiagnosticSummary>>,
 }
 
 impl Handler {
@@ -291,11 +318,14 @@ impl Handler {
                         -> Handler {
         Handler {
             err_count: Cell::new(0),
+            emitted_diagnostic_count: Cell::new(0),
             emitter: RefCell::new(e),
             can_emit_warnings: can_emit_warnings,
             treat_err_as_bug: treat_err_as_bug,
             continue_after_error: Cell::new(true),
             delayed_span_bug: RefCell::new(None),
+            suggestions: RefCell::new(None),
+            summary: RefCell::new(None),
         }
     }
 
@@ -303,6 +333,62 @@ impl Handler {
         self.continue_after_error.set(continue_after_error);
     }
 
+    /// Start remembering the machine-applicable suggestions of every
+    /// diagnostic emitted from now on.
+    pub fn collect_suggestions(&self) {
+        let mut suggestions = self.suggestions.borrow_mut();
+        if suggestions.is_none() {
+            *suggestions = Some(Vec::new());
+        }
+    }
+
+    /// Returns the suggestions collected so far, in the order they were
+    /// emitted.
+    pub fn take_suggestions(&self) -> Vec<CodeSuggestion> {
+        self.suggestions.borrow_mut().as_mut().map_or(Vec::new(), |s| mem::replace(s, Vec::new()))
+    }
+
+    fn record_suggestions(&self, diagnostic: &Diagnostic) {
+        if let Some(ref mut suggestions) = *self.suggestions.borrow_mut() {
+            suggestions.extend(diagnostic.suggestions.iter()
+                                                     .filter(|s| fix::is_machine_applicable(s))
+                                                     .cloned());
+        }
+    }
+
+    /// Only emit the first of a group of near-identical errors or warnings,
+    /// and summarize how often each group was repeated at the end.
+    pub fn enable_diagnostic_summary(&self, cm: Rc<CodeMapper>) {
+        *self.summary.borrow_mut() = Some(summary::DiagnosticSummary::new(cm));
+    }
+
+    /// Whether `diagnostic` repeats an earlier one and should not be emitted.
+    fn is_repeated(&self, diagnostic: &Diagnostic) -> bool {
+        match *self.summary.borrow_mut() {
+            Some(ref mut summary) => summary.record(diagnostic),
+            None => false,
+        }
+    }
+
+    /// Emits a note listing the diagnostics that were left out because they
+    /// repeated an earlier one.
+    pub fn emit_diagnostic_summary(&self) {
+        let rows = match *self.summary.borrow_mut() {
+            Some(ref mut summary) => summary.take_rows(),
+            None => return,
+        };
+        if rows.is_empty() {
+            return;
+        }
+        let mut db = DiagnosticBuilder::new(self,
+                                            Level::Note,
+                                            "some diagnostics were repeated and only shown once");
+        for row in rows {
+            db.note(&row);
+        }
+        db.emit();
+    }
+
     pub fn struct_dummy<'a>(&'a self) -> DiagnosticBuilder<'a> {
         DiagnosticBuilder::new(self, Level::Cancelled, "")
     }
@@ -498,6 +584,14 @@ impl Handler {
         self.err_count.get()
     }
 
+    pub fn emitted_diagnostic_count(&self) -> usize {
+        self.emitted_diagnostic_count.get()
+    }
+
+    fn bump_emitted_diagnostic_count(&self) {
+        self.emitted_diagnostic_count.set(self.emitted_diagnostic_count.get() + 1);
+    }
+
     pub fn has_errors(&self) -> bool {
         self.err_count.get() > 0
     }
@@ -521,6 +615,7 @@ impl Handler {
             }
         }
 
+        self.emit_diagnostic_summary();
         panic!(self.fatal(&s));
     }
     pub fn emit(&self, msp: &MultiSpan, msg: &str, lvl: Level) {
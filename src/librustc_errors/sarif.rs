@@ -0,0 +1,300 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An emitter producing a SARIF 2.1 log of all diagnostics.
+//!
+//! Unlike the human-readable and JSON emitters, SARIF describes a whole run
+//! in a single document. Diagnostics are therefore collected as they are
+//! emitted and the log is written out when the session's emitter is dropped,
+//! which happens when the session's `Handler` goes away. Diagnostics reported
+//! before there is a session, such as warnings about the command line, are
+//! kept until the session's emitter is created and written out in its log.
+//!
+//! See http://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use {CodeMapper, CodeSuggestion, DiagnosticBuilder, Level, SubDiagnostic};
+use emitter::Emitter;
+use registry::Registry;
+
+use rustc_serialize::json::Json;
+use syntax_pos::{MultiSpan, Span};
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::mem;
+use std::rc::Rc;
+
+const SARIF_SCHEMA: &'static str = "https://json.schemastore.org/sarif-2.1.0.json";
+const ERROR_INDEX_URL: &'static str = "https://doc.rust-lang.org/error-index.html";
+
+thread_local! {
+    /// Diagnostics emitted before the session's emitter was created.
+    static EARLY_LOG: RefCell<SarifLog> = RefCell::new(SarifLog::default())
+}
+
+#[derive(Default)]
+struct SarifLog {
+    /// One reporting descriptor per error code seen, keyed by the code.
+    rules: BTreeMap<String, Json>,
+    results: Vec<Json>,
+}
+
+impl SarifLog {
+    fn append(&mut self, other: SarifLog) {
+        self.rules.extend(other.rules);
+        self.results.extend(other.results);
+    }
+}
+
+pub struct SarifEmitter {
+    /// Where the log is written, or `None` for an emitter of early
+    /// diagnostics, which leaves them for the session's emitter.
+    dst: Option<Box<Write + Send>>,
+    registry: Option<Registry>,
+    cm: Option<Rc<CodeMapper>>,
+    log: SarifLog,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, code_map: Option<Rc<CodeMapper>>) -> SarifEmitter {
+        SarifEmitter::new(Box::new(io::stderr()), registry, code_map)
+    }
+
+    pub fn new(dst: Box<Write + Send>,
+               registry: Option<Registry>,
+               code_map: Option<Rc<CodeMapper>>) -> SarifEmitter {
+        let log = EARLY_LOG.with(|log| mem::replace(&mut *log.borrow_mut(), SarifLog::default()));
+        SarifEmitter {
+            dst: Some(dst),
+            registry: registry,
+            cm: code_map,
+            log: log,
+        }
+    }
+
+    /// An emitter for diagnostics reported before there is a session, which
+    /// end up in the log of the session's emitter.
+    pub fn early() -> SarifEmitter {
+        SarifEmitter {
+            dst: None,
+            registry: None,
+            cm: None,
+            log: SarifLog::default(),
+        }
+    }
+
+    fn add_rule(&mut self, code: &str) {
+        if self.log.rules.contains_key(code) {
+            return;
+        }
+        let mut rule = vec![
+            ("id", Json::String(code.to_owned())),
+            ("helpUri", Json::String(format!("{}#{}", ERROR_INDEX_URL, code))),
+        ];
+        let explanation = self.registry.as_ref().and_then(|r| r.find_description(code));
+        if let Some(explanation) = explanation {
+            rule.push(("fullDescription", message(explanation.trim().to_owned())));
+        }
+        self.log.rules.insert(code.to_owned(), object(rule));
+    }
+
+    /// The artifact URI and SARIF region covered by `span`, if there is a
+    /// codemap to look it up in.
+    fn region(&self, span: Span) -> Option<(String, Json)> {
+        let cm = match self.cm {
+            Some(ref cm) => cm,
+            None => return None,
+        };
+        let start = cm.lookup_char_pos(span.lo);
+        let end = cm.lookup_char_pos(span.hi);
+        let region = object(vec![
+            ("startLine", Json::U64(start.line as u64)),
+            ("startColumn", Json::U64(start.col.0 as u64 + 1)),
+            ("endLine", Json::U64(end.line as u64)),
+            ("endColumn", Json::U64(end.col.0 as u64 + 1)),
+        ]);
+        Some((start.file.name.clone(), region))
+    }
+
+    fn location(&self, span: Span, label: Option<String>) -> Option<Json> {
+        self.region(span).map(|(uri, region)| {
+            let mut location = vec![
+                ("physicalLocation", object(vec![
+                    ("artifactLocation", artifact_location(uri)),
+                    ("region", region),
+                ])),
+            ];
+            if let Some(label) = label {
+                location.push(("message", message(label)));
+            }
+            object(location)
+        })
+    }
+
+    /// Locations for the primary and secondary spans of `msp`, in that order.
+    fn locations(&self, msp: &MultiSpan) -> (Vec<Json>, Vec<Json>) {
+        let mut primary = Vec::new();
+        let mut secondary = Vec::new();
+        for span_label in msp.span_labels() {
+            if let Some(location) = self.location(span_label.span, span_label.label) {
+                if span_label.is_primary {
+                    primary.push(location);
+                } else {
+                    secondary.push(location);
+                }
+            }
+        }
+        (primary, secondary)
+    }
+
+    /// One SARIF fix per alternative offered by `suggestion`.
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        let alternatives = suggestion.substitution_parts
+                                     .first()
+                                     .map_or(0, |part| part.substitutions.len());
+        (0..alternatives).filter_map(|i| {
+            // Replacements grouped by the file they apply to.
+            let mut changes: BTreeMap<String, Vec<Json>> = BTreeMap::new();
+            for part in &suggestion.substitution_parts {
+                let (uri, region) = match (part.substitutions.get(i), self.region(part.span)) {
+                    (Some(_), Some(region)) => region,
+                    _ => return None,
+                };
+                let replacement = object(vec![
+                    ("deletedRegion", region),
+                    ("insertedContent", object(vec![
+                        ("text", Json::String(part.substitutions[i].clone())),
+                    ])),
+                ]);
+                changes.entry(uri).or_insert_with(Vec::new).push(replacement);
+            }
+            let changes = changes.into_iter().map(|(uri, replacements)| {
+                object(vec![
+                    ("artifactLocation", artifact_location(uri)),
+                    ("replacements", Json::Array(replacements)),
+                ])
+            }).collect();
+            Some(object(vec![
+                ("description", message(suggestion.msg.clone())),
+                ("artifactChanges", Json::Array(changes)),
+            ]))
+        }).collect()
+    }
+
+    fn add_sub_diagnostic(&self,
+                          sub: &SubDiagnostic,
+                          text: &mut String,
+                          related: &mut Vec<Json>) {
+        let level = sub.level.to_str();
+        let (primary, secondary) = self.locations(&sub.span);
+        if primary.is_empty() && secondary.is_empty() {
+            // Notes without a span of their own are part of the message.
+            text.push_str(&format!("\n{}: {}", level, sub.message()));
+            return;
+        }
+        let msg = format!("{}: {}", level, sub.message());
+        for location in primary.into_iter().chain(secondary) {
+            related.push(match location {
+                Json::Object(mut location) => {
+                    if !location.contains_key("message") {
+                        location.insert("message".to_owned(), message(msg.clone()));
+                    }
+                    Json::Object(location)
+                }
+                location => location,
+            });
+        }
+    }
+
+    fn log(&mut self) -> Json {
+        let SarifLog { rules, results } = mem::replace(&mut self.log, SarifLog::default());
+        let driver = object(vec![
+            ("name", Json::String("rustc".to_owned())),
+            ("informationUri", Json::String("https://www.rust-lang.org/".to_owned())),
+            ("rules", Json::Array(rules.into_iter().map(|(_, rule)| rule).collect())),
+        ]);
+        let run = object(vec![
+            ("tool", object(vec![("driver", driver)])),
+            ("columnKind", Json::String("unicodeCodePoints".to_owned())),
+            ("results", Json::Array(results)),
+        ]);
+        object(vec![
+            ("$schema", Json::String(SARIF_SCHEMA.to_owned())),
+            ("version", Json::String("2.1.0".to_owned())),
+            ("runs", Json::Array(vec![run])),
+        ])
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        let level = match db.level {
+            Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note | Level::Help => "note",
+            Level::Cancelled => return,
+        };
+
+        let (locations, mut related) = self.locations(&db.span);
+        let mut text = db.message();
+        for child in &db.children {
+            self.add_sub_diagnostic(child, &mut text, &mut related);
+        }
+        let fixes = db.suggestions.iter().flat_map(|sugg| self.fixes(sugg)).collect::<Vec<_>>();
+
+        let mut result = vec![
+            ("level", Json::String(level.to_owned())),
+            ("message", message(text)),
+            ("locations", Json::Array(locations)),
+        ];
+        if let Some(ref code) = db.code {
+            self.add_rule(code);
+            result.push(("ruleId", Json::String(code.clone())));
+        }
+        if !related.is_empty() {
+            result.push(("relatedLocations", Json::Array(related)));
+        }
+        if !fixes.is_empty() {
+            result.push(("fixes", Json::Array(fixes)));
+        }
+        self.log.results.push(object(result));
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        let mut dst = match self.dst.take() {
+            Some(dst) => dst,
+            None => {
+                let log = mem::replace(&mut self.log, SarifLog::default());
+                EARLY_LOG.with(|early| early.borrow_mut().append(log));
+                return;
+            }
+        };
+        let log = self.log();
+        // Panicking here could abort the process while unwinding from a
+        // fatal error, so failing to write the log is silently ignored.
+        let _ = writeln!(&mut dst, "{}", log);
+        let _ = dst.flush();
+    }
+}
+
+fn object(fields: Vec<(&'static str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_owned(), value)).collect())
+}
+
+fn message(text: String) -> Json {
+    object(vec![("text", Json::String(text))])
+}
+
+fn artifact_location(uri: String) -> Json {
+    object(vec![("uri", Json::String(uri))])
+}
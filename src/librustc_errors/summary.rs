@@ -0,0 +1,165 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Grouping of near-identical diagnostics for `-Z diagnostic-summary`.
+//!
+//! Errors and warnings are grouped by their level, their error code (or
+//! their message, if they have no code) and the origin of their primary
+//! span. The origin of a span produced by a macro expansion is the outermost
+//! macro it was expanded from, so that every error caused by a broken macro
+//! lands in the same group no matter where the macro is invoked. Only the
+//! first diagnostic of each group is emitted; the rest are counted and
+//! reported in a summary at the end of compilation.
+//!
+//! Diagnostics that don't come from a macro are grouped by their cause
+//! instead: what they say, in their message, labels and notes, and where
+//! their secondary spans point. One mistake, such as a wrong signature, gives
+//! the same cause wherever it shows up, while unrelated diagnostics with the
+//! same code rarely do.
+
+use {CodeMapper, Diagnostic, Level};
+
+use syntax_pos::FileName;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+#[derive(PartialEq, Eq, Hash)]
+struct GroupKey {
+    level: &'static str,
+    /// The error code, or the message for diagnostics without one.
+    what: String,
+    /// The outermost macro the primary span was expanded from, if any.
+    origin: Option<String>,
+    /// The cause of the diagnostic, if it has no macro origin.
+    cause: Option<String>,
+}
+
+struct Group {
+    label: String,
+    origin: Option<String>,
+    count: usize,
+    files: HashSet<FileName>,
+}
+
+pub struct DiagnosticSummary {
+    cm: Rc<CodeMapper>,
+    groups: Vec<Group>,
+    index: HashMap<GroupKey, usize>,
+}
+
+impl DiagnosticSummary {
+    pub fn new(cm: Rc<CodeMapper>) -> DiagnosticSummary {
+        DiagnosticSummary {
+            cm: cm,
+            groups: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Adds `diagnostic` to its group, returning `true` if an earlier member
+    /// of the group has already been emitted.
+    pub fn record(&mut self, diagnostic: &Diagnostic) -> bool {
+        match diagnostic.level {
+            Level::Error | Level::Warning => {}
+            _ => return false,
+        }
+        let span = match diagnostic.span.primary_span() {
+            Some(span) => span,
+            None => return false,
+        };
+
+        let (origin, site) = match span.macro_backtrace().pop() {
+            Some(root) => {
+                let origin = match root.def_site_span {
+                    Some(def_site) => format!("{} defined at {}",
+                                              root.macro_decl_name,
+                                              self.cm.span_to_string(def_site)),
+                    None => root.macro_decl_name,
+                };
+                (Some(origin), root.call_site)
+            }
+            None => (None, span),
+        };
+        let (what, label) = match diagnostic.code {
+            Some(ref code) => (code.clone(), code.clone()),
+            None => {
+                let message = diagnostic.message();
+                let label = format!("{} `{}`", diagnostic.level.to_str(), message);
+                (message, label)
+            }
+        };
+        let cause = match origin {
+            Some(_) => None,
+            None => Some(self.cause(diagnostic)),
+        };
+        let key = GroupKey {
+            level: diagnostic.level.to_str(),
+            what: what,
+            origin: origin.clone(),
+            cause: cause,
+        };
+
+        let groups = &mut self.groups;
+        let i = *self.index.entry(key).or_insert_with(|| {
+            groups.push(Group {
+                label: label,
+                origin: origin,
+                count: 0,
+                files: HashSet::new(),
+            });
+            groups.len() - 1
+        });
+        let group = &mut groups[i];
+        group.count += 1;
+        group.files.insert(self.cm.span_to_filename(site));
+        group.count > 1
+    }
+
+    /// Describes what `diagnostic` says, leaving out where it was reported.
+    fn cause(&self, diagnostic: &Diagnostic) -> String {
+        let mut cause = diagnostic.message();
+        for label in diagnostic.span.span_labels() {
+            if !label.is_primary {
+                cause.push('\n');
+                cause.push_str(&self.cm.span_to_string(label.span));
+            }
+            if let Some(label) = label.label {
+                cause.push('\n');
+                cause.push_str(&label);
+            }
+        }
+        for child in &diagnostic.children {
+            cause.push('\n');
+            cause.push_str(&child.message());
+        }
+        cause
+    }
+
+    /// One line for every group with repeated diagnostics, most repeated
+    /// first. All groups are forgotten afterwards.
+    pub fn take_rows(&mut self) -> Vec<String> {
+        self.index.clear();
+        let mut groups = self.groups.drain(..).filter(|g| g.count > 1).collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label.cmp(&b.label)));
+        groups.into_iter().map(|group| {
+            let files = group.files.len();
+            let mut row = format!("{} repeated {} times in {} file{}",
+                                  group.label,
+                                  group.count,
+                                  files,
+                                  if files == 1 { "" } else { "s" });
+            if let Some(origin) = group.origin {
+                row.push_str(&format!(" (from {})", origin));
+            }
+            row
+        }).collect()
+    }
+}
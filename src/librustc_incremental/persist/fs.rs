@@ -134,6 +134,8 @@ const LOCK_FILE_EXT: &'static str = ".lock";
 const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
 const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
 const METADATA_HASHES_FILENAME: &'static str = "metadata.bin";
+const QUERY_DEP_GRAPH_FILENAME: &'static str = "query-dep-graph.bin";
+const QUERY_CACHE_FILENAME: &'static str = "query-cache.bin";
 
 // We encode integers using the following base, so they are shorter than decimal
 // or hexadecimal numbers (we want short file and directory names). Since these
@@ -157,6 +159,14 @@ pub fn metadata_hash_import_path(import_session_dir: &Path) -> PathBuf {
     import_session_dir.join(METADATA_HASHES_FILENAME)
 }
 
+pub fn query_dep_graph_path(sess: &Session) -> PathBuf {
+    in_incr_comp_dir_sess(sess, QUERY_DEP_GRAPH_FILENAME)
+}
+
+pub fn query_cache_path(sess: &Session) -> PathBuf {
+    in_incr_comp_dir_sess(sess, QUERY_CACHE_FILENAME)
+}
+
 pub fn lock_file_path(session_dir: &Path) -> PathBuf {
     let crate_dir = session_dir.parent().unwrap();
 
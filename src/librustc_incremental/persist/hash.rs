@@ -96,6 +96,26 @@ impl<'a, 'tcx> HashContext<'a, 'tcx> {
         }
     }
 
+    /// The fingerprint of the input node `dep_node` in the red-green dep-graph,
+    /// if it still exists. Unlike `hash`, this does not assume that a node
+    /// from the previous session still refers to something.
+    pub fn input_fingerprint(&mut self, dep_node: &DepNode) -> Option<Fingerprint> {
+        match dep_node.kind {
+            DepKind::Krate |
+            DepKind::Hir |
+            DepKind::HirBody => {
+                self.incremental_hashes_map.get(dep_node).cloned()
+            }
+            DepKind::MetaData => {
+                match dep_node.extract_def_id(self.tcx) {
+                    Some(def_id) if !def_id.is_local() => self.hash(dep_node),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn metadata_hash<K, C>(&mut self,
                            key: K,
                            cnum: CrateNum,
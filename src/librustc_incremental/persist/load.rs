@@ -10,7 +10,7 @@
 
 //! Code to save/load the dep-graph from files.
 
-use rustc::dep_graph::{DepNode, WorkProductId, DepKind};
+use rustc::dep_graph::{self, DepNode, WorkProductId, DepKind, PreviousDepGraph};
 use rustc::hir::def_id::DefId;
 use rustc::hir::svh::Svh;
 use rustc::ich::Fingerprint;
@@ -66,6 +66,81 @@ pub fn load_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     let _ignore = tcx.dep_graph.in_ignore();
     load_dep_graph_if_exists(tcx, incremental_hashes_map);
+    load_query_dep_graph(tcx, incremental_hashes_map);
+    load_query_result_cache(tcx);
+}
+
+/// Loads the dep-graph of the queries run in the previous session and colors
+/// its input nodes by comparing their fingerprints to the current ones, so
+/// that queries depending only on unchanged inputs can be marked green.
+fn load_query_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                  incremental_hashes_map: &IncrementalHashesMap) {
+    let path = query_dep_graph_path(tcx.sess);
+    let data = match file_format::read_file(tcx.sess, &path) {
+        Ok(Some(data)) => data,
+        // The file either didn't exist or was produced by an incompatible
+        // compiler version, so there is nothing to reuse.
+        Ok(None) => return,
+        Err(err) => {
+            tcx.sess.warn(&format!("could not load query dep-graph from `{}`: {}",
+                                   path.display(), err));
+            return
+        }
+    };
+
+    let mut decoder = Decoder::new(&data, 0);
+    let prev_graph = u64::decode(&mut decoder).and_then(|prev_commandline_args_hash| {
+        if prev_commandline_args_hash == tcx.sess.opts.dep_tracking_hash() {
+            dep_graph::SerializedDepGraph::decode(&mut decoder).map(Some)
+        } else {
+            Ok(None)
+        }
+    });
+    let prev_graph = match prev_graph {
+        Ok(Some(prev_graph)) => PreviousDepGraph::new(prev_graph),
+        // Results computed with different arguments can't be reused.
+        Ok(None) => return,
+        Err(err) => {
+            tcx.sess.warn(&format!("decoding error in query dep-graph from `{}`: {}",
+                                   path.display(), err));
+            return
+        }
+    };
+
+    let inputs: Vec<DepNode> = (0..prev_graph.node_count())
+        .map(|index| prev_graph.index_to_node(dep_graph::SerializedDepNodeIndex(index as u32)))
+        .filter(|dep_node| dep_node.kind.is_input())
+        .collect();
+    tcx.dep_graph.set_previous(prev_graph);
+
+    let mut hcx = HashContext::new(tcx, incremental_hashes_map);
+    for dep_node in inputs {
+        // Inputs that were removed or can't be hashed stay uncolored, and
+        // so never let anything depending on them be marked green.
+        if let Some(fingerprint) = hcx.input_fingerprint(&dep_node) {
+            tcx.dep_graph.mark_input(dep_node, fingerprint);
+        }
+    }
+}
+
+/// Makes the query results saved by the previous session available to the
+/// queries whose dep-node turns out green.
+fn load_query_result_cache<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+    let path = query_cache_path(tcx.sess);
+    let data = match file_format::read_file(tcx.sess, &path) {
+        Ok(Some(data)) => data,
+        Ok(None) => return,
+        Err(err) => {
+            tcx.sess.warn(&format!("could not load query result cache from `{}`: {}",
+                                   path.display(), err));
+            return
+        }
+    };
+
+    if let Err(err) = tcx.on_disk_query_result_cache.load(data) {
+        tcx.sess.warn(&format!("decoding error in query result cache from `{}`: {}",
+                               path.display(), err));
+    }
 }
 
 fn load_dep_graph_if_exists<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
@@ -15,6 +15,7 @@ use rustc::ich::Fingerprint;
 use rustc::middle::cstore::EncodedMetadataHashes;
 use rustc::session::Session;
 use rustc::ty::TyCtxt;
+use rustc::ty::on_disk_cache;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::graph;
 use rustc_data_structures::indexed_vec::IndexVec;
@@ -73,6 +74,19 @@ pub fn save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             dep_graph_path(sess),
             |e| encode_dep_graph(tcx, &preds, e));
 
+    save_in(sess,
+            query_dep_graph_path(sess),
+            |e| encode_query_dep_graph(tcx, &mut hcx, e));
+
+    save_in(sess,
+            query_cache_path(sess),
+            |e| on_disk_cache::serialize(tcx, e));
+
+    if tcx.sess.opts.debugging_opts.incremental_info {
+        let (green, red) = tcx.dep_graph.color_counts();
+        eprintln!("incremental: {} green and {} red query dep-nodes", green, red);
+    }
+
     let prev_metadata_hashes = incremental_hashes_map.prev_metadata_hashes.borrow();
     dirty_clean::check_dirty_clean_metadata(tcx,
                                             &*prev_metadata_hashes,
@@ -166,6 +180,19 @@ fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
     }
 }
 
+fn encode_query_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    hcx: &mut HashContext<'a, 'tcx>,
+                                    encoder: &mut Encoder)
+                                    -> io::Result<()> {
+    // First encode the commandline arguments hash
+    tcx.sess.opts.dep_tracking_hash().encode(encoder)?;
+
+    let serialized_graph = tcx.dep_graph.serialize(|dep_node| hcx.input_fingerprint(dep_node));
+    serialized_graph.encode(encoder)?;
+
+    Ok(())
+}
+
 pub fn encode_dep_graph(tcx: TyCtxt,
                         preds: &Predecessors,
                         encoder: &mut Encoder)
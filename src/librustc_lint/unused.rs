@@ -19,6 +19,7 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 
 use syntax::ast;
 use syntax::attr;
+use syntax::errors::Applicability;
 use syntax::feature_gate::{BUILTIN_ATTRIBUTES, AttributeType};
 use syntax::symbol::keywords;
 use syntax::ptr::P;
@@ -68,9 +69,25 @@ impl UnusedMut {
         let used_mutables = cx.tcx.used_mut_nodes.borrow();
         for (_, v) in &mutables {
             if !v.iter().any(|e| used_mutables.contains(e)) {
-                cx.span_lint(UNUSED_MUT,
-                             cx.tcx.hir.span(v[0]),
-                             "variable does not need to be mutable");
+                let span = cx.tcx.hir.span(v[0]);
+                let mut err = cx.struct_span_lint(UNUSED_MUT,
+                                                  span,
+                                                  "variable does not need to be mutable");
+                // Dropping `mut` from only one of several patterns would
+                // bind the variable in inconsistent ways.
+                if v.len() == 1 {
+                    if let Ok(snippet) = cx.sess().codemap().span_to_snippet(span) {
+                        if snippet.starts_with("mut ") {
+                            let binding = snippet["mut ".len()..].trim_left().to_string();
+                            err.span_suggestion_with_applicability(
+                                span,
+                                "remove this `mut`",
+                                binding,
+                                Applicability::MachineApplicable);
+                        }
+                    }
+                }
+                err.emit();
             }
         }
     }
@@ -340,9 +357,17 @@ impl UnusedParens {
         if let ast::ExprKind::Paren(ref inner) = value.node {
             let necessary = struct_lit_needs_parens && contains_exterior_struct_lit(&inner);
             if !necessary {
-                cx.span_lint(UNUSED_PARENS,
-                             value.span,
-                             &format!("unnecessary parentheses around {}", msg))
+                let mut err = cx.struct_span_lint(UNUSED_PARENS,
+                                                  value.span,
+                                                  &format!("unnecessary parentheses around {}",
+                                                           msg));
+                if let Ok(snippet) = cx.sess().codemap().span_to_snippet(inner.span) {
+                    err.span_suggestion_with_applicability(value.span,
+                                                           "remove these parentheses",
+                                                           snippet,
+                                                           Applicability::MachineApplicable);
+                }
+                err.emit();
             }
         }
 
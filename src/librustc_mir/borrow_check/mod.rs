@@ -0,0 +1,1129 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This query borrow-checks the MIR to (further) ensure it is not broken.
+//!
+//! It runs with `-Z borrowck=mir`, instead of the AST borrow checker, and
+//! with `-Z borrowck=compare`, alongside it. The checks are done against
+//! the state computed by dataflow at each statement: the borrows in scope
+//! (`Borrows`), and the paths that may be uninitialized
+//! (`MaybeUninitializedLvals`) or moved out of (`MovingOutStatements`).
+//! With `-Z nll`, borrows go out of scope as computed by the `nll` module
+//! rather than at the end of their lexical region.
+
+use rustc::hir;
+use rustc::hir::def::Def;
+use rustc::hir::def_id::DefId;
+use rustc::hir::map as hir_map;
+use rustc::mir::{AssertMessage, BasicBlock, BorrowKind, Field, Local, Location, Lvalue, Mir};
+use rustc::mir::{Mutability, Operand, ProjectionElem, Rvalue, Statement, StatementKind};
+use rustc::mir::{Terminator, TerminatorKind};
+use rustc::mir::tcx::LvalueTy;
+use rustc::mir::traversal;
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::maps::Providers;
+use rustc::util::nodemap::FxHashSet;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::Idx;
+use syntax::ast;
+use syntax_pos::Span;
+
+use dataflow::{do_dataflow, BitDenotation, BlockSets, DataflowResults, MoveDataParamEnv};
+use dataflow::{Borrows, BorrowData, MaybeInitializedLvals, MaybeUninitializedLvals};
+use dataflow::MovingOutStatements;
+use dataflow::indexes::{BorrowIndex, MoveOutIndex};
+use dataflow::move_paths::{HasMoveData, IllegalMoveOriginKind, LookupResult};
+use dataflow::move_paths::{MoveData, MoveError, MovePathIndex};
+use util::borrowck_errors::{BorrowckErrors, Origin};
+
+mod nll;
+
+pub fn provide(providers: &mut Providers) {
+    *providers = Providers {
+        mir_borrowck,
+        ..*providers
+    };
+}
+
+fn mir_borrowck<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) {
+    if !tcx.sess.opts.debugging_opts.borrowck.use_mir() {
+        return;
+    }
+
+    let id = tcx.hir.as_local_node_id(def_id).unwrap();
+    match tcx.hir.get(id) {
+        hir_map::NodeStructCtor(_) |
+        hir_map::NodeVariant(_) => {
+            // The synthesized constructors of tuple structs and variants
+            // have no body to check.
+            return;
+        }
+        _ => {}
+    }
+    debug!("mir_borrowck({})", tcx.item_path_str(def_id));
+
+    let mir_ref = tcx.mir_validated(def_id).borrow();
+    let mir: &Mir<'tcx> = &mir_ref;
+    let param_env = tcx.param_env(def_id);
+    let attributes = tcx.get_attrs(def_id);
+
+    let move_data = match MoveData::try_gather_moves(mir, tcx, param_env) {
+        Ok(move_data) => move_data,
+        Err((move_data, move_errors)) => {
+            report_move_errors(tcx, mir, move_errors);
+            move_data
+        }
+    };
+    let mdpe = MoveDataParamEnv { move_data: move_data, param_env: param_env };
+    let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+
+    let mut borrows = Borrows::new(mir);
+    if tcx.sess.opts.debugging_opts.nll {
+        let regions = nll::compute_regions(tcx, id, param_env, mir, borrows.borrows());
+        borrows.use_regions(regions);
+    }
+    let flow_borrows = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds, borrows,
+                                   |bd, i| bd.location(i));
+    let flow_inits = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
+                                 MaybeInitializedLvals::new(tcx, mir, &mdpe),
+                                 |bd, i| &bd.move_data().move_paths[i]);
+    let flow_uninits = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
+                                   MaybeUninitializedLvals::new(tcx, mir, &mdpe),
+                                   |bd, i| &bd.move_data().move_paths[i]);
+    let flow_move_outs = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
+                                     MovingOutStatements::new(tcx, mir, &mdpe),
+                                     |bd, i| &bd.move_data().moves[i]);
+
+    let mut flow = InProgress {
+        borrows: FlowInProgress::new(flow_borrows),
+        inits: FlowInProgress::new(flow_inits),
+        uninits: FlowInProgress::new(flow_uninits),
+        move_outs: FlowInProgress::new(flow_move_outs),
+    };
+    let mut mbcx = MirBorrowckCtxt {
+        tcx: tcx,
+        mir: mir,
+        move_data: &mdpe.move_data,
+        param_env: param_env,
+        reported: FxHashSet(),
+        used_mut: FxHashSet(),
+    };
+    mbcx.check(&mut flow);
+
+    // The `unused_mut` lint relies on borrowck to tell which `mut`
+    // variables are mutated; only do it here if the AST borrowck does not.
+    if !tcx.sess.opts.debugging_opts.borrowck.use_ast() {
+        record_used_mut(tcx, mir, &mbcx.used_mut);
+    }
+}
+
+fn report_move_errors<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                mir: &Mir<'tcx>,
+                                move_errors: Vec<MoveError<'tcx>>) {
+    let mut reported = FxHashSet();
+    for move_error in move_errors {
+        let span = span_of(mir, move_error.location);
+        if !reported.insert(span) {
+            continue;
+        }
+        let (mut err, label) = match move_error.kind {
+            IllegalMoveOriginKind::Static => {
+                (tcx.cannot_move_out_of(span, "static item", Origin::Mir),
+                 "cannot move out of static item")
+            }
+            IllegalMoveOriginKind::BorrowedContent => {
+                (tcx.cannot_move_out_of(span, "borrowed content", Origin::Mir),
+                 "cannot move out of borrowed content")
+            }
+            IllegalMoveOriginKind::InteriorOfTypeWithDestructor { container_ty } => {
+                (tcx.cannot_move_out_of_interior_of_drop(span, container_ty, Origin::Mir),
+                 "cannot move out of here")
+            }
+            IllegalMoveOriginKind::InteriorOfSliceOrArray { ty } => {
+                (tcx.cannot_move_out_of_interior_noncopy(span, ty, Origin::Mir),
+                 "cannot move out of here")
+            }
+        };
+        err.span_label(span, label);
+        err.emit();
+    }
+}
+
+fn span_of<'tcx>(mir: &Mir<'tcx>, location: Location) -> Span {
+    let data = &mir[location.block];
+    match data.statements.get(location.statement_index) {
+        Some(statement) => statement.source_info.span,
+        None => data.terminator().source_info.span,
+    }
+}
+
+/// Adds the bindings of the user variables in `used_mut` to
+/// `tcx.used_mut_nodes`.
+fn record_used_mut<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                             mir: &Mir<'tcx>,
+                             used_mut: &FxHashSet<Local>) {
+    let bindings = used_mut.iter().filter_map(|&local| mir.local_decls[local].var_id);
+    tcx.used_mut_nodes.borrow_mut().extend(bindings);
+}
+
+/// The results of a dataflow analysis, along with the state at the point
+/// of the function currently being checked.
+struct FlowInProgress<BD> where BD: BitDenotation {
+    base_results: DataflowResults<BD>,
+    curr_state: IdxSetBuf<BD::Idx>,
+    stmt_gen: IdxSetBuf<BD::Idx>,
+    stmt_kill: IdxSetBuf<BD::Idx>,
+}
+
+impl<BD> FlowInProgress<BD> where BD: BitDenotation {
+    fn new(results: DataflowResults<BD>) -> Self {
+        let bits_per_block = results.sets().bits_per_block();
+        FlowInProgress {
+            base_results: results,
+            curr_state: IdxSetBuf::new_empty(bits_per_block),
+            stmt_gen: IdxSetBuf::new_empty(bits_per_block),
+            stmt_kill: IdxSetBuf::new_empty(bits_per_block),
+        }
+    }
+
+    fn operator(&self) -> &BD {
+        self.base_results.operator()
+    }
+
+    fn contains(&self, idx: &BD::Idx) -> bool {
+        self.curr_state.contains(idx)
+    }
+
+    fn reset_to_entry_of(&mut self, block: BasicBlock) {
+        let on_entry = self.base_results.sets().on_entry_set_for(block.index());
+        self.curr_state.words_mut().clone_from_slice(on_entry.words());
+    }
+
+    fn apply_statement_effect(&mut self, location: Location) {
+        for word in self.stmt_gen.words_mut() { *word = 0; }
+        for word in self.stmt_kill.words_mut() { *word = 0; }
+        {
+            // Statement effects only update the gen and kill sets.
+            let mut ignored = IdxSetBuf::<BD::Idx>::new_empty(0);
+            let mut sets = BlockSets {
+                on_entry: &mut ignored,
+                gen_set: &mut self.stmt_gen,
+                kill_set: &mut self.stmt_kill,
+            };
+            self.base_results.operator().statement_effect(&mut sets,
+                                                          location.block,
+                                                          location.statement_index);
+        }
+        self.curr_state.union(&self.stmt_gen);
+        self.curr_state.subtract(&self.stmt_kill);
+    }
+
+    fn each_state_bit<F>(&self, mut f: F) where F: FnMut(BD::Idx) {
+        for i in 0..self.operator().bits_per_block() {
+            let idx = <BD::Idx as Idx>::new(i);
+            if self.curr_state.contains(&idx) {
+                f(idx);
+            }
+        }
+    }
+}
+
+struct InProgress<'b, 'tcx: 'b> {
+    borrows: FlowInProgress<Borrows<'b, 'tcx>>,
+    inits: FlowInProgress<MaybeInitializedLvals<'b, 'tcx>>,
+    uninits: FlowInProgress<MaybeUninitializedLvals<'b, 'tcx>>,
+    move_outs: FlowInProgress<MovingOutStatements<'b, 'tcx>>,
+}
+
+impl<'b, 'tcx> InProgress<'b, 'tcx> {
+    fn reset_to_entry_of(&mut self, block: BasicBlock) {
+        self.borrows.reset_to_entry_of(block);
+        self.inits.reset_to_entry_of(block);
+        self.uninits.reset_to_entry_of(block);
+        self.move_outs.reset_to_entry_of(block);
+    }
+
+    fn apply_statement_effect(&mut self, location: Location) {
+        self.borrows.apply_statement_effect(location);
+        self.inits.apply_statement_effect(location);
+        self.uninits.apply_statement_effect(location);
+        self.move_outs.apply_statement_effect(location);
+    }
+}
+
+/// How much of an lvalue an access touches: all of the data reachable
+/// from it, or only the data it owns, leaving out what it points to
+/// through references and raw pointers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Depth {
+    Shallow,
+    Deep,
+}
+
+struct MirBorrowckCtxt<'c, 'tcx: 'c> {
+    tcx: TyCtxt<'c, 'tcx, 'tcx>,
+    mir: &'c Mir<'tcx>,
+    move_data: &'c MoveData<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    /// The spans errors were reported at, so that each error is only
+    /// reported once.
+    reported: FxHashSet<Span>,
+    /// The `mut` locals that are assigned to or mutably borrowed.
+    used_mut: FxHashSet<Local>,
+}
+
+impl<'c, 'tcx> MirBorrowckCtxt<'c, 'tcx> {
+    fn check(&mut self, flow: &mut InProgress<'c, 'tcx>) {
+        let mir = self.mir;
+        for (block, data) in traversal::preorder(mir) {
+            flow.reset_to_entry_of(block);
+            for (index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block: block, statement_index: index };
+                self.check_statement(location, statement, flow);
+                flow.apply_statement_effect(location);
+            }
+            let location = Location { block: block, statement_index: data.statements.len() };
+            self.check_terminator(location, data.terminator(), flow);
+        }
+    }
+
+    fn check_statement(&mut self,
+                       location: Location,
+                       statement: &Statement<'tcx>,
+                       flow: &InProgress<'c, 'tcx>) {
+        debug!("check_statement({:?}: {:?})", location, statement);
+        let span = statement.source_info.span;
+        match statement.kind {
+            StatementKind::Assign(ref lhs, ref rhs) => {
+                self.check_rvalue(location, rhs, span, flow);
+                self.mutate_lvalue(location, lhs, span, flow);
+            }
+            StatementKind::SetDiscriminant { ref lvalue, .. } => {
+                self.mutate_lvalue(location, lvalue, span, flow);
+            }
+            StatementKind::InlineAsm { ref outputs, ref inputs, .. } => {
+                for input in inputs {
+                    self.consume_operand(location, input, span, flow);
+                }
+                for output in outputs {
+                    self.mutate_lvalue(location, output, span, flow);
+                }
+            }
+            StatementKind::StorageDead(ref lvalue) => {
+                self.check_storage_dead(location, lvalue, span, flow);
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::EndRegion(_) |
+            StatementKind::Nop => {}
+        }
+    }
+
+    fn check_terminator(&mut self,
+                        location: Location,
+                        terminator: &Terminator<'tcx>,
+                        flow: &InProgress<'c, 'tcx>) {
+        debug!("check_terminator({:?}: {:?})", location, terminator);
+        let span = terminator.source_info.span;
+        match terminator.kind {
+            TerminatorKind::SwitchInt { ref discr, .. } => {
+                self.consume_operand(location, discr, span, flow);
+            }
+            TerminatorKind::Drop { location: ref lvalue, .. } => {
+                self.check_storage_dead(location, lvalue, span, flow);
+            }
+            TerminatorKind::DropAndReplace { location: ref lvalue, ref value, .. } => {
+                self.consume_operand(location, value, span, flow);
+                self.mutate_lvalue(location, lvalue, span, flow);
+            }
+            TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.consume_operand(location, func, span, flow);
+                for arg in args {
+                    self.consume_operand(location, arg, span, flow);
+                }
+                if let Some((ref dest, _)) = *destination {
+                    self.mutate_lvalue(location, dest, span, flow);
+                }
+            }
+            TerminatorKind::Assert { ref cond, ref msg, .. } => {
+                self.consume_operand(location, cond, span, flow);
+                if let AssertMessage::BoundsCheck { ref len, ref index } = *msg {
+                    self.consume_operand(location, len, span, flow);
+                    self.consume_operand(location, index, span, flow);
+                }
+            }
+            TerminatorKind::Goto { .. } |
+            TerminatorKind::Resume |
+            TerminatorKind::Return |
+            TerminatorKind::Unreachable => {}
+        }
+    }
+
+    fn check_rvalue(&mut self,
+                    location: Location,
+                    rvalue: &Rvalue<'tcx>,
+                    span: Span,
+                    flow: &InProgress<'c, 'tcx>) {
+        match *rvalue {
+            Rvalue::Ref(_, kind, ref lvalue) => {
+                self.borrow_lvalue(location, kind, lvalue, span, flow);
+            }
+            Rvalue::Use(ref operand) |
+            Rvalue::Repeat(ref operand, _) |
+            Rvalue::Cast(_, ref operand, _) |
+            Rvalue::UnaryOp(_, ref operand) => {
+                self.consume_operand(location, operand, span, flow);
+            }
+            Rvalue::BinaryOp(_, ref lhs, ref rhs) |
+            Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs) => {
+                self.consume_operand(location, lhs, span, flow);
+                self.consume_operand(location, rhs, span, flow);
+            }
+            Rvalue::Aggregate(_, ref operands) => {
+                for operand in operands {
+                    self.consume_operand(location, operand, span, flow);
+                }
+            }
+            Rvalue::Len(ref lvalue) |
+            Rvalue::Discriminant(ref lvalue) => {
+                self.read_lvalue(location, lvalue, span, flow);
+            }
+            Rvalue::NullaryOp(..) => {}
+        }
+    }
+
+    /// Checks a move or a copy out of an operand.
+    fn consume_operand(&mut self,
+                       location: Location,
+                       operand: &Operand<'tcx>,
+                       span: Span,
+                       flow: &InProgress<'c, 'tcx>) {
+        let lvalue = match *operand {
+            Operand::Consume(ref lvalue) => lvalue,
+            Operand::Constant(..) => return,
+        };
+        let ty = lvalue.ty(self.mir, self.tcx).to_ty(self.tcx);
+        if !ty.moves_by_default(self.tcx, self.param_env, span) {
+            self.read_lvalue(location, lvalue, span, flow);
+            return;
+        }
+
+        self.read_index_operands(location, lvalue, span, flow);
+        self.check_if_path_is_moved(lvalue, span, flow);
+        for borrow in self.conflicting_borrows(location, lvalue, Depth::Deep, flow) {
+            let borrow = &flow.borrows.operator().borrows()[borrow];
+            self.report_move_out_while_borrowed(lvalue, span, borrow);
+        }
+    }
+
+    /// Checks a read of an lvalue that does not move out of it.
+    fn read_lvalue(&mut self,
+                   location: Location,
+                   lvalue: &Lvalue<'tcx>,
+                   span: Span,
+                   flow: &InProgress<'c, 'tcx>) {
+        self.read_index_operands(location, lvalue, span, flow);
+        self.check_if_path_is_moved(lvalue, span, flow);
+        for borrow in self.conflicting_borrows(location, lvalue, Depth::Deep, flow) {
+            let borrow = &flow.borrows.operator().borrows()[borrow];
+            if borrow.kind != BorrowKind::Shared {
+                self.report_use_while_mutably_borrowed(lvalue, span, borrow);
+            }
+        }
+    }
+
+    fn borrow_lvalue(&mut self,
+                     location: Location,
+                     kind: BorrowKind,
+                     lvalue: &Lvalue<'tcx>,
+                     span: Span,
+                     flow: &InProgress<'c, 'tcx>) {
+        self.read_index_operands(location, lvalue, span, flow);
+        self.check_if_path_is_moved(lvalue, span, flow);
+        for borrow in self.conflicting_borrows(location, lvalue, Depth::Deep, flow) {
+            let borrow = &flow.borrows.operator().borrows()[borrow];
+            match (borrow.kind, kind) {
+                (BorrowKind::Shared, BorrowKind::Shared) => {}
+                (BorrowKind::Shared, _) |
+                (_, BorrowKind::Shared) => {
+                    self.report_conflicting_borrow(lvalue, kind, span, borrow);
+                }
+                _ => {
+                    self.report_multiple_mutable_borrows(lvalue, span, borrow);
+                }
+            }
+        }
+
+        let result = match kind {
+            BorrowKind::Shared => Ok(()),
+            BorrowKind::Unique => self.is_unique(lvalue),
+            BorrowKind::Mut => self.is_mutable(lvalue),
+        };
+        match result {
+            Ok(()) => if kind == BorrowKind::Mut {
+                self.mark_used_mut(lvalue);
+            },
+            Err(_) => {
+                if self.reported.insert(span) {
+                    let desc = self.describe_immutable_lvalue(lvalue);
+                    self.tcx.cannot_borrow_path_as_mutable(span, &desc, Origin::Mir).emit();
+                }
+            }
+        }
+    }
+
+    /// Checks an assignment to an lvalue, or any other write that does
+    /// not read its previous value.
+    fn mutate_lvalue(&mut self,
+                     location: Location,
+                     lvalue: &Lvalue<'tcx>,
+                     span: Span,
+                     flow: &InProgress<'c, 'tcx>) {
+        self.read_index_operands(location, lvalue, span, flow);
+
+        if let Lvalue::Local(local) = *lvalue {
+            // Only assigning to a variable that may already be
+            // initialized needs it to be `mut`.
+            let initialized = match self.move_data.rev_lookup.find(lvalue) {
+                LookupResult::Exact(mpi) => flow.inits.contains(&mpi),
+                LookupResult::Parent(_) => false,
+            };
+            if initialized {
+                match self.mir.local_decls[local].mutability {
+                    Mutability::Mut => {
+                        self.used_mut.insert(local);
+                    }
+                    Mutability::Not => self.report_illegal_reassignment(lvalue, span),
+                }
+            }
+        } else {
+            match self.is_mutable(lvalue) {
+                Ok(()) => self.mark_used_mut(lvalue),
+                Err(_) => {
+                    if self.reported.insert(span) {
+                        let desc = self.describe_immutable_lvalue(lvalue);
+                        self.tcx.cannot_assign(span, &desc, Origin::Mir).emit();
+                    }
+                }
+            }
+        }
+
+        for borrow in self.conflicting_borrows(location, lvalue, Depth::Shallow, flow) {
+            let borrow = &flow.borrows.operator().borrows()[borrow];
+            self.report_assignment_to_borrowed(lvalue, span, borrow);
+        }
+    }
+
+    /// Checks that nothing borrowed is dropped, or goes out of scope.
+    fn check_storage_dead(&mut self,
+                          location: Location,
+                          lvalue: &Lvalue<'tcx>,
+                          span: Span,
+                          flow: &InProgress<'c, 'tcx>) {
+        for idx in self.conflicting_borrows(location, lvalue, Depth::Shallow, flow) {
+            // A borrow that ends as part of the same scope exit is fine.
+            if flow.borrows.operator().ends_after(idx, location) {
+                continue;
+            }
+            let borrow = &flow.borrows.operator().borrows()[idx];
+            self.report_borrowed_value_does_not_live_long_enough(lvalue, span, borrow);
+        }
+    }
+
+    /// Reads the operands of the `Index` projections of `lvalue`.
+    fn read_index_operands(&mut self,
+                           location: Location,
+                           lvalue: &Lvalue<'tcx>,
+                           span: Span,
+                           flow: &InProgress<'c, 'tcx>) {
+        if let Lvalue::Projection(ref proj) = *lvalue {
+            if let ProjectionElem::Index(ref index) = proj.elem {
+                self.consume_operand(location, index, span, flow);
+            }
+            self.read_index_operands(location, &proj.base, span, flow);
+        }
+    }
+
+    /// Reports the use of an lvalue that is, or part of which is, maybe
+    /// uninitialized or moved out of.
+    fn check_if_path_is_moved(&mut self,
+                              lvalue: &Lvalue<'tcx>,
+                              span: Span,
+                              flow: &InProgress<'c, 'tcx>) {
+        let (mpi, exact) = match self.move_data.rev_lookup.find(lvalue) {
+            LookupResult::Exact(mpi) => (mpi, true),
+            LookupResult::Parent(Some(mpi)) => (mpi, false),
+            LookupResult::Parent(None) => return,
+        };
+
+        // The lvalue is uninitialized if the closest path to it or any of
+        // its parents is, and partly so if any path within it is.
+        let mut uninit = None;
+        let mut path = Some(mpi);
+        while let Some(p) = path {
+            if flow.uninits.contains(&p) {
+                uninit = Some((p, false));
+                break;
+            }
+            path = self.move_data.move_paths[p].parent;
+        }
+        if uninit.is_none() && exact {
+            uninit = self.uninitialized_child(mpi, flow).map(|child| (child, true));
+        }
+        let (uninit_path, partial) = match uninit {
+            Some(uninit) => uninit,
+            None => return,
+        };
+
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut moves = vec![];
+        flow.move_outs.each_state_bit(|mo| {
+            if self.is_prefix_of(self.move_data.moves[mo].path, uninit_path) ||
+                self.is_prefix_of(uninit_path, self.move_data.moves[mo].path) {
+                moves.push(mo);
+            }
+        });
+        if moves.is_empty() {
+            let mut err = self.tcx.cannot_act_on_uninitialized_variable(span, "use", &desc,
+                                                                        Origin::Mir);
+            err.span_label(span, format!("use of possibly uninitialized `{}`", desc));
+            err.emit();
+        } else {
+            self.report_use_of_moved(span, &desc, partial, &moves);
+        }
+    }
+
+    /// A path within `mpi`, not including `mpi` itself, that may be
+    /// uninitialized.
+    fn uninitialized_child(&self,
+                           mpi: MovePathIndex,
+                           flow: &InProgress<'c, 'tcx>)
+                           -> Option<MovePathIndex> {
+        let mut child = self.move_data.move_paths[mpi].first_child;
+        while let Some(c) = child {
+            if flow.uninits.contains(&c) {
+                return Some(c);
+            }
+            if let Some(grandchild) = self.uninitialized_child(c, flow) {
+                return Some(grandchild);
+            }
+            child = self.move_data.move_paths[c].next_sibling;
+        }
+        None
+    }
+
+    /// Whether the move path `prefix` is `path` or one of its parents.
+    fn is_prefix_of(&self, prefix: MovePathIndex, path: MovePathIndex) -> bool {
+        let mut path = Some(path);
+        while let Some(p) = path {
+            if p == prefix {
+                return true;
+            }
+            path = self.move_data.move_paths[p].parent;
+        }
+        false
+    }
+
+    /// The borrows in scope at `location` that an access of the given
+    /// depth to `lvalue` conflicts with.
+    fn conflicting_borrows(&self,
+                           location: Location,
+                           lvalue: &Lvalue<'tcx>,
+                           depth: Depth,
+                           flow: &InProgress<'c, 'tcx>)
+                           -> Vec<BorrowIndex> {
+        let borrows = flow.borrows.operator();
+        let mut conflicting = vec![];
+        flow.borrows.each_state_bit(|idx| {
+            if borrows.region_contains(idx, location) &&
+                self.lvalues_conflict(&borrows.borrows()[idx].lvalue, lvalue, depth) {
+                conflicting.push(idx);
+            }
+        });
+        conflicting
+    }
+
+    /// Whether accessing `accessed` may touch the data a borrow of
+    /// `borrowed` points to. Two lvalues only don't overlap if they
+    /// differ in a field of a struct or tuple, a variant of an enum or a
+    /// constant index; any other difference, such as two `Index`
+    /// projections, is assumed to overlap.
+    fn lvalues_conflict(&self,
+                        borrowed: &Lvalue<'tcx>,
+                        accessed: &Lvalue<'tcx>,
+                        depth: Depth)
+                        -> bool {
+        let borrowed_path = lvalue_path(borrowed);
+        let accessed_path = lvalue_path(accessed);
+        match (borrowed_path[0], accessed_path[0]) {
+            (&Lvalue::Local(l1), &Lvalue::Local(l2)) if l1 == l2 => {}
+            (&Lvalue::Static(ref s1), &Lvalue::Static(ref s2)) if s1.def_id == s2.def_id => {}
+            _ => return false,
+        }
+
+        for (b, a) in borrowed_path.iter().zip(&accessed_path).skip(1) {
+            let (b, a) = match (*b, *a) {
+                (&Lvalue::Projection(ref b), &Lvalue::Projection(ref a)) => (b, a),
+                _ => bug!("lvalues_conflict: {:?} and {:?} are not projections", b, a),
+            };
+            match (&b.elem, &a.elem) {
+                (&ProjectionElem::Field(f1, _), &ProjectionElem::Field(f2, _)) if f1 != f2 => {
+                    let base_ty = b.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                    match base_ty.sty {
+                        // The fields of a union overlap.
+                        ty::TyAdt(def, _) if def.is_union() => return true,
+                        _ => return false,
+                    }
+                }
+                (&ProjectionElem::Downcast(_, v1), &ProjectionElem::Downcast(_, v2))
+                    if v1 != v2 => return false,
+                (&ProjectionElem::ConstantIndex { offset: o1, from_end: e1, .. },
+                 &ProjectionElem::ConstantIndex { offset: o2, from_end: e2, .. })
+                    if e1 == e2 && o1 != o2 => return false,
+                _ => {}
+            }
+        }
+
+        if depth == Depth::Shallow && borrowed_path.len() > accessed_path.len() {
+            // A shallow access does not reach the data behind a reference
+            // or a raw pointer, which the rest of the borrowed path may go
+            // through.
+            for lvalue in &borrowed_path[accessed_path.len()..] {
+                if let Lvalue::Projection(ref proj) = **lvalue {
+                    if let ProjectionElem::Deref = proj.elem {
+                        let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                        match base_ty.sty {
+                            ty::TyRef(..) | ty::TyRawPtr(..) => return false,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Whether `lvalue` may be mutated, or else the part of it that is
+    /// immutable.
+    fn is_mutable<'d>(&self, lvalue: &'d Lvalue<'tcx>) -> Result<(), &'d Lvalue<'tcx>> {
+        match *lvalue {
+            Lvalue::Local(local) => {
+                match self.mir.local_decls[local].mutability {
+                    Mutability::Mut => Ok(()),
+                    // The fields of the environment of a closure are its
+                    // upvars, whose mutability the environment does not
+                    // record.
+                    Mutability::Not if self.is_closure_env(lvalue) => Ok(()),
+                    Mutability::Not => Err(lvalue),
+                }
+            }
+            Lvalue::Static(ref s) => {
+                match self.tcx.describe_def(s.def_id) {
+                    Some(Def::Static(_, true)) => Ok(()),
+                    _ => Err(lvalue),
+                }
+            }
+            Lvalue::Projection(ref proj) => {
+                match proj.elem {
+                    ProjectionElem::Deref => {
+                        let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                        match base_ty.sty {
+                            ty::TyRef(_, ty::TypeAndMut { mutbl: hir::MutMutable, .. }) => {
+                                self.is_unique(&proj.base)
+                            }
+                            ty::TyRawPtr(ty::TypeAndMut { mutbl: hir::MutMutable, .. }) => {
+                                Ok(())
+                            }
+                            ty::TyRef(..) | ty::TyRawPtr(..) => Err(lvalue),
+                            _ => self.is_mutable(&proj.base),
+                        }
+                    }
+                    ProjectionElem::Field(..) |
+                    ProjectionElem::Index(..) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } |
+                    ProjectionElem::Downcast(..) => self.is_mutable(&proj.base),
+                }
+            }
+        }
+    }
+
+    /// Whether `lvalue` is the only way to reach its data, or else the
+    /// part of it that is shared.
+    fn is_unique<'d>(&self, lvalue: &'d Lvalue<'tcx>) -> Result<(), &'d Lvalue<'tcx>> {
+        match *lvalue {
+            Lvalue::Local(..) | Lvalue::Static(..) => Ok(()),
+            Lvalue::Projection(ref proj) => {
+                match proj.elem {
+                    ProjectionElem::Deref => {
+                        let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                        match base_ty.sty {
+                            ty::TyRef(_, ty::TypeAndMut { mutbl: hir::MutImmutable, .. }) => {
+                                Err(lvalue)
+                            }
+                            ty::TyRawPtr(..) => Ok(()),
+                            _ => self.is_unique(&proj.base),
+                        }
+                    }
+                    ProjectionElem::Field(..) |
+                    ProjectionElem::Index(..) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } |
+                    ProjectionElem::Downcast(..) => self.is_unique(&proj.base),
+                }
+            }
+        }
+    }
+
+    /// Records that the local that owns `lvalue` is mutated, unless
+    /// `lvalue` is reached through a reference or a raw pointer.
+    fn mark_used_mut(&mut self, lvalue: &Lvalue<'tcx>) {
+        match *lvalue {
+            Lvalue::Local(local) => {
+                if let Mutability::Mut = self.mir.local_decls[local].mutability {
+                    self.used_mut.insert(local);
+                }
+            }
+            Lvalue::Static(..) => {}
+            Lvalue::Projection(ref proj) => {
+                if let ProjectionElem::Deref = proj.elem {
+                    let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                    match base_ty.sty {
+                        ty::TyRef(..) | ty::TyRawPtr(..) => return,
+                        _ => {}
+                    }
+                }
+                self.mark_used_mut(&proj.base);
+            }
+        }
+    }
+
+    /// Whether `lvalue` is the environment of a closure, or what the
+    /// environment points to.
+    fn is_closure_env(&self, lvalue: &Lvalue<'tcx>) -> bool {
+        if self.mir.upvar_decls.is_empty() {
+            return false;
+        }
+        match *lvalue {
+            Lvalue::Local(local) => local == Local::new(1),
+            Lvalue::Projection(ref proj) => {
+                match (&proj.elem, &proj.base) {
+                    (&ProjectionElem::Deref, &Lvalue::Local(local)) => local == Local::new(1),
+                    _ => false,
+                }
+            }
+            Lvalue::Static(..) => false,
+        }
+    }
+}
+
+/// The prefixes of `lvalue`, starting with the local or static it is
+/// based on and ending with `lvalue` itself.
+fn lvalue_path<'a, 'tcx>(lvalue: &'a Lvalue<'tcx>) -> Vec<&'a Lvalue<'tcx>> {
+    let mut path = vec![lvalue];
+    let mut lvalue = lvalue;
+    while let Lvalue::Projection(ref proj) = *lvalue {
+        lvalue = &proj.base;
+        path.push(lvalue);
+    }
+    path.reverse();
+    path
+}
+
+// Error reporting
+impl<'c, 'tcx> MirBorrowckCtxt<'c, 'tcx> {
+    fn report_use_of_moved(&mut self,
+                           span: Span,
+                           desc: &str,
+                           partial: bool,
+                           moves: &[MoveOutIndex]) {
+        let adverb = if partial { "partially " } else { "" };
+        let mut err = self.tcx.cannot_act_on_moved_value(span, "use", adverb, desc, Origin::Mir);
+        err.span_label(span, "value used here after move");
+        for &mo in moves {
+            let move_span = span_of(self.mir, self.move_data.moves[mo].source);
+            if move_span == span {
+                err.span_label(move_span, "value moved here in previous iteration of loop");
+            } else {
+                err.span_label(move_span, "value moved here");
+            }
+        }
+        let moved = &self.move_data.move_paths[self.move_data.moves[moves[0]].path].lvalue;
+        let moved_ty = moved.ty(self.mir, self.tcx).to_ty(self.tcx);
+        err.note(&format!("move occurs because `{}` has type `{}`, \
+                           which does not implement the `Copy` trait",
+                          self.describe_lvalue(moved), moved_ty));
+        err.emit();
+    }
+
+    fn report_move_out_while_borrowed(&mut self,
+                                      lvalue: &Lvalue<'tcx>,
+                                      span: Span,
+                                      borrow: &BorrowData<'tcx>) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut err = self.tcx.cannot_move_when_borrowed(span, &desc, Origin::Mir);
+        err.span_label(self.borrow_span(borrow),
+                       format!("borrow of `{}` occurs here", self.describe_lvalue(&borrow.lvalue)));
+        err.span_label(span, format!("move out of `{}` occurs here", desc));
+        err.emit();
+    }
+
+    fn report_use_while_mutably_borrowed(&mut self,
+                                         lvalue: &Lvalue<'tcx>,
+                                         span: Span,
+                                         borrow: &BorrowData<'tcx>) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut err = self.tcx.cannot_use_when_mutably_borrowed(span, &desc, Origin::Mir);
+        err.span_label(self.borrow_span(borrow),
+                       format!("borrow of `{}` occurs here", self.describe_lvalue(&borrow.lvalue)));
+        err.span_label(span, format!("use of borrowed `{}`", self.describe_lvalue(&borrow.lvalue)));
+        err.emit();
+    }
+
+    fn report_conflicting_borrow(&mut self,
+                                 lvalue: &Lvalue<'tcx>,
+                                 kind: BorrowKind,
+                                 span: Span,
+                                 borrow: &BorrowData<'tcx>) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let desc_old = self.describe_lvalue(&borrow.lvalue);
+        let noun_old = if desc == desc_old {
+            "it".to_string()
+        } else {
+            format!("`{}`", desc_old)
+        };
+        let kind_new = borrow_kind_str(kind);
+        let kind_old = borrow_kind_str(borrow.kind);
+        let mut err = self.tcx.cannot_reborrow_already_borrowed(
+            span, &desc, "", kind_new, &noun_old, kind_old, "", Origin::Mir);
+        err.span_label(self.borrow_span(borrow), format!("{} borrow occurs here", kind_old));
+        err.span_label(span, format!("{} borrow occurs here", kind_new));
+        err.emit();
+    }
+
+    fn report_multiple_mutable_borrows(&mut self,
+                                       lvalue: &Lvalue<'tcx>,
+                                       span: Span,
+                                       borrow: &BorrowData<'tcx>) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut err = self.tcx.cannot_mutably_borrow_multiply(span, &desc, "", Origin::Mir);
+        let old_span = self.borrow_span(borrow);
+        if old_span == span {
+            err.span_label(span, "mutable borrow starts here in previous iteration of loop");
+        } else {
+            err.span_label(old_span, "first mutable borrow occurs here");
+            err.span_label(span, "second mutable borrow occurs here");
+        }
+        err.emit();
+    }
+
+    fn report_assignment_to_borrowed(&mut self,
+                                     lvalue: &Lvalue<'tcx>,
+                                     span: Span,
+                                     borrow: &BorrowData<'tcx>) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut err = self.tcx.cannot_assign_to_borrowed(span, &desc, Origin::Mir);
+        err.span_label(self.borrow_span(borrow),
+                       format!("borrow of `{}` occurs here", self.describe_lvalue(&borrow.lvalue)));
+        err.span_label(span, format!("assignment to borrowed `{}` occurs here", desc));
+        err.emit();
+    }
+
+    fn report_illegal_reassignment(&mut self, lvalue: &Lvalue<'tcx>, span: Span) {
+        if !self.reported.insert(span) {
+            return;
+        }
+        let desc = self.describe_lvalue(lvalue);
+        let mut err = self.tcx.cannot_reassign_immutable(span, &desc, Origin::Mir);
+        err.span_label(span, "re-assignment of immutable variable");
+        err.emit();
+    }
+
+    fn report_borrowed_value_does_not_live_long_enough(&mut self,
+                                                       lvalue: &Lvalue<'tcx>,
+                                                       span: Span,
+                                                       borrow: &BorrowData<'tcx>) {
+        let borrow_span = self.borrow_span(borrow);
+        if !self.reported.insert(borrow_span) {
+            return;
+        }
+        let desc = match *lvalue {
+            Lvalue::Local(local) if self.mir.local_decls[local].name.is_some() => {
+                format!("`{}`", self.describe_lvalue(lvalue))
+            }
+            _ => "borrowed value".to_string(),
+        };
+        let mut err = self.tcx.borrowed_value_does_not_live_long_enough(borrow_span, &desc,
+                                                                         Origin::Mir);
+        err.span_label(borrow_span, "borrow occurs here");
+        err.span_label(span, format!("{} dropped here while still borrowed", desc));
+        err.emit();
+    }
+
+    fn borrow_span(&self, borrow: &BorrowData<'tcx>) -> Span {
+        span_of(self.mir, borrow.location)
+    }
+
+    /// Describes an lvalue that cannot be mutated, for E0594 and E0596.
+    fn describe_immutable_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
+        let kind = match *lvalue {
+            Lvalue::Local(_) => "local variable",
+            Lvalue::Static(_) => "static item",
+            Lvalue::Projection(_) if self.upvar_name(lvalue).is_some() => {
+                "captured outer variable"
+            }
+            Lvalue::Projection(ref proj) => {
+                match proj.elem {
+                    ProjectionElem::Deref => {
+                        let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                        match base_ty.sty {
+                            ty::TyRawPtr(..) => "dereference of raw pointer",
+                            ty::TyRef(..) => "borrowed content",
+                            _ => "`Box` content",
+                        }
+                    }
+                    ProjectionElem::Field(..) => "field",
+                    ProjectionElem::Index(..) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } => "indexed content",
+                    ProjectionElem::Downcast(..) => {
+                        return self.describe_immutable_lvalue(&proj.base);
+                    }
+                }
+            }
+        };
+        format!("immutable {} `{}`", kind, self.describe_lvalue(lvalue))
+    }
+
+    /// Describes `lvalue` the way it would be written in the source.
+    fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
+        let mut buf = String::new();
+        self.append_lvalue_to_string(lvalue, &mut buf);
+        buf
+    }
+
+    fn append_lvalue_to_string(&self, lvalue: &Lvalue<'tcx>, buf: &mut String) {
+        match *lvalue {
+            Lvalue::Local(local) => {
+                match self.mir.local_decls[local].name {
+                    Some(name) => buf.push_str(&name.as_str()),
+                    None => buf.push_str(&format!("{:?}", local)),
+                }
+            }
+            Lvalue::Static(ref s) => buf.push_str(&self.tcx.item_path_str(s.def_id)),
+            Lvalue::Projection(ref proj) => {
+                if let Some(name) = self.upvar_name(lvalue) {
+                    buf.push_str(&name.as_str());
+                    return;
+                }
+                match proj.elem {
+                    ProjectionElem::Deref => {
+                        buf.push('*');
+                        self.append_lvalue_to_string(&proj.base, buf);
+                    }
+                    ProjectionElem::Downcast(..) => {
+                        self.append_lvalue_to_string(&proj.base, buf);
+                    }
+                    ProjectionElem::Field(field, _) => {
+                        // Fields are reached through references and boxes
+                        // by auto-deref, so `(*x).f` is written `x.f`.
+                        let base = match proj.base {
+                            Lvalue::Projection(ref base_proj) => match base_proj.elem {
+                                ProjectionElem::Deref => &base_proj.base,
+                                _ => &proj.base,
+                            },
+                            _ => &proj.base,
+                        };
+                        self.append_lvalue_to_string(base, buf);
+                        buf.push('.');
+                        buf.push_str(&self.field_name(&proj.base, field));
+                    }
+                    ProjectionElem::Index(..) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } => {
+                        self.append_lvalue_to_string(&proj.base, buf);
+                        buf.push_str("[..]");
+                    }
+                }
+            }
+        }
+    }
+
+    /// The name of `field` of `base`, or its index if it has no name.
+    fn field_name(&self, base: &Lvalue<'tcx>, field: Field) -> String {
+        match base.ty(self.mir, self.tcx) {
+            LvalueTy::Downcast { adt_def, variant_index, .. } => {
+                adt_def.variants[variant_index].fields[field.index()].name.to_string()
+            }
+            LvalueTy::Ty { ty } => {
+                match ty.sty {
+                    ty::TyAdt(def, _) if !def.is_enum() => {
+                        def.struct_variant().fields[field.index()].name.to_string()
+                    }
+                    _ => field.index().to_string(),
+                }
+            }
+        }
+    }
+
+    /// The name of the upvar `lvalue` is, if it is one. An upvar is a
+    /// field of the environment of the closure, behind a reference if it
+    /// is captured by reference.
+    fn upvar_name(&self, lvalue: &Lvalue<'tcx>) -> Option<ast::Name> {
+        let proj = match *lvalue {
+            Lvalue::Projection(ref proj) => proj,
+            _ => return None,
+        };
+        match proj.elem {
+            ProjectionElem::Field(field, _) if self.is_closure_env(&proj.base) => {
+                self.mir.upvar_decls.get(field.index()).map(|decl| decl.debug_name)
+            }
+            ProjectionElem::Deref => {
+                let base = match proj.base {
+                    Lvalue::Projection(ref base) => base,
+                    _ => return None,
+                };
+                match base.elem {
+                    ProjectionElem::Field(field, _) if self.is_closure_env(&base.base) => {
+                        self.mir.upvar_decls.get(field.index())
+                            .and_then(|decl| if decl.by_ref { Some(decl.debug_name) } else { None })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn borrow_kind_str(kind: BorrowKind) -> &'static str {
+    match kind {
+        BorrowKind::Shared => "immutable",
+        BorrowKind::Unique => "uniquely immutable",
+        BorrowKind::Mut => "mutable",
+    }
+}
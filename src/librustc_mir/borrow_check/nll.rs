@@ -0,0 +1,267 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Liveness-based borrow regions, used with `-Z nll`.
+//!
+//! Without `-Z nll`, a borrow lasts until the end of the lexical region
+//! type check inferred for it. Here, the regions of the MIR are replaced
+//! by inference variables and the MIR is type-checked again, which
+//! relates these variables to each other. The value of a variable is then
+//! a set of points: those where a local whose type mentions it is live,
+//! and the values of the variables it must outlive. A borrow lasts for
+//! the points of its region that are reachable from it.
+
+use rustc::infer::{InferCtxt, MiscVariable};
+use rustc::infer::region_inference::Constraint;
+use rustc::mir::*;
+use rustc::mir::visit::{Lookup, MutVisitor};
+use rustc::ty::{self, ClosureSubsts, RegionVid, Ty, TyCtxt, TypeFoldable};
+use rustc::ty::subst::Substs;
+use rustc::util::nodemap::FxHashSet;
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax::ast;
+use syntax_pos::Span;
+
+use dataflow::{BorrowData, BorrowRegion};
+use dataflow::indexes::BorrowIndex;
+use transform::type_check;
+use util::liveness::{self, LocalSet};
+
+/// Computes the region of each of `borrows`.
+pub fn compute_regions<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 id: ast::NodeId,
+                                 param_env: ty::ParamEnv<'tcx>,
+                                 mir: &Mir<'tcx>,
+                                 borrows: &IndexVec<BorrowIndex, BorrowData<'tcx>>)
+                                 -> IndexVec<BorrowIndex, BorrowRegion> {
+    tcx.infer_ctxt().enter(|infcx| {
+        let mut mir: Mir = mir.clone();
+        RenumberRegions { infcx: &infcx, span: mir.span }.visit_mir(&mut mir);
+        type_check::type_check(&infcx, id, param_env, &mir);
+
+        let values = region_values(&infcx, &mir);
+        borrows.iter().map(|borrow| {
+            let location = borrow.location;
+            let statement = &mir[location.block].statements[location.statement_index];
+            let vid = match statement.kind {
+                StatementKind::Assign(_, Rvalue::Ref(&ty::ReVar(vid), _, _)) => vid,
+                _ => bug!("borrow {} does not have a region variable", borrow),
+            };
+            let value = &values[vid.index as usize];
+            debug!("compute_regions: {} has the region {:?}", borrow, vid);
+            region(&mir, location, value)
+        }).collect()
+    })
+}
+
+/// Replaces every free region of the MIR with a fresh region variable.
+struct RenumberRegions<'a, 'gcx: 'a + 'tcx, 'tcx: 'a> {
+    infcx: &'a InferCtxt<'a, 'gcx, 'tcx>,
+    span: Span,
+}
+
+impl<'a, 'gcx, 'tcx> RenumberRegions<'a, 'gcx, 'tcx> {
+    fn renumber<T: TypeFoldable<'tcx>>(&self, value: &T) -> T {
+        let infcx = self.infcx;
+        let span = self.span;
+        infcx.tcx.fold_regions(value, &mut false, |_, _| {
+            infcx.next_region_var(MiscVariable(span))
+        })
+    }
+}
+
+impl<'a, 'gcx, 'tcx> MutVisitor<'tcx> for RenumberRegions<'a, 'gcx, 'tcx> {
+    fn visit_ty(&mut self, ty: &mut Ty<'tcx>, _: Lookup) {
+        *ty = self.renumber(ty);
+    }
+
+    fn visit_substs(&mut self, substs: &mut &'tcx Substs<'tcx>, _: Location) {
+        *substs = self.renumber(&{*substs});
+    }
+
+    fn visit_closure_substs(&mut self, substs: &mut ClosureSubsts<'tcx>, _: Location) {
+        *substs = self.renumber(substs);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &mut Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(ref mut region, _, _) = *rvalue {
+            *region = self.infcx.next_region_var(MiscVariable(self.span));
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
+/// The points of each region variable of `mir`, indexed by variable.
+fn region_values<'a, 'gcx, 'tcx>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
+                                 mir: &Mir<'tcx>)
+                                 -> Vec<BorrowRegion> {
+    let tcx = infcx.tcx;
+    let mut values: Vec<BorrowRegion> = vec![FxHashSet(); infcx.num_region_vars()];
+    let all_points = all_points(mir);
+    let local_vars: IndexVec<Local, Vec<RegionVid>> = mir.local_decls.iter()
+        .map(|decl| region_vars(tcx, decl.ty))
+        .collect();
+
+    // The regions of the arguments and of the return pointer stand for
+    // regions of the caller, which the whole body is part of.
+    for vars in local_vars.iter().take(mir.arg_count + 1) {
+        for vid in vars {
+            values[vid.index as usize] = all_points.clone();
+        }
+    }
+
+    // A region includes the points where a local whose type mentions it
+    // is live.
+    let live = live_on_entry(mir);
+    for (block, sets) in live.iter_enumerated() {
+        for (statement_index, set) in sets.iter().enumerate() {
+            let location = Location { block, statement_index };
+            for local in mir.local_decls.indices().filter(|local| set.contains(local)) {
+                for vid in &local_vars[local] {
+                    values[vid.index as usize].insert(location);
+                }
+            }
+        }
+    }
+
+    // Pairs `(sup, sub)` of variables where `sup` must outlive `sub`.
+    let mut outlives = vec![];
+    for constraint in infcx.region_constraints() {
+        match constraint {
+            Constraint::ConstrainVarSubVar(sub, sup) => outlives.push((sup, sub)),
+            Constraint::ConstrainRegSubVar(region, sup) => {
+                // Such a region, like `'static`, comes from outside the
+                // body, so it includes all of it.
+                if *region != ty::ReEmpty {
+                    values[sup.index as usize] = all_points.clone();
+                }
+            }
+            Constraint::ConstrainVarSubReg(..) |
+            Constraint::ConstrainRegSubReg(..) => {}
+        }
+    }
+
+    // Reborrowing through a reference must not outlive that reference,
+    // which type check does not require.
+    for data in mir.basic_blocks() {
+        for statement in &data.statements {
+            if let StatementKind::Assign(_, Rvalue::Ref(&ty::ReVar(vid), _, ref lvalue)) =
+                statement.kind {
+                add_reborrow_constraints(tcx, mir, vid, lvalue, &mut outlives);
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(sup, sub) in &outlives {
+            if sup == sub {
+                continue;
+            }
+            let new: Vec<Location> = values[sub.index as usize]
+                .difference(&values[sup.index as usize])
+                .cloned()
+                .collect();
+            if !new.is_empty() {
+                values[sup.index as usize].extend(new);
+                changed = true;
+            }
+        }
+    }
+    values
+}
+
+/// Adds that the regions of the references `lvalue` goes through must
+/// outlive `borrow_region`.
+fn add_reborrow_constraints<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                            mir: &Mir<'tcx>,
+                                            borrow_region: RegionVid,
+                                            lvalue: &Lvalue<'tcx>,
+                                            outlives: &mut Vec<(RegionVid, RegionVid)>) {
+    if let Lvalue::Projection(ref proj) = *lvalue {
+        if let ProjectionElem::Deref = proj.elem {
+            let base_ty = proj.base.ty(mir, tcx).to_ty(tcx);
+            if let ty::TyRef(&ty::ReVar(base_region), _) = base_ty.sty {
+                outlives.push((base_region, borrow_region));
+            }
+        }
+        add_reborrow_constraints(tcx, mir, borrow_region, &proj.base, outlives);
+    }
+}
+
+/// The region variables in `ty`, outside of any binder.
+fn region_vars<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>, ty: Ty<'tcx>) -> Vec<RegionVid> {
+    let mut regions = FxHashSet();
+    tcx.collect_regions(&ty, &mut regions);
+    regions.into_iter().filter_map(|region| match *region {
+        ty::ReVar(vid) => Some(vid),
+        _ => None,
+    }).collect()
+}
+
+fn all_points<'tcx>(mir: &Mir<'tcx>) -> BorrowRegion {
+    mir.basic_blocks().iter_enumerated().flat_map(|(block, data)| {
+        (0..data.statements.len() + 1).map(move |statement_index| {
+            Location { block, statement_index }
+        })
+    }).collect()
+}
+
+/// The locals live on entry to each statement and terminator.
+fn live_on_entry<'tcx>(mir: &Mir<'tcx>) -> IndexVec<BasicBlock, Vec<LocalSet>> {
+    let liveness = liveness::liveness_of_locals(mir);
+    mir.basic_blocks().iter_enumerated().map(|(block, data)| {
+        let mut live = liveness.outs[block].clone();
+        let mut sets = Vec::with_capacity(data.statements.len() + 1);
+        let terminator_location = Location {
+            block: block,
+            statement_index: data.statements.len(),
+        };
+        liveness::terminator_defs_uses(mir, data.terminator(), terminator_location)
+            .apply(&mut live);
+        sets.push(live.clone());
+        for (index, statement) in data.statements.iter().enumerate().rev() {
+            let location = Location { block: block, statement_index: index };
+            liveness::statement_defs_uses(mir, statement, location).apply(&mut live);
+            sets.push(live.clone());
+        }
+        sets.reverse();
+        sets
+    }).collect()
+}
+
+/// The points of `value` reachable from `start` without leaving it.
+fn region<'tcx>(mir: &Mir<'tcx>, start: Location, value: &BorrowRegion) -> BorrowRegion {
+    let mut region = FxHashSet();
+    let mut stack = successors(mir, start);
+    while let Some(location) = stack.pop() {
+        if region.contains(&location) || !value.contains(&location) {
+            continue;
+        }
+        region.insert(location);
+        stack.extend(successors(mir, location));
+    }
+    region
+}
+
+fn successors<'tcx>(mir: &Mir<'tcx>, location: Location) -> Vec<Location> {
+    let data = &mir[location.block];
+    if location.statement_index < data.statements.len() {
+        vec![Location {
+            block: location.block,
+            statement_index: location.statement_index + 1,
+        }]
+    } else {
+        data.terminator().successors().iter().map(|&block| {
+            Location { block: block, statement_index: 0 }
+        }).collect()
+    }
+}
@@ -713,6 +713,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             name: Some(name),
             source_info: source_info,
             is_user_variable: true,
+            var_id: Some(var_id),
         });
         self.var_indices.insert(var_id, var);
 
@@ -503,6 +503,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                 },
                 name: name,
                 is_user_variable: false,
+                var_id: None,
             });
         }
 
@@ -0,0 +1,253 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use rustc::mir::{self, Location, Mir};
+use rustc::mir::visit::Visitor;
+use rustc::middle::region::CodeExtent;
+use rustc::ty::{self, Region};
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use rustc_data_structures::bitslice::BitwiseOperator;
+use rustc_data_structures::indexed_set::IdxSet;
+use rustc_data_structures::indexed_vec::IndexVec;
+
+use dataflow::{BitDenotation, BlockSets, DataflowOperator};
+pub use dataflow::indexes::BorrowIndex;
+
+use std::fmt;
+
+/// The points of the control flow at which a borrow is in scope, as
+/// computed by `-Z nll`.
+pub type BorrowRegion = FxHashSet<Location>;
+
+/// `Borrows` tracks the borrows that may be in scope at each point of
+/// the control flow for a function.
+///
+/// A borrow comes into scope right after the `Rvalue::Ref` creating it.
+/// It goes out of scope at the `EndRegion` statement of the lexical
+/// region type check gave it or, once `use_regions` has been called, at
+/// the points that are not part of the region it was given there.
+pub struct Borrows<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    borrows: IndexVec<BorrowIndex, BorrowData<'tcx>>,
+    location_map: FxHashMap<Location, BorrowIndex>,
+    extent_map: FxHashMap<CodeExtent, FxHashSet<BorrowIndex>>,
+    regions: Option<IndexVec<BorrowIndex, BorrowRegion>>,
+}
+
+/// A borrow, created by an `Rvalue::Ref` at `location`.
+#[derive(Debug)]
+pub struct BorrowData<'tcx> {
+    pub location: Location,
+    pub kind: mir::BorrowKind,
+    pub region: Region<'tcx>,
+    pub lvalue: mir::Lvalue<'tcx>,
+}
+
+impl<'tcx> fmt::Display for BorrowData<'tcx> {
+    fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            mir::BorrowKind::Shared => "",
+            mir::BorrowKind::Unique => "uniq ",
+            mir::BorrowKind::Mut => "mut ",
+        };
+        write!(w, "&{:?} {}{:?}", self.region, kind, self.lvalue)
+    }
+}
+
+impl<'a, 'tcx> Borrows<'a, 'tcx> {
+    pub fn new(mir: &'a Mir<'tcx>) -> Self {
+        let mut visitor = GatherBorrows {
+            borrows: IndexVec::new(),
+            location_map: FxHashMap(),
+            extent_map: FxHashMap(),
+        };
+        visitor.visit_mir(mir);
+        Borrows {
+            mir: mir,
+            borrows: visitor.borrows,
+            location_map: visitor.location_map,
+            extent_map: visitor.extent_map,
+            regions: None,
+        }
+    }
+
+    /// Makes each borrow go out of scope outside of the given region
+    /// rather than at the end of its lexical region.
+    pub fn use_regions(&mut self, regions: IndexVec<BorrowIndex, BorrowRegion>) {
+        assert_eq!(regions.len(), self.borrows.len());
+        self.regions = Some(regions);
+    }
+
+    pub fn borrows(&self) -> &IndexVec<BorrowIndex, BorrowData<'tcx>> { &self.borrows }
+
+    pub fn location(&self, idx: BorrowIndex) -> &Location {
+        &self.borrows[idx].location
+    }
+
+    /// Whether the region of a borrow, if one was given with
+    /// `use_regions`, includes `location`.
+    ///
+    /// Dataflow only kills borrows as the *effect* of the statement at
+    /// a point outside of their region, so the state on entry to that
+    /// statement has to be filtered with this.
+    pub fn region_contains(&self, idx: BorrowIndex, location: Location) -> bool {
+        match self.regions {
+            Some(ref regions) => regions[idx].contains(&location),
+            None => true,
+        }
+    }
+
+    /// Whether the borrow ends as part of the same scope exit as the
+    /// statement or terminator at `location`, i.e. whether the
+    /// `EndRegion` of its lexical region follows it with only drops,
+    /// other `StorageDead`s and the ends of other regions in between. The
+    /// values of a scope are dropped right before its region ends, while
+    /// it is still borrowed.
+    pub fn ends_after(&self, idx: BorrowIndex, location: Location) -> bool {
+        if self.regions.is_some() {
+            return false;
+        }
+        let extent = match *self.borrows[idx].region {
+            ty::ReScope(extent) => extent,
+            _ => return false,
+        };
+        let mut visited = FxHashSet();
+        let mut location = location;
+        loop {
+            let data = &self.mir[location.block];
+            for statement in &data.statements[location.statement_index..] {
+                match statement.kind {
+                    mir::StatementKind::EndRegion(end) if end == extent => return true,
+                    mir::StatementKind::EndRegion(_) |
+                    mir::StatementKind::StorageDead(_) |
+                    mir::StatementKind::Nop => {}
+                    _ => return false,
+                }
+            }
+            let target = match data.terminator().kind {
+                mir::TerminatorKind::Goto { target } |
+                mir::TerminatorKind::Drop { target, .. } => target,
+                _ => return false,
+            };
+            if !visited.insert(target) {
+                return false;
+            }
+            location = Location { block: target, statement_index: 0 };
+        }
+    }
+
+    fn kill_outside_regions(&self, sets: &mut BlockSets<BorrowIndex>, location: Location) {
+        if let Some(ref regions) = self.regions {
+            for (idx, region) in regions.iter_enumerated() {
+                if !region.contains(&location) {
+                    sets.kill(&idx);
+                }
+            }
+        }
+    }
+}
+
+struct GatherBorrows<'tcx> {
+    borrows: IndexVec<BorrowIndex, BorrowData<'tcx>>,
+    location_map: FxHashMap<Location, BorrowIndex>,
+    extent_map: FxHashMap<CodeExtent, FxHashSet<BorrowIndex>>,
+}
+
+impl<'tcx> Visitor<'tcx> for GatherBorrows<'tcx> {
+    fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: Location) {
+        if let mir::Rvalue::Ref(region, kind, ref lvalue) = *rvalue {
+            let idx = self.borrows.push(BorrowData {
+                location: location,
+                kind: kind,
+                region: region,
+                lvalue: lvalue.clone(),
+            });
+            self.location_map.insert(location, idx);
+            if let ty::ReScope(extent) = *region {
+                self.extent_map.entry(extent).or_insert(FxHashSet()).insert(idx);
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
+impl<'a, 'tcx> BitDenotation for Borrows<'a, 'tcx> {
+    type Idx = BorrowIndex;
+    fn name() -> &'static str { "borrows" }
+    fn bits_per_block(&self) -> usize {
+        self.borrows.len()
+    }
+
+    fn start_block_effect(&self, _sets: &mut BlockSets<BorrowIndex>) {
+        // no borrows have been taken prior to function execution, so
+        // this method has no effect on `_sets`.
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<BorrowIndex>,
+                        bb: mir::BasicBlock,
+                        idx: usize) {
+        let location = Location { block: bb, statement_index: idx };
+        self.kill_outside_regions(sets, location);
+
+        let stmt = &self.mir[bb].statements[idx];
+        match stmt.kind {
+            mir::StatementKind::EndRegion(extent) => {
+                if self.regions.is_none() {
+                    if let Some(borrows) = self.extent_map.get(&extent) {
+                        for idx in borrows {
+                            sets.kill(idx);
+                        }
+                    }
+                }
+            }
+            mir::StatementKind::Assign(_, mir::Rvalue::Ref(..)) => {
+                sets.gen(&self.location_map[&location]);
+            }
+            mir::StatementKind::Assign(..) |
+            mir::StatementKind::SetDiscriminant { .. } |
+            mir::StatementKind::StorageLive(_) |
+            mir::StatementKind::StorageDead(_) |
+            mir::StatementKind::InlineAsm { .. } |
+            mir::StatementKind::Nop => {}
+        }
+    }
+
+    fn terminator_effect(&self,
+                         sets: &mut BlockSets<BorrowIndex>,
+                         bb: mir::BasicBlock,
+                         statements_len: usize) {
+        let location = Location { block: bb, statement_index: statements_len };
+        self.kill_outside_regions(sets, location);
+    }
+
+    fn propagate_call_return(&self,
+                             _in_out: &mut IdxSet<BorrowIndex>,
+                             _call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        // there are no effects on the borrows in scope from
+        // returning from a call.
+    }
+}
+
+impl<'a, 'tcx> BitwiseOperator for Borrows<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // union effects of preds when computing borrows
+    }
+}
+
+impl<'a, 'tcx> DataflowOperator for Borrows<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = no borrows in scope by default
+    }
+}
@@ -29,6 +29,8 @@ use super::drop_flag_effects_for_function_entry;
 use super::drop_flag_effects_for_location;
 use super::on_lookup_result_bits;
 
+pub mod borrows;
+
 /// `MaybeInitializedLvals` tracks all l-values that might be
 /// initialized upon reaching a particular point in the control flow
 /// for a function.
@@ -293,7 +295,15 @@ impl<'a, 'tcx> BitDenotation for MaybeInitializedLvals<'a, 'tcx> {
             self.tcx, self.mir, self.mdpe,
             Location { block: bb, statement_index: idx },
             |path, s| Self::update_bits(sets, path, s)
-        )
+        );
+        // A variable whose storage is dead is no longer initialized, even
+        // though it was not moved out of, so that a loop does not see the
+        // value of the previous iteration.
+        if let mir::StatementKind::StorageDead(ref lvalue) = self.mir[bb].statements[idx].kind {
+            on_lookup_result_bits(self.tcx, self.mir, self.move_data(),
+                                  self.move_data().rev_lookup.find(lvalue),
+                                  |mpi| Self::update_bits(sets, mpi, DropFlagState::Absent));
+        }
     }
 
     fn terminator_effect(&self,
@@ -350,7 +360,13 @@ impl<'a, 'tcx> BitDenotation for MaybeUninitializedLvals<'a, 'tcx> {
             self.tcx, self.mir, self.mdpe,
             Location { block: bb, statement_index: idx },
             |path, s| Self::update_bits(sets, path, s)
-        )
+        );
+        // See `MaybeInitializedLvals::statement_effect`.
+        if let mir::StatementKind::StorageDead(ref lvalue) = self.mir[bb].statements[idx].kind {
+            on_lookup_result_bits(self.tcx, self.mir, self.move_data(),
+                                  self.move_data().rev_lookup.find(lvalue),
+                                  |mpi| Self::update_bits(sets, mpi, DropFlagState::Absent));
+        }
     }
 
     fn terminator_effect(&self,
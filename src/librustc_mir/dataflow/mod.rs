@@ -25,6 +25,7 @@ use std::usize;
 
 pub use self::impls::{MaybeInitializedLvals, MaybeUninitializedLvals};
 pub use self::impls::{DefinitelyInitializedLvals, MovingOutStatements};
+pub use self::impls::borrows::{Borrows, BorrowData, BorrowRegion};
 
 pub(crate) use self::drop_flag_effects::*;
 
@@ -213,6 +214,10 @@ impl<O: BitDenotation> DataflowResults<O> {
     pub fn sets(&self) -> &AllSets<O::Idx> {
         &self.0.sets
     }
+
+    pub fn operator(&self) -> &O {
+        &self.0.operator
+    }
 }
 
 // FIXME: This type shouldn't be public, but the graphviz::MirWithFlowState trait
@@ -62,6 +62,9 @@ pub(crate) mod indexes {
 
     /// Index into MoveData.moves.
     new_index!(MoveOutIndex, "mo");
+
+    /// Index into Borrows.borrows.
+    new_index!(BorrowIndex, "bw");
 }
 
 pub use self::indexes::MovePathIndex;
@@ -195,13 +198,39 @@ pub(super) struct MoveDataBuilder<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     param_env: ty::ParamEnv<'tcx>,
     data: MoveData<'tcx>,
+    errors: Vec<MoveError<'tcx>>,
 }
 
-pub enum MovePathError {
-    IllegalMove,
+pub enum MovePathError<'tcx> {
+    IllegalMove(IllegalMoveOriginKind<'tcx>),
     UnionMove { path: MovePathIndex },
 }
 
+/// Why an l-value cannot be moved out of.
+#[derive(Copy, Clone, Debug)]
+pub enum IllegalMoveOriginKind<'tcx> {
+    /// A static item.
+    Static,
+
+    /// Data behind a reference or a raw pointer.
+    BorrowedContent,
+
+    /// A field of a type that implements `Drop`.
+    InteriorOfTypeWithDestructor { container_ty: ty::Ty<'tcx> },
+
+    /// An element of a slice or an array.
+    InteriorOfSliceOrArray { ty: ty::Ty<'tcx> },
+}
+
+/// A move out of an l-value that cannot be moved out of, found while
+/// gathering moves. The borrow checker reports these as errors.
+#[derive(Copy, Clone, Debug)]
+pub struct MoveError<'tcx> {
+    /// The location of the move.
+    pub location: Location,
+    pub kind: IllegalMoveOriginKind<'tcx>,
+}
+
 impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
     fn new(mir: &'a Mir<'tcx>,
            tcx: TyCtxt<'a, 'tcx, 'tcx>,
@@ -225,7 +254,8 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
                 },
                 move_paths: move_paths,
                 path_map: path_map,
-            }
+            },
+            errors: Vec::new(),
         }
     }
 
@@ -261,13 +291,13 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
     ///
     /// Maybe we should have separate "borrowck" and "moveck" modes.
     fn move_path_for(&mut self, lval: &Lvalue<'tcx>)
-                     -> Result<MovePathIndex, MovePathError>
+                     -> Result<MovePathIndex, MovePathError<'tcx>>
     {
         debug!("lookup({:?})", lval);
         match *lval {
             Lvalue::Local(local) => Ok(self.data.rev_lookup.locals[local]),
             // error: can't move out of a static
-            Lvalue::Static(..) => Err(MovePathError::IllegalMove),
+            Lvalue::Static(..) => Err(MovePathError::IllegalMove(IllegalMoveOriginKind::Static)),
             Lvalue::Projection(ref proj) => {
                 self.move_path_for_projection(lval, proj)
             }
@@ -283,25 +313,32 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
     fn move_path_for_projection(&mut self,
                                 lval: &Lvalue<'tcx>,
                                 proj: &LvalueProjection<'tcx>)
-                                -> Result<MovePathIndex, MovePathError>
+                                -> Result<MovePathIndex, MovePathError<'tcx>>
     {
         let base = try!(self.move_path_for(&proj.base));
         let lv_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
         match lv_ty.sty {
             // error: can't move out of borrowed content
-            ty::TyRef(..) | ty::TyRawPtr(..) => return Err(MovePathError::IllegalMove),
+            ty::TyRef(..) | ty::TyRawPtr(..) =>
+                return Err(MovePathError::IllegalMove(IllegalMoveOriginKind::BorrowedContent)),
             // error: can't move out of struct with destructor
             ty::TyAdt(adt, _) if adt.has_dtor(self.tcx) && !adt.is_box() =>
-                return Err(MovePathError::IllegalMove),
+                return Err(MovePathError::IllegalMove(
+                    IllegalMoveOriginKind::InteriorOfTypeWithDestructor {
+                        container_ty: lv_ty
+                    })),
             // move out of union - always move the entire union
             ty::TyAdt(adt, _) if adt.is_union() =>
                 return Err(MovePathError::UnionMove { path: base }),
             // error: can't move out of a slice
             ty::TySlice(..) =>
-                return Err(MovePathError::IllegalMove),
+                return Err(MovePathError::IllegalMove(
+                    IllegalMoveOriginKind::InteriorOfSliceOrArray { ty: lv_ty })),
             ty::TyArray(..) => match proj.elem {
                 // error: can't move out of an array
-                ProjectionElem::Index(..) => return Err(MovePathError::IllegalMove),
+                ProjectionElem::Index(..) =>
+                    return Err(MovePathError::IllegalMove(
+                        IllegalMoveOriginKind::InteriorOfSliceOrArray { ty: lv_ty })),
                 _ => {
                     // FIXME: still badly broken
                 }
@@ -323,7 +360,7 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
         }
     }
 
-    fn finalize(self) -> MoveData<'tcx> {
+    fn finalize(self) -> Result<MoveData<'tcx>, (MoveData<'tcx>, Vec<MoveError<'tcx>>)> {
         debug!("{}", {
             debug!("moves for {:?}:", self.mir.span);
             for (j, mo) in self.data.moves.iter_enumerated() {
@@ -335,7 +372,11 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
             }
             "done dumping moves"
         });
-        self.data
+        if self.errors.is_empty() {
+            Ok(self.data)
+        } else {
+            Err((self.data, self.errors))
+        }
     }
 }
 
@@ -370,10 +411,30 @@ impl<'tcx> MovePathLookup<'tcx> {
 }
 
 impl<'a, 'tcx> MoveData<'tcx> {
+    /// Gathers the moves of `mir`, which must already have been
+    /// borrow-checked, so that it does not move out of anything that
+    /// cannot be moved out of.
     pub fn gather_moves(mir: &Mir<'tcx>,
                         tcx: TyCtxt<'a, 'tcx, 'tcx>,
                         param_env: ty::ParamEnv<'tcx>)
                         -> Self {
+        match gather_moves(mir, tcx, param_env) {
+            Ok(move_data) => move_data,
+            Err((_, errors)) => {
+                span_bug!(mir.span,
+                          "Broken MIR: illegal move at {:?}: {:?}",
+                          errors[0].location, errors[0].kind);
+            }
+        }
+    }
+
+    /// Gathers the moves of `mir`, returning the moves out of l-values
+    /// that cannot be moved out of alongside the move data if there are
+    /// any.
+    pub fn try_gather_moves(mir: &Mir<'tcx>,
+                            tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            param_env: ty::ParamEnv<'tcx>)
+                            -> Result<Self, (Self, Vec<MoveError<'tcx>>)> {
         gather_moves(mir, tcx, param_env)
     }
 }
@@ -381,7 +442,7 @@ impl<'a, 'tcx> MoveData<'tcx> {
 fn gather_moves<'a, 'tcx>(mir: &Mir<'tcx>,
                           tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           param_env: ty::ParamEnv<'tcx>)
-                          -> MoveData<'tcx> {
+                          -> Result<MoveData<'tcx>, (MoveData<'tcx>, Vec<MoveError<'tcx>>)> {
     let mut builder = MoveDataBuilder::new(mir, tcx, param_env);
 
     for (bb, block) in mir.basic_blocks().iter_enumerated() {
@@ -512,12 +573,10 @@ impl<'a, 'tcx> MoveDataBuilder<'a, 'tcx> {
 
         let path = match self.move_path_for(lval) {
             Ok(path) | Err(MovePathError::UnionMove { path }) => path,
-            Err(MovePathError::IllegalMove) => {
-                // Moving out of a bad path. Eventually, this should be a MIR
-                // borrowck error instead of a bug.
-                span_bug!(self.mir.span,
-                          "Broken MIR: moving out of lvalue {:?}: {:?} at {:?}",
-                          lval, lv_ty, loc);
+            Err(MovePathError::IllegalMove(kind)) => {
+                // Moving out of a bad path; the borrow checker reports it.
+                self.errors.push(MoveError { location: loc, kind: kind });
+                return
             }
         };
         let move_out = self.data.moves.push(MoveOut { path: path, source: loc });
@@ -32,6 +32,7 @@ extern crate graphviz as dot;
 #[macro_use]
 extern crate rustc;
 extern crate rustc_data_structures;
+extern crate rustc_errors;
 #[macro_use]
 #[no_link]
 extern crate rustc_bitflags;
@@ -44,6 +45,7 @@ extern crate core; // for NonZero
 
 pub mod diagnostics;
 
+mod borrow_check;
 mod build;
 pub mod dataflow;
 mod hair;
@@ -54,6 +56,9 @@ pub mod util;
 use rustc::ty::maps::Providers;
 
 pub fn provide(providers: &mut Providers) {
+    borrow_check::provide(providers);
     shim::provide(providers);
     transform::provide(providers);
 }
+
+__build_diagnostic_array! { librustc_mir, DIAGNOSTICS }
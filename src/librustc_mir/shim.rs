@@ -128,7 +128,8 @@ fn temp_decl(mutability: Mutability, ty: Ty, span: Span) -> LocalDecl {
     LocalDecl {
         mutability, ty, name: None,
         source_info: SourceInfo { scope: ARGUMENT_VISIBILITY_SCOPE, span },
-        is_user_variable: false
+        is_user_variable: false,
+        var_id: None,
     }
 }
 
@@ -0,0 +1,465 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Constant propagation of integers and booleans.
+//!
+//! This tracks which locals hold a known integer or boolean at each point
+//! of the function and replaces reads of such locals by the constant
+//! itself. For example
+//!
+//!     _2 = CheckedAdd(const 1u32, const 2u32);
+//!     assert(!(_2.1: bool), "attempt to add with overflow") -> bb1;
+//!     ...
+//!     _0 = (_2.0: u32);
+//!
+//! becomes
+//!
+//!     _2 = CheckedAdd(const 1u32, const 2u32);
+//!     assert(!const false, "attempt to add with overflow") -> bb1;
+//!     ...
+//!     _0 = const 3u32;
+//!
+//! after which `SimplifyBranches` removes the assertion and
+//! `DeadStoreElimination` the now unused addition.
+//!
+//! Only whole, never borrowed locals are tracked; writing to a local in any
+//! other way makes its value unknown.
+
+use rustc::middle::const_val::ConstVal;
+use rustc::mir::*;
+use rustc::mir::transform::{MirPass, MirSource};
+use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc_const_math::{ConstInt, ConstMathErr};
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use syntax_pos::Span;
+use util::liveness::{self, LocalSet};
+
+use std::cmp::Ordering;
+
+pub struct ConstPropagation;
+
+impl MirPass for ConstPropagation {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        match source {
+            MirSource::Const(_) => {
+                // Don't run on constants, because constant qualification might reject the
+                // optimized IR.
+                return
+            }
+            MirSource::Static(..) | MirSource::Promoted(..) => {
+                // Don't run on statics and promoted statics, because trans might not be able to
+                // evaluate the optimized IR.
+                return
+            }
+            MirSource::Fn(function_node_id) => {
+                if tcx.is_const_fn(tcx.hir.local_def_id(function_node_id)) {
+                    // Don't run on const functions, as, again, trans might not be able to evaluate
+                    // the optimized IR.
+                    return
+                }
+            }
+        }
+
+        // We only run when the MIR optimization level is > 1.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        let propagator = ConstPropagator {
+            tcx: tcx,
+            borrowed: liveness::borrowed_locals(mir),
+        };
+        let mut entry_states = propagator.analyze(mir);
+
+        let local_tys: IndexVec<Local, Ty<'tcx>> = mir.local_decls.iter()
+            .map(|decl| decl.ty)
+            .collect();
+        for block in mir.basic_blocks().indices() {
+            // Blocks that are never reached are left alone.
+            let mut state = match entry_states[block].take() {
+                Some(state) => state,
+                None => continue,
+            };
+            for index in 0..mir[block].statements.len() {
+                let location = Location { block: block, statement_index: index };
+                {
+                    let statement = &mut mir[block].statements[index];
+                    ConstReplacer {
+                        state: &state,
+                        local_tys: &local_tys,
+                        span: statement.source_info.span,
+                    }.visit_statement(block, statement, location);
+                }
+                let statement = &mir[block].statements[index];
+                propagator.transfer_statement(&mut state, statement, location);
+            }
+            let location = Location {
+                block: block,
+                statement_index: mir[block].statements.len(),
+            };
+            let terminator = mir[block].terminator_mut();
+            ConstReplacer {
+                state: &state,
+                local_tys: &local_tys,
+                span: terminator.source_info.span,
+            }.visit_terminator(block, terminator, location);
+        }
+    }
+}
+
+/// What is known about the value of a local at some point.
+#[derive(Clone, Debug, PartialEq)]
+enum Value<'tcx> {
+    /// The local has not been written to on any path reaching this point.
+    Uninit,
+    /// The local holds this constant.
+    Const(ConstVal<'tcx>),
+    /// The local holds the `(result, overflowed)` pair of a checked
+    /// operation, with the result unknown if it overflowed.
+    Checked(Option<ConstVal<'tcx>>, bool),
+    /// Nothing is known about the value of the local.
+    Unknown,
+}
+
+impl<'tcx> Value<'tcx> {
+    /// Merges `other`, the value on another path reaching this point, into
+    /// `self`. Returns whether `self` changed.
+    fn join(&mut self, other: &Value<'tcx>) -> bool {
+        let joined = match (&*self, other) {
+            (&Value::Unknown, _) | (_, &Value::Uninit) => return false,
+            (&Value::Uninit, _) => other.clone(),
+            (this, other) if this == other => return false,
+            _ => Value::Unknown,
+        };
+        *self = joined;
+        true
+    }
+}
+
+type State<'tcx> = IndexVec<Local, Value<'tcx>>;
+
+/// The constant held by `lvalue` in `state`, if known.
+fn lvalue_value<'tcx>(lvalue: &Lvalue<'tcx>, state: &State<'tcx>) -> Option<ConstVal<'tcx>> {
+    match *lvalue {
+        Lvalue::Local(local) => match state[local] {
+            Value::Const(ref value) => Some(value.clone()),
+            _ => None,
+        },
+        Lvalue::Projection(box Projection {
+            base: Lvalue::Local(local),
+            elem: ProjectionElem::Field(field, _),
+        }) => match (&state[local], field.index()) {
+            (&Value::Checked(ref result, _), 0) => result.clone(),
+            (&Value::Checked(_, overflowed), 1) => Some(ConstVal::Bool(overflowed)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+struct ConstPropagator<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    borrowed: LocalSet,
+}
+
+impl<'a, 'tcx> ConstPropagator<'a, 'tcx> {
+    /// Computes the state on entry to every block that can be reached.
+    fn analyze(&self, mir: &Mir<'tcx>) -> IndexVec<BasicBlock, Option<State<'tcx>>> {
+        let mut entry_state = IndexVec::from_elem(Value::Uninit, &mir.local_decls);
+        for local in mir.args_iter() {
+            entry_state[local] = Value::Unknown;
+        }
+        for local in mir.local_decls.indices() {
+            if self.borrowed.contains(&local) {
+                entry_state[local] = Value::Unknown;
+            }
+        }
+
+        let mut entry_states = IndexVec::from_elem(None, mir.basic_blocks());
+        entry_states[START_BLOCK] = Some(entry_state);
+
+        let mut worklist = vec![START_BLOCK];
+        let mut on_worklist = IdxSetBuf::new_empty(mir.basic_blocks().len());
+        on_worklist.add(&START_BLOCK);
+        while let Some(block) = worklist.pop() {
+            on_worklist.remove(&block);
+
+            let data = &mir[block];
+            let mut state = entry_states[block].clone().unwrap();
+            for (index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block: block, statement_index: index };
+                self.transfer_statement(&mut state, statement, location);
+            }
+            let location = Location { block: block, statement_index: data.statements.len() };
+            self.clobber_writes(&mut state).visit_terminator(block, data.terminator(), location);
+
+            for &successor in data.terminator().successors().iter() {
+                let changed = match entry_states[successor] {
+                    Some(ref mut entry) => {
+                        let mut changed = false;
+                        for (entry_value, value) in entry.iter_mut().zip(state.iter()) {
+                            changed |= entry_value.join(value);
+                        }
+                        changed
+                    }
+                    None => true,
+                };
+                if entry_states[successor].is_none() {
+                    entry_states[successor] = Some(state.clone());
+                }
+                if changed && on_worklist.add(&successor) {
+                    worklist.push(successor);
+                }
+            }
+        }
+
+        entry_states
+    }
+
+    fn clobber_writes<'b>(&'b self, state: &'b mut State<'tcx>) -> ClobberWrites<'b, 'tcx> {
+        ClobberWrites {
+            state: state,
+            borrowed: &self.borrowed,
+        }
+    }
+
+    fn transfer_statement(&self,
+                          state: &mut State<'tcx>,
+                          statement: &Statement<'tcx>,
+                          location: Location) {
+        match statement.kind {
+            StatementKind::Assign(Lvalue::Local(local), ref rvalue)
+                    if !self.borrowed.contains(&local) => {
+                let value = self.eval_rvalue(rvalue, state);
+                debug!("transfer_statement: {:?} = {:?}", local, value);
+                state[local] = value;
+            }
+            _ => {
+                self.clobber_writes(state).visit_statement(location.block, statement, location);
+            }
+        }
+    }
+
+    fn eval_operand(&self, operand: &Operand<'tcx>, state: &State<'tcx>)
+                    -> Option<ConstVal<'tcx>> {
+        match *operand {
+            Operand::Consume(ref lvalue) => lvalue_value(lvalue, state),
+            Operand::Constant(box Constant { literal: Literal::Value { ref value }, .. }) => {
+                match *value {
+                    ConstVal::Integral(_) | ConstVal::Bool(_) => Some(value.clone()),
+                    _ => None,
+                }
+            }
+            Operand::Constant(_) => None,
+        }
+    }
+
+    fn eval_rvalue(&self, rvalue: &Rvalue<'tcx>, state: &State<'tcx>) -> Value<'tcx> {
+        let value = match *rvalue {
+            Rvalue::Use(Operand::Consume(Lvalue::Local(local))) => {
+                return match state[local] {
+                    Value::Uninit => Value::Unknown,
+                    ref value => value.clone(),
+                };
+            }
+            Rvalue::Use(ref operand) => self.eval_operand(operand, state),
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                match (self.eval_operand(lhs, state), self.eval_operand(rhs, state)) {
+                    (Some(lhs), Some(rhs)) => eval_binop(op, lhs, rhs),
+                    _ => None,
+                }
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                let (lhs, rhs) = match (self.eval_operand(lhs, state),
+                                        self.eval_operand(rhs, state)) {
+                    (Some(ConstVal::Integral(lhs)), Some(ConstVal::Integral(rhs))) => (lhs, rhs),
+                    _ => return Value::Unknown,
+                };
+                return match eval_int_binop(op, lhs, rhs) {
+                    Some(Ok(result)) => Value::Checked(Some(ConstVal::Integral(result)), false),
+                    Some(Err(ConstMathErr::Overflow(_))) => Value::Checked(None, true),
+                    _ => Value::Unknown,
+                };
+            }
+            Rvalue::UnaryOp(op, ref operand) => {
+                match (op, self.eval_operand(operand, state)) {
+                    (UnOp::Not, Some(ConstVal::Bool(b))) => Some(ConstVal::Bool(!b)),
+                    (UnOp::Not, Some(ConstVal::Integral(i))) => {
+                        (!i).ok().map(ConstVal::Integral)
+                    }
+                    (UnOp::Neg, Some(ConstVal::Integral(i))) => {
+                        (-i).ok().map(ConstVal::Integral)
+                    }
+                    _ => None,
+                }
+            }
+            Rvalue::Cast(CastKind::Misc, ref operand, ty) => {
+                self.eval_operand(operand, state).and_then(|value| self.eval_cast(value, ty))
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => Value::Const(value),
+            None => Value::Unknown,
+        }
+    }
+
+    fn eval_cast(&self, value: ConstVal<'tcx>, ty: Ty<'tcx>) -> Option<ConstVal<'tcx>> {
+        let bits = match value {
+            ConstVal::Integral(i) => i.to_u128_unchecked(),
+            ConstVal::Bool(b) => b as u128,
+            _ => return None,
+        };
+        let target = &self.tcx.sess.target;
+        match ty.sty {
+            ty::TyInt(ity) => {
+                Some(ConstVal::Integral(
+                    ConstInt::new_signed_truncating(bits as i128, ity, target.int_type)))
+            }
+            ty::TyUint(uty) => {
+                Some(ConstVal::Integral(
+                    ConstInt::new_unsigned_truncating(bits, uty, target.uint_type)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn eval_binop<'tcx>(op: BinOp, lhs: ConstVal<'tcx>, rhs: ConstVal<'tcx>)
+                    -> Option<ConstVal<'tcx>> {
+    match (lhs, rhs) {
+        (ConstVal::Integral(lhs), ConstVal::Integral(rhs)) => {
+            if is_comparison(op) {
+                lhs.try_cmp(rhs).ok().map(|ordering| ConstVal::Bool(compare(op, ordering)))
+            } else {
+                eval_int_binop(op, lhs, rhs).and_then(|result| result.ok())
+                                            .map(ConstVal::Integral)
+            }
+        }
+        (ConstVal::Bool(lhs), ConstVal::Bool(rhs)) => {
+            Some(ConstVal::Bool(match op {
+                BinOp::BitAnd => lhs & rhs,
+                BinOp::BitOr => lhs | rhs,
+                BinOp::BitXor => lhs ^ rhs,
+                _ if is_comparison(op) => compare(op, lhs.cmp(&rhs)),
+                _ => return None,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Evaluates an arithmetic or bitwise operation on two integers, or returns
+/// `None` for any other operation.
+fn eval_int_binop(op: BinOp, lhs: ConstInt, rhs: ConstInt)
+                  -> Option<Result<ConstInt, ConstMathErr>> {
+    Some(match op {
+        BinOp::Add => lhs + rhs,
+        BinOp::Sub => lhs - rhs,
+        BinOp::Mul => lhs * rhs,
+        BinOp::Div => lhs / rhs,
+        BinOp::Rem => lhs % rhs,
+        BinOp::BitXor => lhs ^ rhs,
+        BinOp::BitAnd => lhs & rhs,
+        BinOp::BitOr => lhs | rhs,
+        BinOp::Shl => lhs << rhs,
+        BinOp::Shr => lhs >> rhs,
+        _ => return None,
+    })
+}
+
+fn is_comparison(op: BinOp) -> bool {
+    match op {
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => true,
+        _ => false,
+    }
+}
+
+fn compare(op: BinOp, ordering: Ordering) -> bool {
+    match op {
+        BinOp::Eq => ordering == Ordering::Equal,
+        BinOp::Ne => ordering != Ordering::Equal,
+        BinOp::Lt => ordering == Ordering::Less,
+        BinOp::Le => ordering != Ordering::Greater,
+        BinOp::Gt => ordering == Ordering::Greater,
+        BinOp::Ge => ordering != Ordering::Less,
+        _ => bug!("compare: {:?} is not a comparison", op),
+    }
+}
+
+/// Forgets the value of every local written to by a statement or
+/// terminator, other than by an assignment of the whole local.
+struct ClobberWrites<'b, 'tcx: 'b> {
+    state: &'b mut State<'tcx>,
+    borrowed: &'b LocalSet,
+}
+
+impl<'b, 'tcx> Visitor<'tcx> for ClobberWrites<'b, 'tcx> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            // Borrowed locals are always unknown.
+            if !self.borrowed.contains(&local) {
+                match context {
+                    LvalueContext::StorageLive |
+                    LvalueContext::StorageDead => self.state[local] = Value::Uninit,
+                    _ if context.is_mutating_use() => self.state[local] = Value::Unknown,
+                    _ => {}
+                }
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+/// Replaces the operands whose value is known by constants.
+struct ConstReplacer<'b, 'tcx: 'b> {
+    state: &'b State<'tcx>,
+    local_tys: &'b IndexVec<Local, Ty<'tcx>>,
+    span: Span,
+}
+
+impl<'b, 'tcx> MutVisitor<'tcx> for ConstReplacer<'b, 'tcx> {
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        let replacement = match *operand {
+            Operand::Consume(ref lvalue) => {
+                lvalue_value(lvalue, self.state).map(|value| {
+                    let ty = match *lvalue {
+                        Lvalue::Projection(box Projection {
+                            elem: ProjectionElem::Field(_, ty), ..
+                        }) => ty,
+                        Lvalue::Local(local) => self.local_tys[local],
+                        _ => bug!("visit_operand: unexpected constant {:?}", lvalue),
+                    };
+                    (value, ty)
+                })
+            }
+            Operand::Constant(_) => None,
+        };
+        match replacement {
+            Some((value, ty)) => {
+                debug!("replacing {:?} with {:?}", operand, value);
+                *operand = Operand::Constant(box Constant {
+                    span: self.span,
+                    ty: ty,
+                    literal: Literal::Value { value: value },
+                });
+            }
+            None => self.super_operand(operand, location),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dead store elimination.
+//!
+//! This removes assignments to locals whose value is never read afterwards,
+//! such as the temporaries left behind by constant propagation:
+//!
+//!     _2 = Add(_1, const 1u32);
+//!     _0 = const 3u32;
+//!     return;
+//!
+//! becomes
+//!
+//!     nop;
+//!     _0 = const 3u32;
+//!     return;
+//!
+//! Evaluating an rvalue has no side effects (overflow and bounds checks are
+//! separate `Assert` terminators), so dropping the assignment is always
+//! fine as long as the local is really dead. Locals that are borrowed
+//! anywhere are left alone, as their value may be read through the borrow.
+
+use rustc::mir::*;
+use rustc::mir::transform::{MirPass, MirSource};
+use rustc::session::config::NoDebugInfo;
+use rustc::ty::TyCtxt;
+use util::liveness::{self, LocalSet};
+
+pub struct DeadStoreElimination;
+
+impl MirPass for DeadStoreElimination {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        match source {
+            MirSource::Const(_) |
+            MirSource::Static(..) |
+            MirSource::Promoted(..) => {
+                // Constants are evaluated from their MIR, so leave it as is.
+                return
+            }
+            MirSource::Fn(function_node_id) => {
+                if tcx.is_const_fn(tcx.hir.local_def_id(function_node_id)) {
+                    return
+                }
+            }
+        }
+
+        // We only run when the MIR optimization level is > 1.
+        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
+            return;
+        }
+
+        // User variables must keep their values for the debugger.
+        let keep_vars = tcx.sess.opts.debuginfo != NoDebugInfo;
+
+        let liveness = liveness::liveness_of_locals(mir);
+        let borrowed = liveness::borrowed_locals(mir);
+
+        let mut dead_stores = Vec::new();
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            let mut live = liveness.outs[block].clone();
+            let terminator_location = Location {
+                block: block,
+                statement_index: data.statements.len(),
+            };
+            liveness::terminator_defs_uses(mir, data.terminator(), terminator_location)
+                .apply(&mut live);
+
+            for (index, statement) in data.statements.iter().enumerate().rev() {
+                let location = Location { block: block, statement_index: index };
+                if let StatementKind::Assign(Lvalue::Local(local), ref rvalue) = statement.kind {
+                    if is_dead_store(mir, local, rvalue, &live, &borrowed, keep_vars) {
+                        debug!("removing dead store {:?} at {:?}", statement, location);
+                        dead_stores.push(location);
+                        // The operands of a removed statement are not read.
+                        continue;
+                    }
+                }
+                liveness::statement_defs_uses(mir, statement, location).apply(&mut live);
+            }
+        }
+
+        for location in dead_stores {
+            mir.make_statement_nop(location);
+        }
+    }
+}
+
+fn is_dead_store<'tcx>(mir: &Mir<'tcx>,
+                       local: Local,
+                       rvalue: &Rvalue<'tcx>,
+                       live: &LocalSet,
+                       borrowed: &LocalSet,
+                       keep_vars: bool)
+                       -> bool {
+    if live.contains(&local) || borrowed.contains(&local) {
+        return false;
+    }
+    match mir.local_kind(local) {
+        LocalKind::ReturnPointer => return false,
+        LocalKind::Var if keep_vars => return false,
+        _ => {}
+    }
+    match *rvalue {
+        // Box allocations are initialized through the pointer afterwards.
+        Rvalue::NullaryOp(NullOp::Box, _) => false,
+        _ => true,
+    }
+}
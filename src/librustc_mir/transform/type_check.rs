@@ -11,7 +11,7 @@
 //! This pass type-checks the MIR to ensure it is not broken.
 #![allow(unreachable_code)]
 
-use rustc::infer::{self, InferCtxt, InferOk};
+use rustc::infer::{self, InferCtxt, InferOk, LateBoundRegionConversionTime};
 use rustc::traits;
 use rustc::ty::fold::TypeFoldable;
 use rustc::ty::{self, Ty, TyCtxt, TypeVariants};
@@ -468,7 +468,12 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
                         return;
                     }
                 };
-                let sig = tcx.erase_late_bound_regions(&sig);
+                // Fresh variables relate the late-bound regions of the
+                // arguments and of the result to each other.
+                let (sig, _) = self.infcx.replace_late_bound_regions_with_fresh_var(
+                    term.source_info.span,
+                    LateBoundRegionConversionTime::FnCall,
+                    &sig);
                 let sig = self.normalize(&sig);
                 self.check_call_dest(mir, term, &sig, destination);
 
@@ -694,6 +699,13 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
             self.check_local(mir, local, local_decl);
         }
 
+        self.check_blocks(mir);
+        for block in mir.basic_blocks() {
+            self.check_iscleanup(mir, block);
+        }
+    }
+
+    fn check_blocks(&mut self, mir: &Mir<'tcx>) {
         for block in mir.basic_blocks() {
             for stmt in &block.statements {
                 if stmt.source_info.span != DUMMY_SP {
@@ -703,7 +715,6 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
             }
 
             self.check_terminator(mir, block.terminator());
-            self.check_iscleanup(mir, block);
         }
     }
 
@@ -737,6 +748,21 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
     }
 }
 
+/// Type-checks the statements and terminators of `mir`, leaving the
+/// region constraints this gives rise to in `infcx`.
+///
+/// Unlike the `TypeckMir` pass, this takes MIR whose regions may be
+/// inference variables. It is expected to have passed `TypeckMir` already.
+pub fn type_check<'a, 'gcx, 'tcx>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
+                                  body_id: ast::NodeId,
+                                  param_env: ty::ParamEnv<'gcx>,
+                                  mir: &Mir<'tcx>) {
+    let mut checker = TypeChecker::new(infcx, body_id, param_env);
+    checker.last_span = mir.span;
+    checker.check_blocks(mir);
+    checker.verify_obligations(mir);
+}
+
 pub struct TypeckMir;
 
 impl TypeckMir {
@@ -0,0 +1,195 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Error reporting shared by the AST and the MIR borrow checker.
+//!
+//! Both checkers report the same errors with the same codes and messages,
+//! so the messages are built in one place. The methods here only create
+//! the header of an error; callers add their own labels and notes.
+
+use rustc::session::config::BorrowckMode;
+use rustc::ty::{Ty, TyCtxt};
+use rustc_errors::DiagnosticBuilder;
+use syntax_pos::{MultiSpan, Span};
+
+/// Which borrow checker an error comes from.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Origin { Ast, Mir }
+
+impl Origin {
+    /// The suffix of the messages of this checker. Under `-Z borrowck=compare`
+    /// both checkers run, so each message is tagged with the one that
+    /// emitted it.
+    fn tag(self, mode: BorrowckMode) -> &'static str {
+        if mode != BorrowckMode::Compare {
+            return "";
+        }
+        match self {
+            Origin::Ast => " (Ast)",
+            Origin::Mir => " (Mir)",
+        }
+    }
+}
+
+pub trait BorrowckErrors<'cx>: Sized + Copy {
+    fn borrowck_mode(self) -> BorrowckMode;
+
+    fn struct_span_err_with_code<S: Into<MultiSpan>>(self,
+                                                     sp: S,
+                                                     msg: &str,
+                                                     code: &str)
+                                                     -> DiagnosticBuilder<'cx>;
+
+    fn struct_span_err<S: Into<MultiSpan>>(self,
+                                           sp: S,
+                                           msg: &str)
+                                           -> DiagnosticBuilder<'cx>;
+
+    fn cannot_act_on_uninitialized_variable(self,
+                                            span: Span,
+                                            verb: &str,
+                                            desc: &str,
+                                            o: Origin)
+                                            -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0381,
+                         "{} of possibly uninitialized variable: `{}`{OGN}",
+                         verb, desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_act_on_moved_value(self,
+                                 span: Span,
+                                 verb: &str,
+                                 adverb: &str,
+                                 desc: &str,
+                                 o: Origin)
+                                 -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0382,
+                         "{} of {}moved value: `{}`{OGN}",
+                         verb, adverb, desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_reassign_immutable(self, span: Span, desc: &str, o: Origin)
+                                 -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0384,
+                         "re-assignment of immutable variable `{}`{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_mutably_borrow_multiply(self,
+                                      span: Span,
+                                      desc: &str,
+                                      opt_via: &str,
+                                      o: Origin)
+                                      -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0499,
+                         "cannot borrow `{}`{} as mutable more than once at a time{OGN}",
+                         desc, opt_via, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_reborrow_already_borrowed(self,
+                                        span: Span,
+                                        desc_new: &str,
+                                        msg_new: &str,
+                                        kind_new: &str,
+                                        noun_old: &str,
+                                        kind_old: &str,
+                                        msg_old: &str,
+                                        o: Origin)
+                                        -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0502,
+                         "cannot borrow `{}`{} as {} because {} is also borrowed as {}{}{OGN}",
+                         desc_new, msg_new, kind_new, noun_old, kind_old, msg_old,
+                         OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_use_when_mutably_borrowed(self, span: Span, desc: &str, o: Origin)
+                                        -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0503,
+                         "cannot use `{}` because it was mutably borrowed{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_move_when_borrowed(self, span: Span, desc: &str, o: Origin)
+                                 -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0505,
+                         "cannot move out of `{}` because it is borrowed{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_assign_to_borrowed(self, span: Span, desc: &str, o: Origin)
+                                 -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0506,
+                         "cannot assign to `{}` because it is borrowed{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_move_out_of(self, span: Span, move_from_desc: &str, o: Origin)
+                          -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0507,
+                         "cannot move out of {}{OGN}",
+                         move_from_desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_move_out_of_interior_noncopy(self, span: Span, ty: Ty, o: Origin)
+                                           -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0508,
+                         "cannot move out of type `{}`, a non-copy array{OGN}",
+                         ty, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_move_out_of_interior_of_drop(self, span: Span, container_ty: Ty, o: Origin)
+                                           -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0509,
+                         "cannot move out of type `{}`, which implements the `Drop` trait{OGN}",
+                         container_ty, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_assign(self, span: Span, desc: &str, o: Origin)
+                     -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0594,
+                         "cannot assign to {}{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn cannot_borrow_path_as_mutable(self, span: Span, desc: &str, o: Origin)
+                                     -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0596,
+                         "cannot borrow {} as mutable{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+
+    fn borrowed_value_does_not_live_long_enough(self, span: Span, desc: &str, o: Origin)
+                                                -> DiagnosticBuilder<'cx> {
+        struct_span_err!(self, span, E0597,
+                         "{} does not live long enough{OGN}",
+                         desc, OGN=o.tag(self.borrowck_mode()))
+    }
+}
+
+impl<'cx, 'gcx, 'tcx> BorrowckErrors<'cx> for TyCtxt<'cx, 'gcx, 'tcx> {
+    fn borrowck_mode(self) -> BorrowckMode {
+        self.sess.opts.debugging_opts.borrowck
+    }
+
+    fn struct_span_err_with_code<S: Into<MultiSpan>>(self,
+                                                     sp: S,
+                                                     msg: &str,
+                                                     code: &str)
+                                                     -> DiagnosticBuilder<'cx> {
+        self.sess.struct_span_err_with_code(sp, msg, code)
+    }
+
+    fn struct_span_err<S: Into<MultiSpan>>(self,
+                                           sp: S,
+                                           msg: &str)
+                                           -> DiagnosticBuilder<'cx> {
+        self.sess.struct_span_err(sp, msg)
+    }
+}
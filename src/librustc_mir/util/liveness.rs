@@ -0,0 +1,210 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Liveness analysis of MIR locals.
+//!
+//! A local is live at some point if the value it holds there may be read
+//! later on. This is computed by a backwards dataflow analysis, giving the
+//! locals that are live on entry to and on exit from every basic block.
+//!
+//! Only whole locals are tracked: writing to a field of a local, or through
+//! a pointer stored in it, counts as a use rather than a definition.
+//! Borrowing a local also counts as a use, but the reads made through the
+//! resulting reference later on are not seen, so users of this analysis
+//! have to treat borrowed locals as always live.
+
+use rustc::mir::*;
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::IndexVec;
+
+pub type LocalSet = IdxSetBuf<Local>;
+
+pub struct LivenessResult {
+    /// The locals live on entry to each basic block.
+    pub ins: IndexVec<BasicBlock, LocalSet>,
+
+    /// The locals live on exit from each basic block.
+    pub outs: IndexVec<BasicBlock, LocalSet>,
+}
+
+/// The locals a statement, terminator or basic block defines, i.e. whose
+/// whole value it overwrites, and the locals whose value it reads.
+pub struct DefsUses {
+    pub defs: LocalSet,
+    pub uses: LocalSet,
+}
+
+impl DefsUses {
+    fn new(num_locals: usize) -> DefsUses {
+        DefsUses {
+            defs: LocalSet::new_empty(num_locals),
+            uses: LocalSet::new_empty(num_locals),
+        }
+    }
+
+    /// Turns `bits`, the locals live after the code these definitions and
+    /// uses belong to, into the locals live before it.
+    pub fn apply(&self, bits: &mut LocalSet) -> bool {
+        bits.subtract(&self.defs) | bits.union(&self.uses)
+    }
+
+    /// Adds the definitions and uses of `earlier`, which runs before the
+    /// code `self` describes.
+    fn prepend(&mut self, earlier: &DefsUses) {
+        self.uses.subtract(&earlier.defs);
+        self.uses.union(&earlier.uses);
+        self.defs.union(&earlier.defs);
+    }
+}
+
+struct DefsUsesVisitor {
+    defs_uses: DefsUses,
+}
+
+impl<'tcx> Visitor<'tcx> for DefsUsesVisitor {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            match context {
+                LvalueContext::Store |
+                LvalueContext::Call |
+                LvalueContext::StorageLive |
+                LvalueContext::StorageDead => {
+                    self.defs_uses.defs.add(&local);
+                }
+                LvalueContext::Projection(..) |
+                LvalueContext::Borrow { .. } |
+                LvalueContext::Inspect |
+                LvalueContext::Consume |
+                LvalueContext::Drop => {
+                    self.defs_uses.uses.add(&local);
+                }
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+/// The definitions and uses of a single statement.
+///
+/// The operands of a statement are read before its destination is written,
+/// so a local can be both defined and used by it, in which case it is live
+/// before the statement.
+pub fn statement_defs_uses<'tcx>(mir: &Mir<'tcx>,
+                                 statement: &Statement<'tcx>,
+                                 location: Location)
+                                 -> DefsUses {
+    let mut visitor = DefsUsesVisitor {
+        defs_uses: DefsUses::new(mir.local_decls.len()),
+    };
+    visitor.visit_statement(location.block, statement, location);
+    let DefsUses { mut defs, uses } = visitor.defs_uses;
+    defs.subtract(&uses);
+    DefsUses { defs: defs, uses: uses }
+}
+
+/// The definitions and uses of a terminator.
+pub fn terminator_defs_uses<'tcx>(mir: &Mir<'tcx>,
+                                  terminator: &Terminator<'tcx>,
+                                  location: Location)
+                                  -> DefsUses {
+    let mut visitor = DefsUsesVisitor {
+        defs_uses: DefsUses::new(mir.local_decls.len()),
+    };
+    visitor.visit_terminator(location.block, terminator, location);
+    // Returning reads the return pointer.
+    if let TerminatorKind::Return = terminator.kind {
+        visitor.defs_uses.uses.add(&RETURN_POINTER);
+    }
+    let DefsUses { mut defs, uses } = visitor.defs_uses;
+    defs.subtract(&uses);
+    DefsUses { defs: defs, uses: uses }
+}
+
+fn block_defs_uses<'tcx>(mir: &Mir<'tcx>, block: BasicBlock) -> DefsUses {
+    let data = &mir[block];
+    let terminator_location = Location {
+        block: block,
+        statement_index: data.statements.len(),
+    };
+    let mut defs_uses = terminator_defs_uses(mir, data.terminator(), terminator_location);
+    for (index, statement) in data.statements.iter().enumerate().rev() {
+        let location = Location { block: block, statement_index: index };
+        defs_uses.prepend(&statement_defs_uses(mir, statement, location));
+    }
+    defs_uses
+}
+
+/// Computes which locals are live on entry to and exit from every block.
+pub fn liveness_of_locals<'tcx>(mir: &Mir<'tcx>) -> LivenessResult {
+    let num_locals = mir.local_decls.len();
+    let defs_uses: IndexVec<BasicBlock, DefsUses> = mir.basic_blocks().indices()
+        .map(|block| block_defs_uses(mir, block))
+        .collect();
+
+    let mut ins: IndexVec<BasicBlock, LocalSet> = mir.basic_blocks().indices()
+        .map(|_| LocalSet::new_empty(num_locals))
+        .collect();
+    let mut outs = ins.clone();
+
+    // Liveness flows backwards, so visiting blocks in reverse order makes
+    // this converge faster.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in mir.basic_blocks().indices().rev() {
+            let mut bits = LocalSet::new_empty(num_locals);
+            for &successor in mir[block].terminator().successors().iter() {
+                bits.union(&ins[successor]);
+            }
+            outs[block].clone_from(&bits);
+            defs_uses[block].apply(&mut bits);
+            changed |= ins[block].union(&bits);
+        }
+    }
+
+    LivenessResult { ins: ins, outs: outs }
+}
+
+/// The locals that are borrowed somewhere in `mir`.
+pub fn borrowed_locals<'tcx>(mir: &Mir<'tcx>) -> LocalSet {
+    struct BorrowedLocals {
+        locals: LocalSet,
+    }
+
+    impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+        fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+            if let Rvalue::Ref(_, _, ref lvalue) = *rvalue {
+                let mut base = lvalue;
+                while let Lvalue::Projection(ref proj) = *base {
+                    // Borrowing through a pointer does not borrow the local
+                    // holding the pointer.
+                    if let ProjectionElem::Deref = proj.elem {
+                        break;
+                    }
+                    base = &proj.base;
+                }
+                if let Lvalue::Local(local) = *base {
+                    self.locals.add(&local);
+                }
+            }
+            self.super_rvalue(rvalue, location);
+        }
+    }
+
+    let mut visitor = BorrowedLocals {
+        locals: LocalSet::new_empty(mir.local_decls.len()),
+    };
+    visitor.visit_mir(mir);
+    visitor.locals
+}
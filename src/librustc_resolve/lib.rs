@@ -1326,6 +1326,33 @@ impl<'a> hir::lowering::Resolver for Resolver<'a> {
 }
 
 impl<'a> Resolver<'a> {
+    /// Resolves a path written out as a string, like `vec::Vec` or
+    /// `::std::mem::swap`, in the current scope. This is used by rustdoc to
+    /// resolve links in documentation once the crate has been resolved, so
+    /// nothing is recorded and no errors are reported.
+    pub fn resolve_str_path(&mut self, span: Span, path_str: &str, is_value: bool)
+                            -> Result<Def, ()> {
+        let namespace = if is_value { ValueNS } else { TypeNS };
+        let path: Vec<SpannedIdent> = path_str.split("::").enumerate().map(|(i, segment)| {
+            let name = if i == 0 && segment.is_empty() {
+                keywords::CrateRoot.name()
+            } else {
+                Symbol::intern(segment)
+            };
+            respan(span, Ident::with_empty_ctxt(name))
+        }).collect();
+        let def = match self.resolve_path(&path, Some(namespace), false, span) {
+            PathResult::Module(module) => module.def(),
+            PathResult::NonModule(path_res) if path_res.unresolved_segments() == 0 =>
+                Some(path_res.base_def()),
+            _ => None,
+        };
+        match def {
+            Some(Def::Err) | None => Err(()),
+            Some(def) => Ok(def),
+        }
+    }
+
     pub fn new(session: &'a Session,
                krate: &Crate,
                crate_name: &str,
@@ -1694,8 +1721,8 @@ impl<'a> Resolver<'a> {
     // generate a fake "implementation scope" containing all the
     // implementations thus found, for compatibility with old resolve pass.
 
-    fn with_scope<F>(&mut self, id: NodeId, f: F)
-        where F: FnOnce(&mut Resolver)
+    pub fn with_scope<F, T>(&mut self, id: NodeId, f: F) -> T
+        where F: FnOnce(&mut Resolver) -> T
     {
         let id = self.definitions.local_def_id(id);
         let module = self.module_map.get(&id).cloned(); // clones a reference
@@ -1706,13 +1733,14 @@ impl<'a> Resolver<'a> {
             self.ribs[TypeNS].push(Rib::new(ModuleRibKind(module)));
 
             self.finalize_current_module_macro_resolutions();
-            f(self);
+            let ret = f(self);
 
             self.current_module = orig_module;
             self.ribs[ValueNS].pop();
             self.ribs[TypeNS].pop();
+            ret
         } else {
-            f(self);
+            f(self)
         }
     }
 
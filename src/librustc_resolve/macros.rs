@@ -20,7 +20,7 @@ use rustc::{ty, lint};
 use syntax::ast::{self, Name, Ident};
 use syntax::attr::{self, HasAttrs};
 use syntax::codemap::respan;
-use syntax::errors::DiagnosticBuilder;
+use syntax::errors::{Applicability, DiagnosticBuilder};
 use syntax::ext::base::{self, Annotatable, Determinacy, MultiModifier, MultiDecorator};
 use syntax::ext::base::{MacroKind, SyntaxExtension, Resolver as SyntaxResolver};
 use syntax::ext::expand::{Expansion, ExpansionKind, Invocation, InvocationKind, find_attr_invoc};
@@ -668,11 +668,14 @@ impl<'a> Resolver<'a> {
 
         if let Some(suggestion) = suggestion {
             if suggestion != name {
+                // Similarly named macros are only a guess at what was meant.
                 if let MacroKind::Bang = kind {
-                    err.span_suggestion(span, "you could try the macro",
-                                        format!("{}!", suggestion));
+                    err.span_suggestion_with_applicability(span, "you could try the macro",
+                                                           format!("{}!", suggestion),
+                                                           Applicability::MaybeIncorrect);
                 } else {
-                    err.span_suggestion(span, "try", suggestion.to_string());
+                    err.span_suggestion_with_applicability(span, "try", suggestion.to_string(),
+                                                           Applicability::MaybeIncorrect);
                 }
             } else {
                 err.help("have you added the `#[macro_use]` on the module/import?");
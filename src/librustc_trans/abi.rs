@@ -254,7 +254,8 @@ impl<'tcx> LayoutExt<'tcx> for TyLayout<'tcx> {
             Layout::Univariant { .. } |
             Layout::UntaggedUnion { .. } |
             Layout::General { .. } |
-            Layout::StructWrappedNullablePointer { .. } => true
+            Layout::StructWrappedNullablePointer { .. } |
+            Layout::NicheFilling { .. } => true
         }
     }
 
@@ -377,7 +378,8 @@ impl<'tcx> LayoutExt<'tcx> for TyLayout<'tcx> {
             // Rust-specific types, which we can ignore for C ABIs.
             Layout::FatPointer { .. } |
             Layout::General { .. } |
-            Layout::StructWrappedNullablePointer { .. } => None
+            Layout::StructWrappedNullablePointer { .. } |
+            Layout::NicheFilling { .. } => None
         }
     }
 }
@@ -43,7 +43,7 @@
 
 use std;
 
-use llvm::{ValueRef, True, IntEQ, IntNE};
+use llvm::{ValueRef, True, IntEQ, IntNE, IntULE};
 use rustc::ty::{self, Ty};
 use rustc::ty::layout::{self, LayoutTyper};
 use common::*;
@@ -106,11 +106,14 @@ pub fn finish_type_of<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         layout::CEnum { .. } | layout::General { .. }
         | layout::UntaggedUnion { .. } | layout::RawNullablePointer { .. } => { }
         layout::Univariant { ..}
-        | layout::StructWrappedNullablePointer { .. } => {
+        | layout::StructWrappedNullablePointer { .. }
+        | layout::NicheFilling { .. } => {
             let (nonnull_variant_index, nonnull_variant, packed) = match *l {
                 layout::Univariant { ref variant, .. } => (0, variant, variant.packed),
                 layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } =>
                     (nndiscr, nonnull, nonnull.packed),
+                layout::NicheFilling { dataful, ref variant, .. } =>
+                    (dataful, variant, variant.packed),
                 _ => unreachable!()
             };
             let fields = compute_fields(cx, t, nonnull_variant_index as usize, true);
@@ -141,7 +144,8 @@ fn generic_type_of<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
                 type_of::type_of(cx, nnty)
             }
         }
-        layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } => {
+        layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } |
+        layout::NicheFilling { dataful: nndiscr, variant: ref nonnull, .. } => {
             let fields = compute_fields(cx, t, nndiscr as usize, false);
             match name {
                 None => {
@@ -242,7 +246,7 @@ fn union_fill(cx: &CrateContext, size: u64, align: u64) -> Type {
 
 
 // Double index to account for padding (FieldPath already uses `Struct::memory_index`)
-fn struct_llfields_path(discrfield: &layout::FieldPath) -> Vec<usize> {
+pub fn struct_llfields_path(discrfield: &layout::FieldPath) -> Vec<usize> {
     discrfield.iter().map(|&i| (i as usize) << 1).collect::<Vec<_>>()
 }
 
@@ -335,6 +339,18 @@ pub fn trans_get_discr<'a, 'tcx>(
         layout::StructWrappedNullablePointer { nndiscr, ref discrfield, .. } => {
             struct_wrapped_nullable_bitdiscr(bcx, nndiscr, discrfield, scrutinee, alignment)
         },
+        layout::NicheFilling { dataful, niche_variants: (min, max), niche_start,
+                               ref niche_field, .. } => {
+            let path = struct_llfields_path(niche_field);
+            let niche = bcx.load(bcx.gepi(scrutinee, &path), alignment.to_align());
+            let niche_llty = val_ty(niche);
+            // Values from `niche_start` onwards, up to the number of other
+            // variants, encode those variants; anything else is `dataful`.
+            let relative = bcx.sub(niche, C_integral(niche_llty, niche_start, false));
+            let is_niche = bcx.icmp(IntULE, relative, C_integral(niche_llty, max - min, false));
+            let niche_discr = bcx.add(relative, C_integral(niche_llty, min, false));
+            bcx.select(is_niche, niche_discr, C_integral(niche_llty, dataful, false))
+        }
         _ => bug!("{} is not an enum", t)
     };
     match cast_to {
@@ -428,6 +444,18 @@ pub fn trans_set_discr<'a, 'tcx>(bcx: &Builder<'a, 'tcx>, t: Ty<'tcx>, val: Valu
                 }
             }
         }
+        layout::NicheFilling { dataful, niche_variants: (min, max), niche_start,
+                               ref niche_field, .. } => {
+            if to != dataful {
+                assert!(min <= to && to <= max);
+                let path = struct_llfields_path(niche_field);
+                let niche_ptr = bcx.gepi(val, &path);
+                let niche_llty = val_ty(niche_ptr).element_type();
+                // Wrapping around is fine, the constant is truncated.
+                let niche_value = (to - min).wrapping_add(niche_start);
+                bcx.store(C_integral(niche_llty, niche_value, false), niche_ptr, None);
+            }
+        }
         _ => bug!("Cannot handle {} represented as {:#?}", t, l)
     }
 }
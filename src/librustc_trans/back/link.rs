@@ -27,7 +27,7 @@ use rustc::dep_graph::{DepKind, DepNode};
 use rustc::hir::def_id::CrateNum;
 use rustc::hir::svh::Svh;
 use rustc_back::tempdir::TempDir;
-use rustc_back::{PanicStrategy, RelroLevel};
+use rustc_back::{LinkerFlavor, PanicStrategy, RelroLevel};
 use rustc_incremental::IncrementalHashesMap;
 use context::get_reloc_model;
 use llvm;
@@ -279,6 +279,8 @@ pub fn invalid_output_for_target(sess: &Session,
         (false, _, config::CrateTypeProcMacro) |
         (false, _, config::CrateTypeDylib) => true,
         (_, false, config::CrateTypeExecutable) => true,
+        (true, _, config::CrateTypeProcMacro) |
+        (true, _, config::CrateTypeDylib) => sess.target.target.options.only_cdylib,
         _ => false
     }
 }
@@ -476,9 +478,11 @@ fn link_binary_output(sess: &Session,
 fn object_filenames(trans: &CrateTranslation,
                     outputs: &OutputFilenames)
                     -> Vec<PathBuf> {
-    trans.modules.iter().map(|module| {
-        outputs.temp_path(OutputType::Object, Some(&module.name))
-    }).collect()
+    let upstream_modules = trans.thin_lto_upstream_modules.borrow();
+    trans.modules.iter().map(|module| &module.name)
+        .chain(upstream_modules.iter())
+        .map(|name| outputs.temp_path(OutputType::Object, Some(name)))
+        .collect()
 }
 
 fn archive_search_paths(sess: &Session) -> Vec<PathBuf> {
@@ -989,9 +993,11 @@ fn link_args(cmd: &mut Linker,
     }
 
     // If we're building a dynamic library then some platforms need to make sure
-    // that all symbols are exported correctly from the dynamic library.
+    // that all symbols are exported correctly from the dynamic library. Wasm
+    // modules have no entry point, so executables there export `main` too.
     if crate_type != config::CrateTypeExecutable ||
-       sess.target.target.options.is_like_emscripten {
+       sess.target.target.options.is_like_emscripten ||
+       flavor == LinkerFlavor::WasmLd {
         cmd.export_symbols(tmpdir, crate_type);
     }
 
@@ -1049,6 +1055,11 @@ fn link_args(cmd: &mut Linker,
     // Pass debuginfo flags down to the linker.
     cmd.debuginfo();
 
+    // Make sure the profiler runtime gets linked in when generating profiles.
+    if sess.opts.debugging_opts.pgo_gen.is_some() {
+        cmd.pgo_gen();
+    }
+
     // We want to prevent the compiler from accidentally leaking in any system
     // libraries, so we explicitly ask gcc to not link to any libraries by
     // default. Note that this does not happen for windows because windows pulls
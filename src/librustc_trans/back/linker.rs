@@ -79,6 +79,13 @@ impl<'a, 'tcx> LinkerInfo {
                     is_ld: true,
                 }) as Box<Linker>
             }
+            LinkerFlavor::WasmLd => {
+                Box::new(WasmLd {
+                    cmd: cmd,
+                    sess: sess,
+                    info: self
+                }) as Box<Linker>
+            }
         }
     }
 }
@@ -108,6 +115,7 @@ pub trait Linker {
     fn full_relro(&mut self);
     fn optimize(&mut self);
     fn debuginfo(&mut self);
+    fn pgo_gen(&mut self);
     fn no_default_libraries(&mut self);
     fn build_dylib(&mut self, out_filename: &Path);
     fn args(&mut self, args: &[String]);
@@ -270,6 +278,17 @@ impl<'a> Linker for GccLinker<'a> {
         // Don't do anything special here for GNU-style linkers.
     }
 
+    fn pgo_gen(&mut self) {
+        if !self.sess.target.target.options.linker_is_gnu { return }
+
+        // On Linux LLVM does not make the instrumented code refer to the
+        // profiler runtime's registration hook, expecting the compiler driver
+        // to do so instead (clang passes this same flag). Without it the
+        // object writing the profile out at exit is never linked in.
+        self.cmd.arg("-u");
+        self.cmd.arg("__llvm_profile_runtime");
+    }
+
     fn no_default_libraries(&mut self) {
         if !self.is_ld {
             self.cmd.arg("-nodefaultlibs");
@@ -510,6 +529,10 @@ impl<'a> Linker for MsvcLinker<'a> {
         }
     }
 
+    fn pgo_gen(&mut self) {
+        // Nothing needed here.
+    }
+
     // Currently the compiler doesn't use `dllexport` (an LLVM attribute) to
     // export symbols from a dynamic library. When building a dynamic library,
     // however, we're going to want some symbols exported, so this function
@@ -675,6 +698,10 @@ impl<'a> Linker for EmLinker<'a> {
         });
     }
 
+    fn pgo_gen(&mut self) {
+        // Nothing needed here.
+    }
+
     fn no_default_libraries(&mut self) {
         self.cmd.args(&["-s", "DEFAULT_LIBRARY_FUNCS_TO_INCLUDE=[]"]);
     }
@@ -724,6 +751,131 @@ impl<'a> Linker for EmLinker<'a> {
     }
 }
 
+pub struct WasmLd<'a> {
+    cmd: Command,
+    sess: &'a Session,
+    info: &'a LinkerInfo
+}
+
+impl<'a> Linker for WasmLd<'a> {
+    fn link_dylib(&mut self, lib: &str) {
+        // There are no dynamic libraries on wasm, everything is linked in.
+        self.link_staticlib(lib);
+    }
+
+    fn link_staticlib(&mut self, lib: &str) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_rlib(&mut self, lib: &Path) {
+        self.cmd.arg(lib);
+    }
+
+    fn include_path(&mut self, path: &Path) {
+        self.cmd.arg("-L").arg(path);
+    }
+
+    fn framework_path(&mut self, _path: &Path) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn output_filename(&mut self, path: &Path) {
+        self.cmd.arg("-o").arg(path);
+    }
+
+    fn add_object(&mut self, path: &Path) {
+        self.cmd.arg(path);
+    }
+
+    fn position_independent_executable(&mut self) {
+        // noop
+    }
+
+    fn partial_relro(&mut self) {
+        // noop
+    }
+
+    fn full_relro(&mut self) {
+        // noop
+    }
+
+    fn args(&mut self, args: &[String]) {
+        self.cmd.args(args);
+    }
+
+    fn link_rust_dylib(&mut self, lib: &str, _path: &Path) {
+        self.link_staticlib(lib);
+    }
+
+    fn link_framework(&mut self, _framework: &str) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn link_whole_staticlib(&mut self, lib: &str, _search_path: &[PathBuf]) {
+        self.link_staticlib(lib);
+    }
+
+    fn link_whole_rlib(&mut self, lib: &Path) {
+        self.link_rlib(lib);
+    }
+
+    fn gc_sections(&mut self, _keep_metadata: bool) {
+        self.cmd.arg("--gc-sections");
+    }
+
+    fn optimize(&mut self) {
+        self.cmd.arg(match self.sess.opts.optimize {
+            OptLevel::No => "-O0",
+            OptLevel::Less => "-O1",
+            OptLevel::Default => "-O2",
+            OptLevel::Aggressive => "-O3",
+            // LLD has no size optimization levels of its own.
+            OptLevel::Size |
+            OptLevel::SizeMin => "-O2",
+        });
+    }
+
+    fn debuginfo(&mut self) {
+        // noop
+    }
+
+    fn pgo_gen(&mut self) {
+        // Nothing needed here.
+    }
+
+    fn no_default_libraries(&mut self) {
+        // noop
+    }
+
+    fn build_dylib(&mut self, _out_filename: &Path) {
+        // A cdylib is a wasm module like any other, it just exports symbols.
+    }
+
+    fn export_symbols(&mut self, _tmpdir: &Path, crate_type: CrateType) {
+        for sym in &self.info.exports[&crate_type] {
+            self.cmd.arg("--export").arg(sym);
+        }
+    }
+
+    fn subsystem(&mut self, _subsystem: &str) {
+        // noop
+    }
+
+    fn finalize(&mut self) -> Command {
+        // Imports of functions the module does not define, such as those
+        // declared in `extern` blocks, are left for the embedder to provide.
+        self.cmd.arg("--allow-undefined");
+
+        // Modules are driven by their embedder calling the exported
+        // functions, there is no entry point to start from.
+        self.cmd.arg("--no-entry");
+
+        let mut cmd = Command::new("");
+        ::std::mem::swap(&mut cmd, &mut self.cmd);
+        cmd
+    }
+}
+
 fn exported_symbols(scx: &SharedCrateContext,
                     exported_symbols: &ExportedSymbols,
                     crate_type: CrateType)
@@ -12,13 +12,13 @@ use back::link;
 use back::write;
 use back::symbol_export;
 use rustc::session::config;
-use errors::FatalError;
+use errors::{FatalError, Handler};
 use llvm;
 use llvm::archive_ro::ArchiveRO;
-use llvm::{ModuleRef, TargetMachineRef, True, False};
+use llvm::{ModuleRef, ContextRef, TargetMachineRef, True, False};
 use rustc::util::common::time;
 use rustc::util::common::path2cstr;
-use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use back::write::{ModuleConfig, with_llvm_pmb, CodegenContext};
 
 use libc;
@@ -27,6 +27,8 @@ use flate2::read::DeflateDecoder;
 use std::io::Read;
 use std::ffi::CString;
 use std::path::Path;
+use std::slice;
+use std::sync::Arc;
 
 pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     match crate_type {
@@ -46,103 +48,17 @@ pub fn run(cgcx: &CodegenContext,
            config: &ModuleConfig,
            temp_no_opt_bc_filename: &Path) -> Result<(), FatalError> {
     let handler = cgcx.handler;
-    if cgcx.opts.cg.prefer_dynamic {
-        handler.struct_err("cannot prefer dynamic linking when performing LTO")
-            .note("only 'staticlib', 'bin', and 'cdylib' outputs are \
-                   supported with LTO")
-            .emit();
-        return Err(FatalError)
-    }
-
-    // Make sure we actually can run LTO
-    for crate_type in cgcx.crate_types.iter() {
-        if !crate_type_allows_lto(*crate_type) {
-            let e = handler.fatal("lto can only be run for executables, cdylibs and \
-                                   static library outputs");
-            return Err(e)
-        }
-    }
-
-    let export_threshold =
-        symbol_export::crates_export_threshold(&cgcx.crate_types);
-
-    let symbol_filter = &|&(ref name, _, level): &(String, _, _)| {
-        if symbol_export::is_below_threshold(level, export_threshold) {
-            let mut bytes = Vec::with_capacity(name.len() + 1);
-            bytes.extend(name.bytes());
-            Some(CString::new(bytes).unwrap())
-        } else {
-            None
-        }
-    };
+    check_lto_allowed(cgcx)?;
 
-    let mut symbol_white_list: Vec<CString> = cgcx.exported_symbols
-        .exported_symbols(LOCAL_CRATE)
-        .iter()
-        .filter_map(symbol_filter)
-        .collect();
+    let mut symbol_white_list = exported_symbols_for_lto(cgcx, LOCAL_CRATE);
 
     // For each of our upstream dependencies, find the corresponding rlib and
     // load the bitcode from the archive. Then merge it into the current LLVM
     // module that we've got.
     for &(cnum, ref path) in cgcx.each_linked_rlib_for_lto.iter() {
-        symbol_white_list.extend(
-            cgcx.exported_symbols.exported_symbols(cnum)
-                                 .iter()
-                                 .filter_map(symbol_filter));
-
-        let archive = ArchiveRO::open(&path).expect("wanted an rlib");
-        let bytecodes = archive.iter().filter_map(|child| {
-            child.ok().and_then(|c| c.name().map(|name| (name, c)))
-        }).filter(|&(name, _)| name.ends_with("bytecode.deflate"));
-        for (name, data) in bytecodes {
-            let bc_encoded = data.data();
-
-            let bc_decoded = if is_versioned_bytecode_format(bc_encoded) {
-                time(cgcx.time_passes, &format!("decode {}", name), || {
-                    // Read the version
-                    let version = extract_bytecode_format_version(bc_encoded);
-
-                    if version == 1 {
-                        // The only version existing so far
-                        let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
-                        let compressed_data = &bc_encoded[
-                            link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
-                            (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
-
-                        let mut inflated = Vec::new();
-                        let res = DeflateDecoder::new(compressed_data)
-                            .read_to_end(&mut inflated);
-                        if res.is_err() {
-                            let msg = format!("failed to decompress bc of `{}`",
-                                              name);
-                            Err(handler.fatal(&msg))
-                        } else {
-                            Ok(inflated)
-                        }
-                    } else {
-                        Err(handler.fatal(&format!("Unsupported bytecode format version {}",
-                                                   version)))
-                    }
-                })?
-            } else {
-                time(cgcx.time_passes, &format!("decode {}", name), || {
-                    // the object must be in the old, pre-versioning format, so
-                    // simply inflate everything and let LLVM decide if it can
-                    // make sense of it
-                    let mut inflated = Vec::new();
-                    let res = DeflateDecoder::new(bc_encoded)
-                        .read_to_end(&mut inflated);
-                    if res.is_err() {
-                        let msg = format!("failed to decompress bc of `{}`",
-                                          name);
-                        Err(handler.fatal(&msg))
-                    } else {
-                        Ok(inflated)
-                    }
-                })?
-            };
+        symbol_white_list.extend(exported_symbols_for_lto(cgcx, cnum));
 
+        for (name, bc_decoded) in upstream_bitcode(cgcx, path)? {
             let ptr = bc_decoded.as_ptr();
             debug!("linking {}", name);
             time(cgcx.time_passes, &format!("ll link {}", name), || unsafe {
@@ -214,6 +130,326 @@ pub fn run(cgcx: &CodegenContext,
     Ok(())
 }
 
+/// Performs the global ThinLTO analysis on the coordinator thread.
+///
+/// `modules` are the optimized codegen units of the local crate, serialized
+/// along with their summaries. When `-C lto` is also in effect, the bitcode
+/// of upstream rlibs takes part as well, just like with regular LTO. Each
+/// returned `ThinModule` can afterwards be optimized and codegen'd
+/// independently of the others on a worker thread.
+pub fn run_thin(cgcx: &CodegenContext,
+                modules: Vec<(String, ThinBuffer)>)
+                -> Result<Vec<ThinModule>, FatalError> {
+    let handler = cgcx.handler;
+    let mut modules = modules;
+    let mut symbol_white_list = exported_symbols_for_lto(cgcx, LOCAL_CRATE);
+
+    if cgcx.lto {
+        check_lto_allowed(cgcx)?;
+
+        for &(cnum, ref path) in cgcx.each_linked_rlib_for_lto.iter() {
+            symbol_white_list.extend(exported_symbols_for_lto(cgcx, cnum));
+
+            for (name, bc_decoded) in upstream_bitcode(cgcx, path)? {
+                // The bitcode stored in rlibs has no ThinLTO summary, so it
+                // is round-tripped through LLVM to compute one.
+                let name = name.trim_right_matches(".bytecode.deflate").to_string();
+                let buffer = time(cgcx.time_passes, &format!("summarize {}", name), || unsafe {
+                    summarize_bitcode(handler, &name, &bc_decoded)
+                })?;
+                modules.push((name, buffer));
+            }
+        }
+    }
+
+    let mut module_names = Vec::with_capacity(modules.len());
+    let mut thin_buffers = Vec::with_capacity(modules.len());
+    let mut thin_modules = Vec::with_capacity(modules.len());
+    for (name, buffer) in modules {
+        let name = CString::new(name).unwrap();
+        thin_modules.push(llvm::ThinLTOModule {
+            identifier: name.as_ptr(),
+            data: buffer.data().as_ptr(),
+            len: buffer.data().len(),
+        });
+        module_names.push(name);
+        thin_buffers.push(buffer);
+    }
+
+    let arr: Vec<*const libc::c_char> = symbol_white_list.iter()
+                                                         .map(|c| c.as_ptr())
+                                                         .collect();
+    let data = unsafe {
+        llvm::LLVMRustCreateThinLTOData(thin_modules.as_ptr(),
+                                        thin_modules.len() as libc::size_t,
+                                        arr.as_ptr(),
+                                        arr.len() as libc::size_t)
+    };
+    if data.is_null() {
+        let msg = "failed to prepare thin LTO context".to_string();
+        return Err(write::llvm_err(handler, msg))
+    }
+
+    let shared = Arc::new(ThinShared {
+        data: ThinData(data),
+        thin_buffers: thin_buffers,
+        module_names: module_names,
+    });
+    Ok((0..shared.module_names.len()).map(|idx| {
+        ThinModule {
+            shared: shared.clone(),
+            idx: idx,
+        }
+    }).collect())
+}
+
+unsafe fn summarize_bitcode(handler: &Handler, name: &str, bc: &[u8])
+    -> Result<ThinBuffer, FatalError>
+{
+    let llcx = llvm::LLVMContextCreate();
+    let cname = CString::new(name).unwrap();
+    let llmod = llvm::LLVMRustParseBitcodeForThinLTO(llcx, bc.as_ptr(), bc.len(), cname.as_ptr());
+    if llmod.is_null() {
+        llvm::LLVMContextDispose(llcx);
+        let msg = format!("failed to load bc of `{}`", name);
+        return Err(write::llvm_err(handler, msg))
+    }
+    let buffer = ThinBuffer::new(llmod);
+    llvm::LLVMDisposeModule(llmod);
+    llvm::LLVMContextDispose(llcx);
+    Ok(buffer)
+}
+
+/// A module serialized to bitcode along with its ThinLTO summary.
+pub struct ThinBuffer(*mut llvm::ThinLTOBuffer);
+
+unsafe impl Send for ThinBuffer {}
+unsafe impl Sync for ThinBuffer {}
+
+impl ThinBuffer {
+    pub fn new(m: ModuleRef) -> ThinBuffer {
+        unsafe {
+            ThinBuffer(llvm::LLVMRustThinLTOBufferCreate(m))
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let ptr = llvm::LLVMRustThinLTOBufferPtr(self.0) as *const u8;
+            let len = llvm::LLVMRustThinLTOBufferLen(self.0);
+            slice::from_raw_parts(ptr, len as usize)
+        }
+    }
+}
+
+impl Drop for ThinBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustThinLTOBufferFree(self.0);
+        }
+    }
+}
+
+struct ThinData(*mut llvm::ThinLTOData);
+
+unsafe impl Send for ThinData {}
+unsafe impl Sync for ThinData {}
+
+impl Drop for ThinData {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustFreeThinLTOData(self.0);
+        }
+    }
+}
+
+/// The state shared by all modules taking part in one ThinLTO session.
+struct ThinShared {
+    data: ThinData,
+    thin_buffers: Vec<ThinBuffer>,
+    module_names: Vec<CString>,
+}
+
+/// One module of a ThinLTO session, ready to be optimized with
+/// `ThinModule::optimize` on a worker thread.
+pub struct ThinModule {
+    shared: Arc<ThinShared>,
+    idx: usize,
+}
+
+impl ThinModule {
+    pub fn name(&self) -> &str {
+        self.shared.module_names[self.idx].to_str().unwrap()
+    }
+
+    fn data(&self) -> &[u8] {
+        self.shared.thin_buffers[self.idx].data()
+    }
+
+    /// Parses this module into `llcx` and runs it through the per-module
+    /// ThinLTO steps: renaming and promotion of locals, resolution of weak
+    /// symbols, internalization, importing from the other modules, and
+    /// finally the ThinLTO optimization pipeline.
+    pub unsafe fn optimize(&self,
+                           cgcx: &CodegenContext,
+                           llcx: ContextRef,
+                           tm: TargetMachineRef,
+                           config: &ModuleConfig,
+                           temp_no_opt_bc_filename: &Path)
+                           -> Result<ModuleRef, FatalError> {
+        let handler = cgcx.handler;
+        let data = self.data();
+        let name = &self.shared.module_names[self.idx];
+        let llmod = llvm::LLVMRustParseBitcodeForThinLTO(llcx,
+                                                          data.as_ptr(),
+                                                          data.len(),
+                                                          name.as_ptr());
+        if llmod.is_null() {
+            let msg = format!("failed to parse bitcode for thin LTO module `{}`", self.name());
+            return Err(write::llvm_err(handler, msg))
+        }
+
+        if cgcx.no_landing_pads {
+            llvm::LLVMRustMarkAllFunctionsNounwind(llmod);
+        }
+
+        let thin = self.shared.data.0;
+        let prepared = llvm::LLVMRustPrepareThinLTORename(thin, llmod) &&
+            llvm::LLVMRustPrepareThinLTOResolveWeak(thin, llmod) &&
+            llvm::LLVMRustPrepareThinLTOInternalize(thin, llmod) &&
+            llvm::LLVMRustPrepareThinLTOImport(thin, llmod);
+        if !prepared {
+            llvm::LLVMDisposeModule(llmod);
+            let msg = format!("failed to prepare thin LTO module `{}`", self.name());
+            return Err(write::llvm_err(handler, msg))
+        }
+
+        if cgcx.opts.cg.save_temps {
+            let cstr = path2cstr(temp_no_opt_bc_filename);
+            llvm::LLVMWriteBitcodeToFile(llmod, cstr.as_ptr());
+        }
+
+        // Now that everything this module imports has been pulled in, run
+        // the ThinLTO-specific optimization pipeline over it.
+        let pm = llvm::LLVMCreatePassManager();
+        llvm::LLVMRustAddAnalysisPasses(tm, pm, llmod);
+        with_llvm_pmb(llmod, config, &mut |b| {
+            llvm::LLVMRustPassManagerBuilderPopulateThinLTOPassManager(b, pm);
+        });
+        llvm::LLVMRunPassManager(pm, llmod);
+        llvm::LLVMDisposePassManager(pm);
+
+        Ok(llmod)
+    }
+}
+
+fn check_lto_allowed(cgcx: &CodegenContext) -> Result<(), FatalError> {
+    let handler = cgcx.handler;
+    if cgcx.opts.cg.prefer_dynamic {
+        handler.struct_err("cannot prefer dynamic linking when performing LTO")
+            .note("only 'staticlib', 'bin', and 'cdylib' outputs are \
+                   supported with LTO")
+            .emit();
+        return Err(FatalError)
+    }
+
+    // Make sure we actually can run LTO
+    for crate_type in cgcx.crate_types.iter() {
+        if !crate_type_allows_lto(*crate_type) {
+            let e = handler.fatal("lto can only be run for executables, cdylibs and \
+                                   static library outputs");
+            return Err(e)
+        }
+    }
+    Ok(())
+}
+
+/// The symbols of `cnum` which are exported from the crate types being
+/// produced, and so must not be internalized.
+fn exported_symbols_for_lto(cgcx: &CodegenContext, cnum: CrateNum) -> Vec<CString> {
+    let export_threshold =
+        symbol_export::crates_export_threshold(&cgcx.crate_types);
+
+    cgcx.exported_symbols
+        .exported_symbols(cnum)
+        .iter()
+        .filter_map(|&(ref name, _, level)| {
+            if symbol_export::is_below_threshold(level, export_threshold) {
+                let mut bytes = Vec::with_capacity(name.len() + 1);
+                bytes.extend(name.bytes());
+                Some(CString::new(bytes).unwrap())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Loads and decompresses the bitcode of every module stored in the rlib at
+/// `path`, along with the name of the archive member it came from.
+fn upstream_bitcode(cgcx: &CodegenContext, path: &Path)
+    -> Result<Vec<(String, Vec<u8>)>, FatalError>
+{
+    let handler = cgcx.handler;
+    let archive = ArchiveRO::open(path).expect("wanted an rlib");
+    let bytecodes = archive.iter().filter_map(|child| {
+        child.ok().and_then(|c| c.name().map(|name| (name, c)))
+    }).filter(|&(name, _)| name.ends_with("bytecode.deflate"));
+
+    let mut modules = Vec::new();
+    for (name, data) in bytecodes {
+        let bc_encoded = data.data();
+
+        let bc_decoded = if is_versioned_bytecode_format(bc_encoded) {
+            time(cgcx.time_passes, &format!("decode {}", name), || {
+                // Read the version
+                let version = extract_bytecode_format_version(bc_encoded);
+
+                if version == 1 {
+                    // The only version existing so far
+                    let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
+                    let compressed_data = &bc_encoded[
+                        link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
+                        (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
+
+                    let mut inflated = Vec::new();
+                    let res = DeflateDecoder::new(compressed_data)
+                        .read_to_end(&mut inflated);
+                    if res.is_err() {
+                        let msg = format!("failed to decompress bc of `{}`",
+                                          name);
+                        Err(handler.fatal(&msg))
+                    } else {
+                        Ok(inflated)
+                    }
+                } else {
+                    Err(handler.fatal(&format!("Unsupported bytecode format version {}",
+                                               version)))
+                }
+            })?
+        } else {
+            time(cgcx.time_passes, &format!("decode {}", name), || {
+                // the object must be in the old, pre-versioning format, so
+                // simply inflate everything and let LLVM decide if it can
+                // make sense of it
+                let mut inflated = Vec::new();
+                let res = DeflateDecoder::new(bc_encoded)
+                    .read_to_end(&mut inflated);
+                if res.is_err() {
+                    let msg = format!("failed to decompress bc of `{}`",
+                                      name);
+                    Err(handler.fatal(&msg))
+                } else {
+                    Ok(inflated)
+                }
+            })?
+        };
+
+        modules.push((name.to_string(), bc_decoded));
+    }
+    Ok(modules)
+}
+
 fn is_versioned_bytecode_format(bc: &[u8]) -> bool {
     let magic_id_byte_count = link::RLIB_BYTECODE_OBJECT_MAGIC.len();
     return bc.len() > magic_id_byte_count &&
@@ -36,7 +36,9 @@ use std::ffi::CString;
 use std::fs;
 use std::io;
 use std::io::Write;
+use std::mem;
 use std::path::{Path, PathBuf};
+use std::ptr;
 use std::str;
 use std::sync::mpsc::{channel, Sender};
 use std::slice;
@@ -138,6 +140,7 @@ pub fn create_target_machine(sess: &Session) -> TargetMachineRef {
 
     let ffunction_sections = sess.target.target.options.function_sections;
     let fdata_sections = ffunction_sections;
+    let singlethread = sess.target.target.options.singlethread;
 
     let code_model_arg = match sess.opts.cg.code_model {
         Some(ref s) => &s,
@@ -176,6 +179,7 @@ pub fn create_target_machine(sess: &Session) -> TargetMachineRef {
             is_pie_binary(sess),
             ffunction_sections,
             fdata_sections,
+            singlethread,
         )
     };
 
@@ -220,10 +224,17 @@ pub struct ModuleConfig {
     vectorize_slp: bool,
     merge_functions: bool,
     inline_threshold: Option<usize>,
+    // Where to write the instrumentation profile to, if generating one,
+    // and the profile to optimize with, if using one.
+    pgo_gen: Option<String>,
+    pgo_use: Option<String>,
     // Instead of creating an object file by doing LLVM codegen, just
     // make the object file bitcode. Provides easy compatibility with
     // emscripten's ecc compiler, when used as the linker.
     obj_is_bitcode: bool,
+    // Once optimized, hand the module back to the coordinator for ThinLTO
+    // instead of generating code for it right away.
+    thinlto: bool,
 }
 
 unsafe impl Send for ModuleConfig { }
@@ -251,7 +262,10 @@ impl ModuleConfig {
             vectorize_loop: false,
             vectorize_slp: false,
             merge_functions: false,
-            inline_threshold: None
+            inline_threshold: None,
+            pgo_gen: None,
+            pgo_use: None,
+            thinlto: false,
         }
     }
 
@@ -261,6 +275,8 @@ impl ModuleConfig {
         self.no_builtins = trans.no_builtins;
         self.time_passes = sess.time_passes();
         self.inline_threshold = sess.opts.cg.inline_threshold;
+        self.pgo_gen = sess.opts.debugging_opts.pgo_gen.clone();
+        self.pgo_use = sess.opts.debugging_opts.pgo_use.clone();
         self.obj_is_bitcode = sess.target.target.options.obj_is_bitcode;
 
         // Copy what clang does by turning on loop vectorization at O2 and
@@ -337,6 +353,17 @@ unsafe extern "C" fn diagnostic_handler(info: DiagnosticInfoRef, user: *mut c_vo
                               inline.cookie);
         }
 
+        llvm::diagnostic::PGO(diagnostic_ref) => {
+            // These are about the profile given to `-Z pgo-use` not matching
+            // the code, e.g. because it was collected from an older version.
+            // The message can contain the path of the profile, which need not
+            // be UTF-8, and panicking here would unwind into LLVM.
+            let msg = llvm::build_byte_buffer(|s| {
+                llvm::LLVMRustWriteDiagnosticInfoToString(diagnostic_ref, s)
+            });
+            cgcx.handler.warn(&String::from_utf8_lossy(&msg));
+        }
+
         llvm::diagnostic::Optimization(opt) => {
             let enabled = match cgcx.remark {
                 AllPasses => true,
@@ -451,7 +478,7 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         llvm::LLVMDisposePassManager(fpm);
         llvm::LLVMDisposePassManager(mpm);
 
-        if cgcx.lto {
+        if cgcx.lto && !config.thinlto {
             time(cgcx.time_passes, "all lto passes", || {
                 let temp_no_opt_bc_filename =
                     output_names.temp_path_ext("no-opt.lto.bc", module_name);
@@ -469,6 +496,71 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         }
     }
 
+    if config.thinlto {
+        // Code for this module is generated only after the ThinLTO analysis
+        // over all modules, so serialize it and send it to the coordinator.
+        let buffer = lto::ThinBuffer::new(llmod);
+        drop(cgcx.tx.send(Message::NeedsThinLto {
+            name: mtrans.name.clone(),
+            buffer: buffer,
+        }));
+        llvm::LLVMRustDisposeTargetMachine(tm);
+        return Ok(());
+    }
+
+    codegen(cgcx, &mtrans.name, llmod, config, output_names)
+}
+
+// Runs the ThinLTO steps on `module`, in a fresh LLVM context, and then
+// generates code for it.
+unsafe fn thin_lto_and_codegen(cgcx: &CodegenContext,
+                               module: lto::ThinModule,
+                               config: ModuleConfig,
+                               output_names: OutputFilenames)
+    -> Result<(), FatalError>
+{
+    let llcx = llvm::LLVMContextCreate();
+
+    let fv = HandlerFreeVars {
+        cgcx: cgcx,
+    };
+    let fv = &fv as *const HandlerFreeVars as *mut c_void;
+
+    llvm::LLVMRustSetInlineAsmDiagnosticHandler(llcx, inline_asm_handler, fv);
+    llvm::LLVMContextSetDiagnosticHandler(llcx, diagnostic_handler, fv);
+
+    let module_name = Some(module.name());
+
+    let llmod = time(config.time_passes, &format!("thin lto passes [{}]", cgcx.worker), || {
+        let temp_no_opt_bc_filename =
+            output_names.temp_path_ext("no-opt.thin-lto.bc", module_name);
+        module.optimize(cgcx, llcx, config.tm, &config, &temp_no_opt_bc_filename)
+    })?;
+    if config.emit_lto_bc {
+        let out = output_names.temp_path_ext("lto.bc", module_name);
+        let out = path2cstr(&out);
+        llvm::LLVMWriteBitcodeToFile(llmod, out.as_ptr());
+    }
+
+    codegen(cgcx, module.name(), llmod, config, output_names)?;
+
+    llvm::LLVMDisposeModule(llmod);
+    llvm::LLVMContextDispose(llcx);
+    Ok(())
+}
+
+// Generates the requested outputs (bitcode, assembly, objects, ...) for an
+// optimized module.
+unsafe fn codegen(cgcx: &CodegenContext,
+                  module_name: &str,
+                  llmod: ModuleRef,
+                  config: ModuleConfig,
+                  output_names: OutputFilenames)
+    -> Result<(), FatalError>
+{
+    let tm = config.tm;
+    let module_name = Some(module_name);
+
     // A codegen-specific pass manager is used to generate object
     // files for an LLVM module.
     //
@@ -622,14 +714,22 @@ pub fn run_passes(sess: &Session,
     // case, but it would be confusing to have the validity of
     // `-Z lto -C codegen-units=2` depend on details of the crate being
     // compiled, so we complain regardless.
-    if sess.lto() && sess.opts.cg.codegen_units > 1 {
+    if sess.lto() && sess.opts.cg.codegen_units > 1 && !sess.thinlto() {
         // This case is impossible to handle because LTO expects to be able
         // to combine the entire crate and all its dependencies into a
         // single compilation unit, but each codegen unit is in a separate
-        // LLVM context, so they can't easily be combined.
+        // LLVM context, so they can't easily be combined. ThinLTO, on the
+        // other hand, works across codegen units just fine.
         sess.fatal("can't perform LTO when using multiple codegen units");
     }
 
+    if sess.opts.debugging_opts.thinlto && sess.opts.incremental.is_some() {
+        sess.warn("ignoring -Z thinlto since incremental compilation is enabled");
+    }
+    if sess.thinlto() && unsafe { !llvm::LLVMRustThinLTOAvailable() } {
+        sess.fatal("this compiler's LLVM does not support ThinLTO");
+    }
+
     // Sanity check
     assert!(trans.modules.len() == sess.opts.cg.codegen_units ||
             sess.opts.debugging_opts.incremental.is_some() ||
@@ -666,6 +766,7 @@ pub fn run_passes(sess: &Session,
 
     modules_config.opt_level = Some(get_llvm_opt_level(sess.opts.optimize));
     modules_config.opt_size = Some(get_llvm_opt_size(sess.opts.optimize));
+    modules_config.thinlto = sess.thinlto();
 
     // Save all versions of the bytecode if we're saving our temporaries.
     if sess.opts.cg.save_temps {
@@ -726,7 +827,7 @@ pub fn run_passes(sess: &Session,
 
     {
         let work = build_work_item(sess,
-                                   trans.metadata_module.clone(),
+                                   WorkItemKind::Module(trans.metadata_module.clone()),
                                    metadata_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
@@ -734,7 +835,7 @@ pub fn run_passes(sess: &Session,
 
     if let Some(allocator) = trans.allocator_module.clone() {
         let work = build_work_item(sess,
-                                   allocator,
+                                   WorkItemKind::Module(allocator),
                                    allocator_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
@@ -742,7 +843,7 @@ pub fn run_passes(sess: &Session,
 
     for mtrans in trans.modules.iter() {
         let work = build_work_item(sess,
-                                   mtrans.clone(),
+                                   WorkItemKind::Module(mtrans.clone()),
                                    modules_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
@@ -759,9 +860,16 @@ pub fn run_passes(sess: &Session,
         let num_workers = cmp::min(work_items.len() - 1, 32);
         Client::new(num_workers).expect("failed to create jobserver")
     });
-    scope(|scope| {
-        execute_work(sess, work_items, client, &trans.exported_symbols, scope);
+    let upstream_modules = scope(|scope| {
+        execute_work(sess,
+                     work_items,
+                     client,
+                     &trans.exported_symbols,
+                     &modules_config,
+                     crate_output,
+                     scope)
     });
+    *trans.thin_lto_upstream_modules.borrow_mut() = upstream_modules;
 
     // If in incr. comp. mode, preserve the `.o` files for potential re-use
     for mtrans in trans.modules.iter() {
@@ -950,13 +1058,21 @@ fn dump_incremental_data(trans: &CrateTranslation) {
 }
 
 struct WorkItem {
-    mtrans: ModuleTranslation,
+    kind: WorkItemKind,
     config: ModuleConfig,
     output_names: OutputFilenames
 }
 
+enum WorkItemKind {
+    /// A module coming out of translation, or reused from the incremental
+    /// compilation cache.
+    Module(ModuleTranslation),
+    /// A module whose code is generated after the ThinLTO analysis.
+    ThinLto(lto::ThinModule),
+}
+
 fn build_work_item(sess: &Session,
-                   mtrans: ModuleTranslation,
+                   kind: WorkItemKind,
                    config: ModuleConfig,
                    output_names: OutputFilenames)
                    -> WorkItem
@@ -964,7 +1080,7 @@ fn build_work_item(sess: &Session,
     let mut config = config;
     config.tm = create_target_machine(sess);
     WorkItem {
-        mtrans: mtrans,
+        kind: kind,
         config: config,
         output_names: output_names
     }
@@ -973,12 +1089,25 @@ fn build_work_item(sess: &Session,
 fn execute_work_item(cgcx: &CodegenContext, work_item: WorkItem)
     -> Result<(), FatalError>
 {
+    let mtrans = match work_item.kind {
+        WorkItemKind::Module(mtrans) => mtrans,
+        WorkItemKind::ThinLto(module) => {
+            debug!("running thin lto on {:?}", module.name());
+            return unsafe {
+                thin_lto_and_codegen(cgcx,
+                                     module,
+                                     work_item.config,
+                                     work_item.output_names)
+            }
+        }
+    };
+
     unsafe {
-        match work_item.mtrans.source {
+        match mtrans.source {
             ModuleSource::Translated(mllvm) => {
-                debug!("llvm-optimizing {:?}", work_item.mtrans.name);
+                debug!("llvm-optimizing {:?}", mtrans.name);
                 optimize_and_codegen(cgcx,
-                                     work_item.mtrans,
+                                     mtrans,
                                      mllvm,
                                      work_item.config,
                                      work_item.output_names)?;
@@ -987,13 +1116,13 @@ fn execute_work_item(cgcx: &CodegenContext, work_item: WorkItem)
                 let incr_comp_session_dir = cgcx.incr_comp_session_dir
                                                 .as_ref()
                                                 .unwrap();
-                let name = &work_item.mtrans.name;
+                let name = &mtrans.name;
                 for (kind, saved_file) in wp.saved_files {
                     let obj_out = work_item.output_names.temp_path(kind, Some(name));
                     let source_file = in_incr_comp_dir(&incr_comp_session_dir,
                                                        &saved_file);
                     debug!("copying pre-existing module `{}` from {:?} to {}",
-                           work_item.mtrans.name,
+                           mtrans.name,
                            source_file,
                            obj_out.display());
                     match link_or_copy(&source_file, &obj_out) {
@@ -1017,6 +1146,7 @@ pub enum Message {
     Token(io::Result<Acquired>),
     Diagnostic(Diagnostic),
     Done { success: bool },
+    NeedsThinLto { name: String, buffer: lto::ThinBuffer },
     InlineAsmError(u32, String),
     AbortIfErrors,
 }
@@ -1031,7 +1161,10 @@ fn execute_work<'a>(sess: &'a Session,
                     mut work_items: Vec<WorkItem>,
                     jobserver: Client,
                     exported_symbols: &'a ExportedSymbols,
-                    scope: &Scope<'a>) {
+                    modules_config: &ModuleConfig,
+                    crate_output: &OutputFilenames,
+                    scope: &Scope<'a>)
+                    -> Vec<String> {
     let (tx, rx) = channel();
     let tx2 = tx.clone();
 
@@ -1107,9 +1240,34 @@ fn execute_work<'a>(sess: &'a Session,
     // Before that work finishes, however, we may acquire a token. In that case
     // we actually wastefully acquired the token, so we relinquish it back to
     // the jobserver.
+    //
+    // ## ThinLTO
+    //
+    // With ThinLTO, workers don't generate code for the modules they
+    // optimize but send them back to us in a `NeedsThinLto` message. Once
+    // all modules have been optimized and nothing else is running we perform
+    // the global ThinLTO analysis right here, and then schedule one more
+    // unit of work per module to finish ThinLTO and generate code for it.
     let mut tokens = Vec::new();
     let mut running = 0;
-    while work_items.len() > 0 || running > 0 {
+    let mut needs_thin_lto = Vec::new();
+    let mut upstream_modules = Vec::new();
+    while work_items.len() > 0 || running > 0 || needs_thin_lto.len() > 0 {
+
+        if work_items.len() == 0 && running == 0 && needs_thin_lto.len() > 0 {
+            let modules = mem::replace(&mut needs_thin_lto, Vec::new());
+            let (items, upstream) = generate_thin_lto_work(sess,
+                                                           exported_symbols,
+                                                           modules_config,
+                                                           crate_output,
+                                                           &tx,
+                                                           modules);
+            work_items = items;
+            upstream_modules = upstream;
+            for _ in 1..work_items.len() {
+                helper.request_token();
+            }
+        }
 
         // Spin up what work we can, only doing this while we've got available
         // parallelism slots and work left to spawn.
@@ -1147,6 +1305,12 @@ fn execute_work<'a>(sess: &'a Session,
                 sess.fatal("aborting due to worker thread panic");
             }
 
+            // A worker has optimized a module which now awaits the ThinLTO
+            // analysis.
+            Message::NeedsThinLto { name, buffer } => {
+                needs_thin_lto.push((name, buffer));
+            }
+
             // Our worker wants us to emit an error message, so get ahold of our
             // `sess` and print it out
             Message::Diagnostic(diag) => {
@@ -1180,6 +1344,61 @@ fn execute_work<'a>(sess: &'a Session,
 
     // Just in case, check this on the way out.
     sess.diagnostic().abort_if_errors();
+
+    upstream_modules
+}
+
+// Runs the global ThinLTO analysis over `modules` and returns the work
+// items finishing ThinLTO for each of them, along with the names of the
+// modules which were pulled in from upstream crates.
+fn generate_thin_lto_work(sess: &Session,
+                          exported_symbols: &ExportedSymbols,
+                          modules_config: &ModuleConfig,
+                          crate_output: &OutputFilenames,
+                          tx: &Sender<Message>,
+                          modules: Vec<(String, lto::ThinBuffer)>)
+                          -> (Vec<WorkItem>, Vec<String>) {
+    let num_local_modules = modules.len();
+    let cgcx = CodegenContext {
+        crate_types: sess.crate_types.borrow().clone(),
+        each_linked_rlib_for_lto: linked_rlibs_for_lto(sess),
+        lto: sess.lto(),
+        no_landing_pads: sess.no_landing_pads(),
+        opts: &sess.opts,
+        time_passes: sess.time_passes(),
+        exported_symbols: exported_symbols,
+        handler: sess.diagnostic(),
+        plugin_passes: sess.plugin_llvm_passes.borrow().clone(),
+        remark: sess.opts.cg.remark.clone(),
+        worker: 0,
+        incr_comp_session_dir: None,
+        tx: tx.clone(),
+    };
+    let thin_modules = time(sess.time_passes(), "thin lto analysis", || {
+        lto::run_thin(&cgcx, modules)
+    }).unwrap_or_else(|e| panic!(e));
+
+    let upstream_modules = thin_modules[num_local_modules..].iter()
+        .map(|module| module.name().to_string())
+        .collect();
+    let work_items = thin_modules.into_iter().map(|module| {
+        build_work_item(sess,
+                        WorkItemKind::ThinLto(module),
+                        modules_config.clone(),
+                        crate_output.clone())
+    }).collect();
+    (work_items, upstream_modules)
+}
+
+fn linked_rlibs_for_lto(sess: &Session) -> Vec<(CrateNum, PathBuf)> {
+    let mut each_linked_rlib_for_lto = Vec::new();
+    drop(link::each_linked_rlib(sess, &mut |cnum, path| {
+        if link::ignored_for_lto(sess, cnum) {
+            return
+        }
+        each_linked_rlib_for_lto.push((cnum, path.to_path_buf()));
+    }));
+    each_linked_rlib_for_lto
 }
 
 struct SharedEmitter {
@@ -1216,13 +1435,7 @@ fn spawn_work<'a>(sess: &'a Session,
     let depth = time_depth();
     let lto = sess.lto();
     let crate_types = sess.crate_types.borrow().clone();
-    let mut each_linked_rlib_for_lto = Vec::new();
-    drop(link::each_linked_rlib(sess, &mut |cnum, path| {
-        if link::ignored_for_lto(sess, cnum) {
-            return
-        }
-        each_linked_rlib_for_lto.push((cnum, path.to_path_buf()));
-    }));
+    let each_linked_rlib_for_lto = linked_rlibs_for_lto(sess);
     let time_passes = sess.time_passes();
     let no_landing_pads = sess.no_landing_pads();
     let opts = &sess.opts;
@@ -1329,10 +1542,23 @@ pub unsafe fn with_llvm_pmb(llmod: ModuleRef,
     let opt_size = config.opt_size.unwrap_or(llvm::CodeGenOptSizeNone);
     let inline_threshold = config.inline_threshold;
 
+    let pgo_gen_path = config.pgo_gen.as_ref().map(|s| {
+        let s = if s.is_empty() { "default_%m.profraw" } else { s };
+        CString::new(s.as_bytes()).unwrap()
+    });
+
+    let pgo_use_path = config.pgo_use.as_ref().map(|s| {
+        CString::new(s.as_bytes()).unwrap()
+    });
+
     llvm::LLVMRustConfigurePassManagerBuilder(builder, opt_level,
                                               config.merge_functions,
                                               config.vectorize_slp,
-                                              config.vectorize_loop);
+                                              config.vectorize_loop,
+                                              pgo_gen_path.as_ref()
+                                                          .map_or(ptr::null(), |s| s.as_ptr()),
+                                              pgo_use_path.as_ref()
+                                                          .map_or(ptr::null(), |s| s.as_ptr()));
     llvm::LLVMPassManagerBuilderSetSizeLevel(builder, opt_size as u32);
 
     if opt_size != llvm::CodeGenOptSizeNone {
@@ -75,6 +75,7 @@ use value::Value;
 use rustc::util::nodemap::{NodeSet, FxHashMap, FxHashSet};
 
 use libc::c_uint;
+use std::cell::RefCell;
 use std::ffi::{CStr, CString};
 use std::str;
 use std::sync::Arc;
@@ -972,6 +973,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             no_builtins: no_builtins,
             linker_info: linker_info,
             windows_subsystem: None,
+            thin_lto_upstream_modules: RefCell::new(vec![]),
         };
     }
 
@@ -1222,6 +1224,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         no_builtins: no_builtins,
         linker_info: linker_info,
         windows_subsystem: windows_subsystem,
+        thin_lto_upstream_modules: RefCell::new(vec![]),
     }
 }
 
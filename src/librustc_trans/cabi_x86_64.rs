@@ -116,7 +116,8 @@ fn classify_arg<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>, arg: &ArgType<'tcx>)
 
             Layout::FatPointer { .. } |
             Layout::General { .. } |
-            Layout::StructWrappedNullablePointer { .. } => return Err(Memory)
+            Layout::StructWrappedNullablePointer { .. } |
+            Layout::NicheFilling { .. } => return Err(Memory)
         }
 
         Ok(())
@@ -62,7 +62,8 @@ pub fn type_is_immediate<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>, ty: Ty<'tcx>) -
         Layout::General { .. } |
         Layout::UntaggedUnion { .. } |
         Layout::RawNullablePointer { .. } |
-        Layout::StructWrappedNullablePointer { .. } => {
+        Layout::StructWrappedNullablePointer { .. } |
+        Layout::NicheFilling { .. } => {
             !layout.is_unsized() && layout.size(ccx).bytes() == 0
         }
     }
@@ -1331,6 +1331,36 @@ impl<'tcx> EnumMemberDescriptionFactory<'tcx> {
                     }
                 ]
             },
+            layout::NicheFilling { ref variant, dataful, .. } => {
+                // There is no encoding debuggers understand for the values
+                // of the other variants, so only describe the `dataful` one.
+                let (variant_type_metadata, variant_llvm_type, member_description_factory) =
+                    describe_enum_variant(cx,
+                                          self.enum_type,
+                                          variant,
+                                          &adt.variants[dataful as usize],
+                                          OptimizedDiscriminant,
+                                          self.containing_scope,
+                                          self.span);
+
+                let variant_member_descriptions =
+                    member_description_factory.create_member_descriptions(cx);
+
+                set_members_of_composite_type(cx,
+                                              variant_type_metadata,
+                                              variant_llvm_type,
+                                              &variant_member_descriptions[..]);
+
+                vec![
+                    MemberDescription {
+                        name: "".to_string(),
+                        llvm_type: variant_llvm_type,
+                        type_metadata: variant_type_metadata,
+                        offset: FixedMemberOffset { bytes: 0 },
+                        flags: DIFlags::FlagZero
+                    }
+                ]
+            },
             layout::CEnum { .. } => span_bug!(self.span, "This should be unreachable."),
             ref l @ _ => bug!("Not an enum layout: {:#?}", l)
         }
@@ -1393,7 +1423,8 @@ fn describe_enum_variant<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         layout::General{discr, ..} => Some((discr, false)),
         layout::Univariant { .. }
         | layout::RawNullablePointer { .. }
-        | layout::StructWrappedNullablePointer { .. } => None,
+        | layout::StructWrappedNullablePointer { .. }
+        | layout::NicheFilling { .. } => None,
         ref l @ _ => bug!("This should be unreachable. Type is {:#?} layout is {:#?}", enum_type, l)
     };
 
@@ -1556,6 +1587,7 @@ fn prepare_enum_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         },
         layout::RawNullablePointer { .. }           |
         layout::StructWrappedNullablePointer { .. } |
+        layout::NicheFilling { .. }                 |
         layout::Univariant { .. }                      => None,
         layout::General { discr, .. } => Some(discriminant_type_metadata(discr, false)),
         ref l @ _ => bug!("Not an enum layout: {:#?}", l)
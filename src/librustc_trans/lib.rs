@@ -37,6 +37,8 @@
 use rustc::dep_graph::WorkProduct;
 use syntax_pos::symbol::Symbol;
 
+use std::cell::RefCell;
+
 extern crate flate2;
 extern crate crossbeam;
 extern crate libc;
@@ -170,7 +172,10 @@ pub struct CrateTranslation {
     pub exported_symbols: back::symbol_export::ExportedSymbols,
     pub no_builtins: bool,
     pub windows_subsystem: Option<String>,
-    pub linker_info: back::linker::LinkerInfo
+    pub linker_info: back::linker::LinkerInfo,
+    /// Modules of upstream crates which ThinLTO generated code for, whose
+    /// objects are linked in along with those of `modules`.
+    pub thin_lto_upstream_modules: RefCell<Vec<String>>,
 }
 
 __build_diagnostic_array! { librustc_trans, DIAGNOSTICS }
@@ -1071,10 +1071,56 @@ fn trans_const<'a, 'tcx>(
                 C_null(type_of::type_of(ccx, t))
             }
         }
+        layout::NicheFilling { ref variant, dataful, niche_variants: (min, _), niche_start,
+                               ref niche_field, .. } => {
+            if variant_index as u64 == dataful {
+                C_struct(ccx, &build_const_struct(ccx, &variant, vals), false)
+            } else {
+                // Only the niche field is defined, it holds the value encoding
+                // this variant, like in `adt::trans_set_discr`.
+                let llty = type_of::type_of(ccx, t);
+                let path = adt::struct_llfields_path(niche_field);
+                let niche_value = (variant_index as u64 - min).wrapping_add(niche_start);
+                const_with_niche(ccx, llty, &path[1..], niche_value)
+            }
+        }
         _ => bug!("trans_const: cannot handle type {} repreented as {:#?}", t, l)
     }
 }
 
+/// Builds an undefined constant of type `llty`, except for the integer at
+/// `path` (as for `gepi`, without the leading 0), which is set to `value`.
+fn const_with_niche(ccx: &CrateContext, llty: Type, path: &[usize], value: u64) -> ValueRef {
+    let (&i, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return C_integral(llty, value, false),
+    };
+    match llty.kind() {
+        llvm::TypeKind::Struct => {
+            let elts = llty.field_types().iter().enumerate().map(|(j, &field_llty)| {
+                if j == i {
+                    const_with_niche(ccx, field_llty, rest, value)
+                } else {
+                    C_undef(field_llty)
+                }
+            }).collect::<Vec<_>>();
+            C_struct(ccx, &elts, llty.is_packed())
+        }
+        llvm::TypeKind::Array => {
+            let elt_llty = llty.element_type();
+            let elts = (0..llty.array_length()).map(|j| {
+                if j == i {
+                    const_with_niche(ccx, elt_llty, rest, value)
+                } else {
+                    C_undef(elt_llty)
+                }
+            }).collect::<Vec<_>>();
+            C_array(elt_llty, &elts)
+        }
+        kind => bug!("const_with_niche: unexpected {:?} in niche field path", kind)
+    }
+}
+
 /// Building structs is a little complicated, because we might need to
 /// insert padding if a field's value is less aligned than its type.
 ///
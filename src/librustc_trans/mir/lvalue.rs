@@ -267,6 +267,17 @@ impl<'a, 'tcx> LvalueRef<'tcx> {
                 self.struct_field_ptr(bcx, &nonnull,
                      &adt::compute_fields(bcx.ccx, t, discr, false), ix, false)
             }
+            layout::NicheFilling { ref variant, dataful, .. } => {
+                let fields = adt::compute_fields(bcx.ccx, t, discr, false);
+                if discr as u64 == dataful {
+                    self.struct_field_ptr(bcx, &variant, &fields, ix, false)
+                } else {
+                    // The other variants only have zero-sized fields.
+                    let ty = type_of::type_of(bcx.ccx, fields[ix]);
+                    assert_eq!(machine::llsize_of_alloc(bcx.ccx, ty), 0);
+                    (bcx.pointercast(self.llval, ty.ptr_to()), Alignment::Packed)
+                }
+            }
             _ => bug!("element access in type without elements: {} represented as {:#?}", t, l)
         }
     }
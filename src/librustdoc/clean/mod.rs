@@ -25,8 +25,9 @@ use syntax::attr;
 use syntax::codemap::Spanned;
 use syntax::ptr::P;
 use syntax::symbol::keywords;
-use syntax_pos::{self, DUMMY_SP, Pos};
+use syntax_pos::{self, BytePos, DUMMY_SP, Pos};
 
+use rustc::lint::{self, Lint};
 use rustc::middle::privacy::AccessLevels;
 use rustc::middle::resolve_lifetime as rl;
 use rustc::middle::lang_items;
@@ -50,6 +51,8 @@ use core::DocContext;
 use doctree;
 use visit_ast;
 use html::item_type::ItemType;
+use html::format::href;
+use html::markdown::markdown_links;
 
 pub mod inline;
 mod simplify;
@@ -116,7 +119,7 @@ pub struct Crate {
     pub external_traits: FxHashMap<DefId, Trait>,
 }
 
-impl<'a, 'tcx> Clean<Crate> for visit_ast::RustdocVisitor<'a, 'tcx> {
+impl<'a, 'tcx, 'rcx> Clean<Crate> for visit_ast::RustdocVisitor<'a, 'tcx, 'rcx> {
     fn clean(&self, cx: &DocContext) -> Crate {
         use ::visit_lib::LibEmbargoVisitor;
 
@@ -277,6 +280,11 @@ impl Item {
     pub fn doc_value<'a>(&'a self) -> Option<&'a str> {
         self.attrs.doc_value()
     }
+    /// Gets the resolved links in the documentation, relative to the page
+    /// currently being rendered.
+    pub fn links(&self) -> Vec<(String, String)> {
+        self.attrs.links()
+    }
     pub fn is_crate(&self) -> bool {
         match self.inner {
             StrippedItem(box ModuleItem(Module { is_crate: true, ..})) |
@@ -429,6 +437,19 @@ impl Clean<Item> for doctree::Module {
             "".to_string()
         };
 
+        // Links in outer documentation are resolved in the parent module,
+        // while inner documentation (`//!`) is written from within the module.
+        let inner_doc = self.attrs.iter().any(|attr| {
+            attr.check_name("doc") && attr.style == ast::AttrStyle::Inner
+        });
+        if inner_doc {
+            cx.mod_ids.borrow_mut().push(self.id);
+        }
+        let attrs = self.attrs.clean(cx);
+        if !inner_doc {
+            cx.mod_ids.borrow_mut().push(self.id);
+        }
+
         let mut items: Vec<Item> = vec![];
         items.extend(self.extern_crates.iter().map(|x| x.clean(cx)));
         items.extend(self.imports.iter().flat_map(|x| x.clean(cx)));
@@ -445,6 +466,7 @@ impl Clean<Item> for doctree::Module {
         items.extend(self.impls.iter().flat_map(|x| x.clean(cx)));
         items.extend(self.macros.iter().map(|x| x.clean(cx)));
         items.extend(self.def_traits.iter().map(|x| x.clean(cx)));
+        cx.mod_ids.borrow_mut().pop();
 
         // determine if we should display the inner contents or
         // the outer `mod` item for the source code.
@@ -463,7 +485,7 @@ impl Clean<Item> for doctree::Module {
 
         Item {
             name: Some(name),
-            attrs: self.attrs.clean(cx),
+            attrs: attrs,
             source: whence.clean(cx),
             visibility: self.vis.clean(cx),
             stability: self.stab.clean(cx),
@@ -537,6 +559,10 @@ pub struct Attributes {
     pub doc_strings: Vec<String>,
     pub other_attrs: Vec<ast::Attribute>,
     pub span: Option<syntax_pos::Span>,
+    /// Paths used as link targets in the documentation, along with the item
+    /// each one resolved to and the URL fragment of the associated item,
+    /// variant or field it names, if any.
+    pub links: Vec<(String, DefId, Option<String>)>,
 }
 
 impl Attributes {
@@ -562,6 +588,7 @@ impl Attributes {
             doc_strings: doc_strings,
             other_attrs: other_attrs,
             span: sp,
+            links: vec![],
         }
     }
 
@@ -570,6 +597,25 @@ impl Attributes {
     pub fn doc_value<'a>(&'a self) -> Option<&'a str> {
         self.doc_strings.first().map(|s| &s[..])
     }
+
+    /// Gets the links in the documentation which resolved to an item with a
+    /// page, as pairs of the link as written and the URL it points to.
+    ///
+    /// This has to be called while rendering, as the URLs are relative to the
+    /// page currently being rendered.
+    pub fn links(&self) -> Vec<(String, String)> {
+        self.links.iter().filter_map(|&(ref link, did, ref fragment)| {
+            if let Some((mut href, ..)) = href(did) {
+                if let Some(ref fragment) = *fragment {
+                    href.push_str("#");
+                    href.push_str(fragment);
+                }
+                Some((link.clone(), href))
+            } else {
+                None
+            }
+        }).collect()
+    }
 }
 
 impl AttributesExt for Attributes {
@@ -579,9 +625,230 @@ impl AttributesExt for Attributes {
 }
 
 impl Clean<Attributes> for [ast::Attribute] {
-    fn clean(&self, _cx: &DocContext) -> Attributes {
-        Attributes::from_ast(self)
+    fn clean(&self, cx: &DocContext) -> Attributes {
+        let mut attrs = Attributes::from_ast(self);
+
+        // Links in the documentation of other crates were written against
+        // their own scopes, which can't be resolved here.
+        let span = match attrs.span {
+            Some(span) if !cx.sess().codemap().lookup_char_pos(span.lo).file.is_imported() => {
+                span
+            }
+            _ => return attrs,
+        };
+
+        let dox = attrs.doc_strings.join("\n");
+        for link in markdown_links(&dox) {
+            match resolve_link(cx, &link) {
+                Ok(Some((did, fragment))) => attrs.links.push((link, did, fragment)),
+                Ok(None) => {}
+                Err(()) => report_unresolved_link(cx, self, &link, span),
+            }
+        }
+        attrs
+    }
+}
+
+/// Reports a link in the documentation that can't be resolved, as the
+/// `intra_doc_link_resolution_failure` lint.
+fn report_unresolved_link(cx: &DocContext,
+                          attrs: &[ast::Attribute],
+                          link: &str,
+                          attrs_span: syntax_pos::Span) {
+    let lint = lint::builtin::INTRA_DOC_LINK_RESOLUTION_FAILURE;
+    let span = link_span(cx, attrs, link).unwrap_or(attrs_span);
+    let msg = format!("unresolved link to `{}`", link.trim_matches('`'));
+    lint::raw_emit_lint(cx.sess(), &cx.sess().lint_store.borrow(), lint,
+                        lint_level(cx, lint), Some(span), &msg);
+}
+
+/// The span of `link` in the source of the `doc` attributes, if it is
+/// written there as is.
+fn link_span(cx: &DocContext,
+             attrs: &[ast::Attribute],
+             link: &str)
+             -> Option<syntax_pos::Span> {
+    let codemap = cx.sess().codemap();
+    let patterns = [format!("[{}]", link), format!("({})", link)];
+    attrs.iter().filter(|attr| attr.check_name("doc")).filter_map(|attr| {
+        let snippet = match codemap.span_to_snippet(attr.span) {
+            Ok(snippet) => snippet,
+            Err(_) => return None,
+        };
+        patterns.iter().filter_map(|pattern| snippet.find(&pattern[..])).next().map(|offset| {
+            let lo = attr.span.lo + BytePos(offset as u32 + 1);
+            syntax_pos::Span { lo: lo, hi: lo + BytePos(link.len() as u32), ctxt: attr.span.ctxt }
+        })
+    }).next()
+}
+
+/// The level of `lint` in the module currently being cleaned. rustdoc doesn't
+/// run the lint passes, so the lint attributes of the enclosing modules are
+/// looked at here.
+fn lint_level(cx: &DocContext, lint: &'static Lint) -> lint::LevelSource {
+    let level = attr_lint_level(cx, lint).unwrap_or((lint.default_level, lint::Default));
+    // As in the lint passes, the level of `warnings` applies to every lint
+    // that would otherwise warn.
+    if level.0 == lint::Warn {
+        if let Some(warnings) = attr_lint_level(cx, lint::builtin::WARNINGS) {
+            return warnings;
+        }
+    }
+    level
+}
+
+/// The level set for `lint` by the lint attributes of the enclosing modules,
+/// either by its name or by a group it belongs to.
+fn attr_lint_level(cx: &DocContext, lint: &'static Lint) -> Option<lint::LevelSource> {
+    let lint_id = lint::LintId::of(lint);
+    let name = lint.name_lower();
+    let groups = cx.sess().lint_store.borrow().get_lint_groups();
+    let names_lint = |attr_name: &str| {
+        attr_name == name || groups.iter().any(|&(group, ref lints, _)| {
+            group == attr_name && lints.contains(&lint_id)
+        })
+    };
+
+    // Go from the crate root inwards, so that inner attributes override outer
+    // ones, except for lowering a `forbid`.
+    let mut level = None;
+    for &id in cx.mod_ids.borrow().iter() {
+        let attrs = if id == ast::CRATE_NODE_ID {
+            cx.tcx.hir.krate_attrs()
+        } else {
+            cx.tcx.hir.attrs(id)
+        };
+        let attr_levels = lint::gather_attrs(attrs).into_iter().filter_map(|attr| attr.ok());
+        for (attr_name, attr_level, span) in attr_levels {
+            if !names_lint(&attr_name.as_str()) {
+                continue;
+            }
+            if let Some((lint::Forbid, _)) = level {
+                if attr_level != lint::Forbid {
+                    continue;
+                }
+            }
+            level = Some((attr_level, lint::Node(attr_name, span)));
+        }
+    }
+    level
+}
+
+/// Resolves a path used as a link target in documentation, in the scope of the
+/// module currently being cleaned.
+///
+/// The path may be wrapped in backticks, end in `()` to only look for values,
+/// and start with `crate::` to be resolved from the crate root. Returns
+/// `Ok(None)` if the path names something that doesn't have a page, like a
+/// primitive type.
+fn resolve_link(cx: &DocContext, link: &str) -> Result<Option<(DefId, Option<String>)>, ()> {
+    let mut path_str = link.trim_matches('`');
+    let is_value = path_str.ends_with("()");
+    if is_value {
+        path_str = &path_str[..path_str.len() - 2];
+    }
+    let path_string;
+    if path_str.starts_with("crate::") {
+        path_string = format!("::{}", &path_str["crate::".len()..]);
+        path_str = &path_string;
+    }
+
+    if !is_value {
+        if let Ok(def) = resolve_path_str(cx, path_str, false) {
+            return Ok(link_target(cx, def));
+        }
+    }
+    if let Ok(def) = resolve_path_str(cx, path_str, true) {
+        return Ok(link_target(cx, def));
     }
+
+    // The resolver doesn't know about inherent associated items or fields, so
+    // look those up on the type named by the rest of the path.
+    let (ty_path, name) = match path_str.rfind("::") {
+        Some(idx) => (&path_str[..idx], &path_str[idx + 2..]),
+        None => return Err(()),
+    };
+    let ty_def = resolve_path_str(cx, ty_path, false)?;
+    match ty_def {
+        Def::Struct(did) | Def::Union(did) | Def::Enum(did) => {
+            let item = cx.tcx.inherent_impls(did).iter().filter_map(|&impl_did| {
+                cx.tcx.associated_items(impl_did).find(|item| &*item.name.as_str() == name)
+            }).next();
+            if let Some(item) = item {
+                return Ok(Some((register_def(cx, ty_def),
+                                Some(associated_item_fragment(&item)))));
+            }
+            let adt = cx.tcx.adt_def(did);
+            if !adt.is_enum() {
+                let field = adt.struct_variant().fields.iter().find(|field| {
+                    &*field.name.as_str() == name
+                });
+                if field.is_some() {
+                    return Ok(Some((register_def(cx, ty_def),
+                                    Some(format!("{}.{}", ItemType::StructField, name)))));
+                }
+            }
+            Err(())
+        }
+        Def::Trait(did) => {
+            match cx.tcx.associated_items(did).find(|item| &*item.name.as_str() == name) {
+                Some(item) => Ok(Some((register_def(cx, Def::Trait(did)),
+                                       Some(associated_item_fragment(&item))))),
+                None => Err(()),
+            }
+        }
+        _ => Err(()),
+    }
+}
+
+fn resolve_path_str(cx: &DocContext, path_str: &str, is_value: bool) -> Result<Def, ()> {
+    let module_id = cx.mod_ids.borrow().last().cloned().unwrap_or(ast::CRATE_NODE_ID);
+    cx.resolver.borrow_mut().with_scope(module_id, |resolver| {
+        resolver.resolve_str_path(DUMMY_SP, path_str, is_value)
+    })
+}
+
+/// Finds the item whose page a resolved link points to, and the URL fragment of
+/// the associated item or variant on that page it names.
+fn link_target(cx: &DocContext, def: Def) -> Option<(DefId, Option<String>)> {
+    match def {
+        Def::Mod(..) | Def::Struct(..) | Def::Union(..) | Def::Enum(..) |
+        Def::Trait(..) | Def::TyAlias(..) | Def::Fn(..) | Def::Static(..) |
+        Def::Const(..) | Def::Macro(..) => Some((register_def(cx, def), None)),
+        Def::StructCtor(did, _) => {
+            let struct_did = cx.tcx.parent_def_id(did).unwrap();
+            Some((register_def(cx, Def::Struct(struct_did)), None))
+        }
+        Def::Variant(did) | Def::VariantCtor(did, _) => {
+            let fragment = format!("{}.{}", ItemType::Variant, cx.tcx.item_name(did));
+            Some((register_def(cx, Def::Variant(did)), Some(fragment)))
+        }
+        Def::Method(did) | Def::AssociatedConst(did) | Def::AssociatedTy(did) => {
+            let item = cx.tcx.associated_item(did);
+            match item.container {
+                ty::TraitContainer(trait_did) => {
+                    Some((register_def(cx, Def::Trait(trait_did)),
+                          Some(associated_item_fragment(&item))))
+                }
+                ty::ImplContainer(_) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn associated_item_fragment(item: &ty::AssociatedItem) -> String {
+    let item_type = match item.kind {
+        ty::AssociatedKind::Method => {
+            match item.container {
+                ty::TraitContainer(_) if !item.defaultness.has_value() => ItemType::TyMethod,
+                _ => ItemType::Method,
+            }
+        }
+        ty::AssociatedKind::Const => ItemType::AssociatedConst,
+        ty::AssociatedKind::Type => ItemType::AssociatedType,
+    };
+    format!("{}.{}", item_type, item.name)
 }
 
 #[derive(Clone, RustcEncodable, RustcDecodable, PartialEq, Debug)]
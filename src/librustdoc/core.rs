@@ -18,10 +18,11 @@ use rustc::middle::privacy::AccessLevels;
 use rustc::ty::{self, TyCtxt, GlobalArenas};
 use rustc::hir::map as hir_map;
 use rustc::lint;
-use rustc::util::nodemap::FxHashMap;
+use rustc::util::nodemap::{FxHashMap, NodeSet};
 use rustc_trans;
 use rustc_trans::back::link;
 use rustc_resolve as resolve;
+use rustc_metadata::creader::CrateLoader;
 use rustc_metadata::cstore::CStore;
 
 use syntax::{ast, codemap};
@@ -38,6 +39,7 @@ use visit_ast::RustdocVisitor;
 use clean;
 use clean::Clean;
 use html::render::RenderInfo;
+use scrape_examples;
 use arena::DroplessArena;
 
 pub use rustc::session::config::Input;
@@ -45,8 +47,13 @@ pub use rustc::session::search_paths::SearchPaths;
 
 pub type ExternalPaths = FxHashMap<DefId, (Vec<String>, clean::TypeKind)>;
 
-pub struct DocContext<'a, 'tcx: 'a> {
+pub struct DocContext<'a, 'tcx: 'a, 'rcx: 'a> {
     pub tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    /// The name resolver, kept around to resolve the paths used as link
+    /// targets in documentation.
+    pub resolver: &'a RefCell<resolve::Resolver<'rcx>>,
+    /// The stack of modules being cleaned, whose scopes links are resolved in
+    pub mod_ids: RefCell<Vec<ast::NodeId>>,
     pub populated_all_crate_impls: Cell<bool>,
     // Note that external items for which `doc(hidden)` applies to are shown as
     // non-reachable while local items aren't. This is because we're reusing
@@ -67,7 +74,7 @@ pub struct DocContext<'a, 'tcx: 'a> {
     pub lt_substs: RefCell<FxHashMap<ast::NodeId, clean::Lifetime>>,
 }
 
-impl<'a, 'tcx> DocContext<'a, 'tcx> {
+impl<'a, 'tcx, 'rcx> DocContext<'a, 'tcx, 'rcx> {
     pub fn sess(&self) -> &session::Session {
         &self.tcx.sess
     }
@@ -107,7 +114,8 @@ pub fn run_core(search_paths: SearchPaths,
                 triple: Option<String>,
                 maybe_sysroot: Option<PathBuf>,
                 allow_warnings: bool,
-                force_unstable_if_unmarked: bool) -> (clean::Crate, RenderInfo)
+                force_unstable_if_unmarked: bool,
+                scrape_examples: scrape_examples::Options) -> (clean::Crate, RenderInfo)
 {
     // Parse, resolve, and typecheck the given crate.
 
@@ -129,6 +137,8 @@ pub fn run_core(search_paths: SearchPaths,
         // Ensure that rustdoc works even if rustc is feature-staged
         unstable_features: UnstableFeatures::Allow,
         actually_rustdoc: true,
+        // Tests are kept when scraping, so that their calls are found too.
+        test: scrape_examples.output_path.is_some(),
         debugging_opts: config::DebuggingOptions {
             force_unstable_if_unmarked: force_unstable_if_unmarked,
             ..config::basic_debugging_options()
@@ -159,18 +169,39 @@ pub fn run_core(search_paths: SearchPaths,
 
     let name = link::find_crate_name(Some(&sess), &krate.attrs, &input);
 
-    let driver::ExpansionResult { defs, analysis, resolutions, mut hir_forest, .. } = {
-        let result = driver::phase_2_configure_and_expand(&sess,
-                                                          &cstore,
-                                                          krate,
-                                                          None,
-                                                          &name,
-                                                          None,
-                                                          resolve::MakeGlobMap::No,
-                                                          |_| Ok(()));
+    let mut crate_loader = CrateLoader::new(&sess, &cstore, &name);
+    let resolver_arenas = resolve::Resolver::arenas();
+    let driver::InnerExpansionResult { mut hir_forest, resolver, .. } = {
+        let result = driver::phase_2_configure_and_expand_inner(&sess,
+                                                                &cstore,
+                                                                krate,
+                                                                None,
+                                                                &name,
+                                                                None,
+                                                                resolve::MakeGlobMap::No,
+                                                                &resolver_arenas,
+                                                                &mut crate_loader,
+                                                                |_| Ok(()));
         abort_on_err(result, &sess)
     };
 
+    // The resolver is kept alive for resolving links in documentation, so
+    // the analysis passes get copies of what they need from it.
+    let defs = resolver.definitions.clone();
+    let resolutions = ty::Resolutions {
+        freevars: resolver.freevars.clone(),
+        export_map: resolver.export_map.clone(),
+        trait_map: resolver.trait_map.clone(),
+        maybe_unused_trait_imports: resolver.maybe_unused_trait_imports.clone(),
+    };
+    let analysis = ty::CrateAnalysis {
+        access_levels: Rc::new(AccessLevels::default()),
+        reachable: Rc::new(NodeSet()),
+        name: name.to_string(),
+        glob_map: None,
+    };
+    let resolver = RefCell::new(resolver);
+
     let arena = DroplessArena::new();
     let arenas = GlobalArenas::new();
     let hir_map = hir_map::map_crate(&mut hir_forest, defs);
@@ -197,12 +228,26 @@ pub fn run_core(search_paths: SearchPaths,
                                   .collect()
         };
 
+        if let Some(ref output_path) = scrape_examples.output_path {
+            if let Err(e) = scrape_examples::scrape(tcx, &scrape_examples.target_crates,
+                                                    output_path) {
+                sess.fatal(&e);
+            }
+        }
+        let mut renderinfo = RenderInfo::default();
+        match scrape_examples::load(tcx, &scrape_examples.with_examples) {
+            Ok(scraped) => renderinfo.scraped_examples = scraped,
+            Err(e) => sess.fatal(&e),
+        }
+
         let ctxt = DocContext {
             tcx: tcx,
+            resolver: &resolver,
+            mod_ids: Default::default(),
             populated_all_crate_impls: Cell::new(false),
             access_levels: RefCell::new(access_levels),
             external_traits: Default::default(),
-            renderinfo: Default::default(),
+            renderinfo: RefCell::new(renderinfo),
             ty_substs: Default::default(),
             lt_substs: Default::default(),
         };
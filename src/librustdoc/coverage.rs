@@ -0,0 +1,160 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Documentation coverage reports, for `--show-coverage`.
+//!
+//! This walks the crate as left by the passes (so `strip-hidden` and
+//! `strip-private` have already removed what isn't public API) and counts the
+//! documented items and the items with code examples, both per module and per
+//! kind of item.
+
+use std::cmp;
+use std::collections::BTreeMap;
+
+use serialize::json::{Json, ToJson};
+
+use clean::{self, Item};
+use fold::DocFolder;
+use html::markdown::has_rust_code_blocks;
+
+#[derive(Default, Clone, Copy)]
+struct ItemCount {
+    total: u64,
+    with_docs: u64,
+    with_examples: u64,
+}
+
+impl ItemCount {
+    fn count_item(&mut self, has_docs: bool, has_examples: bool) {
+        self.total += 1;
+        if has_docs {
+            self.with_docs += 1;
+        }
+        if has_examples {
+            self.with_examples += 1;
+        }
+    }
+
+    fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            (self.with_docs as f64 * 100.0) / self.total as f64
+        }
+    }
+}
+
+impl ToJson for ItemCount {
+    fn to_json(&self) -> Json {
+        let mut json = BTreeMap::new();
+        json.insert("total".to_string(), self.total.to_json());
+        json.insert("documented".to_string(), self.with_docs.to_json());
+        json.insert("undocumented".to_string(), (self.total - self.with_docs).to_json());
+        json.insert("with_examples".to_string(), self.with_examples.to_json());
+        Json::Object(json)
+    }
+}
+
+#[derive(Default)]
+struct CoverageCalculator {
+    /// The path of the module currently being walked.
+    module_path: Vec<String>,
+    modules: BTreeMap<String, ItemCount>,
+    kinds: BTreeMap<String, ItemCount>,
+    total: ItemCount,
+}
+
+impl CoverageCalculator {
+    fn count(&mut self, item: &Item) {
+        let (has_docs, has_examples) = match item.doc_value() {
+            Some(doc) if !doc.trim().is_empty() => (true, has_rust_code_blocks(doc)),
+            _ => (false, false),
+        };
+        let module = self.module_path.join("::");
+        self.modules.entry(module).or_insert_with(ItemCount::default)
+            .count_item(has_docs, has_examples);
+        self.kinds.entry(item.type_().to_string()).or_insert_with(ItemCount::default)
+            .count_item(has_docs, has_examples);
+        self.total.count_item(has_docs, has_examples);
+    }
+}
+
+impl DocFolder for CoverageCalculator {
+    fn fold_item(&mut self, i: Item) -> Option<Item> {
+        match i.inner {
+            // Stripped items aren't part of the public API, and the items of
+            // trait implementations inherit the trait's documentation.
+            clean::StrippedItem(..) |
+            clean::ImplItem(clean::Impl { trait_: Some(_), .. }) => return Some(i),
+            // There is nothing to document on these themselves.
+            clean::ExternCrateItem(..) |
+            clean::ImportItem(..) |
+            clean::ImplItem(..) |
+            clean::DefaultImplItem(..) => {}
+            // Fields of tuple structs aren't expected to be documented.
+            clean::StructFieldItem(..) if i.name.as_ref().map_or(false, |name| {
+                name.chars().all(|c| c.is_digit(10))
+            }) => {}
+            clean::ModuleItem(..) => {
+                self.module_path.push(i.name.clone().unwrap_or_default());
+                self.count(&i);
+                let ret = self.fold_item_recur(i);
+                self.module_path.pop();
+                return ret;
+            }
+            _ => self.count(&i),
+        }
+        self.fold_item_recur(i)
+    }
+}
+
+/// Prints the documentation coverage of `krate` to stdout, as tables or, if
+/// `json` is set, as a JSON object.
+pub fn run(krate: clean::Crate, json: bool) {
+    let mut calculator = CoverageCalculator::default();
+    if let Some(module) = krate.module {
+        calculator.fold_item(module);
+    }
+
+    if json {
+        let mut json = BTreeMap::new();
+        json.insert("total".to_string(), calculator.total.to_json());
+        json.insert("modules".to_string(), calculator.modules.to_json());
+        json.insert("kinds".to_string(), calculator.kinds.to_json());
+        println!("{}", Json::Object(json));
+    } else {
+        print_table("Module", &calculator.modules, calculator.total);
+        println!("");
+        print_table("Item kind", &calculator.kinds, calculator.total);
+    }
+}
+
+fn print_table(title: &str, counts: &BTreeMap<String, ItemCount>, total: ItemCount) {
+    let width = counts.keys().map(|k| k.len()).fold(cmp::max(title.len(), "Total".len()),
+                                                      cmp::max);
+    let separator = format!("+-{}-+------------+--------------+------------+------------+",
+                            "-".repeat(width));
+    let print_row = |name: &str, count: &ItemCount| {
+        println!("| {:<width$} | {:>10} | {:>12} | {:>10} | {:>9.1}% |",
+                 name, count.with_docs, count.total - count.with_docs, count.with_examples,
+                 count.percentage(), width = width);
+    };
+
+    println!("{}", separator);
+    println!("| {:<width$} | {:>10} | {:>12} | {:>10} | {:>10} |",
+             title, "Documented", "Undocumented", "Examples", "Percentage", width = width);
+    println!("{}", separator);
+    for (name, count) in counts {
+        print_row(name, count);
+    }
+    println!("{}", separator);
+    print_row("Total", &total);
+    println!("{}", separator);
+}
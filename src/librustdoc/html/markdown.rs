@@ -29,6 +29,7 @@ use libc;
 use std::slice;
 
 use std::ascii::AsciiExt;
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
 use std::default::Default;
@@ -56,15 +57,16 @@ pub enum RenderType {
 /// A unit struct which has the `fmt::Display` trait implemented. When
 /// formatted, this struct will emit the HTML corresponding to the rendered
 /// version of the contained markdown string.
-// The second parameter is whether we need a shorter version or not.
-pub struct Markdown<'a>(pub &'a str, pub RenderType);
+/// The second parameter is a list of link replacements, pairing the Rust
+/// paths used as link targets with the URLs they resolved to.
+pub struct Markdown<'a>(pub &'a str, pub &'a [(String, String)], pub RenderType);
 /// A unit struct like `Markdown`, that renders the markdown with a
 /// table of contents.
 pub struct MarkdownWithToc<'a>(pub &'a str, pub RenderType);
 /// A unit struct like `Markdown`, that renders the markdown escaping HTML tags.
 pub struct MarkdownHtml<'a>(pub &'a str, pub RenderType);
 /// A unit struct like `Markdown`, that renders only the first paragraph.
-pub struct MarkdownSummaryLine<'a>(pub &'a str);
+pub struct MarkdownSummaryLine<'a>(pub &'a str, pub &'a [(String, String)]);
 
 /// Controls whether a line will be hidden or shown in HTML output.
 ///
@@ -812,6 +814,15 @@ pub fn old_find_testable_code(doc: &str, tests: &mut ::test::Collector, position
     }
 }
 
+/// Returns whether `doc` contains any Rust code blocks, which are the code
+/// examples run as documentation tests.
+pub fn has_rust_code_blocks(doc: &str) -> bool {
+    Parser::new(doc).any(|event| match event {
+        Event::Start(Tag::CodeBlock(s)) => s.is_empty() || LangString::parse(&*s).rust,
+        _ => false,
+    })
+}
+
 pub fn find_testable_code(doc: &str, tests: &mut ::test::Collector, position: Span) {
     tests.set_position(position);
 
@@ -963,18 +974,19 @@ impl LangString {
 
 impl<'a> fmt::Display for Markdown<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let Markdown(md, render_type) = *self;
+        let Markdown(md, links, render_type) = *self;
 
         // This is actually common enough to special-case
         if md.is_empty() { return Ok(()) }
+        let md = rewrite_links(md, links);
         if render_type == RenderType::Hoedown {
-            render(fmt, md, false, 0)
+            render(fmt, &md, false, 0)
         } else {
             let mut opts = Options::empty();
             opts.insert(OPTION_ENABLE_TABLES);
             opts.insert(OPTION_ENABLE_FOOTNOTES);
 
-            let p = Parser::new_ext(md, opts);
+            let p = Parser::new_ext(&md, opts);
 
             let mut s = String::with_capacity(md.len() * 3 / 2);
 
@@ -1046,11 +1058,12 @@ impl<'a> fmt::Display for MarkdownHtml<'a> {
 
 impl<'a> fmt::Display for MarkdownSummaryLine<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let MarkdownSummaryLine(md) = *self;
+        let MarkdownSummaryLine(md, links) = *self;
         // This is actually common enough to special-case
         if md.is_empty() { return Ok(()) }
+        let md = rewrite_links(md, links);
 
-        let p = Parser::new(md);
+        let p = Parser::new(&md);
 
         let mut s = String::new();
 
@@ -1060,6 +1073,103 @@ impl<'a> fmt::Display for MarkdownSummaryLine<'a> {
     }
 }
 
+/// Points the links in `md` which were resolved as Rust paths at their URLs.
+///
+/// Inline links get their destination replaced, and a reference definition is
+/// appended for each link so that shortcut links like [`Vec::push`] resolve.
+/// Definitions already written in the documentation come first, so they win.
+fn rewrite_links<'a>(md: &'a str, links: &[(String, String)]) -> Cow<'a, str> {
+    if links.is_empty() {
+        return Cow::Borrowed(md);
+    }
+
+    let mut md = md.to_owned();
+    let mut definitions = String::from("\n\n");
+    for &(ref link, ref href) in links {
+        md = md.replace(&format!("]({})", link), &format!("]({})", href));
+        definitions.push_str(&format!("[{}]: {}\n", link, href));
+    }
+    md.push_str(&definitions);
+    Cow::Owned(md)
+}
+
+/// Collects the link targets in `md` which may be Rust paths.
+///
+/// These are the destinations of inline links like `[Foo](bar::Foo)`, and the
+/// labels of shortcut links which are written in backticks or contain `::`,
+/// like [`Vec::push`]. Code blocks and code spans are skipped.
+pub fn markdown_links(md: &str) -> Vec<String> {
+    fn is_path(s: &str) -> bool {
+        let s = s.trim_matches('`');
+        let s = if s.ends_with("()") { &s[..s.len() - 2] } else { s };
+        !s.is_empty() && !s.starts_with(|c: char| c.is_digit(10)) &&
+            s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':')
+    }
+
+    let mut links = vec![];
+    let mut in_code_block = false;
+    for line in md.lines() {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'`' => {
+                    // Skip over the code span opened by this run of backticks.
+                    let run = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                    let ticks = &line[i..i + run];
+                    i += run;
+                    if let Some(end) = line[i..].find(ticks) {
+                        i += end + run;
+                    }
+                }
+                b'[' if i == 0 || !((bytes[i - 1] as char).is_alphanumeric() ||
+                                    b"_]!\\".contains(&bytes[i - 1])) => {
+                    let end = match line[i + 1..].find(|c: char| c == '[' || c == ']') {
+                        Some(end) if bytes[i + 1 + end] == b']' => i + 1 + end,
+                        _ => {
+                            i += 1;
+                            continue;
+                        }
+                    };
+                    let label = &line[i + 1..end];
+                    i = end + 1;
+                    match bytes.get(i) {
+                        Some(&b'(') => {
+                            if let Some(close) = line[i..].find(')') {
+                                let dest = &line[i + 1..i + close];
+                                if is_path(dest) && !dest.contains('`') {
+                                    links.push(dest.to_owned());
+                                }
+                                i += close + 1;
+                            }
+                        }
+                        // Reference links and reference definitions.
+                        Some(&b'[') | Some(&b':') => {}
+                        _ => {
+                            let in_backticks = label.len() > 1 && label.starts_with('`') &&
+                                label.ends_with('`');
+                            if is_path(label) && (in_backticks || label.contains("::")) {
+                                links.push(label.to_owned());
+                            }
+                        }
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+    }
+    links
+}
+
 pub fn plain_summary_line(md: &str) -> String {
     struct ParserWrapper<'a> {
         inner: Parser<'a>,
@@ -1113,7 +1223,7 @@ pub fn plain_summary_line(md: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::{LangString, Markdown, MarkdownHtml};
-    use super::plain_summary_line;
+    use super::{markdown_links, plain_summary_line};
     use super::RenderType;
     use html::render::reset_ids;
 
@@ -1161,14 +1271,14 @@ mod tests {
     #[test]
     fn issue_17736() {
         let markdown = "# title";
-        format!("{}", Markdown(markdown, RenderType::Pulldown));
+        format!("{}", Markdown(markdown, &[], RenderType::Pulldown));
         reset_ids(true);
     }
 
     #[test]
     fn test_header() {
         fn t(input: &str, expect: &str) {
-            let output = format!("{}", Markdown(input, RenderType::Pulldown));
+            let output = format!("{}", Markdown(input, &[], RenderType::Pulldown));
             assert_eq!(output, expect, "original: {}", input);
             reset_ids(true);
         }
@@ -1190,7 +1300,7 @@ mod tests {
     #[test]
     fn test_header_ids_multiple_blocks() {
         fn t(input: &str, expect: &str) {
-            let output = format!("{}", Markdown(input, RenderType::Pulldown));
+            let output = format!("{}", Markdown(input, &[], RenderType::Pulldown));
             assert_eq!(output, expect, "original: {}", input);
         }
 
@@ -1228,6 +1338,23 @@ mod tests {
         t("## header", "header");
     }
 
+    #[test]
+    fn test_markdown_links() {
+        fn t(input: &str, expect: &[&str]) {
+            let output = markdown_links(input);
+            assert_eq!(output, expect, "original: {}", input);
+        }
+
+        t("see [`Vec::push`] and [`Vec`]", &["`Vec::push`", "`Vec`"]);
+        t("see [std::mem::swap] and [the docs](crate::foo::Bar)",
+          &["std::mem::swap", "crate::foo::Bar"]);
+        t("call [`foo()`] or [`Foo::new()`]", &["`foo()`", "`Foo::new()`"]);
+        t("not a path: [Rust](https://www.rust-lang.org), [link], [x][y]", &[]);
+        t("code: `[Foo::bar]` and a[`Bar`]", &[]);
+        t("[`Foo`]: https://example.com", &[]);
+        t("```\n[`Foo`]\n```\n[`Bar`]", &["`Bar`"]);
+    }
+
     #[test]
     fn test_markdown_html_escape() {
         fn t(input: &str, expect: &str) {
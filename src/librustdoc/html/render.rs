@@ -74,6 +74,7 @@ use html::format::fmt_impl_for_trait_page;
 use html::item_type::ItemType;
 use html::markdown::{self, Markdown, MarkdownHtml, MarkdownSummaryLine, RenderType};
 use html::{highlight, layout};
+use scrape_examples::CallData;
 
 /// A pair of name and its optional document.
 pub type NameDoc = (String, Option<String>);
@@ -253,6 +254,10 @@ pub struct Cache {
     // the access levels from crateanalysis.
     pub access_levels: Arc<AccessLevels<DefId>>,
 
+    /// Calls to local functions scraped from the crate's examples, shown on
+    /// the pages of the functions they call.
+    pub scraped_examples: FxHashMap<DefId, Vec<CallData>>,
+
     // Private fields only used when initially crawling a crate to build a cache
 
     stack: Vec<String>,
@@ -274,7 +279,7 @@ pub struct Cache {
 
 /// Temporary storage for data obtained during `RustdocVisitor::clean()`.
 /// Later on moved into `CACHE_KEY`.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct RenderInfo {
     pub inlined: FxHashSet<DefId>,
     pub external_paths: ::core::ExternalPaths,
@@ -282,6 +287,7 @@ pub struct RenderInfo {
     pub deref_trait_did: Option<DefId>,
     pub deref_mut_trait_did: Option<DefId>,
     pub owned_box_did: Option<DefId>,
+    pub scraped_examples: FxHashMap<DefId, Vec<CallData>>,
 }
 
 /// Helper struct to render all source code to HTML pages
@@ -493,7 +499,7 @@ pub fn run(mut krate: clean::Crate,
         }
     }
     try_err!(fs::create_dir_all(&dst), &dst);
-    krate = render_sources(&dst, &mut scx, krate)?;
+    krate = render_sources(&dst, &mut scx, krate, &renderinfo.scraped_examples)?;
     let cx = Context {
         current: Vec::new(),
         dst: dst,
@@ -502,7 +508,30 @@ pub fn run(mut krate: clean::Crate,
         render_type: render_type,
     };
 
-    // Crawl the crate to build various caches used for the output
+    let (krate, mut cache) = build_cache(krate, renderinfo, &cx.dst);
+
+    // Build our search index
+    let index = build_index(&krate, &mut cache);
+
+    // Freeze the cache now that the index has been built. Put an Arc into TLS
+    // for future parallelization opportunities
+    let cache = Arc::new(cache);
+    CACHE_KEY.with(|v| *v.borrow_mut() = cache.clone());
+    CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
+
+    write_shared(&cx, &krate, &*cache, index)?;
+
+    // And finally render the whole crate's documentation
+    cx.krate(krate)
+}
+
+/// Crawls the crate to build the various caches used for the output. External
+/// crates are looked up relative to the output directory `dst`.
+///
+/// Returns the crate with its implementations moved out into the cache.
+pub fn build_cache(mut krate: clean::Crate,
+                   renderinfo: RenderInfo,
+                   dst: &Path) -> (clean::Crate, Cache) {
     let RenderInfo {
         inlined: _,
         external_paths,
@@ -510,6 +539,7 @@ pub fn run(mut krate: clean::Crate,
         deref_trait_did,
         deref_mut_trait_did,
         owned_box_did,
+        scraped_examples,
     } = renderinfo;
 
     let external_paths = external_paths.into_iter()
@@ -535,6 +565,7 @@ pub fn run(mut krate: clean::Crate,
         deref_mut_trait_did: deref_mut_trait_did,
         owned_box_did: owned_box_did,
         typarams: external_typarams,
+        scraped_examples: scraped_examples,
     };
 
     // Cache where all our extern crates are located
@@ -544,7 +575,7 @@ pub fn run(mut krate: clean::Crate,
             None => PathBuf::new(),
         };
         cache.extern_locations.insert(n, (e.name.clone(), src_root,
-                                          extern_location(e, &cx.dst)));
+                                          extern_location(e, dst)));
 
         let did = DefId { krate: n, index: CRATE_DEF_INDEX };
         cache.external_paths.insert(did, (vec![e.name.to_string()], ItemType::Module));
@@ -565,20 +596,7 @@ pub fn run(mut krate: clean::Crate,
 
     cache.stack.push(krate.name.clone());
     krate = cache.fold_crate(krate);
-
-    // Build our search index
-    let index = build_index(&krate, &mut cache);
-
-    // Freeze the cache now that the index has been built. Put an Arc into TLS
-    // for future parallelization opportunities
-    let cache = Arc::new(cache);
-    CACHE_KEY.with(|v| *v.borrow_mut() = cache.clone());
-    CURRENT_LOCATION_KEY.with(|s| s.borrow_mut().clear());
-
-    write_shared(&cx, &krate, &*cache, index)?;
-
-    // And finally render the whole crate's documentation
-    cx.krate(krate)
+    (krate, cache)
 }
 
 /// Build the search index from the collected metadata
@@ -806,7 +824,9 @@ fn write_shared(cx: &Context,
 }
 
 fn render_sources(dst: &Path, scx: &mut SharedContext,
-                  krate: clean::Crate) -> Result<clean::Crate, Error> {
+                  krate: clean::Crate,
+                  scraped_examples: &FxHashMap<DefId, Vec<CallData>>)
+                  -> Result<clean::Crate, Error> {
     info!("emitting source files");
     let dst = dst.join("src").join(&krate.name);
     try_err!(fs::create_dir_all(&dst), &dst);
@@ -814,7 +834,20 @@ fn render_sources(dst: &Path, scx: &mut SharedContext,
         dst: dst,
         scx: scx,
     };
-    Ok(folder.fold_crate(krate))
+    let krate = folder.fold_crate(krate);
+
+    // The files examples were scraped from are rendered too, so that the
+    // examples can link to them. Those which can't be found were already
+    // warned about when the examples were loaded, and are shown without a link.
+    if folder.scx.include_sources {
+        for call in scraped_examples.values().flat_map(|calls| calls) {
+            let path = Path::new(&call.file);
+            if path.is_file() {
+                try_err!(folder.emit_source(&call.file), path);
+            }
+        }
+    }
+    Ok(krate)
 }
 
 /// Writes the entire contents of a string to a destination, not attempting to
@@ -1630,9 +1663,50 @@ fn document(w: &mut fmt::Formatter, cx: &Context, item: &clean::Item) -> fmt::Re
     document_stability(w, cx, item)?;
     let prefix = render_assoc_const_value(item);
     document_full(w, item, cx.render_type, &prefix)?;
+    document_scraped_examples(w, cx, item)?;
     Ok(())
 }
 
+/// Renders the calls to `item` found in the crate's examples, with links to
+/// their sources.
+fn document_scraped_examples(w: &mut fmt::Formatter, cx: &Context,
+                             item: &clean::Item) -> fmt::Result {
+    let cache = cache();
+    let calls = match cache.scraped_examples.get(&item.def_id) {
+        Some(calls) if !calls.is_empty() => calls,
+        _ => return Ok(()),
+    };
+    write!(w, "<div class='scraped-examples'>\
+               <h4>Examples found in repository</h4>")?;
+    for call in calls {
+        let lines = call.call_lines().iter().map(|&(lo, hi)| {
+            if lo == hi { lo.to_string() } else { format!("{}-{}", lo, hi) }
+        }).collect::<Vec<_>>().join(", ");
+        let label = if lines.contains(|c| c == ',' || c == '-') { "lines" } else { "line" };
+        let name = Escape(&call.file);
+        match cx.shared.local_sources.get(&PathBuf::from(&call.file)) {
+            Some(href) => {
+                write!(w, "<p><a href='{root}src/{krate}/{href}#{lo}-{hi}'>{name}</a> \
+                           ({label} {lines})</p>",
+                       root = cx.root_path(),
+                       krate = cx.shared.layout.krate,
+                       href = href,
+                       lo = call.line,
+                       hi = call.end_line(),
+                       name = name,
+                       label = label,
+                       lines = lines)?;
+            }
+            None => write!(w, "<p>{} ({} {})</p>", name, label, lines)?,
+        }
+        w.write_str(&highlight::render_with_highlighting(&call.excerpt,
+                                                         Some("rust scraped-example"),
+                                                         None,
+                                                         None))?;
+    }
+    write!(w, "</div>")
+}
+
 fn document_short(w: &mut fmt::Formatter, item: &clean::Item, link: AssocItemLink,
                   render_type: RenderType, prefix: &str) -> fmt::Result {
     if let Some(s) = item.doc_value() {
@@ -1642,7 +1716,8 @@ fn document_short(w: &mut fmt::Formatter, item: &clean::Item, link: AssocItemLin
         } else {
             format!("{}", &plain_summary_line(Some(s)))
         };
-        write!(w, "<div class='docblock'>{}{}</div>", prefix, Markdown(&markdown, render_type))?;
+        write!(w, "<div class='docblock'>{}{}</div>",
+               prefix, Markdown(&markdown, &item.links(), render_type))?;
     } else if !prefix.is_empty() {
         write!(w, "<div class='docblock'>{}</div>", prefix)?;
     }
@@ -1666,7 +1741,8 @@ fn render_assoc_const_value(item: &clean::Item) -> String {
 fn document_full(w: &mut fmt::Formatter, item: &clean::Item,
                  render_type: RenderType, prefix: &str) -> fmt::Result {
     if let Some(s) = item.doc_value() {
-        write!(w, "<div class='docblock'>{}{}</div>", prefix, Markdown(s, render_type))?;
+        write!(w, "<div class='docblock'>{}{}</div>",
+               prefix, Markdown(s, &item.links(), render_type))?;
     } else if !prefix.is_empty() {
         write!(w, "<div class='docblock'>{}</div>", prefix)?;
     }
@@ -1858,10 +1934,10 @@ fn item_module(w: &mut fmt::Formatter, cx: &Context,
                        stab_docs = stab_docs,
                        docs = if cx.render_type == RenderType::Hoedown {
                            format!("{}",
-                                   shorter(Some(&Markdown(doc_value,
+                                   shorter(Some(&Markdown(doc_value, &myitem.links(),
                                                           RenderType::Hoedown).to_string())))
                        } else {
-                           format!("{}", MarkdownSummaryLine(doc_value))
+                           format!("{}", MarkdownSummaryLine(doc_value, &myitem.links()))
                        },
                        class = myitem.type_(),
                        stab = myitem.stability_class().unwrap_or("".to_string()),
@@ -2919,7 +2995,8 @@ fn render_impl(w: &mut fmt::Formatter, cx: &Context, i: &Impl, link: AssocItemLi
         write!(w, "</span>")?;
         write!(w, "</h3>\n")?;
         if let Some(ref dox) = i.impl_item.doc_value() {
-            write!(w, "<div class='docblock'>{}</div>", Markdown(dox, cx.render_type))?;
+            write!(w, "<div class='docblock'>{}</div>",
+                   Markdown(dox, &i.impl_item.links(), cx.render_type))?;
         }
     }
 
@@ -3030,6 +3107,7 @@ fn render_impl(w: &mut fmt::Formatter, cx: &Context, i: &Impl, link: AssocItemLi
                 } else {
                     document_stability(w, cx, item)?;
                     document_full(w, item, cx.render_type, &prefix)?;
+                    document_scraped_examples(w, cx, item)?;
                 }
             } else {
                 document_stability(w, cx, item)?;
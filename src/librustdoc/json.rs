@@ -0,0 +1,150 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rustdoc's JSON backend.
+//!
+//! This serializes the cleaned crate, along with the paths of all the items it
+//! refers to, into a single `<crate name>.json` file laid out as:
+//!
+//! ```text
+//! {
+//!     "schema": "<SCHEMA_VERSION>",
+//!     "crate": {
+//!         "name": <crate name>,
+//!         "src": <path of the crate root>,
+//!         "module": <the root module, an encoded `clean::Item`>,
+//!         "primitives": [<encoded (DefId, PrimitiveType, Attributes)>, ...],
+//!         "externs": [{ "krate": <crate num>, "crate": <encoded ExternalCrate> }, ...],
+//!         "external_traits": [{ "def_id": <encoded DefId>, "trait": <encoded Trait> }, ...]
+//!     },
+//!     "paths": [{
+//!         "def_id": <encoded DefId>,
+//!         "path": [<path segments>, ...],
+//!         "kind": <item type, as used in HTML file names>,
+//!         "local": <whether the item is in this crate>
+//!     }, ...],
+//!     "external_crates": [{
+//!         "krate": <crate num>,
+//!         "name": <crate name>,
+//!         "html_root_url": <URL of the crate's documentation, or null>
+//!     }, ...]
+//! }
+//! ```
+//!
+//! Items, and everything they contain, are encoded with the `Encodable`
+//! implementations of the `clean` types, so any change to those types changes
+//! the schema.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use rustc::hir::def_id::DefId;
+use serialize::Encodable;
+use serialize::json::{self, Json, ToJson};
+
+use clean;
+use html::item_type::ItemType;
+use html::render::{self, Cache, Error, ExternalLocation, RenderInfo};
+
+/// The version of the layout of the JSON output. This has to be bumped
+/// whenever the output or the serialized `clean` types change.
+pub const SCHEMA_VERSION: &'static str = "0.9.0";
+
+/// Writes the documentation for `krate` as JSON into the directory `dst`.
+pub fn run(krate: clean::Crate, renderinfo: RenderInfo, dst: PathBuf) -> Result<(), Error> {
+    let name = krate.name.clone();
+
+    // Building the cache moves implementations out of the module tree, so the
+    // crate has to be serialized first.
+    let crate_json = crate_to_json(&krate);
+    let (_, cache) = render::build_cache(krate, renderinfo, &dst);
+
+    let mut json = BTreeMap::new();
+    json.insert("schema".to_string(), SCHEMA_VERSION.to_json());
+    json.insert("crate".to_string(), crate_json);
+    json.insert("paths".to_string(), paths_to_json(&cache));
+    json.insert("external_crates".to_string(), external_crates_to_json(&cache));
+
+    fs::create_dir_all(&dst).map_err(|e| Error::new(e, &dst))?;
+    let path = dst.join(format!("{}.json", name));
+    let mut file = BufWriter::new(File::create(&path).map_err(|e| Error::new(e, &path))?);
+    write!(file, "{}", Json::Object(json)).map_err(|e| Error::new(e, &path))?;
+    Ok(())
+}
+
+fn crate_to_json(krate: &clean::Crate) -> Json {
+    let externs = krate.externs.iter().map(|&(cnum, ref e)| {
+        let mut json = BTreeMap::new();
+        json.insert("krate".to_string(), cnum.as_u32().to_json());
+        json.insert("crate".to_string(), encode(e));
+        Json::Object(json)
+    }).collect();
+
+    let mut external_traits = krate.external_traits.iter().collect::<Vec<_>>();
+    external_traits.sort_by_key(|&(&did, _)| did);
+    let external_traits = external_traits.into_iter().map(|(did, t)| {
+        let mut json = BTreeMap::new();
+        json.insert("def_id".to_string(), encode(did));
+        json.insert("trait".to_string(), encode(t));
+        Json::Object(json)
+    }).collect();
+
+    let mut json = BTreeMap::new();
+    json.insert("name".to_string(), krate.name.to_json());
+    json.insert("src".to_string(), krate.src.to_string_lossy().to_json());
+    json.insert("module".to_string(), encode(&krate.module));
+    json.insert("primitives".to_string(), encode(&krate.primitives));
+    json.insert("externs".to_string(), Json::Array(externs));
+    json.insert("external_traits".to_string(), Json::Array(external_traits));
+    Json::Object(json)
+}
+
+fn paths_to_json(cache: &Cache) -> Json {
+    let path_to_json = |did: DefId, fqp: &[String], ty: ItemType, local: bool| {
+        let mut json = BTreeMap::new();
+        json.insert("def_id".to_string(), encode(&did));
+        json.insert("path".to_string(), fqp.to_json());
+        json.insert("kind".to_string(), ty.to_string().to_json());
+        json.insert("local".to_string(), local.to_json());
+        (did, Json::Object(json))
+    };
+
+    let mut paths = cache.paths.iter().map(|(&did, &(ref fqp, ty))| {
+        path_to_json(did, &fqp[..], ty, true)
+    }).chain(cache.external_paths.iter().map(|(&did, &(ref fqp, ty))| {
+        path_to_json(did, &fqp[..], ty, false)
+    })).collect::<Vec<_>>();
+    paths.sort_by_key(|&(did, _)| did);
+    Json::Array(paths.into_iter().map(|(_, json)| json).collect())
+}
+
+fn external_crates_to_json(cache: &Cache) -> Json {
+    let mut crates = cache.extern_locations.iter().collect::<Vec<_>>();
+    crates.sort_by_key(|&(&cnum, _)| cnum);
+    Json::Array(crates.into_iter().map(|(cnum, &(ref name, _, ref location))| {
+        let mut json = BTreeMap::new();
+        json.insert("krate".to_string(), cnum.as_u32().to_json());
+        json.insert("name".to_string(), name.to_json());
+        json.insert("html_root_url".to_string(), match *location {
+            ExternalLocation::Remote(ref url) => url.to_json(),
+            ExternalLocation::Local | ExternalLocation::Unknown => Json::Null,
+        });
+        Json::Object(json)
+    }).collect())
+}
+
+/// Encodes `t` to JSON with its `Encodable` implementation.
+// FIXME: this round-trips through a string, as there's no way to encode
+// straight to a `Json` value.
+fn encode<T: Encodable>(t: &T) -> Json {
+    json::from_str(&json::as_json(t).to_string()).expect("rustdoc generated invalid JSON")
+}
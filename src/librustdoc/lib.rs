@@ -71,8 +71,10 @@ pub mod externalfiles;
 
 pub mod clean;
 pub mod core;
+pub mod coverage;
 pub mod doctree;
 pub mod fold;
+pub mod json;
 pub mod html {
     pub mod highlight;
     pub mod escape;
@@ -86,6 +88,7 @@ pub mod html {
 pub mod markdown;
 pub mod passes;
 pub mod plugins;
+pub mod scrape_examples;
 pub mod visit_ast;
 pub mod visit_lib;
 pub mod test;
@@ -139,7 +142,9 @@ pub fn opts() -> Vec<RustcOptGroup> {
                      "[rust]")
         }),
         stable("w", |o| {
-            o.optopt("w", "output-format", "the output type to write", "[html]")
+            o.optopt("w", "output-format",
+                     "the output types to write, separated by commas; `json` is unstable",
+                     "[html|json]")
         }),
         stable("o", |o| o.optopt("o", "output", "where to place the output", "PATH")),
         stable("crate-name", |o| {
@@ -243,6 +248,31 @@ pub fn opts() -> Vec<RustcOptGroup> {
         unstable("display-warnings", |o| {
             o.optflag("", "display-warnings", "to print code warnings when testing doc")
         }),
+        unstable("merge-doctests", |o| {
+            o.optflag("", "merge-doctests",
+                      "with --test, compile the doctests which allow it into a single program \
+                       instead of one program each")
+        }),
+        unstable("show-coverage", |o| {
+            o.optflag("", "show-coverage",
+                      "calculate the percentage of public items with documentation \
+                       instead of generating it, printed as JSON with `--output-format json`")
+        }),
+        unstable("scrape-examples-output-path", |o| {
+            o.optopt("", "scrape-examples-output-path",
+                     "write the calls this crate makes to the functions of the \
+                      `--scrape-examples-target-crate`s to PATH instead of documenting it",
+                     "PATH")
+        }),
+        unstable("scrape-examples-target-crate", |o| {
+            o.optmulti("", "scrape-examples-target-crate",
+                       "crate whose functions to scrape calls to", "NAME")
+        }),
+        unstable("with-examples", |o| {
+            o.optmulti("", "with-examples",
+                       "show the calls scraped into PATH as examples in the documentation",
+                       "PATH")
+        }),
     ]
 }
 
@@ -354,6 +384,7 @@ pub fn main_args(args: &[String]) -> isize {
     let playground_url = matches.opt_str("playground-url");
     let maybe_sysroot = matches.opt_str("sysroot").map(PathBuf::from);
     let display_warnings = matches.opt_present("display-warnings");
+    let merge_doctests = matches.opt_present("merge-doctests");
 
     match (should_test, markdown_input) {
         (true, true) => {
@@ -362,7 +393,7 @@ pub fn main_args(args: &[String]) -> isize {
         }
         (true, false) => {
             return test::run(input, cfgs, libs, externs, test_args, crate_name, maybe_sysroot,
-                             render_type, display_warnings)
+                             render_type, display_warnings, merge_doctests)
         }
         (false, true) => return markdown::render(input,
                                                  output.unwrap_or(PathBuf::from("doc")),
@@ -372,26 +403,49 @@ pub fn main_args(args: &[String]) -> isize {
         (false, false) => {}
     }
 
-    let output_format = matches.opt_str("w");
+    let output_formats = match matches.opt_str("w") {
+        Some(s) => s.split(',').map(|s| s.trim().to_string()).collect::<Vec<_>>(),
+        None => vec!["html".to_string()],
+    };
+    if let Some(s) = output_formats.iter().find(|s| *s != "html" && *s != "json") {
+        print_error(format!("unknown output format: {}", s));
+        return 1;
+    }
+    let write_json = output_formats.iter().any(|s| s == "json");
+    let write_html = output_formats.iter().any(|s| s == "html");
+    if write_json && !nightly_options::is_unstable_enabled(&matches) {
+        print_error("the `json` output format is unstable and requires `-Z unstable-options`");
+        return 1;
+    }
+    let show_coverage = matches.opt_present("show-coverage");
+    let scraping = matches.opt_present("scrape-examples-output-path");
     let res = acquire_input(input, externs, &matches, move |out| {
         let Output { krate, passes, renderinfo } = out;
+        if scraping {
+            return 0;
+        }
+        if show_coverage {
+            coverage::run(krate, write_json);
+            return 0;
+        }
+        let dst = output.unwrap_or(PathBuf::from("doc"));
         info!("going to format");
-        match output_format.as_ref().map(|s| &**s) {
-            Some("html") | None => {
-                html::render::run(krate, &external_html, playground_url,
-                                  output.unwrap_or(PathBuf::from("doc")),
-                                  passes.into_iter().collect(),
-                                  css_file_extension,
-                                  renderinfo,
-                                  render_type)
-                    .expect("failed to generate documentation");
-                0
-            }
-            Some(s) => {
-                print_error(format!("unknown output format: {}", s));
-                1
-            }
+        if write_json && !write_html {
+            json::run(krate, renderinfo, dst).expect("failed to generate JSON documentation");
+            return 0;
         }
+        if write_json {
+            json::run(krate.clone(), renderinfo.clone(), dst.clone())
+                .expect("failed to generate JSON documentation");
+        }
+        html::render::run(krate, &external_html, playground_url,
+                          dst,
+                          passes.into_iter().collect(),
+                          css_file_extension,
+                          renderinfo,
+                          render_type)
+            .expect("failed to generate documentation");
+        0
     });
     res.unwrap_or_else(|s| {
         print_error(format!("input error: {}", s));
@@ -469,6 +523,11 @@ where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
     let force_unstable_if_unmarked = matches.opt_strs("Z").iter().any(|x| {
         *x == "force-unstable-if-unmarked"
     });
+    let scrape_examples = scrape_examples::Options {
+        output_path: matches.opt_str("scrape-examples-output-path").map(PathBuf::from),
+        target_crates: matches.opt_strs("scrape-examples-target-crate"),
+        with_examples: matches.opt_strs("with-examples").into_iter().map(PathBuf::from).collect(),
+    };
 
     let (tx, rx) = channel();
     rustc_driver::monitor(move || {
@@ -476,7 +535,7 @@ where R: 'static + Send, F: 'static + Send + FnOnce(Output) -> R {
 
         let (mut krate, renderinfo) =
             core::run_core(paths, cfgs, externs, Input::File(cr), triple, maybe_sysroot,
-                           display_warnings, force_unstable_if_unmarked);
+                           display_warnings, force_unstable_if_unmarked, scrape_examples);
 
         info!("finished with rustc");
 
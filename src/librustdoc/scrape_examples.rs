@@ -0,0 +1,267 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scraping of the calls a crate's examples and tests make to its functions,
+//! to show them as usage examples in its documentation.
+//!
+//! This happens in two steps. First, rustdoc is run on each example crate with
+//! `--scrape-examples-output-path`, which type-checks it and writes out the
+//! calls it makes to functions of the `--scrape-examples-target-crate`s. Then
+//! the crate itself is documented `--with-examples` pointing at those files,
+//! and the calls are shown on the pages of the functions they call.
+
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use rustc::hir;
+use rustc::hir::def_id::{CrateNum, DefId};
+use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
+use rustc::ty::{self, TyCtxt};
+use rustc::util::nodemap::FxHashMap;
+use serialize::json;
+use syntax_pos::{NO_EXPANSION, Pos, Span};
+
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Where to write the calls scraped from the crate being documented, if
+    /// it is an example crate.
+    pub output_path: Option<PathBuf>,
+    /// The crates whose functions calls are scraped for.
+    pub target_crates: Vec<String>,
+    /// Files of calls scraped from examples, to show in the documentation.
+    pub with_examples: Vec<PathBuf>,
+}
+
+/// How many lines before and after a call are shown with it.
+const CONTEXT_LINES: usize = 2;
+
+/// Calls made to a function from one part of an example.
+#[derive(Clone, Debug, RustcEncodable, RustcDecodable)]
+pub struct CallData {
+    /// The source file of the example.
+    pub file: String,
+    /// The line `excerpt` starts on.
+    pub line: usize,
+    /// The lines of the calls, and a few around them.
+    pub excerpt: String,
+    /// The byte ranges of the calls within `excerpt`.
+    pub calls: Vec<(usize, usize)>,
+}
+
+impl CallData {
+    /// Gets the line `excerpt` ends on.
+    pub fn end_line(&self) -> usize {
+        self.line + self.excerpt.matches('\n').count()
+    }
+
+    /// Gets the lines of the example's source file which the calls span.
+    pub fn call_lines(&self) -> Vec<(usize, usize)> {
+        self.calls.iter().map(|&(lo, hi)| {
+            let lo_line = self.line + self.excerpt[..lo].matches('\n').count();
+            let hi_line = self.line + self.excerpt[..hi].matches('\n').count();
+            (lo_line, hi_line)
+        }).collect()
+    }
+}
+
+/// Calls found in examples, keyed by the path of the function being called.
+type AllCalls = BTreeMap<String, Vec<CallData>>;
+
+/// Gets the key `def_id`'s calls are stored under, which has to be the same
+/// whichever crate it's computed from.
+fn call_key(tcx: TyCtxt, def_id: DefId) -> String {
+    format!("{}{}", tcx.crate_name(def_id.krate), tcx.def_path(def_id).to_string_no_crate())
+}
+
+struct FindCalls<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    tables: &'a ty::TypeckTables<'tcx>,
+    target_crates: Vec<CrateNum>,
+    /// The span of the item whose body is being walked.
+    item_span: Span,
+    calls: AllCalls,
+}
+
+impl<'a, 'tcx> FindCalls<'a, 'tcx> {
+    fn record_call(&mut self, def_id: DefId, span: Span) {
+        let tcx = self.tcx;
+        let codemap = tcx.sess.codemap();
+        let item_span = self.item_span;
+        // Calls from macro expansions have no source to show.
+        if span.ctxt != NO_EXPANSION || item_span.ctxt != NO_EXPANSION ||
+           span.lo < item_span.lo || span.hi > item_span.hi {
+            return;
+        }
+        let lo = codemap.lookup_char_pos(span.lo);
+        let hi_line = codemap.lookup_char_pos(span.hi).line;
+        let file = lo.file;
+
+        let calls = self.calls.entry(call_key(tcx, def_id)).or_insert_with(Vec::new);
+        // A call within the excerpt of an earlier one is shown in that excerpt.
+        let existing = calls.iter().position(|call| {
+            call.file == file.name && call.line <= lo.line && hi_line <= call.end_line()
+        });
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                // The excerpt doesn't go outside the item making the call.
+                let first_line = cmp::max(codemap.lookup_char_pos(item_span.lo).line,
+                                          lo.line.saturating_sub(CONTEXT_LINES));
+                let last_line = cmp::min(codemap.lookup_char_pos(item_span.hi).line,
+                                         hi_line + CONTEXT_LINES);
+                let window = Span {
+                    lo: file.line_bounds(first_line - 1).0,
+                    hi: file.line_bounds(last_line - 1).1,
+                    ctxt: NO_EXPANSION,
+                };
+                let excerpt = match codemap.span_to_snippet(window) {
+                    Ok(excerpt) => excerpt,
+                    Err(_) => return,
+                };
+                calls.push(CallData {
+                    file: file.name.clone(),
+                    line: first_line,
+                    excerpt: excerpt.trim_right().to_string(),
+                    calls: Vec::new(),
+                });
+                calls.len() - 1
+            }
+        };
+        let start = file.line_bounds(calls[index].line - 1).0;
+        calls[index].calls.push(((span.lo - start).to_usize(), (span.hi - start).to_usize()));
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for FindCalls<'a, 'tcx> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
+        NestedVisitorMap::All(&self.tcx.hir)
+    }
+
+    fn visit_nested_body(&mut self, body: hir::BodyId) {
+        let orig_tables = mem::replace(&mut self.tables, self.tcx.body_tables(body));
+        let body = self.tcx.hir.body(body);
+        self.visit_body(body);
+        self.tables = orig_tables;
+    }
+
+    fn visit_item(&mut self, item: &'tcx hir::Item) {
+        let orig_item_span = mem::replace(&mut self.item_span, item.span);
+        intravisit::walk_item(self, item);
+        self.item_span = orig_item_span;
+    }
+
+    fn visit_trait_item(&mut self, item: &'tcx hir::TraitItem) {
+        let orig_item_span = mem::replace(&mut self.item_span, item.span);
+        intravisit::walk_trait_item(self, item);
+        self.item_span = orig_item_span;
+    }
+
+    fn visit_impl_item(&mut self, item: &'tcx hir::ImplItem) {
+        let orig_item_span = mem::replace(&mut self.item_span, item.span);
+        intravisit::walk_impl_item(self, item);
+        self.item_span = orig_item_span;
+    }
+
+    fn visit_expr(&mut self, expr: &'tcx hir::Expr) {
+        intravisit::walk_expr(self, expr);
+
+        let def_id = match expr.node {
+            hir::ExprCall(ref callee, _) => {
+                match self.tables.expr_ty(callee).sty {
+                    ty::TyFnDef(def_id, _) => def_id,
+                    _ => return,
+                }
+            }
+            hir::ExprMethodCall(..) => {
+                match self.tables.type_dependent_defs.get(&expr.id) {
+                    Some(def) => def.def_id(),
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        if self.target_crates.contains(&def_id.krate) {
+            self.record_call(def_id, expr.span);
+        }
+    }
+}
+
+/// Writes the calls the local crate makes to functions of the target crates
+/// to `output_path`.
+pub fn scrape<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                        target_crates: &[String],
+                        output_path: &Path) -> Result<(), String> {
+    let target_crates = tcx.sess.cstore.crates().into_iter().filter(|&cnum| {
+        target_crates.iter().any(|name| *name == *tcx.crate_name(cnum).as_str())
+    }).collect();
+
+    let mut finder = FindCalls {
+        tcx: tcx,
+        tables: &ty::TypeckTables::empty(),
+        target_crates: target_crates,
+        item_span: tcx.hir.krate().span,
+        calls: AllCalls::new(),
+    };
+    intravisit::walk_crate(&mut finder, tcx.hir.krate());
+
+    let contents = json::encode(&finder.calls).map_err(|e| e.to_string())?;
+    File::create(output_path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("failed to write `{}`: {}", output_path.display(), e))
+}
+
+/// Loads the calls scraped into `paths`, and finds the local functions they
+/// were made to.
+pub fn load<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                      paths: &[PathBuf]) -> Result<FxHashMap<DefId, Vec<CallData>>, String> {
+    let mut all_calls = AllCalls::new();
+    for path in paths {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("failed to read `{}`: {}", path.display(), e))?;
+        let calls: AllCalls = json::decode(&contents)
+            .map_err(|e| format!("failed to load `{}`: {}", path.display(), e))?;
+        for (key, calls) in calls {
+            all_calls.entry(key).or_insert_with(Vec::new).extend(calls);
+        }
+    }
+    if all_calls.is_empty() {
+        return Ok(FxHashMap());
+    }
+
+    // The sources of the examples are rendered so the calls can link to them,
+    // and those which can't be found are shown without a link.
+    let files: BTreeSet<_> = all_calls.values().flat_map(|calls| calls)
+        .map(|call| &call.file[..]).collect();
+    for file in files {
+        if !Path::new(file).is_file() {
+            tcx.sess.warn(&format!("the source of example `{}` could not be found, \
+                                    so its calls are shown without a link", file));
+        }
+    }
+
+    let krate = tcx.hir.krate();
+    let ids = krate.items.values().map(|item| item.id)
+        .chain(krate.trait_items.values().map(|item| item.id))
+        .chain(krate.impl_items.values().map(|item| item.id));
+    let mut scraped = FxHashMap();
+    for id in ids {
+        let def_id = tcx.hir.local_def_id(id);
+        if let Some(calls) = all_calls.remove(&call_key(tcx, def_id)) {
+            scraped.insert(def_id, calls);
+        }
+    }
+    Ok(scraped)
+}
@@ -60,7 +60,8 @@ pub fn run(input: &str,
            crate_name: Option<String>,
            maybe_sysroot: Option<PathBuf>,
            render_type: RenderType,
-           display_warnings: bool)
+           display_warnings: bool,
+           merge_doctests: bool)
            -> isize {
     let input_path = PathBuf::from(input);
     let input = config::Input::File(input_path.clone());
@@ -111,7 +112,8 @@ pub fn run(input: &str,
                                        maybe_sysroot,
                                        Some(codemap),
                                        None,
-                                       render_type);
+                                       render_type,
+                                       merge_doctests);
 
     {
         let dep_graph = DepGraph::new(false);
@@ -175,27 +177,6 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     // the test harness wants its own `main` & top level functions, so
     // never wrap the test in `fn main() { ... }`
     let test = maketest(test, Some(cratename), as_test_harness, opts);
-    let input = config::Input::Str {
-        name: driver::anon_src(),
-        input: test.to_owned(),
-    };
-    let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
-
-    let sessopts = config::Options {
-        maybe_sysroot: maybe_sysroot.or_else(
-            || Some(env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf())),
-        search_paths: libs,
-        crate_types: vec![config::CrateTypeExecutable],
-        output_types: outputs,
-        externs: externs,
-        cg: config::CodegenOptions {
-            prefer_dynamic: true,
-            .. config::basic_codegen_options()
-        },
-        test: as_test_harness,
-        unstable_features: UnstableFeatures::from_environment(),
-        ..config::basic_options().clone()
-    };
 
     // Shuffle around a few input and output handles here. We're going to pass
     // an explicit handle into rustc to collect output messages, but we also
@@ -207,13 +188,6 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     //
     // The basic idea is to not use a default Handler for rustc, and then also
     // not print things by default to the actual stderr.
-    struct Sink(Arc<Mutex<Vec<u8>>>);
-    impl Write for Sink {
-        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-            Write::write(&mut *self.0.lock().unwrap(), data)
-        }
-        fn flush(&mut self) -> io::Result<()> { Ok(()) }
-    }
     struct Bomb(Arc<Mutex<Vec<u8>>>, Box<Write+Send>);
     impl Drop for Bomb {
         fn drop(&mut self) {
@@ -221,13 +195,82 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
         }
     }
     let data = Arc::new(Mutex::new(Vec::new()));
-    let codemap = Rc::new(CodeMap::new(sessopts.file_path_mapping()));
-    let emitter = errors::emitter::EmitterWriter::new(box Sink(data.clone()),
-                                                      Some(codemap.clone()));
     let old = io::set_panic(Some(box Sink(data.clone())));
     let _bomb = Bomb(data.clone(), old.unwrap_or(box io::stdout()));
 
     // Compile the code
+    let outdir = TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir");
+    let (compile_result, libdir) = compile_program(test, cfgs, libs, externs, no_run,
+                                                   as_test_harness, maybe_sysroot,
+                                                   outdir.path(), data.clone());
+
+    match (compile_result, compile_fail) {
+        (Ok(()), true) => {
+            panic!("test compiled while it wasn't supposed to")
+        }
+        (Ok(()), false) => {}
+        (Err(()), true) => {
+            if error_codes.len() > 0 {
+                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
+                error_codes.retain(|err| !out.contains(err));
+            }
+        }
+        (Err(()), false) => {
+            panic!("couldn't compile the test")
+        }
+    }
+
+    if error_codes.len() > 0 {
+        panic!("Some expected error codes were not found: {:?}", error_codes);
+    }
+
+    if no_run { return }
+
+    run_program(Command::new(&outdir.path().join("rust_out")), &libdir, should_panic);
+}
+
+/// A `Write` handle which appends everything written to it to a shared buffer.
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Sink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        Write::write(&mut *self.0.lock().unwrap(), data)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Compiles the program `src` into an executable named `rust_out` in `outdir`,
+/// writing the compiler's diagnostics to `data`.
+///
+/// Returns whether the compilation succeeded, along with the target library
+/// path the executable has to be run with.
+fn compile_program(src: String, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
+                   no_run: bool, as_test_harness: bool, maybe_sysroot: Option<PathBuf>,
+                   outdir: &Path, data: Arc<Mutex<Vec<u8>>>) -> (Result<(), ()>, PathBuf) {
+    let input = config::Input::Str {
+        name: driver::anon_src(),
+        input: src,
+    };
+    let outputs = OutputTypes::new(&[(OutputType::Exe, None)]);
+
+    let sessopts = config::Options {
+        maybe_sysroot: maybe_sysroot.or_else(
+            || Some(env::current_exe().unwrap().parent().unwrap().parent().unwrap().to_path_buf())),
+        search_paths: libs,
+        crate_types: vec![config::CrateTypeExecutable],
+        output_types: outputs,
+        externs: externs,
+        cg: config::CodegenOptions {
+            prefer_dynamic: true,
+            .. config::basic_codegen_options()
+        },
+        test: as_test_harness,
+        unstable_features: UnstableFeatures::from_environment(),
+        ..config::basic_options().clone()
+    };
+
+    let codemap = Rc::new(CodeMap::new(sessopts.file_path_mapping()));
+    let emitter = errors::emitter::EmitterWriter::new(box Sink(data), Some(codemap.clone()));
     let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
 
     let dep_graph = DepGraph::new(false);
@@ -238,12 +281,11 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     rustc_trans::init(&sess);
     rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
 
-    let outdir = Mutex::new(TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir"));
     let libdir = sess.target_filesearch(PathKind::All).get_lib_path();
     let mut control = driver::CompileController::basic();
     sess.parse_sess.config =
         config::build_configuration(&sess, config::parse_cfgspecs(cfgs.clone()));
-    let out = Some(outdir.lock().unwrap().path().to_path_buf());
+    let out = Some(outdir.to_path_buf());
 
     if no_run {
         control.after_analysis.stop = Compilation::Stop;
@@ -257,41 +299,21 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
         Ok(Ok(())) | Ok(Err(CompileIncomplete::Stopped)) => Ok(()),
         Err(_) | Ok(Err(CompileIncomplete::Errored(_))) => Err(())
     };
+    (compile_result, libdir)
+}
 
-    match (compile_result, compile_fail) {
-        (Ok(()), true) => {
-            panic!("test compiled while it wasn't supposed to")
-        }
-        (Ok(()), false) => {}
-        (Err(()), true) => {
-            if error_codes.len() > 0 {
-                let out = String::from_utf8(data.lock().unwrap().to_vec()).unwrap();
-                error_codes.retain(|err| !out.contains(err));
-            }
-        }
-        (Err(()), false) => {
-            panic!("couldn't compile the test")
-        }
-    }
-
-    if error_codes.len() > 0 {
-        panic!("Some expected error codes were not found: {:?}", error_codes);
-    }
-
-    if no_run { return }
-
-    // Run the code!
-    //
+/// Runs a compiled test, and panics if it didn't exit the way it was expected
+/// to.
+fn run_program(mut cmd: Command, libdir: &Path, should_panic: bool) {
     // We're careful to prepend the *target* dylib search path to the child's
     // environment to ensure that the target loads the right libraries at
     // runtime. It would be a sad day if the *host* libraries were loaded as a
     // mistake.
-    let mut cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
     let var = DynamicLibrary::envvar();
     let newpath = {
         let path = env::var_os(var).unwrap_or(OsString::new());
         let mut path = env::split_paths(&path).collect::<Vec<_>>();
-        path.insert(0, libdir.clone());
+        path.insert(0, libdir.to_path_buf());
         env::join_paths(path).unwrap()
     };
     cmd.env(var, &newpath);
@@ -313,6 +335,110 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     }
 }
 
+/// The doctests of a crate which are compiled together into a single program
+/// rather than one program each, for `--merge-doctests`.
+///
+/// The program is compiled when the first of these tests is run, and runs the
+/// test whose index it's given as its argument. If it fails to compile, each
+/// of the tests falls back to being compiled on its own.
+struct MergedDoctests {
+    cratename: String,
+    cfgs: Vec<String>,
+    libs: SearchPaths,
+    externs: Externs,
+    opts: TestOptions,
+    maybe_sysroot: Option<PathBuf>,
+    tests: Vec<String>,
+    /// The directory holding the compiled program and the target library path
+    /// to run it with, or `None` if it failed to compile.
+    compiled: Option<Option<(TempDir, PathBuf)>>,
+}
+
+impl MergedDoctests {
+    /// Checks whether a test can be merged with the others: it has to be run
+    /// as a plain program, and can't rely on being its own crate by defining
+    /// `main` or using crate attributes or `extern crate`.
+    fn can_merge(test: &str, should_ignore: bool, no_run: bool, as_test_harness: bool,
+                 compile_fail: bool, error_codes: &[String]) -> bool {
+        !should_ignore && !no_run && !as_test_harness && !compile_fail &&
+            error_codes.is_empty() && !test.contains("fn main") &&
+            !test.contains("extern crate") && partition_source(test).0.trim().is_empty()
+    }
+
+    /// Generates the source of the merged program, wrapping each test in its
+    /// own function instead of `main`.
+    fn program(&self) -> String {
+        let mut prog = String::new();
+        for attr in &self.opts.attrs {
+            prog.push_str(&format!("#![{}]\n", attr));
+        }
+        if !self.opts.no_crate_inject && self.cratename != "std" &&
+           self.tests.iter().any(|test| test.contains(&self.cratename[..])) {
+            prog.push_str(&format!("extern crate {};\n", self.cratename));
+        }
+        for (i, test) in self.tests.iter().enumerate() {
+            prog.push_str(&format!("fn __doctest_{}() {{\n{}\n}}\n", i, test.trim()));
+        }
+        prog.push_str("fn main() {\n");
+        prog.push_str("    match &::std::env::args().nth(1).expect(\"missing doctest\")[..] {\n");
+        for i in 0..self.tests.len() {
+            prog.push_str(&format!("        \"{0}\" => __doctest_{0}(),\n", i));
+        }
+        prog.push_str("        test => panic!(\"unknown doctest {}\", test),\n");
+        prog.push_str("    }\n}\n");
+
+        info!("merged test program: {}", prog);
+
+        prog
+    }
+
+    fn compile(&self) -> Option<(TempDir, PathBuf)> {
+        let outdir = TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir");
+        // The diagnostics are dropped: if this fails, the tests are compiled
+        // on their own, which reports the errors for the right test.
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let (compile_result, libdir) = compile_program(self.program(),
+                                                       self.cfgs.clone(),
+                                                       self.libs.clone(),
+                                                       self.externs.clone(),
+                                                       false,
+                                                       false,
+                                                       self.maybe_sysroot.clone(),
+                                                       outdir.path(),
+                                                       data);
+        match compile_result {
+            Ok(()) => Some((outdir, libdir)),
+            Err(()) => None,
+        }
+    }
+
+    /// Runs the test at `index`, compiling the merged program first if that
+    /// hasn't been done yet.
+    ///
+    /// Returns `false` if the program failed to compile, in which case the
+    /// test has to be compiled on its own instead.
+    fn run_test(merged: &Mutex<MergedDoctests>, index: usize, should_panic: bool) -> bool {
+        let (program, libdir) = {
+            let mut merged = merged.lock().unwrap();
+            if merged.compiled.is_none() {
+                let compiled = merged.compile();
+                merged.compiled = Some(compiled);
+            }
+            match merged.compiled {
+                Some(Some((ref outdir, ref libdir))) => {
+                    (outdir.path().join("rust_out"), libdir.clone())
+                }
+                _ => return false,
+            }
+        };
+
+        let mut cmd = Command::new(&program);
+        cmd.arg(index.to_string());
+        run_program(cmd, &libdir, should_panic);
+        true
+    }
+}
+
 pub fn maketest(s: &str, cratename: Option<&str>, dont_insert_main: bool,
                 opts: &TestOptions) -> String {
     let (crate_attrs, everything_else) = partition_source(s);
@@ -395,13 +521,29 @@ pub struct Collector {
     filename: Option<String>,
     // to be removed when hoedown will be removed as well
     pub render_type: RenderType,
+    /// The tests which are compiled together, if `--merge-doctests` is used.
+    merged: Option<Arc<Mutex<MergedDoctests>>>,
 }
 
 impl Collector {
     pub fn new(cratename: String, cfgs: Vec<String>, libs: SearchPaths, externs: Externs,
                use_headers: bool, opts: TestOptions, maybe_sysroot: Option<PathBuf>,
                codemap: Option<Rc<CodeMap>>, filename: Option<String>,
-               render_type: RenderType) -> Collector {
+               render_type: RenderType, merge_doctests: bool) -> Collector {
+        let merged = if merge_doctests {
+            Some(Arc::new(Mutex::new(MergedDoctests {
+                cratename: cratename.clone(),
+                cfgs: cfgs.clone(),
+                libs: libs.clone(),
+                externs: externs.clone(),
+                opts: opts.clone(),
+                maybe_sysroot: maybe_sysroot.clone(),
+                tests: Vec::new(),
+                compiled: None,
+            })))
+        } else {
+            None
+        };
         Collector {
             tests: Vec::new(),
             old_tests: HashMap::new(),
@@ -419,6 +561,7 @@ impl Collector {
             codemap: codemap,
             filename: filename,
             render_type: render_type,
+            merged: merged,
         }
     }
 
@@ -482,6 +625,16 @@ impl Collector {
         let cratename = self.cratename.to_string();
         let opts = self.opts.clone();
         let maybe_sysroot = self.maybe_sysroot.clone();
+        let merged = match self.merged {
+            Some(ref merged) if MergedDoctests::can_merge(&test, should_ignore, no_run,
+                                                          as_test_harness, compile_fail,
+                                                          &error_codes) => {
+                let mut doctests = merged.lock().unwrap();
+                doctests.tests.push(test.clone());
+                Some((merged.clone(), doctests.tests.len() - 1))
+            }
+            _ => None,
+        };
         debug!("Creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
             desc: testing::TestDesc {
@@ -490,6 +643,7 @@ impl Collector {
                 // compiler failures are test failures
                 should_panic: testing::ShouldPanic::No,
                 allow_fail: allow_fail,
+                timeout: None,
             },
             testfn: testing::DynTestFn(box move |()| {
                 let panic = io::set_panic(None);
@@ -498,6 +652,11 @@ impl Collector {
                     rustc_driver::in_rustc_thread(move || {
                         io::set_panic(panic);
                         io::set_print(print);
+                        if let Some((merged, index)) = merged {
+                            if MergedDoctests::run_test(&merged, index, should_panic) {
+                                return;
+                            }
+                        }
                         runtest(&test,
                                 &cratename,
                                 cfgs,
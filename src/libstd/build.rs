@@ -21,7 +21,8 @@ fn main() {
     let target = env::var("TARGET").expect("TARGET was not set");
     let host = env::var("HOST").expect("HOST was not set");
     if cfg!(feature = "backtrace") && !target.contains("apple") && !target.contains("msvc") &&
-        !target.contains("emscripten") && !target.contains("fuchsia") && !target.contains("redox") {
+        !target.contains("emscripten") && !target.contains("fuchsia") &&
+        !target.contains("redox") && !target.contains("wasm32") {
         let _ = build_libbacktrace(&host, &target);
     }
 
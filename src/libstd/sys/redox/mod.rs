@@ -38,6 +38,8 @@ pub mod thread;
 pub mod thread_local;
 pub mod time;
 
+pub use libc::strlen;
+
 #[cfg(not(test))]
 pub fn init() {}
 
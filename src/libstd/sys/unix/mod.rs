@@ -82,6 +82,7 @@ pub fn init() {
 pub use sys::android::signal;
 #[cfg(not(target_os = "android"))]
 pub use libc::signal;
+pub use libc::strlen;
 
 pub fn decode_error_kind(errno: i32) -> ErrorKind {
     match errno as libc::c_int {
@@ -0,0 +1,91 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+use fmt;
+use time::Duration;
+
+// There is no clock to read without importing one from the embedder, so
+// these types can be created from other instances but never from `now`.
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+pub struct Instant(Duration);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub struct SystemTime {
+    secs: u64,
+    nanos: u32,
+}
+
+pub const UNIX_EPOCH: SystemTime = SystemTime {
+    secs: 0,
+    nanos: 0,
+};
+
+impl Instant {
+    pub fn now() -> Instant {
+        panic!("not supported on web assembly");
+    }
+
+    pub fn sub_instant(&self, other: &Instant) -> Duration {
+        self.0 - other.0
+    }
+
+    pub fn add_duration(&self, other: &Duration) -> Instant {
+        Instant(self.0 + *other)
+    }
+
+    pub fn sub_duration(&self, other: &Duration) -> Instant {
+        Instant(self.0 - *other)
+    }
+}
+
+impl SystemTime {
+    pub fn now() -> SystemTime {
+        panic!("not supported on web assembly");
+    }
+
+    pub fn sub_time(&self, other: &SystemTime)
+                    -> Result<Duration, Duration> {
+        if self >= other {
+            Ok(self.duration() - other.duration())
+        } else {
+            Err(other.duration() - self.duration())
+        }
+    }
+
+    pub fn add_duration(&self, other: &Duration) -> SystemTime {
+        SystemTime::from_duration(self.duration() + *other)
+    }
+
+    pub fn sub_duration(&self, other: &Duration) -> SystemTime {
+        SystemTime::from_duration(self.duration() - *other)
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::new(self.secs, self.nanos)
+    }
+
+    fn from_duration(dur: Duration) -> SystemTime {
+        SystemTime {
+            secs: dur.as_secs(),
+            nanos: dur.subsec_nanos(),
+        }
+    }
+}
+
+impl fmt::Debug for SystemTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SystemTime")
+         .field("secs", &self.secs)
+         .field("nanos", &self.nanos)
+         .finish()
+    }
+}
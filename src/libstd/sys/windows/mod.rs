@@ -45,6 +45,8 @@ pub mod thread_local;
 pub mod time;
 pub mod stdio;
 
+pub use libc::strlen;
+
 #[cfg(not(test))]
 pub fn init() {
 }
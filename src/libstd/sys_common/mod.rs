@@ -44,10 +44,12 @@ pub mod thread_local;
 pub mod util;
 pub mod wtf8;
 
-#[cfg(target_os = "redox")]
+#[cfg(any(target_os = "redox",
+          all(target_arch = "wasm32", not(target_os = "emscripten"))))]
 pub use sys::net;
 
-#[cfg(not(target_os = "redox"))]
+#[cfg(not(any(target_os = "redox",
+              all(target_arch = "wasm32", not(target_os = "emscripten")))))]
 pub mod net;
 
 #[cfg(feature = "backtrace")]
@@ -358,6 +358,9 @@ declare_features! (
     // Allows a test to fail without failing the whole suite
     (active, allow_fail, "1.19.0", Some(42219)),
 
+    // Allows a test to override the `--test-timeout` of the test runner
+    (active, test_timeout, "1.20.0", None),
+
     // Allows unsized tuple coercion.
     (active, unsized_tuple_coercion, "1.20.0", Some(42877)),
 
@@ -844,6 +847,11 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                  "allow_fail attribute is currently unstable",
                                  cfg_fn!(allow_fail))),
 
+    ("timeout", Normal, Gated(Stability::Unstable,
+                              "test_timeout",
+                              "the `#[timeout]` attribute is currently unstable",
+                              cfg_fn!(test_timeout))),
+
     // Crate level attributes
     ("crate_name", CrateLevel, Ungated),
     ("crate_type", CrateLevel, Ungated),
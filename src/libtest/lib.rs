@@ -57,24 +57,40 @@ use std::cmp;
 use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io;
 use std::iter::repeat;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, ExitStatus, Stdio};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
 use std::thread;
-use std::time::{Instant, Duration};
+use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
 
 const TEST_WARN_TIMEOUT_S: u64 = 60;
 
+// With `--isolate`, each test is run by re-executing the test binary with
+// this variable set to the path of a file. The child runs the single test
+// selected by its filter and, once the test has returned, writes the outcome
+// to that file. A process which exits without writing it, whatever its exit
+// code, has failed.
+const ISOLATED_TEST_VAR: &'static str = "__RUST_TEST_ISOLATED";
+const ISOLATED_TR_OK: &'static str = "ok";
+const ISOLATED_TR_FAILED: &'static str = "failed";
+
+// A benchmark is only reported as changed against a baseline if the change
+// is statistically significant and larger than the noise threshold.
+const BASELINE_SIGNIFICANCE: f64 = 0.05;
+const BASELINE_NOISE_PCT: f64 = 1.0;
+
 // to be used by rustc to compile tests in libtest
 pub mod test {
     pub use {Bencher, TestName, TestResult, TestDesc, TestDescAndFn, TestOpts, TrFailed,
              TrFailedMsg, TrIgnored, TrOk, Metric, MetricMap, StaticTestFn, StaticTestName,
              DynTestName, DynTestFn, run_test, test_main, test_main_static, filter_tests,
-             parse_opts, StaticBenchFn, ShouldPanic, Options};
+             parse_opts, StaticBenchFn, ShouldPanic, Options, OutputFormat};
 }
 
 pub mod stats;
@@ -187,6 +203,7 @@ impl fmt::Debug for TestFn {
 pub struct Bencher {
     mode: BenchMode,
     summary: Option<stats::Summary>,
+    samples: Vec<f64>,
     pub bytes: u64,
 }
 
@@ -211,6 +228,8 @@ pub struct TestDesc {
     pub ignore: bool,
     pub should_panic: ShouldPanic,
     pub allow_fail: bool,
+    /// Overrides `--test-timeout` for this test, in seconds.
+    pub timeout: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -279,14 +298,29 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Options) {
         None => return,
     };
     opts.options = options;
+    if let Some(result_path) = env::var_os(ISOLATED_TEST_VAR) {
+        run_isolated_child(&opts, tests, PathBuf::from(result_path));
+    }
+    // A baseline which can't be read is a mistake in the options rather than
+    // a failure to run the tests.
+    let baseline = match opts.baseline {
+        Some(ref name) => match load_baseline(name) {
+            Ok(baseline) => Some((name.clone(), baseline)),
+            Err(msg) => {
+                let _ = writeln!(io::stderr(), "error: {}", msg);
+                process::exit(101);
+            }
+        },
+        None => None,
+    };
     if opts.list {
         if let Err(e) = list_tests_console(&opts, tests) {
             panic!("io error when listing tests: {:?}", e);
         }
     } else {
-        match run_tests_console(&opts, tests) {
+        match run_tests_console_with_baseline(&opts, tests, baseline) {
             Ok(true) => {}
-            Ok(false) => std::process::exit(101),
+            Ok(false) => process::exit(101),
             Err(e) => panic!("io error when running tests: {:?}", e),
         }
     }
@@ -330,6 +364,17 @@ pub enum ColorConfig {
     NeverColor,
 }
 
+/// How test events are written to stdout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One line per test (the default).
+    Pretty,
+    /// One character per test.
+    Terse,
+    /// One JSON object per line for each test event.
+    Json,
+}
+
 #[derive(Debug)]
 pub struct TestOpts {
     pub list: bool,
@@ -339,10 +384,20 @@ pub struct TestOpts {
     pub run_tests: bool,
     pub bench_benchmarks: bool,
     pub logfile: Option<PathBuf>,
+    pub junit_xml: Option<PathBuf>,
     pub nocapture: bool,
     pub color: ColorConfig,
-    pub quiet: bool,
+    pub format: OutputFormat,
     pub test_threads: Option<usize>,
+    pub isolate: bool,
+    pub test_timeout: Option<u64>,
+    pub report_slowest: Option<usize>,
+    pub shard_index: usize,
+    pub shard_count: usize,
+    pub shuffle_seed: Option<u64>,
+    pub save_baseline: Option<String>,
+    pub baseline: Option<String>,
+    pub fail_on_regression: bool,
     pub skip: Vec<String>,
     pub options: Options,
 }
@@ -358,10 +413,20 @@ impl TestOpts {
             run_tests: false,
             bench_benchmarks: false,
             logfile: None,
+            junit_xml: None,
             nocapture: false,
             color: AutoColor,
-            quiet: false,
+            format: OutputFormat::Pretty,
             test_threads: None,
+            isolate: false,
+            test_timeout: None,
+            report_slowest: None,
+            shard_index: 0,
+            shard_count: 1,
+            shuffle_seed: None,
+            save_baseline: None,
+            baseline: None,
+            fail_on_regression: false,
             skip: vec![],
             options: Options::new(),
         }
@@ -380,10 +445,28 @@ fn optgroups() -> getopts::Options {
         .optflag("h", "help", "Display this message (longer with --help)")
         .optopt("", "logfile", "Write logs to the specified file instead \
                                 of stdout", "PATH")
+        .optopt("", "junit-xml", "Write a JUnit XML report of the test run to the \
+                                  specified file", "PATH")
         .optflag("", "nocapture", "don't capture stdout/stderr of each \
                                    task, allow printing directly")
         .optopt("", "test-threads", "Number of threads used for running tests \
                                      in parallel", "n_threads")
+        .optflag("", "isolate", "Run each test in its own process, so that aborts and \
+                                 calls to `process::exit` fail only that test")
+        .optopt("", "test-timeout", "Fail tests that run for longer than SECS seconds",
+                "SECS")
+        .optopt("", "report-slowest", "Report the N slowest tests at the end of the run", "N")
+        .optopt("", "shard-index", "Run only the tests of shard N (counting from 0) of \
+                                    the tests selected by the filters", "N")
+        .optopt("", "shard-count", "Number of shards to split the tests into", "M")
+        .optflagopt("", "shuffle", "Run tests in a random order. The seed is printed so \
+                                    that the order can be reproduced", "SEED")
+        .optopt("", "save-baseline", "Save the results of the benchmarks as the baseline \
+                                      NAME", "NAME")
+        .optopt("", "baseline", "Compare the results of the benchmarks against the baseline \
+                                 NAME", "NAME")
+        .optflag("", "fail-on-regression", "Fail the run if a benchmark is significantly \
+                                            slower than the baseline")
         .optmulti("", "skip", "Skip tests whose names contain FILTER (this flag can \
                                be used multiple times)","FILTER")
         .optflag("q", "quiet", "Display one character per test instead of one line")
@@ -391,7 +474,11 @@ fn optgroups() -> getopts::Options {
         .optopt("", "color", "Configure coloring of output:
             auto   = colorize if stdout is a tty and tests are run on serially (default);
             always = always colorize output;
-            never  = never colorize output;", "auto|always|never");
+            never  = never colorize output;", "auto|always|never")
+        .optopt("", "format", "Configure formatting of output:
+            pretty = print verbose output;
+            terse  = display one character per test;
+            json   = output a json document per test event", "pretty|terse|json");
     return opts
 }
 
@@ -450,10 +537,14 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     let quiet = matches.opt_present("quiet");
     let exact = matches.opt_present("exact");
     let list = matches.opt_present("list");
+    let isolate = matches.opt_present("isolate");
 
     let logfile = matches.opt_str("logfile");
     let logfile = logfile.map(|s| PathBuf::from(&s));
 
+    let junit_xml = matches.opt_str("junit-xml");
+    let junit_xml = junit_xml.map(|s| PathBuf::from(&s));
+
     let bench_benchmarks = matches.opt_present("bench");
     let run_tests = !bench_benchmarks || matches.opt_present("test");
 
@@ -479,6 +570,85 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             None,
     };
 
+    let format = match matches.opt_str("format").as_ref().map(|s| &**s) {
+        None if quiet => OutputFormat::Terse,
+        Some("pretty") | None => OutputFormat::Pretty,
+        Some("terse") => OutputFormat::Terse,
+        Some("json") => OutputFormat::Json,
+
+        Some(v) => {
+            return Some(Err(format!("argument for --format must be pretty, terse, or json (was \
+                                     {})",
+                                    v)))
+        }
+    };
+
+    let test_timeout = match matches.opt_str("test-timeout") {
+        Some(secs) =>
+            match secs.parse::<u64>() {
+                Ok(0) =>
+                    return Some(Err(format!("argument for --test-timeout must not be 0"))),
+                Ok(secs) => Some(secs),
+                Err(e) =>
+                    return Some(Err(format!("argument for --test-timeout must be a number > 0 \
+                                             (error: {})", e)))
+            },
+        None =>
+            None,
+    };
+
+    let report_slowest = match matches.opt_str("report-slowest") {
+        Some(n_str) =>
+            match n_str.parse::<usize>() {
+                Ok(n) => Some(n),
+                Err(e) =>
+                    return Some(Err(format!("argument for --report-slowest must be a number \
+                                             (error: {})", e)))
+            },
+        None =>
+            None,
+    };
+
+    let shard_index = match matches.opt_str("shard-index").map(|s| s.parse::<usize>()) {
+        Some(Ok(n)) => n,
+        Some(Err(e)) =>
+            return Some(Err(format!("argument for --shard-index must be a number \
+                                     (error: {})", e))),
+        None => 0,
+    };
+
+    let shard_count = match matches.opt_str("shard-count").map(|s| s.parse::<usize>()) {
+        Some(Ok(0)) =>
+            return Some(Err(format!("argument for --shard-count must not be 0"))),
+        Some(Ok(n)) => n,
+        Some(Err(e)) =>
+            return Some(Err(format!("argument for --shard-count must be a number > 0 \
+                                     (error: {})", e))),
+        None => 1,
+    };
+
+    if shard_index >= shard_count {
+        return Some(Err(format!("--shard-index ({}) must be less than --shard-count ({})",
+                                shard_index, shard_count)));
+    }
+
+    let shuffle_seed = if matches.opt_present("shuffle") {
+        match matches.opt_str("shuffle").map(|s| s.parse::<u64>()) {
+            Some(Ok(seed)) => Some(seed),
+            Some(Err(e)) =>
+                return Some(Err(format!("argument for --shuffle must be a number \
+                                         (error: {})", e))),
+            None => Some(random_seed()),
+        }
+    } else {
+        None
+    };
+
+    let fail_on_regression = matches.opt_present("fail-on-regression");
+    if fail_on_regression && !matches.opt_present("baseline") {
+        return Some(Err(format!("--fail-on-regression requires --baseline")));
+    }
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -499,10 +669,20 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         run_tests: run_tests,
         bench_benchmarks: bench_benchmarks,
         logfile: logfile,
+        junit_xml: junit_xml,
         nocapture: nocapture,
         color: color,
-        quiet: quiet,
+        format: format,
         test_threads: test_threads,
+        isolate: isolate,
+        test_timeout: test_timeout,
+        report_slowest: report_slowest,
+        shard_index: shard_index,
+        shard_count: shard_count,
+        shuffle_seed: shuffle_seed,
+        save_baseline: matches.opt_str("save-baseline"),
+        baseline: matches.opt_str("baseline"),
+        fail_on_regression: fail_on_regression,
         skip: matches.opt_strs("skip"),
         options: Options::new(),
     };
@@ -513,6 +693,7 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
+    ns_iter_samples: Vec<f64>,
     mb_s: usize,
 }
 
@@ -536,9 +717,10 @@ enum OutputLocation<T> {
 
 struct ConsoleTestState<T> {
     log_out: Option<File>,
+    junit_out: Option<File>,
     out: OutputLocation<T>,
     use_color: bool,
-    quiet: bool,
+    format: OutputFormat,
     total: usize,
     passed: usize,
     failed: usize,
@@ -549,16 +731,33 @@ struct ConsoleTestState<T> {
     metrics: MetricMap,
     failures: Vec<(TestDesc, Vec<u8>)>,
     not_failures: Vec<(TestDesc, Vec<u8>)>,
+    junit_cases: Vec<(TestDesc, TestResult, Vec<u8>, Duration)>,
+    start_time: Instant,
+    report_slowest: Option<usize>,
+    shuffle_seed: Option<u64>,
+    save_baseline: Option<String>,
+    baseline: Option<(String, BTreeMap<String, Vec<f64>>)>,
+    bench_samples: BTreeMap<String, Vec<f64>>,
+    fail_on_regression: bool,
+    regressions: usize,
+    exec_times: Vec<(TestDesc, Duration)>,
     max_name_len: usize, // number of columns to fill when aligning names
     options: Options,
 }
 
 impl<T: Write> ConsoleTestState<T> {
-    pub fn new(opts: &TestOpts, _: Option<T>) -> io::Result<ConsoleTestState<io::Stdout>> {
+    pub fn new(opts: &TestOpts,
+               baseline: Option<(String, BTreeMap<String, Vec<f64>>)>,
+               _: Option<T>)
+               -> io::Result<ConsoleTestState<io::Stdout>> {
         let log_out = match opts.logfile {
             Some(ref path) => Some(File::create(path)?),
             None => None,
         };
+        let junit_out = match opts.junit_xml {
+            Some(ref path) => Some(File::create(path)?),
+            None => None,
+        };
         let out = match term::stdout() {
             None => Raw(io::stdout()),
             Some(t) => Pretty(t),
@@ -567,8 +766,9 @@ impl<T: Write> ConsoleTestState<T> {
         Ok(ConsoleTestState {
             out: out,
             log_out: log_out,
+            junit_out: junit_out,
             use_color: use_color(opts),
-            quiet: opts.quiet,
+            format: opts.format,
             total: 0,
             passed: 0,
             failed: 0,
@@ -579,6 +779,16 @@ impl<T: Write> ConsoleTestState<T> {
             metrics: MetricMap::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
+            junit_cases: Vec::new(),
+            start_time: Instant::now(),
+            report_slowest: opts.report_slowest,
+            shuffle_seed: opts.shuffle_seed,
+            save_baseline: opts.save_baseline.clone(),
+            baseline: baseline,
+            bench_samples: BTreeMap::new(),
+            fail_on_regression: opts.fail_on_regression,
+            regressions: 0,
+            exec_times: Vec::new(),
             max_name_len: 0,
             options: opts.options,
         })
@@ -610,7 +820,7 @@ impl<T: Write> ConsoleTestState<T> {
 
     pub fn write_short_result(&mut self, verbose: &str, quiet: &str, color: term::color::Color)
                               -> io::Result<()> {
-        if self.quiet {
+        if self.format == OutputFormat::Terse {
             self.write_pretty(quiet, color)
         } else {
             self.write_pretty(verbose, color)?;
@@ -653,16 +863,35 @@ impl<T: Write> ConsoleTestState<T> {
 
     pub fn write_run_start(&mut self, len: usize) -> io::Result<()> {
         self.total = len;
+        self.start_time = Instant::now();
+        if self.format == OutputFormat::Json {
+            let seed = match self.shuffle_seed {
+                Some(seed) => format!(", \"shuffle_seed\": {}", seed),
+                None => String::new(),
+            };
+            return self.write_plain(&format!(
+                "{{ \"type\": \"suite\", \"event\": \"started\", \"test_count\": {}{} }}\n",
+                len, seed));
+        }
         let noun = if len != 1 {
             "tests"
         } else {
             "test"
         };
-        self.write_plain(&format!("\nrunning {} {}\n", len, noun))
+        self.write_plain(&format!("\nrunning {} {}\n", len, noun))?;
+        if let Some(seed) = self.shuffle_seed {
+            self.write_plain(&format!("shuffling tests with seed {} (use --shuffle={} to \
+                                       reproduce this order)\n", seed, seed))?;
+        }
+        Ok(())
     }
 
     pub fn write_test_start(&mut self, test: &TestDesc, align: NamePadding) -> io::Result<()> {
-        if self.quiet && align != PadOnRight {
+        if self.format == OutputFormat::Json {
+            self.write_plain(&format!(
+                "{{ \"type\": \"test\", \"event\": \"started\", \"name\": \"{}\" }}\n",
+                JsonEscaped(test.name.as_slice())))
+        } else if self.format == OutputFormat::Terse && align != PadOnRight {
             Ok(())
         } else {
             let name = test.padded_name(self.max_name_len, align);
@@ -670,7 +899,11 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
-    pub fn write_result(&mut self, result: &TestResult) -> io::Result<()> {
+    pub fn write_result(&mut self, test: &TestDesc, result: &TestResult, stdout: &[u8])
+                        -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            return self.write_json_result(test, result, stdout);
+        }
         match *result {
             TrOk => self.write_ok(),
             TrFailed | TrFailedMsg(_) => self.write_failed(),
@@ -687,7 +920,41 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
+    pub fn write_json_result(&mut self, test: &TestDesc, result: &TestResult, stdout: &[u8])
+                             -> io::Result<()> {
+        let name = JsonEscaped(test.name.as_slice());
+        let line = match *result {
+            TrOk => json_test_event(&name, "ok", None, stdout),
+            TrFailed => json_test_event(&name, "failed", None, stdout),
+            TrFailedMsg(ref msg) => json_test_event(&name, "failed", Some(msg), stdout),
+            TrIgnored => json_test_event(&name, "ignored", None, &[]),
+            TrAllowedFail => json_test_event(&name, "allowed_failure", None, stdout),
+            TrMetrics(ref mm) => {
+                let MetricMap(ref mm) = *mm;
+                let metrics = mm.iter()
+                                .map(|(k, v)| format!("\"{}\": {{ \"value\": {}, \"noise\": {} }}",
+                                                      JsonEscaped(k), v.value, v.noise))
+                                .collect::<Vec<_>>();
+                format!("{{ \"type\": \"metric\", \"name\": \"{}\", \"metrics\": {{ {} }} }}",
+                        name, metrics.join(", "))
+            }
+            TrBench(ref bs) => {
+                let median = bs.ns_iter_summ.median as usize;
+                let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
+                format!("{{ \"type\": \"bench\", \"name\": \"{}\", \"median\": {}, \
+                         \"deviation\": {}, \"mb_per_second\": {} }}",
+                        name, median, deviation, bs.mb_s)
+            }
+        };
+        self.write_plain(&format!("{}\n", line))
+    }
+
     pub fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            return self.write_plain(&format!(
+                "{{ \"type\": \"test\", \"event\": \"timeout\", \"name\": \"{}\" }}\n",
+                JsonEscaped(desc.name.as_slice())));
+        }
         self.write_plain(&format!("test {} has been running for over {} seconds\n",
                                   desc.name,
                                   TEST_WARN_TIMEOUT_S))
@@ -716,6 +983,89 @@ impl<T: Write> ConsoleTestState<T> {
                     test.name))
     }
 
+    pub fn record_junit_case(&mut self,
+                             test: &TestDesc,
+                             result: &TestResult,
+                             stdout: &[u8],
+                             exec_time: Duration) {
+        if self.junit_out.is_some() {
+            self.junit_cases.push((test.clone(), result.clone(), stdout.to_vec(), exec_time));
+        }
+    }
+
+    pub fn write_junit_report(&mut self) -> io::Result<()> {
+        let mut out = match self.junit_out.take() {
+            Some(out) => out,
+            None => return Ok(()),
+        };
+
+        let skipped = self.junit_cases.iter().filter(|c| c.1 == TrIgnored).count();
+        let failures = self.junit_cases.iter().filter(|c| match c.1 {
+            TrFailed | TrFailedMsg(_) => true,
+            _ => false,
+        }).count();
+
+        write!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        write!(out, "<testsuites>\n")?;
+        write!(out, "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" \
+                     skipped=\"{}\" time=\"{}\">\n",
+               XmlEscaped(&junit_suite_name()),
+               self.junit_cases.len(),
+               failures,
+               skipped,
+               fmt_secs(self.start_time.elapsed()))?;
+        for &(ref desc, ref result, ref stdout, exec_time) in &self.junit_cases {
+            let name = desc.name.as_slice();
+            let (class_name, test_name) = match name.rfind("::") {
+                Some(i) => (&name[..i], &name[i + 2..]),
+                None => ("", name),
+            };
+            write!(out, "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"",
+                   XmlEscaped(class_name),
+                   XmlEscaped(test_name),
+                   fmt_secs(exec_time))?;
+            if *result == TrOk && stdout.is_empty() {
+                write!(out, "/>\n")?;
+                continue;
+            }
+            write!(out, ">\n")?;
+            match *result {
+                TrFailed => {
+                    write!(out, "      <failure type=\"failure\"/>\n")?;
+                }
+                TrFailedMsg(ref msg) => {
+                    write!(out, "      <failure type=\"failure\" message=\"{}\"/>\n",
+                           XmlEscaped(msg))?;
+                }
+                TrIgnored => write!(out, "      <skipped/>\n")?,
+                TrAllowedFail => {
+                    write!(out, "      <skipped message=\"failed (allowed)\"/>\n")?;
+                }
+                TrOk | TrMetrics(_) | TrBench(_) => {}
+            }
+            // A test case has a single `<system-out>`, so the measurements of
+            // metrics and benchmarks are followed by what the test printed.
+            let mut system_out = match *result {
+                TrMetrics(ref mm) => mm.fmt_metrics(),
+                TrBench(ref bs) => fmt_bench_samples(bs),
+                _ => String::new(),
+            };
+            if !stdout.is_empty() {
+                if !system_out.is_empty() {
+                    system_out.push('\n');
+                }
+                system_out.push_str(&String::from_utf8_lossy(stdout));
+            }
+            if !system_out.is_empty() {
+                write!(out, "      <system-out>{}</system-out>\n", XmlEscaped(&system_out))?;
+            }
+            write!(out, "    </testcase>\n")?;
+        }
+        write!(out, "  </testsuite>\n")?;
+        write!(out, "</testsuites>\n")?;
+        out.flush()
+    }
+
     pub fn write_failures(&mut self) -> io::Result<()> {
         self.write_plain("\nfailures:\n")?;
         let mut failures = Vec::new();
@@ -768,17 +1118,149 @@ impl<T: Write> ConsoleTestState<T> {
         Ok(())
     }
 
+    pub fn record_exec_time(&mut self, test: &TestDesc, result: &TestResult, exec_time: Duration) {
+        match *result {
+            TrOk | TrFailed | TrFailedMsg(_) | TrAllowedFail if self.report_slowest.is_some() => {
+                self.exec_times.push((test.clone(), exec_time));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn write_slowest(&mut self, n: usize) -> io::Result<()> {
+        let mut exec_times = self.exec_times.clone();
+        exec_times.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.as_slice()
+                                                              .cmp(b.0.name.as_slice())));
+        exec_times.truncate(n);
+        if exec_times.is_empty() {
+            return Ok(());
+        }
+        self.write_plain("\nslowest tests:\n")?;
+        for (test, exec_time) in exec_times {
+            self.write_plain(&format!("    {:>9}s {}\n", fmt_secs(exec_time), test.name))?;
+        }
+        Ok(())
+    }
+
+    pub fn save_bench_baseline(&mut self) -> io::Result<()> {
+        let path = match self.save_baseline {
+            Some(ref name) => baseline_path(name)?,
+            None => return Ok(()),
+        };
+        // Keep the entries of benchmarks that weren't run this time, e.g.
+        // because they live in another test binary.
+        let mut baseline = match read_baseline(&path) {
+            Ok(baseline) => baseline,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+        for (name, samples) in &self.bench_samples {
+            baseline.insert(name.clone(), samples.clone());
+        }
+        write_baseline(&path, &baseline)
+    }
+
+    pub fn write_baseline_comparison(&mut self) -> io::Result<()> {
+        use stats::Stats;
+
+        let (name, baseline) = match self.baseline.take() {
+            Some(baseline) => baseline,
+            None => return Ok(()),
+        };
+        let mut lines = Vec::new();
+        for (bench, samples) in &self.bench_samples {
+            let old = match baseline.get(bench) {
+                Some(old) if !old.is_empty() && !samples.is_empty() => old,
+                _ => {
+                    lines.push((bench.clone(), None));
+                    continue
+                }
+            };
+            let old_median = old.median();
+            let change = (samples.median() - old_median) / old_median * 100.0;
+            let p_value = stats::welch_t_test(old, samples);
+            let verdict = if p_value >= BASELINE_SIGNIFICANCE ||
+                             change.abs() < BASELINE_NOISE_PCT {
+                "no change"
+            } else if change > 0.0 {
+                self.regressions += 1;
+                "regressed"
+            } else {
+                "improved"
+            };
+            lines.push((bench.clone(), Some((change, p_value, verdict))));
+        }
+
+        if self.format == OutputFormat::Json {
+            for (bench, comparison) in lines {
+                let line = match comparison {
+                    Some((change, p_value, verdict)) => {
+                        format!("{{ \"type\": \"bench_change\", \"name\": \"{}\", \
+                                 \"change_pct\": {:.2}, \"p_value\": {:.3}, \
+                                 \"verdict\": \"{}\" }}\n",
+                                JsonEscaped(&bench), change, p_value, verdict)
+                    }
+                    None => {
+                        format!("{{ \"type\": \"bench_change\", \"name\": \"{}\", \
+                                 \"verdict\": \"new\" }}\n",
+                                JsonEscaped(&bench))
+                    }
+                };
+                self.write_plain(&line)?;
+            }
+            return Ok(());
+        }
+
+        self.write_plain(&format!("\nchanges against baseline `{}`:\n", name))?;
+        let width = lines.iter().map(|l| l.0.len()).max().unwrap_or(0);
+        for (bench, comparison) in lines {
+            match comparison {
+                Some((change, p_value, verdict)) => {
+                    self.write_plain(&format!("    {:<width$} {:>+8.2}% (p = {:.3}) ",
+                                              bench, change, p_value, width = width))?;
+                    match verdict {
+                        "regressed" => self.write_pretty(verdict, term::color::RED)?,
+                        "improved" => self.write_pretty(verdict, term::color::GREEN)?,
+                        _ => self.write_plain(verdict)?,
+                    }
+                    self.write_plain("\n")?;
+                }
+                None => {
+                    self.write_plain(&format!("    {:<width$} not in baseline\n",
+                                              bench, width = width))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_success(&self) -> bool {
+        self.failed == 0 && !(self.fail_on_regression && self.regressions > 0)
+    }
+
     pub fn write_run_finish(&mut self) -> io::Result<bool> {
         assert!(self.passed + self.failed + self.ignored + self.measured +
                     self.allowed_fail == self.total);
 
+        self.write_junit_report()?;
+        self.save_bench_baseline()?;
+
+        if self.format == OutputFormat::Json {
+            self.write_baseline_comparison()?;
+            return self.write_json_run_finish();
+        }
+
         if self.options.display_output {
             self.write_outputs()?;
         }
-        let success = self.failed == 0;
-        if !success {
+        if self.failed != 0 {
             self.write_failures()?;
         }
+        if let Some(n) = self.report_slowest {
+            self.write_slowest(n)?;
+        }
+        self.write_baseline_comparison()?;
+        let success = self.is_success();
 
         self.write_plain("\ntest result: ")?;
         if success {
@@ -808,6 +1290,154 @@ impl<T: Write> ConsoleTestState<T> {
         self.write_plain(&s)?;
         return Ok(success);
     }
+
+    fn write_json_run_finish(&mut self) -> io::Result<bool> {
+        let success = self.is_success();
+        let s = format!("{{ \"type\": \"suite\", \"event\": \"{}\", \"passed\": {}, \
+                         \"failed\": {}, \"allowed_fail\": {}, \"ignored\": {}, \
+                         \"measured\": {}, \"filtered_out\": {} }}\n",
+                        if success { "ok" } else { "failed" },
+                        self.passed,
+                        self.failed,
+                        self.allowed_fail,
+                        self.ignored,
+                        self.measured,
+                        self.filtered_out);
+        self.write_plain(&s)?;
+        Ok(success)
+    }
+}
+
+fn json_test_event(name: &JsonEscaped, event: &str, message: Option<&str>, stdout: &[u8])
+                   -> String {
+    let mut line = format!("{{ \"type\": \"test\", \"event\": \"{}\", \"name\": \"{}\"",
+                           event, name);
+    if let Some(message) = message {
+        line.push_str(&format!(", \"message\": \"{}\"", JsonEscaped(message)));
+    }
+    if !stdout.is_empty() {
+        line.push_str(&format!(", \"stdout\": \"{}\"",
+                               JsonEscaped(&String::from_utf8_lossy(stdout))));
+    }
+    line.push_str(" }");
+    line
+}
+
+/// Displays a string with the escaping required inside XML text and attribute values.
+struct XmlEscaped<'a>(&'a str);
+
+impl<'a> fmt::Display for XmlEscaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in self.0.char_indices() {
+            let escaped = match c {
+                '&' => "&amp;",
+                '<' => "&lt;",
+                '>' => "&gt;",
+                '"' => "&quot;",
+                '\'' => "&apos;",
+                '\t' | '\n' | '\r' => continue,
+                // Other control characters are not allowed in XML 1.0
+                // documents at all, so drop them.
+                '\x00'...'\x1f' => "",
+                _ => continue,
+            };
+            f.write_str(&self.0[start..i])?;
+            f.write_str(escaped)?;
+            start = i + c.len_utf8();
+        }
+        f.write_str(&self.0[start..])
+    }
+}
+
+// Benchmark baselines are stored in `RUST_BENCH_BASELINE_DIR`, or next to
+// the test binary, with one line per benchmark: its name, a tab, and its
+// ns/iter samples separated by spaces.
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let dir = match env::var_os("RUST_BENCH_BASELINE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let exe = env::current_exe()?;
+            exe.parent().map(|p| p.to_path_buf()).unwrap_or_else(PathBuf::new)
+        }
+    };
+    Ok(dir.join(format!("{}.bench-baseline", name)))
+}
+
+// Reads the baseline called `name`, describing what went wrong if it can't be.
+fn load_baseline(name: &str) -> Result<BTreeMap<String, Vec<f64>>, String> {
+    let path = baseline_path(name)
+        .map_err(|e| format!("failed to find baseline `{}`: {}", name, e))?;
+    read_baseline(&path)
+        .map_err(|e| format!("failed to read baseline `{}` from `{}`: {}",
+                             name, path.display(), e))
+}
+
+fn read_baseline(path: &Path) -> io::Result<BTreeMap<String, Vec<f64>>> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    let mut baseline = BTreeMap::new();
+    for line in contents.lines() {
+        let mut parts = line.splitn(2, '\t');
+        if let (Some(name), Some(samples)) = (parts.next(), parts.next()) {
+            let samples = samples.split(' ').filter_map(|s| s.parse().ok()).collect();
+            baseline.insert(name.to_owned(), samples);
+        }
+    }
+    Ok(baseline)
+}
+
+fn write_baseline(path: &Path, baseline: &BTreeMap<String, Vec<f64>>) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut out = File::create(path)?;
+    for (name, samples) in baseline {
+        let samples = samples.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        writeln!(out, "{}\t{}", name, samples.join(" "))?;
+    }
+    Ok(())
+}
+
+// The name of the JUnit test suite, taken from the name of the test binary
+fn junit_suite_name() -> String {
+    env::current_exe().ok()
+                      .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                      .unwrap_or_else(|| "test".to_owned())
+}
+
+// Format a duration as fractional seconds
+fn fmt_secs(dur: Duration) -> String {
+    format!("{}.{:03}", dur.as_secs(), dur.subsec_nanos() / 1_000_000)
+}
+
+/// Displays a string with the escaping required inside a JSON string literal.
+struct JsonEscaped<'a>(&'a str);
+
+impl<'a> fmt::Display for JsonEscaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in self.0.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                '\x00'...'\x1f' => {
+                    f.write_str(&self.0[start..i])?;
+                    write!(f, "\\u{:04x}", c as u32)?;
+                    start = i + c.len_utf8();
+                    continue;
+                }
+                _ => continue,
+            };
+            f.write_str(&self.0[start..i])?;
+            f.write_str(escaped)?;
+            start = i + c.len_utf8();
+        }
+        f.write_str(&self.0[start..])
+    }
 }
 
 // Format a number with thousands separators
@@ -853,7 +1483,7 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
 
 // List the tests to console, and optionally to logfile. Filters are honored.
 pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<()> {
-    let mut st = ConsoleTestState::new(opts, None::<io::Stdout>)?;
+    let mut st = ConsoleTestState::new(opts, None, None::<io::Stdout>)?;
 
     let mut ntest = 0;
     let mut nbench = 0;
@@ -881,7 +1511,7 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
         }
     }
 
-    if !opts.quiet {
+    if opts.format == OutputFormat::Pretty {
         if ntest != 0 || nbench != 0 || nmetric != 0 {
             st.write_plain("\n")?;
         }
@@ -896,6 +1526,23 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
 
 // A simple console test runner
 pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<bool> {
+    let baseline = match opts.baseline {
+        Some(ref name) => {
+            let baseline = load_baseline(name)
+                .map_err(|msg| io::Error::new(io::ErrorKind::Other, msg))?;
+            Some((name.clone(), baseline))
+        }
+        None => None,
+    };
+    run_tests_console_with_baseline(opts, tests, baseline)
+}
+
+// Runs the tests like `run_tests_console`, comparing the benchmarks against the
+// already loaded baseline named in the options.
+fn run_tests_console_with_baseline(opts: &TestOpts,
+                                   tests: Vec<TestDescAndFn>,
+                                   baseline: Option<(String, BTreeMap<String, Vec<f64>>)>)
+                                   -> io::Result<bool> {
 
     fn callback<T: Write>(event: &TestEvent, st: &mut ConsoleTestState<T>) -> io::Result<()> {
         match (*event).clone() {
@@ -903,9 +1550,11 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             TeFilteredOut(filtered_out) => Ok(st.filtered_out = filtered_out),
             TeWait(ref test, padding) => st.write_test_start(test, padding),
             TeTimeout(ref test) => st.write_timeout(test),
-            TeResult(test, result, stdout) => {
+            TeResult(test, result, stdout, exec_time) => {
+                st.record_junit_case(&test, &result, &stdout, exec_time);
+                st.record_exec_time(&test, &result, exec_time);
                 st.write_log_result(&test, &result)?;
-                st.write_result(&result)?;
+                st.write_result(&test, &result, &stdout)?;
                 match result {
                     TrOk => {
                         st.passed += 1;
@@ -926,6 +1575,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                         st.metrics.insert_metric(test.name.as_slice(),
                                                  bs.ns_iter_summ.median,
                                                  bs.ns_iter_summ.max - bs.ns_iter_summ.min);
+                        st.bench_samples.insert(test.name.to_string(), bs.ns_iter_samples);
                         st.measured += 1
                     }
                     TrFailed => {
@@ -946,7 +1596,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         }
     }
 
-    let mut st = ConsoleTestState::new(opts, None::<io::Stdout>)?;
+    let mut st = ConsoleTestState::new(opts, baseline, None::<io::Stdout>)?;
     fn len_if_padded(t: &TestDescAndFn) -> usize {
         match t.testfn.padding() {
             PadNone => 0,
@@ -968,6 +1618,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let test_b = TestDesc {
@@ -975,13 +1626,15 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let mut st = ConsoleTestState {
         log_out: None,
+        junit_out: None,
         out: Raw(Vec::new()),
         use_color: false,
-        quiet: false,
+        format: OutputFormat::Pretty,
         total: 0,
         passed: 0,
         failed: 0,
@@ -994,6 +1647,16 @@ fn should_sort_failures_before_printing_them() {
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
+        junit_cases: Vec::new(),
+        start_time: Instant::now(),
+        report_slowest: None,
+        shuffle_seed: None,
+        save_baseline: None,
+        baseline: None,
+        bench_samples: BTreeMap::new(),
+        fail_on_regression: false,
+        regressions: 0,
+        exec_times: Vec::new(),
     };
 
     st.write_failures().unwrap();
@@ -1046,7 +1709,7 @@ fn stdout_isatty() -> bool {
 pub enum TestEvent {
     TeFiltered(Vec<TestDesc>),
     TeWait(TestDesc, NamePadding),
-    TeResult(TestDesc, TestResult, Vec<u8>),
+    TeResult(TestDesc, TestResult, Vec<u8>, Duration),
     TeTimeout(TestDesc),
     TeFilteredOut(usize),
 }
@@ -1057,7 +1720,7 @@ pub type MonitorMsg = (TestDesc, TestResult, Vec<u8>);
 pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) -> io::Result<()>
     where F: FnMut(TestEvent) -> io::Result<()>
 {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::mpsc::RecvTimeoutError;
 
     let tests_len = tests.len();
@@ -1066,6 +1729,9 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
     if !opts.bench_benchmarks {
         filtered_tests = convert_benchmarks_to_tests(filtered_tests);
     }
+    if let Some(seed) = opts.shuffle_seed {
+        shuffle_tests(seed, &mut filtered_tests);
+    }
 
     let filtered_out = tests_len - filtered_tests.len();
     callback(TeFilteredOut(filtered_out))?;
@@ -1096,6 +1762,13 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
     let (tx, rx) = channel::<MonitorMsg>();
 
     let mut running_tests: HashMap<TestDesc, Instant> = HashMap::new();
+    let mut start_times: HashMap<TestDesc, Instant> = HashMap::new();
+    // Tests that have a hard deadline, and tests that were reported as
+    // failed after missing it. We can't stop the thread of a test that
+    // timed out, so its result is dropped if it ever arrives. It gives up
+    // its slot right away, as it may never finish.
+    let mut deadlines: HashMap<TestDesc, Instant> = HashMap::new();
+    let mut abandoned: HashSet<TestDesc> = HashSet::new();
 
     fn get_timed_out_tests(running_tests: &mut HashMap<TestDesc, Instant>) -> Vec<TestDesc> {
         let now = Instant::now();
@@ -1108,8 +1781,9 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
         timed_out
     };
 
-    fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>) -> Option<Duration> {
-        running_tests.values().min().map(|next_timeout| {
+    fn calc_timeout(running_tests: &HashMap<TestDesc, Instant>,
+                    deadlines: &HashMap<TestDesc, Instant>) -> Option<Duration> {
+        running_tests.values().chain(deadlines.values()).min().map(|next_timeout| {
             let now = Instant::now();
             if *next_timeout >= now {
                 *next_timeout - now
@@ -1127,15 +1801,25 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
                 // that hang forever.
                 callback(TeWait(test.desc.clone(), test.testfn.padding()))?;
             }
-            let timeout = Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S);
+            let now = Instant::now();
+            let timeout = now + Duration::from_secs(TEST_WARN_TIMEOUT_S);
             running_tests.insert(test.desc.clone(), timeout);
+            start_times.insert(test.desc.clone(), now);
+            // In `--isolate` mode, the test's process is killed once its
+            // timeout has passed, and the test is reported as failed then.
+            match test.desc.timeout.or(opts.test_timeout) {
+                Some(secs) if !opts.isolate => {
+                    deadlines.insert(test.desc.clone(), now + Duration::from_secs(secs));
+                }
+                _ => {}
+            }
             run_test(opts, !opts.run_tests, test, tx.clone());
             pending += 1;
         }
 
         let mut res;
         loop {
-            if let Some(timeout) = calc_timeout(&running_tests) {
+            if let Some(timeout) = calc_timeout(&running_tests, &deadlines) {
                 res = rx.recv_timeout(timeout);
                 for test in get_timed_out_tests(&mut running_tests) {
                     callback(TeTimeout(test))?;
@@ -1143,30 +1827,61 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
                 if res != Err(RecvTimeoutError::Timeout) {
                     break;
                 }
+                let expired = get_timed_out_tests(&mut deadlines);
+                if !expired.is_empty() {
+                    for desc in expired {
+                        running_tests.remove(&desc);
+                        let exec_time = start_times.remove(&desc).map(|t| t.elapsed())
+                                                   .unwrap_or(Duration::new(0, 0));
+                        let secs = desc.timeout.or(opts.test_timeout).unwrap_or(0);
+                        let result = TrFailedMsg(format!("test did not finish within {} seconds",
+                                                         secs));
+                        abandoned.insert(desc.clone());
+                        if concurrency != 1 {
+                            callback(TeWait(desc.clone(), PadNone))?;
+                        }
+                        callback(TeResult(desc, result, Vec::new(), exec_time))?;
+                        pending -= 1;
+                    }
+                    break;
+                }
             } else {
                 res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
                 break;
             }
         }
 
-        let (desc, result, stdout) = res.unwrap();
+        let (desc, result, stdout) = match res {
+            Err(RecvTimeoutError::Timeout) => continue,
+            res => res.unwrap(),
+        };
+        if abandoned.remove(&desc) {
+            continue;
+        }
         running_tests.remove(&desc);
+        deadlines.remove(&desc);
+        let exec_time = start_times.remove(&desc).map(|t| t.elapsed())
+                                   .unwrap_or(Duration::new(0, 0));
 
         if concurrency != 1 {
             callback(TeWait(desc.clone(), PadNone))?;
         }
-        callback(TeResult(desc, result, stdout))?;
+        callback(TeResult(desc, result, stdout, exec_time))?;
         pending -= 1;
     }
 
     if opts.bench_benchmarks {
         // All benchmarks run at the end, in serial.
         // (this includes metric fns)
+        // Tests that timed out may still send their results, so benchmarks
+        // get a channel of their own.
+        let (tx, rx) = channel::<MonitorMsg>();
         for b in filtered_benchs_and_metrics {
             callback(TeWait(b.desc.clone(), b.testfn.padding()))?;
+            let start = Instant::now();
             run_test(opts, false, b, tx.clone());
             let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            callback(TeResult(test, result, stdout, start.elapsed()))?;
         }
     }
     Ok(())
@@ -1342,9 +2057,41 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
     // Sort the tests alphabetically
     filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
 
+    // Keep only the tests of our shard. This relies on the order above, so
+    // that every shard sees the same partition.
+    if opts.shard_count > 1 {
+        filtered = filtered.into_iter()
+                           .enumerate()
+                           .filter(|&(i, _)| i % opts.shard_count == opts.shard_index)
+                           .map(|(_, test)| test)
+                           .collect();
+    }
+
     filtered
 }
 
+// Shuffles the tests with a Fisher-Yates shuffle driven by a SplitMix64
+// generator, so that a given seed always yields the same order.
+pub fn shuffle_tests(seed: u64, tests: &mut [TestDescAndFn]) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    for i in (1..tests.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        tests.swap(i, j);
+    }
+}
+
+fn random_seed() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+    now.as_secs() ^ ((now.subsec_nanos() as u64) << 32)
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests.into_iter().map(|x| {
@@ -1466,6 +2213,14 @@ pub fn run_test(opts: &TestOpts,
             monitor_ch.send((desc, TrMetrics(mm), Vec::new())).unwrap();
             return;
         }
+        DynTestFn(_) | StaticTestFn(_) if opts.isolate => {
+            let isolated_opts = IsolatedTestOpts {
+                run_ignored: opts.run_ignored,
+                nocapture: opts.nocapture,
+                timeout: desc.timeout.or(opts.test_timeout),
+            };
+            run_test_isolated(desc, monitor_ch, isolated_opts)
+        }
         DynTestFn(f) => {
             let cb = move |()| {
                 __rust_begin_short_backtrace(|| f.call_box(()))
@@ -1478,6 +2233,192 @@ pub fn run_test(opts: &TestOpts,
     }
 }
 
+// The options of the harness which apply to each test's process in
+// `--isolate` mode.
+struct IsolatedTestOpts {
+    run_ignored: bool,
+    nocapture: bool,
+    // The test's process is killed if it runs for longer than this.
+    timeout: Option<u64>,
+}
+
+// Runs a test in a child process by re-executing the current binary with a
+// filter that selects exactly this test.
+fn run_test_isolated(desc: TestDesc,
+                     monitor_ch: Sender<MonitorMsg>,
+                     opts: IsolatedTestOpts) {
+    let name = desc.name.clone();
+    let runtest = move || {
+        let result_path = isolated_result_path();
+        let (result, output) = match spawn_isolated_test(&desc, &opts, &result_path) {
+            Ok((Some(status), output)) => {
+                let outcome = File::open(&result_path).and_then(|mut f| {
+                    let mut s = String::new();
+                    f.read_to_string(&mut s).map(|_| s)
+                });
+                let result = match outcome {
+                    Ok(ref s) if status.success() && s == ISOLATED_TR_OK => TrOk,
+                    Ok(ref s) if status.success() && s.starts_with(ISOLATED_TR_FAILED) => {
+                        match s[ISOLATED_TR_FAILED.len()..].trim() {
+                            "" => TrFailed,
+                            msg => TrFailedMsg(msg.to_string()),
+                        }
+                    }
+                    _ => match status.code() {
+                        Some(code) => {
+                            TrFailedMsg(format!("test process exited unexpectedly with \
+                                                 status {}", code))
+                        }
+                        None => TrFailedMsg(exit_signal_msg(&status)),
+                    },
+                };
+                (result, output)
+            }
+            Ok((None, output)) => {
+                let secs = opts.timeout.unwrap_or(0);
+                (TrFailedMsg(format!("test did not finish within {} seconds and was killed",
+                                     secs)),
+                 output)
+            }
+            Err(e) => (TrFailedMsg(format!("failed to spawn test process: {}", e)), Vec::new()),
+        };
+        let _ = fs::remove_file(&result_path);
+        let result = match result {
+            TrFailed | TrFailedMsg(_) if desc.allow_fail => TrAllowedFail,
+            result => result,
+        };
+        monitor_ch.send((desc.clone(), result, output)).unwrap();
+    };
+
+    let cfg = thread::Builder::new().name(match name {
+        DynTestName(ref name) => name.clone(),
+        StaticTestName(name) => name.to_owned(),
+    });
+    cfg.spawn(runtest).unwrap();
+}
+
+// Gets a path for an isolated test to write its outcome to, which is unique
+// among the tests of this and of any concurrently running harness.
+fn isolated_result_path() -> PathBuf {
+    static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+    env::temp_dir().join(format!("rust-test-{}-{}-{}.result",
+                                 now.as_secs(),
+                                 now.subsec_nanos(),
+                                 NEXT_ID.fetch_add(1, Ordering::SeqCst)))
+}
+
+// Runs the test's process, returning its exit status and output, or no exit
+// status if it had to be killed after running for longer than the timeout.
+fn spawn_isolated_test(desc: &TestDesc, opts: &IsolatedTestOpts, result_path: &Path)
+                       -> io::Result<(Option<ExitStatus>, Vec<u8>)> {
+    let mut cmd = Command::new(env::current_exe()?);
+    cmd.env(ISOLATED_TEST_VAR, result_path)
+       .arg("--exact")
+       .arg(desc.name.as_slice())
+       .stdin(Stdio::null());
+    if opts.run_ignored {
+        cmd.arg("--ignored");
+    }
+    if opts.nocapture {
+        cmd.arg("--nocapture");
+    } else {
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = cmd.spawn()?;
+
+    // Both pipes are drained while waiting, so that a test which writes a lot
+    // of output can't block on a full pipe.
+    let stdout = child.stdout.take().map(|mut r| thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = r.read_to_end(&mut buf);
+        buf
+    }));
+    let stderr = child.stderr.take().map(|mut r| thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = r.read_to_end(&mut buf);
+        buf
+    }));
+
+    let status = match opts.timeout {
+        Some(secs) => wait_or_kill(&mut child, Duration::from_secs(secs))?,
+        None => Some(child.wait()?),
+    };
+    let mut output = Vec::new();
+    for reader in stdout.into_iter().chain(stderr) {
+        output.extend(reader.join().unwrap_or(Vec::new()));
+    }
+    Ok((status, output))
+}
+
+// Waits for `child` to exit, killing it and waiting for it to be gone if it
+// doesn't within `timeout`.
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            // The child may have exited since `try_wait`, in which case there
+            // is nothing left to kill.
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(unix)]
+fn exit_signal_msg(status: &ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+    match status.signal() {
+        Some(signal) => format!("test process terminated by signal {}", signal),
+        None => format!("test process terminated abnormally ({})", status),
+    }
+}
+
+#[cfg(not(unix))]
+fn exit_signal_msg(status: &ExitStatus) -> String {
+    format!("test process terminated abnormally ({})", status)
+}
+
+// The child side of `--isolate`: runs the single test selected by the filter
+// on the main thread, without capturing its output, writes its outcome to the
+// file named by `result_path` and exits.
+fn run_isolated_child(opts: &TestOpts, tests: Vec<TestDescAndFn>, result_path: PathBuf) -> ! {
+    // The test itself must not be able to find where its outcome is written.
+    env::remove_var(ISOLATED_TEST_VAR);
+    let mut tests = convert_benchmarks_to_tests(filter_tests(opts, tests));
+    if tests.len() != 1 {
+        let _ = writeln!(io::stderr(), "error: isolated test filter matched {} tests",
+                         tests.len());
+        process::exit(101);
+    }
+    let TestDescAndFn { desc, testfn } = tests.pop().unwrap();
+    let testfn: Box<FnBox<()>> = match testfn {
+        DynTestFn(f) => Box::new(move |()| __rust_begin_short_backtrace(|| f.call_box(()))),
+        StaticTestFn(f) => Box::new(move |()| __rust_begin_short_backtrace(f)),
+        _ => panic!("only tests can be run in an isolated process"),
+    };
+    let result = catch_unwind(AssertUnwindSafe(|| testfn.call_box(())));
+    let _ = io::stdout().flush();
+    let outcome = match calc_result(&desc, result) {
+        TrOk => ISOLATED_TR_OK.to_string(),
+        TrFailedMsg(msg) => format!("{} {}", ISOLATED_TR_FAILED, msg),
+        _ => ISOLATED_TR_FAILED.to_string(),
+    };
+    match File::create(&result_path).and_then(|mut f| f.write_all(outcome.as_bytes())) {
+        Ok(()) => process::exit(0),
+        Err(e) => {
+            let _ = writeln!(io::stderr(), "error: failed to write `{}`: {}",
+                             result_path.display(), e);
+            process::exit(101)
+        }
+    }
+}
+
 /// Fixed frame used to clean the backtrace with `RUST_BACKTRACE=1`.
 #[inline(never)]
 fn __rust_begin_short_backtrace<F: FnOnce()>(f: F) {
@@ -1577,7 +2518,9 @@ impl Bencher {
             return;
         }
 
-        self.summary = Some(iter(&mut inner));
+        let (summary, samples) = iter_samples(&mut inner);
+        self.summary = Some(summary);
+        self.samples = samples;
     }
 
     pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
@@ -1605,6 +2548,14 @@ fn ns_iter_inner<T, F>(inner: &mut F, k: u64) -> u64
 
 pub fn iter<T, F>(inner: &mut F) -> stats::Summary
     where F: FnMut() -> T
+{
+    iter_samples(inner).0
+}
+
+// Like `iter`, but also returns the (winsorized) ns/iter samples that the
+// summary was computed from.
+fn iter_samples<T, F>(inner: &mut F) -> (stats::Summary, Vec<f64>)
+    where F: FnMut() -> T
 {
     // Initial bench run to get ballpark figure.
     let ns_single = ns_iter_inner(inner, 1);
@@ -1647,13 +2598,13 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
         // stable median.
         if loop_run > Duration::from_millis(100) && summ.median_abs_dev_pct < 1.0 &&
            summ.median - summ5.median < summ5.median_abs_dev {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         total_run = total_run + loop_run;
         // Longest we ever run for is 3s.
         if total_run > Duration::from_secs(3) {
-            return summ5;
+            return (summ5, samples.to_vec());
         }
 
         // If we overflow here just return the results so far. We check a
@@ -1663,7 +2614,7 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
         n = match n.checked_mul(10) {
             Some(_) => n * 2,
             None => {
-                return summ5;
+                return (summ5, samples.to_vec());
             }
         };
     }
@@ -1680,6 +2631,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Auto,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
 
@@ -1690,6 +2642,7 @@ pub mod bench {
 
                 BenchSamples {
                     ns_iter_summ: ns_iter_summ,
+                    ns_iter_samples: bs.samples,
                     mb_s: mb_s as usize,
                 }
             }
@@ -1699,6 +2652,7 @@ pub mod bench {
                 let samples: &mut [f64] = &mut [0.0_f64; 1];
                 BenchSamples {
                     ns_iter_summ: stats::Summary::new(samples),
+                    ns_iter_samples: Vec::new(),
                     mb_s: 0,
                 }
             }
@@ -1711,6 +2665,7 @@ pub mod bench {
         let mut bs = Bencher {
             mode: BenchMode::Single,
             summary: None,
+            samples: Vec::new(),
             bytes: 0,
         };
         bs.bench(f);
@@ -1721,9 +2676,18 @@ pub mod bench {
 mod tests {
     use test::{TrFailed, TrFailedMsg, TrIgnored, TrOk, filter_tests, parse_opts, TestDesc,
                TestDescAndFn, TestOpts, run_test, MetricMap, StaticTestName, DynTestName,
-               DynTestFn, ShouldPanic};
+               DynTestFn, ShouldPanic, OutputFormat};
     use std::sync::mpsc::channel;
     use bench;
+    use std::path::PathBuf;
+    use std::thread;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use {JsonEscaped, XmlEscaped, run_tests, shuffle_tests, load_baseline, read_baseline,
+         write_baseline};
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use TestEvent::TeResult;
     use Bencher;
 
     #[test]
@@ -1737,6 +2701,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1755,6 +2720,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1775,6 +2741,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1795,6 +2762,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage("error message"),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1817,6 +2785,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage(expected),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1835,6 +2804,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(move |()| f())),
         };
@@ -1854,6 +2824,159 @@ mod tests {
         assert!((opts.run_ignored));
     }
 
+    #[test]
+    fn parse_format_flag() {
+        let args = vec!["progname".to_string(), "--format".to_string(), "json".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_format_flag"),
+        };
+        assert_eq!(opts.format, OutputFormat::Json);
+
+        let args = vec!["progname".to_string(), "-q".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_format_flag"),
+        };
+        assert_eq!(opts.format, OutputFormat::Terse);
+
+        let args = vec!["progname".to_string(), "--format".to_string(), "xml".to_string()];
+        assert!(match parse_opts(&args) {
+            Some(Err(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn json_escaping() {
+        let s = JsonEscaped("a \"quoted\"\tpath\\ with\nnewline\x01").to_string();
+        assert_eq!(s, "a \\\"quoted\\\"\\tpath\\\\ with\\nnewline\\u0001");
+    }
+
+    #[test]
+    fn parse_junit_xml_flag() {
+        let args = vec!["progname".to_string(), "--junit-xml".to_string(),
+                        "report.xml".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_junit_xml_flag"),
+        };
+        assert_eq!(opts.junit_xml, Some(PathBuf::from("report.xml")));
+    }
+
+    #[test]
+    fn xml_escaping() {
+        let s = XmlEscaped("<a href=\"x\">'b' & c</a>\n\x07").to_string();
+        assert_eq!(s, "&lt;a href=&quot;x&quot;&gt;&apos;b&apos; &amp; c&lt;/a&gt;\n");
+    }
+
+    #[test]
+    fn parse_isolate_flag() {
+        let args = vec!["progname".to_string(), "--isolate".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_isolate_flag"),
+        };
+        assert!(opts.isolate);
+    }
+
+    #[test]
+    fn parse_test_timeout_flag() {
+        let args = vec!["progname".to_string(), "--test-timeout".to_string(), "30".to_string(),
+                        "--report-slowest".to_string(), "5".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_test_timeout_flag"),
+        };
+        assert_eq!(opts.test_timeout, Some(30));
+        assert_eq!(opts.report_slowest, Some(5));
+
+        let args = vec!["progname".to_string(), "--test-timeout".to_string(), "0".to_string()];
+        assert!(match parse_opts(&args) {
+            Some(Err(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn test_timeout_fails_hung_test() {
+        fn f() {
+            thread::sleep(Duration::from_secs(30));
+        }
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(Box::new(move |()| f())),
+        };
+        let mut opts = TestOpts::new();
+        opts.run_tests = true;
+        opts.test_threads = Some(1);
+        let mut results = Vec::new();
+        run_tests(&opts, vec![desc], |event| {
+            if let TeResult(_, result, _, _) = event {
+                results.push(result);
+            }
+            Ok(())
+        }).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(match results[0] {
+            TrFailedMsg(_) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn hung_test_does_not_block_the_rest() {
+        let hung = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("hung"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(Box::new(move |()| {
+                loop {
+                    thread::park();
+                }
+            })),
+        };
+        let next = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("next"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: None,
+            },
+            testfn: DynTestFn(Box::new(move |()| {})),
+        };
+        let mut opts = TestOpts::new();
+        opts.run_tests = true;
+        opts.test_threads = Some(1);
+        let mut results = Vec::new();
+        run_tests(&opts, vec![hung, next], |event| {
+            if let TeResult(desc, result, _, _) = event {
+                results.push((desc.name.to_string(), result));
+            }
+            Ok(())
+        }).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(match results[0] {
+            (ref name, TrFailedMsg(_)) => name == "hung",
+            _ => false,
+        });
+        assert!(match results[1] {
+            (ref name, TrOk) => name == "next",
+            _ => false,
+        });
+    }
+
     #[test]
     pub fn filter_for_ignored_option() {
         // When we run ignored tests the test filter should filter out all the
@@ -1869,6 +2992,7 @@ mod tests {
                                  ignore: true,
                                  should_panic: ShouldPanic::No,
                                  allow_fail: false,
+                                 timeout: None,
                              },
                              testfn: DynTestFn(Box::new(move |()| {})),
                          },
@@ -1878,6 +3002,7 @@ mod tests {
                                  ignore: false,
                                  should_panic: ShouldPanic::No,
                                  allow_fail: false,
+                                 timeout: None,
                              },
                              testfn: DynTestFn(Box::new(move |()| {})),
                          }];
@@ -1902,6 +3027,7 @@ mod tests {
                     ignore: false,
                     should_panic: ShouldPanic::No,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(Box::new(move |()| {}))
             })
@@ -1960,6 +3086,120 @@ mod tests {
         assert_eq!(exact.len(), 1);
     }
 
+    #[test]
+    pub fn shard_tests() {
+        fn tests() -> Vec<TestDescAndFn> {
+            (0..10).map(|i| TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(format!("test{}", i)),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                    timeout: None,
+                },
+                testfn: DynTestFn(Box::new(move |()| {}))
+            })
+            .collect()
+        }
+
+        let mut names = Vec::new();
+        for shard_index in 0..3 {
+            let shard = filter_tests(&TestOpts {
+                    shard_index: shard_index,
+                    shard_count: 3,
+                    ..TestOpts::new()
+                }, tests());
+            assert!(shard.len() == 3 || shard.len() == 4);
+            names.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+        }
+        names.sort();
+        let all = filter_tests(&TestOpts::new(), tests()).into_iter()
+                                                         .map(|t| t.desc.name.to_string())
+                                                         .collect::<Vec<_>>();
+        assert_eq!(names, all);
+
+        let args = vec!["progname".to_string(), "--shard-index".to_string(), "3".to_string(),
+                        "--shard-count".to_string(), "3".to_string()];
+        assert!(match parse_opts(&args) {
+            Some(Err(_)) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    pub fn shuffle_is_deterministic() {
+        fn names(seed: u64) -> Vec<String> {
+            let mut tests = (0..20).map(|i| TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(format!("test{}", i)),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                    timeout: None,
+                },
+                testfn: DynTestFn(Box::new(move |()| {}))
+            })
+            .collect::<Vec<_>>();
+            shuffle_tests(seed, &mut tests);
+            tests.into_iter().map(|t| t.desc.name.to_string()).collect()
+        }
+
+        assert_eq!(names(42), names(42));
+        assert!(names(42) != names(43));
+
+        let args = vec!["progname".to_string(), "--shuffle=42".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in shuffle_is_deterministic"),
+        };
+        assert_eq!(opts.shuffle_seed, Some(42));
+    }
+
+    #[test]
+    fn bench_baseline_roundtrip() {
+        // A directory of its own, so that concurrent runs of this test
+        // don't write to the same file.
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let dir = env::temp_dir().join(format!("libtest-bench-baseline-{}-{}",
+                                               now.as_secs(), now.subsec_nanos()));
+        let path = dir.join("roundtrip.bench-baseline");
+        let mut baseline = BTreeMap::new();
+        baseline.insert("bench::a".to_string(), vec![1.5, 2.0, 2.5]);
+        baseline.insert("bench::b".to_string(), vec![100.0]);
+        write_baseline(&path, &baseline).unwrap();
+        let read = read_baseline(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(read == baseline);
+    }
+
+    #[test]
+    fn missing_bench_baseline_names_file() {
+        match load_baseline("libtest-no-such-baseline") {
+            Err(msg) => assert!(msg.contains("libtest-no-such-baseline.bench-baseline")),
+            Ok(_) => panic!("loaded a baseline which doesn't exist"),
+        }
+    }
+
+    #[test]
+    fn parse_baseline_flags() {
+        let args = vec!["progname".to_string(), "--bench".to_string(),
+                        "--fail-on-regression".to_string()];
+        assert!(match parse_opts(&args) {
+            Some(Err(_)) => true,
+            _ => false,
+        });
+
+        let args = vec!["progname".to_string(), "--bench".to_string(),
+                        "--baseline".to_string(), "main".to_string(),
+                        "--fail-on-regression".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in parse_baseline_flags"),
+        };
+        assert_eq!(opts.baseline, Some("main".to_string()));
+        assert!(opts.fail_on_regression);
+    }
+
     #[test]
     pub fn sort_tests() {
         let mut opts = TestOpts::new();
@@ -1984,6 +3224,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(move |()| testfn())),
                 };
@@ -327,12 +327,44 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
     }
 }
 
+/// Perform Welch's two-sample t-test, returning the two-sided p-value of the
+/// hypothesis that `a` and `b` are drawn from distributions with equal means.
+///
+/// The t distribution is approximated by the normal distribution, which is
+/// accurate enough for the number of samples a benchmark run produces.
+///
+/// See: https://en.wikipedia.org/wiki/Welch%27s_t-test
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return 1.0;
+    }
+    let std_err = (a.var() / a.len() as f64 + b.var() / b.len() as f64).sqrt();
+    if std_err == 0.0 {
+        return if a.mean() == b.mean() { 1.0 } else { 0.0 };
+    }
+    let t = (a.mean() - b.mean()) / std_err;
+    erfc(t.abs() / 2f64.sqrt())
+}
+
+// Complementary error function, with a fractional error below 1.2e-7.
+// See Numerical Recipes in C, 2nd edition, section 6.2.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418 +
+               t * (-0.18628806 + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 +
+               t * (-0.82215223 + t * 0.17087277))))))));
+    let r = t * (-z * z + poly).exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
 // Test vectors generated from R, using the script src/etc/stat-test-vectors.r.
 
 #[cfg(test)]
 mod tests {
     use stats::Stats;
     use stats::Summary;
+    use stats::welch_t_test;
     use std::f64;
     use std::io::prelude::*;
     use std::io;
@@ -868,6 +900,18 @@ mod tests {
         check(val, summ);
     }
 
+    #[test]
+    fn test_welch_t_test() {
+        let a = [10.0, 10.5, 9.5, 10.2, 9.8, 10.1, 9.9, 10.0];
+        assert_approx_eq!(welch_t_test(&a, &a), 1.0);
+
+        let b = [20.0, 20.5, 19.5, 20.2, 19.8, 20.1, 19.9, 20.0];
+        assert!(welch_t_test(&a, &b) < 0.001);
+
+        let c = [10.1, 10.4, 9.6, 10.2, 9.7, 10.0, 10.0, 9.9];
+        assert!(welch_t_test(&a, &c) > 0.5);
+    }
+
     #[test]
     fn test_sum_f64s() {
         assert_eq!([0.5f64, 3.2321f64, 1.5678f64].sum(), 5.2999);
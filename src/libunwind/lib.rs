@@ -19,12 +19,19 @@
 #![feature(unwind_attributes)]
 #![feature(static_nobundle)]
 
-#![cfg_attr(not(target_env = "msvc"), feature(libc))]
+#![cfg_attr(not(any(target_env = "msvc",
+                    all(target_arch = "wasm32", not(target_os = "emscripten")))),
+            feature(libc))]
 
-#[cfg(not(target_env = "msvc"))]
+// There is no unwinder to bind to on MSVC, which uses SEH, or on wasm32 without
+// Emscripten, which can only abort on panic.
+#[cfg(not(any(target_env = "msvc",
+              all(target_arch = "wasm32", not(target_os = "emscripten")))))]
 extern crate libc;
 
-#[cfg(not(target_env = "msvc"))]
+#[cfg(not(any(target_env = "msvc",
+              all(target_arch = "wasm32", not(target_os = "emscripten")))))]
 mod libunwind;
-#[cfg(not(target_env = "msvc"))]
+#[cfg(not(any(target_env = "msvc",
+              all(target_arch = "wasm32", not(target_os = "emscripten")))))]
 pub use libunwind::*;
@@ -8,9 +8,13 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+// revisions: ast mir
+//[mir]compile-flags: -Z borrowck=mir
+
 fn main() {
     let i: isize;
 
     println!("{}", false && { i = 5; true });
-    println!("{}", i); //~ ERROR use of possibly uninitialized variable: `i`
+    println!("{}", i); //[ast]~ ERROR use of possibly uninitialized variable: `i`
+                       //[mir]~^ ERROR use of possibly uninitialized variable: `i`
 }
@@ -8,6 +8,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+// revisions: ast mir
+//[mir]compile-flags: -Z borrowck=mir
+
 // Check that `&mut` objects cannot be borrowed twice, just like
 // other `&mut` pointers.
 
@@ -18,7 +21,8 @@ trait Foo {
 
 fn test(x: &mut Foo) {
     let _y = x.f1();
-    x.f2(); //~ ERROR cannot borrow `*x` as mutable
+    x.f2(); //[ast]~ ERROR cannot borrow `*x` as mutable
+            //[mir]~^ ERROR cannot borrow `*x` as mutable more than once at a time
 }
 
 fn main() {}
@@ -0,0 +1,77 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// revisions: ast mir
+//[mir]compile-flags: -Z borrowck=mir
+
+// Check that the MIR borrow checker reports the same errors as the AST
+// one for the basic cases.
+
+fn use_after_move() {
+    let x = Box::new(1);
+    let y = x;
+    drop(x); //[ast]~ ERROR use of moved value: `x`
+             //[mir]~^ ERROR use of moved value: `x`
+    drop(y);
+}
+
+fn move_while_borrowed() {
+    let x = Box::new(1);
+    let r = &x;
+    let y = x; //[ast]~ ERROR cannot move out of `x` because it is borrowed
+               //[mir]~^ ERROR cannot move out of `x` because it is borrowed
+    drop(r);
+    drop(y);
+}
+
+fn borrow_mut_while_shared() {
+    let mut x = 1;
+    let r = &x;
+    let m = &mut x; //[ast]~ ERROR cannot borrow `x` as mutable because it is also borrowed
+                    //[mir]~^ ERROR cannot borrow `x` as mutable because it is also borrowed
+    drop(r);
+    drop(m);
+}
+
+fn borrow_mut_twice() {
+    let mut x = 1;
+    let m1 = &mut x;
+    let m2 = &mut x; //[ast]~ ERROR cannot borrow `x` as mutable more than once at a time
+                     //[mir]~^ ERROR cannot borrow `x` as mutable more than once at a time
+    drop(m1);
+    drop(m2);
+}
+
+fn use_while_mutably_borrowed() {
+    let mut x = 1;
+    let m = &mut x;
+    let y = x; //[ast]~ ERROR cannot use `x` because it was mutably borrowed
+               //[mir]~^ ERROR cannot use `x` because it was mutably borrowed
+    drop(m);
+    drop(y);
+}
+
+fn reassign_immutable() {
+    let x = 1;
+    drop(x);
+    x = 2; //[ast]~ ERROR re-assignment of immutable variable `x`
+           //[mir]~^ ERROR re-assignment of immutable variable `x`
+    drop(x);
+}
+
+fn borrow_immutable_as_mut() {
+    let x = 1;
+    let m = &mut x; //[ast]~ ERROR cannot borrow immutable local variable `x` as mutable
+                    //[mir]~^ ERROR cannot borrow immutable local variable `x` as mutable
+    drop(m);
+}
+
+fn main() {
+}
@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck=mir -Z nll
+
+// Check that with `-Z nll`, a borrow stored through a shared reference,
+// by way of interior mutability, lasts as long as where it is stored.
+
+use std::cell::{Cell, RefCell};
+
+struct Holder<'a> {
+    slot: RefCell<Option<&'a u32>>,
+}
+
+impl<'a> Holder<'a> {
+    fn hold(&self, x: &'a u32) {
+        *self.slot.borrow_mut() = Some(x);
+    }
+}
+
+fn cell() {
+    let mut v = vec![1, 2, 3];
+    let c = Cell::new(None);
+    c.set(Some(&v[0]));
+    v.clear(); //~ ERROR cannot borrow `v` as mutable
+    c.get();
+}
+
+fn ref_cell() {
+    let mut v = vec![1, 2, 3];
+    let h = Holder { slot: RefCell::new(None) };
+    h.hold(&v[0]);
+    v.clear(); //~ ERROR cannot borrow `v` as mutable
+    h.slot.borrow();
+}
+
+fn main() {
+}
@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that reading bytes of a union that were never written is an error
+// once the value of the constant is needed.
+
+#![allow(const_err)]
+
+union U {
+    byte: u8,
+    short: u16,
+}
+
+const SHORT: u16 = unsafe { U { byte: 1 }.short };
+//~^ ERROR constant evaluation error
+//~| attempted to read undefined bytes
+
+fn main() {
+    let _: [u8; SHORT as usize]; //~ NOTE for array length here
+}
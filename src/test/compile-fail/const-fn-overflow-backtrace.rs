@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that errors inside `const fn`s point at the calls that led there.
+
+#![feature(const_fn)]
+#![allow(const_err)]
+
+const fn dec(x: u8) -> u8 {
+    x - 1 //~ ERROR constant evaluation error
+          //~| attempt to subtract with overflow
+}
+
+const fn twice_dec(x: u8) -> u8 {
+    dec(dec(x)) //~ NOTE inside call to `dec`
+}
+
+fn main() {
+    let _: [u8; twice_dec(1) as usize];
+    //~^ NOTE inside call to `twice_dec`
+    //~| NOTE for array length here
+}
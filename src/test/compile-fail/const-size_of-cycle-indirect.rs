@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that a cycle going through the value of a constant is detected, like
+// in `const-size_of-cycle.rs`.
+
+#![feature(const_fn)]
+
+struct Foo {
+    bytes: [u8; SIZE]
+    //~^ ERROR unsupported cyclic reference between types/traits detected
+}
+
+const SIZE: usize = std::mem::size_of::<Foo>();
+
+fn main() {}
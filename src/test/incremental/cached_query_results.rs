@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that results loaded from the query result cache in the second and
+// third session are the same as when they were computed. `unchanged` and
+// `Point::norm` are reused from the cache, while `changed` is recomputed.
+// `unchanged` also has spans from a macro expansion, which are reused along
+// with their expansion info.
+
+// revisions:rpass1 rpass2 rpass3
+// compile-flags: -Z query-dep-graph
+
+#![feature(rustc_attrs)]
+
+macro_rules! sum_norms {
+    ($points:expr) => {
+        $points.iter().fold(0, |sum: i32, p: &Point| sum + p.norm())
+    }
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    #[rustc_clean(label="TypeckTables", cfg="rpass2")]
+    #[rustc_clean(label="TypeckTables", cfg="rpass3")]
+    fn norm(&self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+#[rustc_clean(label="TypeckTables", cfg="rpass2")]
+#[rustc_clean(label="TypeckTables", cfg="rpass3")]
+fn unchanged(points: &[Point]) -> i32 {
+    sum_norms!(points)
+}
+
+#[cfg(rpass1)]
+fn changed() -> i32 {
+    1
+}
+
+#[cfg(not(rpass1))]
+#[rustc_dirty(label="TypeckTables", cfg="rpass2")]
+#[rustc_clean(label="TypeckTables", cfg="rpass3")]
+fn changed() -> i32 {
+    2
+}
+
+fn main() {
+    let points = vec![Point { x: 1, y: -2 }, Point { x: -3, y: 4 }];
+    assert_eq!(unchanged(&points), 10);
+    assert!(changed() > 0);
+}
@@ -0,0 +1,48 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn test() -> u32 {
+    let x = 2 + 3;
+    x * 2
+}
+
+fn main() {
+    test();
+}
+
+// END RUST SOURCE
+// START rustc.node4.DeadStoreElimination.before.mir
+//  bb0: {
+//      _2 = CheckedAdd(const 2u32, const 3u32);
+//      goto -> bb1;
+//  }
+//  bb1: {
+//      _1 = const 5u32;
+//      goto -> bb2;
+//  }
+//  bb2: {
+//      _0 = const 10u32;
+//      return;
+//  }
+// END rustc.node4.DeadStoreElimination.before.mir
+// START rustc.node4.DeadStoreElimination.after.mir
+//  bb0: {
+//      nop;
+//      goto -> bb1;
+//  }
+//  bb1: {
+//      nop;
+//      goto -> bb2;
+//  }
+//  bb2: {
+//      _0 = const 10u32;
+//      return;
+//  }
+// END rustc.node4.DeadStoreElimination.after.mir
@@ -0,0 +1,21 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn plus_one(x: i32) -> i32 {
+    x + 1
+}
+
+fn main() {
+    let (a, b) = (1, 2);
+    // Comparing for equality is only a guess at what was meant, so this is
+    // left alone.
+    if a = b {}
+    plus_one(a);
+}
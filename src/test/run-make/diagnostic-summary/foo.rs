@@ -0,0 +1,33 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+macro_rules! bad {
+    () => { let _: u32 = "not a number"; }
+}
+
+mod bar;
+
+// Takes a `u32` where its callers have a `u64`, so every call is an error.
+fn half(x: u32) -> u32 {
+    x / 2
+}
+
+fn main() {
+    bad!();
+    bad!();
+    bar::baz();
+    let big: u64 = 10;
+    half(big);
+    half(big);
+    bar::quarter(big);
+    // Unrelated errors with the same code are not grouped together.
+    let _: u32 = "one";
+    let _: bool = 2;
+}
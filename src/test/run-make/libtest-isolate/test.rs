@@ -0,0 +1,40 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::env;
+use std::fs::File;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn passes() {}
+
+#[test]
+fn aborts() {
+    process::abort();
+}
+
+// Exit statuses must not be mistaken for the outcome of the test.
+#[test]
+fn exits() {
+    process::exit(50);
+}
+
+#[test]
+fn exits_successfully() {
+    process::exit(0);
+}
+
+#[test]
+fn hangs() {
+    thread::sleep(Duration::from_secs(4));
+    File::create(env::var("HANG_MARKER").unwrap()).unwrap();
+}
@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::env;
+
+#[inline(never)]
+pub fn count_short(args: &[String]) -> usize {
+    let mut count = 0;
+    for arg in args {
+        if arg.len() < 4 {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    assert_eq!(count_short(&args), 2);
+}
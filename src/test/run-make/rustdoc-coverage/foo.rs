@@ -0,0 +1,35 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The crate documentation.
+
+#![crate_name = "foo"]
+
+/// Documented, with an example:
+///
+/// ```
+/// foo::documented();
+/// ```
+pub fn documented() {}
+
+pub fn undocumented() {}
+
+/// Tuple fields aren't counted.
+pub struct Tuple(pub u32);
+
+pub mod inner {
+    /// Documented.
+    pub struct Inner {
+        pub field: u32,
+    }
+}
+
+#[allow(dead_code)]
+fn private() {}
@@ -0,0 +1,41 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! ```
+//! assert_eq!(foo::add(1, 2), 3);
+//! ```
+
+/// Tests defining the same items don't clash when they are merged.
+///
+/// ```
+/// struct Local(u32);
+/// assert_eq!(foo::add(Local(2).0, 2), 4);
+/// ```
+pub fn add(a: u32, b: u32) -> u32 { a + b }
+
+/// ```
+/// struct Local;
+/// let _ = Local;
+/// ```
+///
+/// ```should_panic
+/// panic!("merged tests are still run in their own process");
+/// ```
+///
+/// ```compile_fail
+/// let x: u32 = "not a number";
+/// ```
+///
+/// ```
+/// fn main() {
+///     assert_eq!(foo::add(0, 0), 0);
+/// }
+/// ```
+pub struct Thing;
@@ -0,0 +1,22 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Links to [`Missing`] and [`Bar::missing`].
+
+#![cfg_attr(deny, deny(intra_doc_link_resolution_failure))]
+#![cfg_attr(deny_warnings, deny(warnings))]
+
+pub struct Bar;
+
+#[allow(intra_doc_link_resolution_failure)]
+pub mod quiet {
+    /// Links to [`Gone`].
+    pub struct Baz;
+}
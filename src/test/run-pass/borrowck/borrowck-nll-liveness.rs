@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck=mir -Z nll
+
+// Check that with `-Z nll`, a borrow ends once the reference, or anything
+// it was stored into, is not used anymore, rather than at the end of its
+// lexical scope.
+
+fn first(v: &mut Vec<u32>) -> &mut u32 {
+    &mut v[0]
+}
+
+fn main() {
+    let mut v = vec![1, 2, 3];
+    let r = &v;
+    let n = r.len() as u32;
+    v.push(n);
+
+    let x = first(&mut v);
+    *x += 1;
+    v.push(4);
+
+    let mut s = String::new();
+    let t = &mut s;
+    t.push('a');
+    s.push('b');
+
+    assert_eq!(v, [2, 2, 3, 3, 4]);
+    assert_eq!(s, "ab");
+}
@@ -0,0 +1,40 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that array lengths calling `const fn`s can be evaluated while the
+// items that use them are still being type-checked.
+
+#![feature(const_fn)]
+
+const fn len() -> usize {
+    4
+}
+
+const fn twice_len() -> usize {
+    len() * 2
+}
+
+const LEN: usize = twice_len();
+
+struct Buf {
+    data: [u8; len()],
+}
+
+fn first(buf: [u8; twice_len()]) -> u8 {
+    buf[0]
+}
+
+fn main() {
+    let buf = Buf { data: [1; len()] };
+    assert_eq!(buf.data.len(), 4);
+    assert_eq!(first([2; LEN]), 2);
+    let lengths = [len(); twice_len()];
+    assert_eq!(lengths.len(), 8);
+}
@@ -0,0 +1,56 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check constants that can only be evaluated by running their MIR.
+
+#![feature(const_fn)]
+
+const fn double(x: usize) -> usize {
+    x * 2
+}
+
+const fn quadruple(x: usize) -> usize {
+    double(double(x))
+}
+
+struct Pair {
+    a: u8,
+    b: u16,
+}
+
+const fn pair(a: u8) -> Pair {
+    Pair { a: a, b: a as u16 * 256 }
+}
+
+const PAIR: Pair = pair(3);
+
+#[repr(u8)]
+enum E {
+    A = PAIR.a,
+    B = double(PAIR.a as usize) as u8,
+}
+
+union U {
+    bytes: [u8; 2],
+    word: u16,
+}
+
+const WORD: u16 = unsafe { U { bytes: [1, 1] }.word };
+
+fn main() {
+    let array: [u8; quadruple(2)] = [0; quadruple(2)];
+    assert_eq!(array.len(), 8);
+    assert_eq!(PAIR.b, 768);
+    assert_eq!(E::A as u8, 3);
+    assert_eq!(E::B as u8, 6);
+
+    let words: [u8; WORD as usize] = [0; 257];
+    assert_eq!(words.len(), 257);
+}
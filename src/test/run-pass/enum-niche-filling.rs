@@ -0,0 +1,79 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that enums storing their discriminant in the invalid values of
+// a field read and write it correctly, both at runtime and in constants.
+
+use std::cmp::Ordering;
+use std::mem::size_of;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Cmp { Less, Equal, Greater }
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Tagged {
+    Empty,
+    Full { val: u32, flag: bool },
+    Void,
+}
+
+// The `char` is misaligned, so its invalid values can't be used.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(packed)]
+struct Packed {
+    byte: u8,
+    ch: char,
+}
+
+const NONE_BOOL: Option<bool> = None;
+const SOME_NONE_BOOL: Option<Option<bool>> = Some(None);
+const VOID: Tagged = Tagged::Void;
+
+fn identity<T>(x: T) -> T { x }
+
+pub fn main() {
+    assert_eq!(identity(Some(true)), Some(true));
+    assert_eq!(identity(Some(false)), Some(false));
+    assert_eq!(identity(NONE_BOOL), None);
+
+    let nested = [None, Some(None), Some(Some(false)), Some(Some(true))];
+    for (i, &x) in nested.iter().enumerate() {
+        let index = match x {
+            None => 0,
+            Some(None) => 1,
+            Some(Some(false)) => 2,
+            Some(Some(true)) => 3,
+        };
+        assert_eq!(index, i);
+    }
+    assert_eq!(SOME_NONE_BOOL, Some(None));
+
+    let chars = [Some('\u{10FFFF}'), None, Some('\0')];
+    assert_eq!(identity(chars), [Some('\u{10FFFF}'), None, Some('\0')]);
+
+    let ordering = [None, Some(Ordering::Less), Some(Ordering::Greater)];
+    assert_eq!(identity(ordering), [None, Some(Ordering::Less), Some(Ordering::Greater)]);
+    assert_eq!(identity([None, Some(Cmp::Equal)]), [None, Some(Cmp::Equal)]);
+
+    let mut tagged = Tagged::Empty;
+    assert_eq!(identity(tagged), Tagged::Empty);
+    tagged = Tagged::Full { val: 42, flag: true };
+    assert_eq!(identity(tagged), Tagged::Full { val: 42, flag: true });
+    if let Tagged::Full { ref mut flag, .. } = tagged {
+        *flag = false;
+    }
+    assert_eq!(identity(tagged), Tagged::Full { val: 42, flag: false });
+    assert_eq!(identity(VOID), Tagged::Void);
+
+    assert_eq!(size_of::<Option<Packed>>(), size_of::<Packed>() + 1);
+    let packed = Some(Packed { byte: 1, ch: 'x' });
+    assert_eq!(identity(packed), Some(Packed { byte: 1, ch: 'x' }));
+    assert_eq!(identity(None::<Packed>), None);
+}
@@ -0,0 +1,54 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z threads=4 -Z borrowck=compare
+
+// Check that type-checking and borrow-checking the bodies of a crate on
+// several threads gives the same results as on one. Without a compiler
+// built with parallel queries, `-Z threads` only warns.
+
+#![feature(const_fn)]
+
+const LEN: usize = double(2);
+
+const fn double(x: usize) -> usize {
+    x * 2
+}
+
+struct Counter {
+    counts: [u32; LEN],
+}
+
+impl Counter {
+    fn new() -> Counter {
+        Counter { counts: [0; LEN] }
+    }
+
+    fn bump(&mut self, i: usize) -> u32 {
+        let count = &mut self.counts[i % LEN];
+        *count += 1;
+        *count
+    }
+}
+
+fn total(counter: &Counter) -> u32 {
+    counter.counts.iter().map(|&count| count).sum()
+}
+
+fn apply<F: FnMut(usize) -> u32>(n: usize, mut f: F) -> Vec<u32> {
+    (0..n).map(|i| f(i)).collect()
+}
+
+fn main() {
+    let mut counter = Counter::new();
+    let bumped = apply(6, |i| counter.bump(i));
+    assert_eq!(bumped, [1, 1, 1, 1, 2, 2]);
+    assert_eq!(total(&counter), 6);
+}
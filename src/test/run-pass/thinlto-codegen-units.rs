@@ -0,0 +1,55 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// min-llvm-version 4.0
+// compile-flags: -Z thinlto -C codegen-units=3 -O
+
+// Check that ThinLTO across codegen units keeps functions callable from the
+// other units, including private ones which have to be promoted.
+
+fn one() -> usize { 1 }
+
+static mut COUNT: usize = 0;
+
+mod a {
+    pub fn two() -> usize {
+        ::one() + ::one()
+    }
+
+    #[inline(never)]
+    fn bump() {
+        unsafe { ::COUNT += 1; }
+    }
+
+    pub fn bump_twice() {
+        bump();
+        bump();
+    }
+}
+
+mod b {
+    pub fn three() -> usize {
+        ::one() + ::a::two()
+    }
+
+    pub fn four() -> usize {
+        let v: Vec<usize> = vec![1, 1, 1, 1];
+        v.iter().sum()
+    }
+}
+
+fn main() {
+    assert_eq!(one(), 1);
+    assert_eq!(a::two(), 2);
+    assert_eq!(b::three(), 3);
+    assert_eq!(b::four(), 4);
+    a::bump_twice();
+    assert_eq!(unsafe { COUNT }, 2);
+}
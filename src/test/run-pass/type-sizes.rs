@@ -42,6 +42,12 @@ enum ReorderedEnum {
     B(u8, u16, u8),
 }
 
+enum NicheFilledEnumWithAbsentVariant {
+    A(bool, u8),
+    B,
+    C,
+}
+
 pub fn main() {
     assert_eq!(size_of::<u8>(), 1 as usize);
     assert_eq!(size_of::<u32>(), 4 as usize);
@@ -67,4 +73,12 @@ pub fn main() {
     assert_eq!(size_of::<e3>(), 4 as usize);
     assert_eq!(size_of::<ReorderedStruct>(), 4);
     assert_eq!(size_of::<ReorderedEnum>(), 6);
+
+    // The discriminant is stored in the invalid values of the `bool`.
+    assert_eq!(size_of::<NicheFilledEnumWithAbsentVariant>(), 2);
+    assert_eq!(size_of::<Option<bool>>(), 1);
+    assert_eq!(size_of::<Option<Option<bool>>>(), 1);
+    assert_eq!(size_of::<Option<char>>(), 4);
+    assert_eq!(size_of::<Option<std::cmp::Ordering>>(), 1);
+    assert_eq!(size_of::<Option<(u32, bool)>>(), 8);
 }
@@ -0,0 +1,46 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+// @has foo/index.html
+// @has - '//a/@href' '../foo/struct.ThisType.html'
+// @has - '//a/@href' '../foo/struct.ThisType.html#method.this_method'
+// @has - '//a/@href' '../foo/enum.ThisEnum.html#variant.ThisVariant'
+// @has - '//a/@href' '../foo/trait.ThisTrait.html#tymethod.this_trait_method'
+// @has - '//a/@href' '../foo/fn.this_function.html'
+// @has - '//a/@href' '../foo/inner/struct.Inner.html'
+// @has - '//a/@href' 'enum.Option.html#method.unwrap'
+//! Here's a link to [`ThisType`], [`ThisType::this_method`], [`ThisEnum::ThisVariant`],
+//! [`ThisTrait::this_trait_method`], [`this_function()`],
+//! [a struct in a module](crate::inner::Inner) and [`Option::unwrap`].
+
+pub struct ThisType;
+
+impl ThisType {
+    pub fn this_method() {}
+}
+
+pub enum ThisEnum { ThisVariant, }
+
+pub trait ThisTrait {
+    fn this_trait_method();
+}
+
+pub fn this_function() {}
+
+pub mod inner {
+    // @has foo/inner/struct.Inner.html '//a/@href' '../../foo/inner/struct.Other.html'
+    // @has - '//a/@href' '../../foo/struct.ThisType.html'
+    /// Links are resolved in the scope of the module: [`Other`], [`super::ThisType`].
+    pub struct Inner;
+
+    pub struct Other;
+}
@@ -0,0 +1,46 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z print-type-sizes
+
+// This file illustrates how enums store their discriminant in the
+// invalid values of a field of their only non-zero-sized variant,
+// modelled after cases like `Option<bool>` and `Option<Ordering>`.
+//
+// It avoids using u64/i64 because on some targets that is only 4-byte
+// aligned (while on most it is 8-byte aligned) and so the resulting
+// padding and overall computed sizes can be quite different.
+
+#![allow(dead_code)]
+
+pub enum MyOption<T> { None, Some(T) }
+
+impl<T> Default for MyOption<T> {
+    fn default() -> Self { MyOption::None }
+}
+
+pub enum Cmp { Less, Equal, Greater }
+
+pub enum Tagged {
+    Empty,
+    Full { val: u32, flag: bool },
+}
+
+impl Default for Tagged {
+    fn default() -> Self { Tagged::Empty }
+}
+
+pub fn main() {
+    let _a: MyOption<bool> = Default::default();
+    let _b: MyOption<char> = Default::default();
+    let _c: MyOption<Cmp> = Default::default();
+    let _d: MyOption<MyOption<bool>> = Default::default();
+    let _e: Tagged = Default::default();
+}
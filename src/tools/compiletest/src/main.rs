@@ -332,8 +332,9 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         filter: config.filter.clone(),
         filter_exact: config.filter_exact,
         run_ignored: config.run_ignored,
-        quiet: config.quiet,
+        format: if config.quiet { test::OutputFormat::Terse } else { test::OutputFormat::Pretty },
         logfile: config.logfile.clone(),
+        junit_xml: None,
         run_tests: true,
         bench_benchmarks: true,
         nocapture: match env::var("RUST_TEST_NOCAPTURE") {
@@ -342,6 +343,15 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         },
         color: config.color,
         test_threads: None,
+        isolate: false,
+        test_timeout: None,
+        report_slowest: None,
+        shard_index: 0,
+        shard_count: 1,
+        shuffle_seed: None,
+        save_baseline: None,
+        baseline: None,
+        fail_on_regression: false,
         skip: vec![],
         list: false,
         options: test::Options::new(),
@@ -473,6 +483,7 @@ pub fn make_test(config: &Config, testpaths: &TestPaths) -> test::TestDescAndFn
             ignore: ignore,
             should_panic: should_panic,
             allow_fail: false,
+            timeout: None,
         },
         testfn: make_test_closure(config, testpaths),
     }